@@ -0,0 +1,17 @@
+use martian_adapters::{AdapterFactory, ModelFilter};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    AdapterFactory::init_from_modelsdev().await?;
+
+    let provider = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "openai".to_string());
+    let matrix =
+        AdapterFactory::capability_matrix(Some(ModelFilter::new().with_provider(provider)))
+            .await
+            .with_capabilities(&["streaming", "vision", "video", "tools", "json_output"]);
+    print!("{}", matrix.render());
+
+    Ok(())
+}
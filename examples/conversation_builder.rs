@@ -1,6 +1,6 @@
 use martian_adapters::{
     ContentEntry, ContentEntryData, ContentTurn, Conversation, ConversationRole, FunctionCall,
-    ImageUrl, ToolCall, Turn, TurnType,
+    ImageUrl, ToolCall, ToolResultContent, Turn, TurnType,
 };
 
 fn main() {
@@ -11,14 +11,20 @@ fn main() {
     simple_conv.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::System,
         content: "You are a helpful assistant.".to_string(),
+        name: None,
+        metadata: None,
     }));
     simple_conv.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::User,
         content: "What is the capital of France?".to_string(),
+        name: None,
+        metadata: None,
     }));
     simple_conv.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::Assistant,
         content: "The capital of France is Paris.".to_string(),
+        name: None,
+        metadata: None,
     }));
     println!("   Turns: {}", simple_conv.len());
     println!(
@@ -47,6 +53,8 @@ fn main() {
                 },
             },
         ],
+        name: None,
+        metadata: None,
     }));
     println!("   Turns: {}", vision_conv.len());
     println!(
@@ -59,6 +67,8 @@ fn main() {
     tool_conv.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::User,
         content: "What's the weather in Paris?".to_string(),
+        name: None,
+        metadata: None,
     }));
     tool_conv.add_turn(TurnType::ToolCalls {
         role: ConversationRole::Assistant,
@@ -71,15 +81,22 @@ fn main() {
                 arguments: r#"{"location": "Paris"}"#.to_string(),
             },
         }],
+        metadata: None,
     });
     tool_conv.add_turn(TurnType::ToolOutput {
         role: ConversationRole::Tool,
-        content: Some(r#"{"temperature": 18, "condition": "Cloudy"}"#.to_string()),
+        content: Some(ToolResultContent::Json(serde_json::json!({
+            "temperature": 18,
+            "condition": "Cloudy",
+        }))),
         tool_call_id: "call_abc123".to_string(),
+        metadata: None,
     });
     tool_conv.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::Assistant,
         content: "The weather in Paris is currently 18°C and cloudy.".to_string(),
+        name: None,
+        metadata: None,
     }));
     println!("   Turns: {}", tool_conv.len());
     println!("   Contains tool calls: true\n");
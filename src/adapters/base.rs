@@ -1,10 +1,33 @@
-use crate::error::Result;
-use crate::models::{AdapterChatCompletion, AdapterChatCompletionChunk, Conversation, Model};
+use crate::adapters::batch::BatchOutcome;
+use crate::adapters::builtin::BuiltinTool;
+use crate::adapters::events::{stream_events, StreamEventStream};
+use crate::adapters::json_stream::{json_event_stream, JsonEventStream};
+use crate::adapters::postprocess::PostProcessor;
+use crate::adapters::preflight::ContextOverflowPolicy;
+use crate::adapters::stream::{
+    coalesce_stream, instrument_stream_since, simulate_stream, SimulatedStreamOptions,
+    StreamMetrics, StreamOptions,
+};
+use crate::adapters::validation::OutputValidation;
+use crate::error::{AdapterError, Result};
+use crate::models::{
+    AdapterChatCompletion, AdapterChatCompletionChunk, Conversation, ConversationRole,
+    CostBreakdown, Model, Turn, TurnType,
+};
+use crate::utils::{estimate_schema_tokens, estimate_tokens, stable_hash};
 use async_trait::async_trait;
-use futures::stream::Stream;
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 
+const CONTINUE_INSTRUCTION: &str =
+    "Continue exactly where you left off, without repeating anything.";
+const EXPAND_INSTRUCTION: &str =
+    "Your answer is too short. Continue it with more detail, without repeating anything.";
+/// Continuation requests `execute_with_options` makes per choice to reach
+/// `min_tokens`.
+const MAX_EXPANSION_ROUNDS: u32 = 3;
+
 pub type AdapterStream = Pin<Box<dyn Stream<Item = Result<AdapterChatCompletionChunk>> + Send>>;
 
 #[async_trait]
@@ -24,9 +47,355 @@ pub trait BaseAdapter: Send + Sync {
         conversation: &Conversation,
         options: &ExecuteOptions,
     ) -> Result<AdapterStream>;
+
+    async fn execute_stream_with(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        stream_options: &StreamOptions,
+    ) -> Result<AdapterStream> {
+        let stream = self.execute_stream(conversation, options).await?;
+        Ok(coalesce_stream(stream, stream_options.clone()))
+    }
+
+    /// Streams from models that support it; for the others, performs a
+    /// regular request and replays the result with `simulate_stream` so
+    /// callers can consume every model the same way.
+    async fn execute_stream_or_simulate(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        simulation: &SimulatedStreamOptions,
+    ) -> Result<AdapterStream> {
+        if self.get_model().capabilities.supports_streaming {
+            return self.execute_stream(conversation, options).await;
+        }
+        let response = self.execute(conversation, options).await?;
+        Ok(simulate_stream(response, simulation.clone()))
+    }
+
+    async fn execute_stream_instrumented(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<(AdapterStream, StreamMetrics)> {
+        let started = tokio::time::Instant::now();
+        let stream = self.execute_stream(conversation, options).await?;
+        Ok(instrument_stream_since(stream, started))
+    }
+
+    async fn execute_stream_events(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<StreamEventStream> {
+        let stream = self.execute_stream(conversation, options).await?;
+        Ok(stream_events(stream))
+    }
+
+    /// Streams a JSON-mode response as path events so structured output can
+    /// be rendered before the document is complete.
+    async fn execute_stream_json(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<JsonEventStream> {
+        if !options
+            .response_format
+            .as_ref()
+            .is_some_and(ResponseFormat::is_json)
+        {
+            return Err(AdapterError::ConfigError(
+                "execute_stream_json requires a JSON response_format".to_string(),
+            ));
+        }
+        let stream = self.execute_stream(conversation, options).await?;
+        Ok(json_event_stream(stream))
+    }
+
+    /// Asks the model to continue `partial` as its own reply. The returned
+    /// choices contain the partial text followed by the generated continuation.
+    async fn continue_response(
+        &self,
+        conversation: &Conversation,
+        partial: &str,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        let model = self.get_model();
+        if !model.capabilities.supports_prefill {
+            return Err(AdapterError::UnsupportedFeature {
+                model: model.get_path(),
+                feature: "prefill".to_string(),
+            });
+        }
+
+        let prefilled = conversation.clone().with_assistant_prefill(partial);
+        let mut response = self.execute(&prefilled, options).await?;
+        for choice in &mut response.choices {
+            let generated = choice.message.content.take().unwrap_or_default();
+            choice.message.content = Some(format!("{}{}", partial, generated));
+        }
+        Ok(response)
+    }
+
+    /// Executes every conversation, at most `concurrency` at a time, and
+    /// keeps going past failures. Results stay in input order.
+    async fn execute_batch(
+        &self,
+        conversations: &[Conversation],
+        options: &ExecuteOptions,
+        concurrency: usize,
+    ) -> BatchOutcome<AdapterChatCompletion> {
+        let results = stream::iter(0..conversations.len())
+            .map(|index| self.execute(&conversations[index], options))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await;
+        BatchOutcome::new(results)
+    }
+
+    /// Executes the request with the client-side options applied, in this
+    /// order:
+    ///
+    /// 1. `target_length_hint` is added to the conversation as an
+    ///    instruction.
+    /// 2. Choices that stopped on `length` are continued, at most
+    ///    `auto_continue` times each.
+    /// 3. On models without native `min_tokens`, choices shorter than
+    ///    `min_tokens` are asked to expand, at most three times each.
+    /// 4. `post_processors` run on the stitched content.
+    /// 5. The first choice is checked against `output_validation`. A failing
+    ///    reply is sent back with a corrective instruction and steps 2-4
+    ///    repeat until it passes or the retries run out.
+    ///
+    /// Usage and cost of every request are merged into the returned
+    /// response. `execute` ignores these options.
+    async fn execute_with_options(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        let mut attempt_conversation = with_length_hint(conversation, options.target_length_hint);
+        let Some(validation) = options.output_validation.clone() else {
+            return complete(self, &attempt_conversation, options).await;
+        };
+
+        // Usage and cost of the failed attempts so far.
+        let mut spent: Option<AdapterChatCompletion> = None;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let mut response = complete(self, &attempt_conversation, options).await?;
+            if let Some(previous) = spent.take() {
+                add_usage(&mut response, &previous);
+            }
+
+            let output = response
+                .choices
+                .first()
+                .and_then(|choice| choice.message.content.clone())
+                .unwrap_or_default();
+            let reason = match validation.validator.validate(&output) {
+                Ok(()) => return Ok(response),
+                Err(reason) => reason,
+            };
+            spent = Some(response);
+
+            if attempts > validation.max_retries {
+                return Err(AdapterError::OutputValidationFailed {
+                    attempts,
+                    reason,
+                    output,
+                });
+            }
+            attempt_conversation.add_turn(TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: output,
+                name: None,
+                metadata: None,
+            }));
+            attempt_conversation.add_turn(TurnType::Basic(Turn {
+                role: ConversationRole::User,
+                content: validation.correction(&reason),
+                name: None,
+                metadata: None,
+            }));
+        }
+    }
+}
+
+/// Adds the usage and cost of a follow-up request to `response`.
+fn add_usage(response: &mut AdapterChatCompletion, next: &AdapterChatCompletion) {
+    if let Some(usage) = &next.usage {
+        match &mut response.usage {
+            Some(total) => total.accumulate(usage),
+            None => response.usage = Some(usage.clone()),
+        }
+    }
+    response.cost += next.cost;
+    if let Some(breakdown) = &next.cost_breakdown {
+        response
+            .cost_breakdown
+            .get_or_insert_with(CostBreakdown::default)
+            .accumulate(breakdown);
+    }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// `conversation` with the length hint as a system turn after the leading
+/// system turns.
+fn with_length_hint(conversation: &Conversation, target: Option<u32>) -> Conversation {
+    let mut conversation = conversation.clone();
+    if let Some(target) = target {
+        let position = conversation
+            .turns
+            .iter()
+            .take_while(|turn| *turn.role() == ConversationRole::System)
+            .count();
+        conversation.turns.insert(
+            position,
+            TurnType::Basic(Turn {
+                role: ConversationRole::System,
+                content: format!(
+                    "Aim for a reply of about {} words.",
+                    (target * 3 / 4).max(1)
+                ),
+                name: None,
+                metadata: None,
+            }),
+        );
+    }
+    conversation
+}
+
+/// `conversation` followed by `partial` as the assistant's reply and
+/// `instruction` as the user's answer to it.
+fn follow_up(conversation: &Conversation, partial: &str, instruction: &str) -> Conversation {
+    let mut follow_up = conversation.clone();
+    follow_up.add_turn(TurnType::Basic(Turn {
+        role: ConversationRole::Assistant,
+        content: partial.to_string(),
+        name: None,
+        metadata: None,
+    }));
+    follow_up.add_turn(TurnType::Basic(Turn {
+        role: ConversationRole::User,
+        content: instruction.to_string(),
+        name: None,
+        metadata: None,
+    }));
+    follow_up
+}
+
+/// Steps 2-4 of `BaseAdapter::execute_with_options` for one request.
+async fn complete<A: BaseAdapter + ?Sized>(
+    adapter: &A,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+) -> Result<AdapterChatCompletion> {
+    let mut response = adapter.execute(conversation, options).await?;
+    continue_truncated(adapter, conversation, options, &mut response).await?;
+    expand_short(adapter, conversation, options, &mut response).await?;
+    response.post_process(options.post_processors.as_deref().unwrap_or_default());
+    Ok(response)
+}
+
+/// Re-prompts choices that stopped on `length` and stitches the segments
+/// together.
+async fn continue_truncated<A: BaseAdapter + ?Sized>(
+    adapter: &A,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+    response: &mut AdapterChatCompletion,
+) -> Result<()> {
+    let max_rounds = options.auto_continue.unwrap_or(0);
+    if max_rounds == 0 {
+        return Ok(());
+    }
+    let continue_options = ExecuteOptions {
+        auto_continue: None,
+        n: None,
+        ..options.clone()
+    };
+    let supports_prefill = adapter.get_model().capabilities.supports_prefill;
+
+    for index in 0..response.choices.len() {
+        for _ in 0..max_rounds {
+            let choice = &response.choices[index];
+            if !choice.is_truncated() {
+                break;
+            }
+
+            let partial = choice.message.content.clone().unwrap_or_default();
+            let continuation = if supports_prefill {
+                conversation.clone().with_assistant_prefill(partial.clone())
+            } else {
+                follow_up(conversation, &partial, CONTINUE_INSTRUCTION)
+            };
+            let next = adapter.execute(&continuation, &continue_options).await?;
+            add_usage(response, &next);
+
+            let Some(next_choice) = next.choices.into_iter().next() else {
+                break;
+            };
+            let segment = next_choice.message.content.unwrap_or_default();
+            let choice = &mut response.choices[index];
+            choice.message.content = Some(partial + &segment);
+            choice.finish_reason = next_choice.finish_reason;
+        }
+    }
+    Ok(())
+}
+
+/// Asks choices shorter than `options.min_tokens` to continue, for models
+/// without native `min_tokens`.
+async fn expand_short<A: BaseAdapter + ?Sized>(
+    adapter: &A,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+    response: &mut AdapterChatCompletion,
+) -> Result<()> {
+    let min_tokens = match options.min_tokens {
+        Some(min_tokens) if !adapter.get_model().capabilities.supports_min_tokens => min_tokens,
+        _ => return Ok(()),
+    };
+    let expand_options = ExecuteOptions {
+        n: None,
+        min_tokens: None,
+        ..options.clone()
+    };
+
+    for index in 0..response.choices.len() {
+        for _ in 0..MAX_EXPANSION_ROUNDS {
+            let message = &response.choices[index].message;
+            let partial = message.content.clone().unwrap_or_default();
+            if estimate_tokens(&partial) >= min_tokens || message.tool_calls.is_some() {
+                break;
+            }
+
+            let expansion = follow_up(conversation, &partial, EXPAND_INSTRUCTION);
+            let next = adapter.execute(&expansion, &expand_options).await?;
+            add_usage(response, &next);
+
+            let Some(next_choice) = next.choices.into_iter().next() else {
+                break;
+            };
+            let segment = next_choice.message.content.unwrap_or_default();
+            if segment.trim().is_empty() {
+                break;
+            }
+            let choice = &mut response.choices[index];
+            choice.message.content = Some(format!(
+                "{}\n\n{}",
+                partial.trim_end(),
+                segment.trim_start()
+            ));
+            choice.finish_reason = next_choice.finish_reason;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ExecuteOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f64>,
@@ -38,30 +407,382 @@ pub struct ExecuteOptions {
     pub tools: Option<Vec<serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<String>,
+    /// Provider-side tools, translated into each provider's native shape.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub builtin_tools: Option<Vec<BuiltinTool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<ResponseFormat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub n: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Processing tier, mapped to each provider's equivalent by the
+    /// provider quirks. The tier that served the request is reported in
+    /// `AdapterChatCompletion::service_tier`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+    /// Sent to providers that support it natively, emulated by
+    /// `execute_with_options` elsewhere.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_tokens: Option<u32>,
+    /// Expected content of the reply, e.g. the file being edited, for
+    /// providers with predicted outputs. Dropped with a warning elsewhere.
+    #[serde(skip)]
+    pub prediction: Option<String>,
+    /// Continuation rounds per truncated choice, see `execute_with_options`.
+    #[serde(skip)]
+    pub auto_continue: Option<u32>,
+    /// What `prepare_request` does when the conversation is too long.
+    #[serde(skip)]
+    pub context_overflow: Option<ContextOverflowPolicy>,
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+    /// Checked, with corrective retries, by `execute_with_options`.
+    #[serde(skip)]
+    pub output_validation: Option<OutputValidation>,
+    /// Applied to the replies of `execute_with_options`.
+    #[serde(skip)]
+    pub post_processors: Option<Vec<PostProcessor>>,
+    #[serde(skip)]
+    pub credentials: Option<ProviderCredentials>,
+    /// Compare reported usage with local estimates and warn when they
+    /// drift apart by more than this fraction, see `UsageDrift`.
+    #[serde(skip)]
+    pub usage_drift_threshold: Option<f64>,
+    /// Desired reply length in tokens, passed to the model as an
+    /// instruction by `execute_with_options`.
+    #[serde(skip)]
+    pub target_length_hint: Option<u32>,
+}
+
+/// Per-request overrides of the adapter's own credentials, for gateways that
+/// forward end-user keys. Unset fields keep the adapter's values.
+#[derive(Clone, Default, PartialEq)]
+pub struct ProviderCredentials {
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub org: Option<String>,
+    pub project: Option<String>,
+}
+
+impl std::fmt::Debug for ProviderCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderCredentials")
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("base_url", &self.base_url)
+            .field("org", &self.org)
+            .field("project", &self.project)
+            .finish()
+    }
+}
+
+impl ProviderCredentials {
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn with_org(mut self, org: impl Into<String>) -> Self {
+        self.org = Some(org.into());
+        self
+    }
+
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+}
+
+impl ExecuteOptions {
+    pub fn builder() -> ExecuteOptionsBuilder {
+        ExecuteOptionsBuilder::default()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(temperature) = self.temperature {
+            if !(0.0..=2.0).contains(&temperature) {
+                return Err(invalid_option(format!(
+                    "temperature must be between 0 and 2, got {}",
+                    temperature
+                )));
+            }
+        }
+        if let Some(top_p) = self.top_p {
+            if !(0.0..=1.0).contains(&top_p) {
+                return Err(invalid_option(format!(
+                    "top_p must be between 0 and 1, got {}",
+                    top_p
+                )));
+            }
+        }
+        if self.max_tokens == Some(0) {
+            return Err(invalid_option(
+                "max_tokens must be greater than 0".to_string(),
+            ));
+        }
+        if self.n == Some(0) {
+            return Err(invalid_option("n must be greater than 0".to_string()));
+        }
+        if let (Some(min_tokens), Some(max_tokens)) = (self.min_tokens, self.max_tokens) {
+            if min_tokens > max_tokens {
+                return Err(invalid_option(format!(
+                    "min_tokens ({}) must not exceed max_tokens ({})",
+                    min_tokens, max_tokens
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Layers these options over `defaults`: every field set here wins, unset
+    /// fields fall back to the default profile.
+    pub fn merge(self, defaults: &ExecuteOptions) -> ExecuteOptions {
+        let defaults = defaults.clone();
+        ExecuteOptions {
+            temperature: self.temperature.or(defaults.temperature),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            top_p: self.top_p.or(defaults.top_p),
+            tools: self.tools.or(defaults.tools),
+            tool_choice: self.tool_choice.or(defaults.tool_choice),
+            builtin_tools: self.builtin_tools.or(defaults.builtin_tools),
+            response_format: self.response_format.or(defaults.response_format),
+            n: self.n.or(defaults.n),
+            user: self.user.or(defaults.user),
+            seed: self.seed.or(defaults.seed),
+            service_tier: self.service_tier.or(defaults.service_tier),
+            min_tokens: self.min_tokens.or(defaults.min_tokens),
+            prediction: self.prediction.or(defaults.prediction),
+            auto_continue: self.auto_continue.or(defaults.auto_continue),
+            context_overflow: self.context_overflow.or(defaults.context_overflow),
+            idempotency_key: self.idempotency_key.or(defaults.idempotency_key),
+            output_validation: self.output_validation.or(defaults.output_validation),
+            post_processors: self.post_processors.or(defaults.post_processors),
+            credentials: self.credentials.or(defaults.credentials),
+            usage_drift_threshold: self
+                .usage_drift_threshold
+                .or(defaults.usage_drift_threshold),
+            target_length_hint: self.target_length_hint.or(defaults.target_length_hint),
+        }
+    }
+
+    /// Stable hash of the conversation together with the wire-level options,
+    /// suitable as a cache or dedup key. Client-side fields such as
+    /// `idempotency_key`, `credentials` and turn metadata are not part of the
+    /// hash.
+    pub fn content_hash(&self, conversation: &Conversation) -> String {
+        format!(
+            "{:016x}",
+            stable_hash(&(conversation.without_metadata(), self))
+        )
+    }
+
+    pub fn idempotency_header(&self) -> Option<(&'static str, &str)> {
+        self.idempotency_key
+            .as_deref()
+            .map(|key| ("Idempotency-Key", key))
+    }
+}
+
+fn invalid_option(message: String) -> AdapterError {
+    AdapterError::ConfigError(format!("invalid execute options: {}", message))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteOptionsBuilder {
+    options: ExecuteOptions,
+}
+
+impl ExecuteOptionsBuilder {
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.options.temperature = Some(temperature);
+        self
+    }
+
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.options.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.options.top_p = Some(top_p);
+        self
+    }
+
+    pub fn tools(mut self, tools: Vec<serde_json::Value>) -> Self {
+        self.options.tools = Some(tools);
+        self
+    }
+
+    pub fn tool_choice(mut self, tool_choice: impl Into<String>) -> Self {
+        self.options.tool_choice = Some(tool_choice.into());
+        self
+    }
+
+    pub fn builtin_tool(mut self, tool: BuiltinTool) -> Self {
+        self.options
+            .builtin_tools
+            .get_or_insert_with(Vec::new)
+            .push(tool);
+        self
+    }
+
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.options.response_format = Some(response_format);
+        self
+    }
+
+    pub fn n(mut self, n: u32) -> Self {
+        self.options.n = Some(n);
+        self
+    }
+
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.options.user = Some(user.into());
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.options.seed = Some(seed);
+        self
+    }
+
+    pub fn auto_continue(mut self, rounds: u32) -> Self {
+        self.options.auto_continue = Some(rounds);
+        self
+    }
+
+    pub fn context_overflow(mut self, policy: ContextOverflowPolicy) -> Self {
+        self.options.context_overflow = Some(policy);
+        self
+    }
+
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.options.idempotency_key = Some(key.into());
+        self
+    }
+
+    pub fn output_validation(mut self, validation: OutputValidation) -> Self {
+        self.options.output_validation = Some(validation);
+        self
+    }
+
+    pub fn post_processor(mut self, processor: PostProcessor) -> Self {
+        self.options
+            .post_processors
+            .get_or_insert_with(Vec::new)
+            .push(processor);
+        self
+    }
+
+    pub fn credentials(mut self, credentials: ProviderCredentials) -> Self {
+        self.options.credentials = Some(credentials);
+        self
+    }
+
+    pub fn usage_drift_threshold(mut self, threshold: f64) -> Self {
+        self.options.usage_drift_threshold = Some(threshold);
+        self
+    }
+
+    pub fn min_tokens(mut self, min_tokens: u32) -> Self {
+        self.options.min_tokens = Some(min_tokens);
+        self
+    }
+
+    pub fn service_tier(mut self, tier: ServiceTier) -> Self {
+        self.options.service_tier = Some(tier);
+        self
+    }
+
+    pub fn prediction(mut self, prediction: impl Into<String>) -> Self {
+        self.options.prediction = Some(prediction.into());
+        self
+    }
+
+    pub fn target_length_hint(mut self, tokens: u32) -> Self {
+        self.options.target_length_hint = Some(tokens);
+        self
+    }
+
+    pub fn build(self) -> Result<ExecuteOptions> {
+        self.options.validate()?;
+        Ok(self.options)
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ResponseFormat {
     #[serde(rename = "type")]
     pub format_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<JsonSchemaFormat>,
+}
+
+/// The schema of a `json_schema` response format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 impl ResponseFormat {
     pub fn json() -> Self {
         Self {
             format_type: "json_object".to_string(),
+            json_schema: None,
         }
     }
 
     pub fn text() -> Self {
         Self {
             format_type: "text".to_string(),
+            json_schema: None,
         }
     }
+
+    /// Structured output matching `schema`, enforced strictly.
+    pub fn json_schema(name: impl Into<String>, schema: serde_json::Value) -> Self {
+        Self {
+            format_type: "json_schema".to_string(),
+            json_schema: Some(JsonSchemaFormat {
+                name: name.into(),
+                schema,
+                strict: Some(true),
+            }),
+        }
+    }
+
+    /// A `max_tokens` that fits a reply matching the schema with headroom,
+    /// see `estimate_schema_tokens`. `None` without a schema or when the
+    /// schema does not bound the reply's length.
+    pub fn estimated_max_tokens(&self) -> Option<u32> {
+        let schema = &self.json_schema.as_ref()?.schema;
+        let tokens = estimate_schema_tokens(schema)?.saturating_mul(3) / 2;
+        Some(tokens.clamp(64, 16_384).next_multiple_of(64))
+    }
+
+    pub fn is_json(&self) -> bool {
+        matches!(self.format_type.as_str(), "json_object" | "json_schema")
+    }
+}
+
+/// OpenAI's `service_tier` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTier {
+    /// Whatever the project is configured for, priority capacity included.
+    Auto,
+    Default,
+    /// Cheaper, slower processing that may be queued.
+    Flex,
+    Priority,
 }
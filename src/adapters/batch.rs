@@ -0,0 +1,121 @@
+use crate::error::{AdapterError, ErrorClass, Result};
+use crate::models::{AdapterChatCompletion, TokenUsage};
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+
+/// Failures of one `ErrorClass` in a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureSummary {
+    pub count: usize,
+    pub indices: Vec<usize>,
+    /// The message of the first failure, as an example.
+    pub sample: String,
+}
+
+/// Per-item results of a batch, in input order.
+#[derive(Debug)]
+pub struct BatchOutcome<T> {
+    pub results: Vec<Result<T>>,
+}
+
+impl<T> BatchOutcome<T> {
+    pub fn new(results: Vec<Result<T>>) -> Self {
+        Self { results }
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn successes(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().ok().map(|value| (index, value)))
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &AdapterError)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.as_ref().err().map(|err| (index, err)))
+    }
+
+    pub fn failure_count(&self) -> usize {
+        self.failures().count()
+    }
+
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    pub fn failure_summary(&self) -> HashMap<ErrorClass, FailureSummary> {
+        let mut summary: HashMap<ErrorClass, FailureSummary> = HashMap::new();
+        for (index, err) in self.failures() {
+            let entry = summary
+                .entry(err.classification())
+                .or_insert_with(|| FailureSummary {
+                    count: 0,
+                    indices: Vec::new(),
+                    sample: err.to_string(),
+                });
+            entry.count += 1;
+            entry.indices.push(index);
+        }
+        summary
+    }
+
+    /// Runs `call` again for every item whose error is retryable, at most
+    /// `concurrency` at a time, replacing the failed results. Returns how
+    /// many items were retried.
+    pub async fn retry_failures<F, Fut>(&mut self, concurrency: usize, call: F) -> usize
+    where
+        F: Fn(usize) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let indices: Vec<usize> = self
+            .failures()
+            .filter(|(_, err)| err.is_retryable())
+            .map(|(index, _)| index)
+            .collect();
+        let retried: Vec<(usize, Result<T>)> = stream::iter(indices)
+            .map(|index| {
+                let attempt = call(index);
+                async move { (index, attempt.await) }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        let count = retried.len();
+        for (index, result) in retried {
+            self.results[index] = result;
+        }
+        count
+    }
+
+    pub fn into_results(self) -> Vec<Result<T>> {
+        self.results
+    }
+}
+
+impl BatchOutcome<AdapterChatCompletion> {
+    /// Usage summed over the successful items.
+    pub fn usage(&self) -> TokenUsage {
+        let mut total = TokenUsage::new(0, 0);
+        for (_, response) in self.successes() {
+            if let Some(usage) = &response.usage {
+                total.accumulate(usage);
+            }
+        }
+        total
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.successes().map(|(_, response)| response.cost).sum()
+    }
+}
@@ -0,0 +1,258 @@
+use crate::adapters::{AdapterFactory, ModelFilter};
+use crate::error::{AdapterError, Result};
+use crate::models::{AlternativeReason, CostTracker, Model, ModelGraph};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Where a tenant stands relative to its budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BudgetState {
+    #[default]
+    Normal,
+    /// Requests go to cheaper models.
+    Downgraded,
+    /// Requests are rejected.
+    Exhausted,
+}
+
+/// Sent to `BudgetRouter::on_change` when a tenant changes state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetEvent {
+    pub tenant: String,
+    pub from: BudgetState,
+    pub to: BudgetState,
+    /// The share of the budget spent, see `CostTracker::budget_used`.
+    pub used: f64,
+}
+
+type BudgetCallback = Arc<dyn Fn(&BudgetEvent) + Send + Sync>;
+
+/// Routes a tenant's requests by how much of its `CostTracker` budget is
+/// spent: to the requested model at first, to cheaper models from
+/// `AdapterFactory` once `downgrade_at` is reached, and nowhere once
+/// `reject_at` is. A tenant only moves back to a lower state when its
+/// spend falls `hysteresis` below the threshold, so routing does not flap
+/// around it. Tenants without a budget are never limited.
+pub struct BudgetRouter {
+    tracker: Arc<CostTracker>,
+    downgrade_at: f64,
+    reject_at: f64,
+    hysteresis: f64,
+    filter: Option<ModelFilter>,
+    states: Mutex<HashMap<String, BudgetState>>,
+    on_change: Option<BudgetCallback>,
+}
+
+impl std::fmt::Debug for BudgetRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BudgetRouter")
+            .field("downgrade_at", &self.downgrade_at)
+            .field("reject_at", &self.reject_at)
+            .field("hysteresis", &self.hysteresis)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BudgetRouter {
+    /// Downgrades at 80% of the budget and rejects at 100%, with 5%
+    /// hysteresis.
+    pub fn new(tracker: Arc<CostTracker>) -> Self {
+        Self {
+            tracker,
+            downgrade_at: 0.8,
+            reject_at: 1.0,
+            hysteresis: 0.05,
+            filter: None,
+            states: Mutex::new(HashMap::new()),
+            on_change: None,
+        }
+    }
+
+    pub fn with_thresholds(mut self, downgrade_at: f64, reject_at: f64) -> Self {
+        self.downgrade_at = downgrade_at;
+        self.reject_at = reject_at;
+        self
+    }
+
+    pub fn with_hysteresis(mut self, hysteresis: f64) -> Self {
+        self.hysteresis = hysteresis;
+        self
+    }
+
+    /// Restricts the models requests are downgraded to.
+    pub fn with_filter(mut self, filter: ModelFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn on_change<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&BudgetEvent) + Send + Sync + 'static,
+    {
+        self.on_change = Some(Arc::new(callback));
+        self
+    }
+
+    /// Updates and returns the state of `tenant` from its current spend.
+    pub fn state(&self, tenant: &str) -> BudgetState {
+        let Some(used) = self.tracker.budget_used(tenant) else {
+            return BudgetState::Normal;
+        };
+        let mut states = self.states.lock().unwrap();
+        let from = states.get(tenant).copied().unwrap_or_default();
+        let reached = |threshold: f64, state: BudgetState| {
+            used >= threshold || (from >= state && used >= threshold - self.hysteresis)
+        };
+        let to = if reached(self.reject_at, BudgetState::Exhausted) {
+            BudgetState::Exhausted
+        } else if reached(self.downgrade_at, BudgetState::Downgraded) {
+            BudgetState::Downgraded
+        } else {
+            BudgetState::Normal
+        };
+        states.insert(tenant.to_string(), to);
+        drop(states);
+        if let (true, Some(callback)) = (from != to, &self.on_change) {
+            callback(&BudgetEvent {
+                tenant: tenant.to_string(),
+                from,
+                to,
+                used,
+            });
+        }
+        to
+    }
+
+    /// Like `route`, with cheaper models taken from `candidates` instead of
+    /// the global catalog.
+    pub fn route_with(&self, tenant: &str, model: &Model, candidates: &[Model]) -> Result<Model> {
+        self.route_in(self.state(tenant), tenant, model, candidates)
+    }
+
+    /// Routes for an already computed `state`, so that a spend landing
+    /// between reading the state and fetching candidates cannot route a
+    /// downgraded tenant without any.
+    fn route_in(
+        &self,
+        state: BudgetState,
+        tenant: &str,
+        model: &Model,
+        candidates: &[Model],
+    ) -> Result<Model> {
+        match state {
+            BudgetState::Normal => Ok(model.clone()),
+            BudgetState::Exhausted => Err(AdapterError::BudgetExceeded {
+                limit: self.tracker.budget(tenant).unwrap_or_default(),
+                spent: self.tracker.spent_by(tenant),
+            }),
+            BudgetState::Downgraded => {
+                let cheaper: Vec<Model> = candidates
+                    .iter()
+                    .filter(|candidate| self.filter.as_ref().is_none_or(|f| f.matches(candidate)))
+                    .filter(|candidate| {
+                        candidate.cost.prompt < model.cost.prompt
+                            && candidate.cost.completion <= model.cost.completion
+                    })
+                    .cloned()
+                    .collect();
+                let mut models = cheaper.clone();
+                models.push(model.clone());
+                let graph = ModelGraph::infer(&models);
+                // A cheaper tier of the same line first, then the cheapest.
+                let downgrade = model
+                    .suggest_alternative(AlternativeReason::TooExpensive, &graph)
+                    .or_else(|| {
+                        cheaper
+                            .iter()
+                            .min_by(|a, b| a.cost.prompt.total_cmp(&b.cost.prompt))
+                    });
+                Ok(downgrade.unwrap_or(model).clone())
+            }
+        }
+    }
+
+    /// The model to send `tenant`'s request for `model` to, or
+    /// `AdapterError::BudgetExceeded`. A downgraded tenant keeps `model`
+    /// when nothing cheaper matches the filter.
+    pub async fn route(&self, tenant: &str, model: &Model) -> Result<Model> {
+        let state = self.state(tenant);
+        let candidates = match state {
+            BudgetState::Downgraded => {
+                AdapterFactory::get_supported_models(self.filter.clone()).await
+            }
+            _ => Vec::new(),
+        };
+        self.route_in(state, tenant, model, &candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CostRecord;
+
+    fn model(name: &str, prompt: f64) -> Model {
+        Model::test(name).with_cost(prompt, prompt * 4.0)
+    }
+
+    fn spend(tracker: &CostTracker, cost: f64) {
+        tracker.record_entry(CostRecord {
+            timestamp: 0,
+            model: "gpt-4o".to_string(),
+            tenant: Some("acme".to_string()),
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost,
+        });
+    }
+
+    #[test]
+    fn test_budget_routing() {
+        let tracker = Arc::new(CostTracker::new());
+        tracker.set_budget("acme", 10.0);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let router = BudgetRouter::new(tracker.clone())
+            .on_change(move |event| sink.lock().unwrap().push(event.to));
+        let gpt4o = model("gpt-4o", 2.5);
+        let candidates = [model("gpt-4o-mini", 0.15), model("gpt-3.5-turbo", 0.5)];
+
+        spend(&tracker, 5.0);
+        assert_eq!(
+            router.route_with("acme", &gpt4o, &candidates).unwrap().name,
+            "gpt-4o"
+        );
+        spend(&tracker, 3.5);
+        assert_eq!(
+            router.route_with("acme", &gpt4o, &candidates).unwrap().name,
+            "gpt-4o-mini"
+        );
+        assert_eq!(
+            router
+                .route_with("globex", &gpt4o, &candidates)
+                .unwrap()
+                .name,
+            "gpt-4o"
+        );
+
+        // A refund to 78% stays within the hysteresis band.
+        spend(&tracker, -0.7);
+        assert_eq!(router.state("acme"), BudgetState::Downgraded);
+        spend(&tracker, -1.0);
+        assert_eq!(router.state("acme"), BudgetState::Normal);
+
+        spend(&tracker, 3.5);
+        assert!(matches!(
+            router.route_with("acme", &gpt4o, &candidates),
+            Err(AdapterError::BudgetExceeded { limit, .. }) if limit == 10.0
+        ));
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                BudgetState::Downgraded,
+                BudgetState::Normal,
+                BudgetState::Exhausted
+            ]
+        );
+    }
+}
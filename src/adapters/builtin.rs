@@ -0,0 +1,288 @@
+use crate::error::{AdapterError, Result};
+use crate::models::ModelCapabilities;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// A tool that runs on the provider's side. Each variant is translated into
+/// the provider's native request shape by [`BuiltinTool::apply`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuiltinTool {
+    WebSearch {
+        #[serde(default)]
+        options: WebSearchOptions,
+    },
+    /// OpenAI's code interpreter, Anthropic's code execution tool or
+    /// Gemini's code execution. What ran ends up in
+    /// `Message::code_executions`.
+    CodeInterpreter {
+        #[serde(default)]
+        options: CodeInterpreterOptions,
+    },
+    /// Anthropic's `computer` tool or OpenAI's `computer_use_preview`.
+    /// Requested actions end up in `Message::computer_calls`; only models
+    /// with `supports_computer_use` accept it.
+    ComputerUse { options: ComputerUseOptions },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebSearchOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_uses: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_domains: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_location: Option<UserLocation>,
+    /// `low`, `medium` or `high`; only OpenAI honours it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_size: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodeInterpreterOptions {
+    /// Previously uploaded files to make available to the code; OpenAI only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComputerUseOptions {
+    pub display_width: u32,
+    pub display_height: u32,
+    /// `browser`, `mac`, `windows` or `linux`; OpenAI only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserLocation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub city: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+}
+
+impl BuiltinTool {
+    pub fn web_search() -> Self {
+        Self::WebSearch {
+            options: WebSearchOptions::default(),
+        }
+    }
+
+    pub fn web_search_with(options: WebSearchOptions) -> Self {
+        Self::WebSearch { options }
+    }
+
+    pub fn code_interpreter() -> Self {
+        Self::CodeInterpreter {
+            options: CodeInterpreterOptions::default(),
+        }
+    }
+
+    pub fn computer_use(display_width: u32, display_height: u32) -> Self {
+        Self::ComputerUse {
+            options: ComputerUseOptions {
+                display_width,
+                display_height,
+                environment: None,
+            },
+        }
+    }
+
+    pub fn is_supported_by(&self, capabilities: &ModelCapabilities) -> bool {
+        match self {
+            Self::ComputerUse { .. } => capabilities.supports_computer_use,
+            Self::WebSearch { .. } | Self::CodeInterpreter { .. } => true,
+        }
+    }
+
+    /// Adds the tool to a request `body` for `provider`. Options the
+    /// provider has no equivalent for are rejected rather than dropped.
+    pub fn apply(&self, provider: &str, model: &str, body: &mut Value) -> Result<()> {
+        match self {
+            Self::WebSearch { options } => apply_web_search(options, provider, model, body),
+            Self::CodeInterpreter { options } => {
+                apply_code_interpreter(options, provider, model, body)
+            }
+            Self::ComputerUse { options } => apply_computer_use(options, provider, model, body),
+        }
+    }
+}
+
+fn unsupported(tool: &str, feature: &str, provider: &str, model: &str) -> AdapterError {
+    AdapterError::UnsupportedFeature {
+        model: model.to_string(),
+        feature: format!("{} {} on {}", tool, feature, provider),
+    }
+}
+
+fn apply_code_interpreter(
+    options: &CodeInterpreterOptions,
+    provider: &str,
+    model: &str,
+    body: &mut Value,
+) -> Result<()> {
+    let tool = match provider {
+        "openai" | "azure" => json!({
+            "type": "code_interpreter",
+            "container": {"type": "auto", "file_ids": options.file_ids},
+        }),
+        _ if !options.file_ids.is_empty() => {
+            return Err(unsupported("code interpreter", "file_ids", provider, model))
+        }
+        "anthropic" => json!({"type": "code_execution_20250522", "name": "code_execution"}),
+        "gemini" | "google" | "vertex" => json!({"code_execution": {}}),
+        _ => return Err(unsupported("code interpreter", "tool", provider, model)),
+    };
+    push_tool(body, tool);
+    Ok(())
+}
+
+fn apply_computer_use(
+    options: &ComputerUseOptions,
+    provider: &str,
+    model: &str,
+    body: &mut Value,
+) -> Result<()> {
+    let tool = match provider {
+        "openai" | "azure" => json!({
+            "type": "computer_use_preview",
+            "display_width": options.display_width,
+            "display_height": options.display_height,
+            "environment": options.environment.as_deref().unwrap_or("browser"),
+        }),
+        "anthropic" => json!({
+            "type": "computer_20250124",
+            "name": "computer",
+            "display_width_px": options.display_width,
+            "display_height_px": options.display_height,
+        }),
+        _ => return Err(unsupported("computer use", "tool", provider, model)),
+    };
+    push_tool(body, tool);
+    Ok(())
+}
+
+fn apply_web_search(
+    options: &WebSearchOptions,
+    provider: &str,
+    model: &str,
+    body: &mut Value,
+) -> Result<()> {
+    let unsupported = |feature: &str| unsupported("web search", feature, provider, model);
+    match provider {
+        "openai" | "azure" => {
+            if !options.allowed_domains.is_empty() || !options.blocked_domains.is_empty() {
+                return Err(unsupported("domain filters"));
+            }
+            if options.max_uses.is_some() {
+                return Err(unsupported("max_uses"));
+            }
+            let mut search = Map::new();
+            if let Some(size) = &options.context_size {
+                search.insert("search_context_size".to_string(), json!(size));
+            }
+            if let Some(location) = &options.user_location {
+                search.insert(
+                    "user_location".to_string(),
+                    json!({"type": "approximate", "approximate": location}),
+                );
+            }
+            object(body).insert("web_search_options".to_string(), Value::Object(search));
+        }
+        "anthropic" => {
+            let mut tool = json!({"type": "web_search_20250305", "name": "web_search"});
+            if let Some(max_uses) = options.max_uses {
+                tool["max_uses"] = json!(max_uses);
+            }
+            if !options.allowed_domains.is_empty() {
+                tool["allowed_domains"] = json!(options.allowed_domains);
+            }
+            if !options.blocked_domains.is_empty() {
+                tool["blocked_domains"] = json!(options.blocked_domains);
+            }
+            if let Some(location) = &options.user_location {
+                tool["user_location"] = json!(location);
+                tool["user_location"]["type"] = json!("approximate");
+            }
+            push_tool(body, tool);
+        }
+        "gemini" | "google" | "vertex" => {
+            if !options.allowed_domains.is_empty() || !options.blocked_domains.is_empty() {
+                return Err(unsupported("domain filters"));
+            }
+            push_tool(body, json!({"google_search": {}}));
+        }
+        _ => return Err(unsupported("tool")),
+    }
+    Ok(())
+}
+
+fn object(body: &mut Value) -> &mut Map<String, Value> {
+    if !body.is_object() {
+        *body = Value::Object(Map::new());
+    }
+    body.as_object_mut().expect("body is an object")
+}
+
+fn push_tool(body: &mut Value, tool: Value) {
+    let tools = object(body)
+        .entry("tools")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    if !tools.is_array() {
+        *tools = Value::Array(Vec::new());
+    }
+    if let Value::Array(tools) = tools {
+        tools.push(tool);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_tools_map_to_native_tools() {
+        let tool = BuiltinTool::web_search_with(WebSearchOptions {
+            allowed_domains: vec!["docs.rs".to_string()],
+            ..Default::default()
+        });
+
+        let mut body = json!({"tools": [{"type": "function"}]});
+        tool.apply("anthropic", "claude", &mut body).unwrap();
+        assert_eq!(body["tools"][1]["type"], "web_search_20250305");
+        assert_eq!(body["tools"][1]["allowed_domains"][0], "docs.rs");
+
+        let mut body = json!({});
+        assert!(tool.apply("openai", "gpt-4o", &mut body).is_err());
+        BuiltinTool::web_search()
+            .apply("openai", "gpt-4o", &mut body)
+            .unwrap();
+        assert_eq!(body["web_search_options"], json!({}));
+
+        let mut body = json!({});
+        BuiltinTool::web_search()
+            .apply("gemini", "gemini-2.0-flash", &mut body)
+            .unwrap();
+        assert_eq!(body["tools"][0], json!({"google_search": {}}));
+
+        BuiltinTool::code_interpreter()
+            .apply("gemini", "gemini-2.0-flash", &mut body)
+            .unwrap();
+        assert_eq!(body["tools"][1], json!({"code_execution": {}}));
+        let files = BuiltinTool::CodeInterpreter {
+            options: CodeInterpreterOptions {
+                file_ids: vec!["file-1".to_string()],
+            },
+        };
+        assert!(files
+            .apply("gemini", "gemini-2.0-flash", &mut body)
+            .is_err());
+    }
+}
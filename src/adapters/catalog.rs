@@ -0,0 +1,158 @@
+use crate::error::{AdapterError, Result};
+use crate::models::{Cost, Model};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const CATALOG_SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatalogSnapshot {
+    pub version: u32,
+    pub exported_at: u64,
+    pub models: Vec<Model>,
+}
+
+impl CatalogSnapshot {
+    pub fn new(mut models: Vec<Model>) -> Self {
+        models.sort_by_key(Model::get_path);
+        Self {
+            version: CATALOG_SNAPSHOT_VERSION,
+            exported_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+            models,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let snapshot: Self = serde_json::from_str(json)?;
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.version != CATALOG_SNAPSHOT_VERSION {
+            return Err(AdapterError::ConfigError(format!(
+                "unsupported catalog snapshot version {} (expected {})",
+                self.version, CATALOG_SNAPSHOT_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CatalogDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub price_changes: Vec<PriceChange>,
+    pub capability_changes: Vec<CapabilityChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PriceChange {
+    pub model: String,
+    pub old: Cost,
+    pub new: Cost,
+}
+
+impl PriceChange {
+    pub fn is_increase(&self) -> bool {
+        self.new.prompt > self.old.prompt || self.new.completion > self.old.completion
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapabilityChange {
+    pub model: String,
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+}
+
+impl CatalogDiff {
+    pub fn between(old: &HashMap<String, Model>, new: &HashMap<String, Model>) -> Self {
+        let mut diff = CatalogDiff::default();
+
+        for (path, model) in new {
+            let Some(previous) = old.get(path) else {
+                diff.added.push(path.clone());
+                continue;
+            };
+
+            if cost_changed(&previous.cost, &model.cost) {
+                diff.price_changes.push(PriceChange {
+                    model: path.clone(),
+                    old: previous.cost,
+                    new: model.cost,
+                });
+            }
+
+            if let Some(change) = capability_change(path, previous, model) {
+                diff.capability_changes.push(change);
+            }
+        }
+        diff.removed = old
+            .keys()
+            .filter(|path| !new.contains_key(*path))
+            .cloned()
+            .collect();
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.price_changes.sort_by(|a, b| a.model.cmp(&b.model));
+        diff.capability_changes
+            .sort_by(|a, b| a.model.cmp(&b.model));
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.price_changes.is_empty()
+            && self.capability_changes.is_empty()
+    }
+}
+
+fn cost_changed(old: &Cost, new: &Cost) -> bool {
+    old.prompt != new.prompt
+        || old.completion != new.completion
+        || old.request != new.request
+        || old.cache_read != new.cache_read
+        || old.cache_write != new.cache_write
+}
+
+fn capability_change(path: &str, old: &Model, new: &Model) -> Option<CapabilityChange> {
+    let old_flags = serde_json::to_value(&old.capabilities).ok()?;
+    let new_flags = serde_json::to_value(&new.capabilities).ok()?;
+    let (old_flags, new_flags) = (old_flags.as_object()?, new_flags.as_object()?);
+
+    let mut enabled = Vec::new();
+    let mut disabled = Vec::new();
+    for (name, value) in new_flags {
+        if old_flags.get(name) == Some(value) {
+            continue;
+        }
+        match value.as_bool() {
+            Some(true) => enabled.push(name.clone()),
+            Some(false) => disabled.push(name.clone()),
+            None => {}
+        }
+    }
+
+    if enabled.is_empty() && disabled.is_empty() {
+        return None;
+    }
+    enabled.sort();
+    disabled.sort();
+    Some(CapabilityChange {
+        model: path.to_string(),
+        enabled,
+        disabled,
+    })
+}
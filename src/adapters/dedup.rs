@@ -0,0 +1,101 @@
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{AdapterChatCompletion, Conversation, Model};
+use async_trait::async_trait;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::sync::Arc;
+
+type SharedOutcome = std::result::Result<AdapterChatCompletion, Arc<AdapterError>>;
+type InflightRequest = Shared<BoxFuture<'static, SharedOutcome>>;
+
+/// Wraps an adapter so that concurrent `execute` calls carrying the same
+/// `idempotency_key` share a single upstream request. Calls without a key and
+/// streaming calls are passed through untouched.
+pub struct Deduplicated<A> {
+    inner: Arc<A>,
+    inflight: Arc<DashMap<String, InflightRequest>>,
+}
+
+impl<A: BaseAdapter + 'static> Deduplicated<A> {
+    pub fn new(adapter: A) -> Self {
+        Self::from_arc(Arc::new(adapter))
+    }
+
+    pub fn from_arc(adapter: Arc<A>) -> Self {
+        Self {
+            inner: adapter,
+            inflight: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inflight.len()
+    }
+}
+
+#[async_trait]
+impl<A: BaseAdapter + 'static> BaseAdapter for Deduplicated<A> {
+    fn get_model(&self) -> &Model {
+        self.inner.get_model()
+    }
+
+    fn set_api_key(&mut self, api_key: String) -> Result<()> {
+        Arc::get_mut(&mut self.inner)
+            .ok_or_else(|| {
+                AdapterError::ConfigError(
+                    "cannot change the API key of a shared adapter".to_string(),
+                )
+            })?
+            .set_api_key(api_key)
+    }
+
+    async fn execute(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        let Some(key) = options.idempotency_key.clone() else {
+            return self.inner.execute(conversation, options).await;
+        };
+
+        let request = match self.inflight.entry(key.clone()) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                let inner = self.inner.clone();
+                let conversation = conversation.clone();
+                let options = options.clone();
+                let request = async move {
+                    inner
+                        .execute(&conversation, &options)
+                        .await
+                        .map_err(Arc::new)
+                }
+                .boxed()
+                .shared();
+                entry.insert(request.clone());
+                request
+            }
+        };
+
+        let outcome = request.clone().await;
+        self.inflight
+            .remove_if(&key, |_, pending| pending.ptr_eq(&request));
+        drop(request);
+
+        outcome.map_err(|err| Arc::try_unwrap(err).unwrap_or_else(AdapterError::Shared))
+    }
+
+    async fn execute_stream(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterStream> {
+        self.inner.execute_stream(conversation, options).await
+    }
+}
@@ -0,0 +1,368 @@
+use crate::adapters::AdapterStream;
+use crate::error::Result;
+use crate::models::{AdapterChatCompletionChunk, FunctionCall, ToolCall, ToolCallDelta};
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::{BTreeMap, VecDeque};
+use std::pin::Pin;
+
+pub type StreamEventStream = Pin<Box<dyn Stream<Item = Result<StreamEvent>> + Send>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    ContentDelta {
+        choice_index: u32,
+        content: String,
+    },
+    ToolCallStarted {
+        choice_index: u32,
+        call_index: u32,
+        id: String,
+        name: String,
+    },
+    ToolCallArgumentsDelta {
+        choice_index: u32,
+        call_index: u32,
+        arguments: String,
+    },
+    ToolCallCompleted(ToolCall),
+    Finished(FinishSummary),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FinishSummary {
+    pub id: String,
+    pub model: String,
+    /// Full text per choice index.
+    pub content: BTreeMap<u32, String>,
+    pub finish_reasons: BTreeMap<u32, String>,
+    pub tool_calls: Vec<ToolCall>,
+    /// The same outcome broken down by choice index, for `n > 1` streams
+    /// whose chunks interleave choices.
+    pub choices: BTreeMap<u32, ChoiceSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChoiceSummary {
+    pub finish_reason: Option<String>,
+    /// Length of the choice's text in characters.
+    pub content_length: usize,
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl FinishSummary {
+    pub fn choice(&self, index: u32) -> Option<&ChoiceSummary> {
+        self.choices.get(&index)
+    }
+
+    /// Full text of the choice at `index`, empty when it produced none.
+    pub fn content_of(&self, index: u32) -> &str {
+        self.content.get(&index).map_or("", String::as_str)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingCall {
+    id: Option<String>,
+    call_type: Option<String>,
+    name: Option<String>,
+    arguments: String,
+    started: bool,
+}
+
+#[derive(Debug, Default)]
+struct EventAssembler {
+    calls: BTreeMap<(u32, u32), PendingCall>,
+    summary: FinishSummary,
+}
+
+impl EventAssembler {
+    fn accept(&mut self, chunk: AdapterChatCompletionChunk, events: &mut Vec<StreamEvent>) {
+        if self.summary.id.is_empty() {
+            self.summary.id = chunk.id;
+            self.summary.model = chunk.model;
+        }
+
+        for choice in chunk.choices {
+            let choice_index = choice.index;
+            self.summary.choices.entry(choice_index).or_default();
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                self.summary
+                    .content
+                    .entry(choice_index)
+                    .or_default()
+                    .push_str(&content);
+                self.choice(choice_index).content_length += content.chars().count();
+                events.push(StreamEvent::ContentDelta {
+                    choice_index,
+                    content,
+                });
+            }
+
+            for delta in choice.delta.tool_calls.unwrap_or_default() {
+                self.accept_tool_delta(choice_index, delta, events);
+            }
+
+            if let Some(reason) = choice.finish_reason {
+                self.complete_calls(|(choice, _)| choice == choice_index, events);
+                self.choice(choice_index).finish_reason = Some(reason.clone());
+                self.summary.finish_reasons.insert(choice_index, reason);
+            }
+        }
+    }
+
+    fn choice(&mut self, choice_index: u32) -> &mut ChoiceSummary {
+        self.summary.choices.entry(choice_index).or_default()
+    }
+
+    fn accept_tool_delta(
+        &mut self,
+        choice_index: u32,
+        delta: ToolCallDelta,
+        events: &mut Vec<StreamEvent>,
+    ) {
+        let call_index = delta.index;
+        // Providers stream calls one after another, so a new index means the
+        // previous calls of this choice are done.
+        if !self.calls.contains_key(&(choice_index, call_index)) {
+            self.complete_calls(
+                |(choice, index)| choice == choice_index && index < call_index,
+                events,
+            );
+        }
+
+        let call = self.calls.entry((choice_index, call_index)).or_default();
+        if delta.id.is_some() {
+            call.id = delta.id;
+        }
+        if delta.call_type.is_some() {
+            call.call_type = delta.call_type;
+        }
+        let function = delta.function.unwrap_or_default();
+        if let Some(name) = function.name {
+            call.name.get_or_insert_with(String::new).push_str(&name);
+        }
+
+        if !call.started {
+            if let (Some(id), Some(name)) = (&call.id, &call.name) {
+                call.started = true;
+                events.push(StreamEvent::ToolCallStarted {
+                    choice_index,
+                    call_index,
+                    id: id.clone(),
+                    name: name.clone(),
+                });
+            }
+        }
+
+        if let Some(arguments) = function.arguments.filter(|a| !a.is_empty()) {
+            call.arguments.push_str(&arguments);
+            events.push(StreamEvent::ToolCallArgumentsDelta {
+                choice_index,
+                call_index,
+                arguments,
+            });
+        }
+    }
+
+    fn complete_calls(
+        &mut self,
+        matches: impl Fn((u32, u32)) -> bool,
+        events: &mut Vec<StreamEvent>,
+    ) {
+        let keys: Vec<_> = self.calls.keys().copied().filter(|k| matches(*k)).collect();
+        for key in keys {
+            let Some(call) = self.calls.remove(&key) else {
+                continue;
+            };
+            let tool_call = ToolCall {
+                id: call.id.unwrap_or_default(),
+                call_type: call.call_type.unwrap_or_else(|| "function".to_string()),
+                function: FunctionCall {
+                    name: call.name.unwrap_or_default(),
+                    arguments: call.arguments,
+                },
+            };
+            self.summary.tool_calls.push(tool_call.clone());
+            self.choice(key.0).tool_calls.push(tool_call.clone());
+            events.push(StreamEvent::ToolCallCompleted(tool_call));
+        }
+    }
+
+    fn finish(&mut self, events: &mut Vec<StreamEvent>) {
+        self.complete_calls(|_| true, events);
+        events.push(StreamEvent::Finished(std::mem::take(&mut self.summary)));
+    }
+}
+
+/// Turns raw chunks into content and tool-call lifecycle events. Tool call
+/// arguments are reassembled across deltas, and `Finished` is emitted once
+/// the underlying stream ends. An error ends the event stream.
+pub fn stream_events(stream: AdapterStream) -> StreamEventStream {
+    struct State {
+        inner: AdapterStream,
+        assembler: EventAssembler,
+        pending: VecDeque<StreamEvent>,
+        done: bool,
+    }
+
+    let state = State {
+        inner: stream,
+        assembler: EventAssembler::default(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((Ok(event), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            let mut events = Vec::new();
+            match state.inner.next().await {
+                Some(Ok(chunk)) => state.assembler.accept(chunk, &mut events),
+                Some(Err(err)) => {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+                None => {
+                    state.done = true;
+                    state.assembler.finish(&mut events);
+                }
+            }
+            state.pending.extend(events);
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ChunkChoice, Delta, FunctionCallDelta};
+
+    fn chunk(delta: Delta, finish_reason: Option<&str>) -> AdapterChatCompletionChunk {
+        AdapterChatCompletionChunk {
+            id: "c1".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1,
+            model: "gpt-4o".to_string(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta,
+                finish_reason: finish_reason.map(str::to_string),
+            }],
+        }
+    }
+
+    fn tool_delta(index: u32, id: Option<&str>, name: Option<&str>, arguments: &str) -> Delta {
+        Delta {
+            role: None,
+            content: None,
+            tool_calls: Some(vec![ToolCallDelta {
+                index,
+                id: id.map(str::to_string),
+                call_type: id.map(|_| "function".to_string()),
+                function: Some(FunctionCallDelta {
+                    name: name.map(str::to_string),
+                    arguments: Some(arguments.to_string()),
+                }),
+            }]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_lifecycle() {
+        let chunks = vec![
+            chunk(
+                Delta {
+                    role: None,
+                    content: Some("Checking".to_string()),
+                    tool_calls: None,
+                },
+                None,
+            ),
+            chunk(tool_delta(0, Some("call_1"), Some("weather"), ""), None),
+            chunk(tool_delta(0, None, None, "{\"city\":"), None),
+            chunk(tool_delta(0, None, None, "\"Oslo\"}"), None),
+            chunk(tool_delta(1, Some("call_2"), Some("time"), "{}"), None),
+            chunk(
+                Delta {
+                    role: None,
+                    content: None,
+                    tool_calls: None,
+                },
+                Some("tool_calls"),
+            ),
+        ];
+        let raw: AdapterStream = Box::pin(stream::iter(chunks.into_iter().map(Ok)));
+        let events: Vec<_> = stream_events(raw)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+
+        let completed: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                StreamEvent::ToolCallCompleted(call) => Some(call.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(completed.len(), 2);
+        assert_eq!(completed[0].id, "call_1");
+        assert_eq!(completed[0].function.arguments, "{\"city\":\"Oslo\"}");
+        assert_eq!(completed[1].function.name, "time");
+
+        assert!(matches!(
+            &events[1],
+            StreamEvent::ToolCallStarted { id, name, .. } if id == "call_1" && name == "weather"
+        ));
+        let StreamEvent::Finished(summary) = events.last().unwrap() else {
+            panic!("stream did not finish");
+        };
+        assert_eq!(summary.content[&0], "Checking");
+        assert_eq!(summary.finish_reasons[&0], "tool_calls");
+        assert_eq!(summary.tool_calls, completed);
+        assert_eq!(summary.choice(0).unwrap().tool_calls, completed);
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_choices_are_summarized_separately() {
+        let text = |content: &str| Delta {
+            role: None,
+            content: Some(content.to_string()),
+            tool_calls: None,
+        };
+        let mut chunks = vec![
+            chunk(text("Hello"), None),
+            chunk(tool_delta(0, Some("call_1"), Some("search"), "{}"), None),
+            chunk(text(" world"), Some("stop")),
+            chunk(text(""), Some("tool_calls")),
+        ];
+        chunks[1].choices[0].index = 1;
+        chunks[3].choices[0].index = 1;
+
+        let raw: AdapterStream = Box::pin(stream::iter(chunks.into_iter().map(Ok)));
+        let events: Vec<_> = stream_events(raw)
+            .map(|event| event.unwrap())
+            .collect()
+            .await;
+        let StreamEvent::Finished(summary) = events.last().unwrap() else {
+            panic!("stream did not finish");
+        };
+
+        let first = summary.choice(0).unwrap();
+        assert_eq!(first.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(first.content_length, 11);
+        assert!(first.tool_calls.is_empty());
+        assert_eq!(summary.content_of(0), "Hello world");
+
+        let second = summary.choice(1).unwrap();
+        assert_eq!(second.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(second.content_length, 0);
+        assert_eq!(second.tool_calls[0].id, "call_1");
+        assert_eq!(summary.content_of(1), "");
+    }
+}
@@ -1,16 +1,25 @@
-use crate::config::{ProviderDefaults, VendorMappings};
+use crate::adapters::catalog::{CatalogDiff, CatalogSnapshot};
+use crate::adapters::{select_model, SelectionConstraints};
+use crate::config::{reload_config, ModelLanguages, ProviderDefaults, VendorMappings};
 use crate::error::{AdapterError, Result};
-use crate::models::{Cost, Model, ModelProperties, ModelsDevResponse};
+use crate::models::{
+    CapabilityMatrix, Conversation, Cost, Model, ModelProperties, ModelsDevResponse,
+    OpenAIModelsList, RateLimitInfo,
+};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 pub struct AdapterFactory {
     models: HashMap<String, Model>,
+    limits: HashMap<String, RateLimitInfo>,
 }
 
 static FACTORY: Lazy<RwLock<AdapterFactory>> = Lazy::new(|| RwLock::new(AdapterFactory::new()));
 
+static CATALOG_CHANGES: Lazy<broadcast::Sender<CatalogDiff>> =
+    Lazy::new(|| broadcast::channel(16).0);
+
 impl Default for AdapterFactory {
     fn default() -> Self {
         Self::new()
@@ -21,16 +30,70 @@ impl AdapterFactory {
     pub fn new() -> Self {
         Self {
             models: HashMap::new(),
+            limits: HashMap::new(),
         }
     }
 
+    /// Loads and validates the provider config files, so that a broken
+    /// `ADAPTERS_CONFIG_DIR` override is reported at startup rather than
+    /// silently replaced by the built-in copies. Validation problems come
+    /// back as `AdapterError::InvalidConfig`.
+    pub fn init_config() -> Result<()> {
+        reload_config()
+    }
+
     pub async fn init_from_modelsdev() -> Result<()> {
+        Self::init_config()?;
         let response = Self::fetch_modelsdev_api().await?;
         let mut factory = FACTORY.write().await;
         factory.populate_from_modelsdev(response)?;
         Ok(())
     }
 
+    pub fn subscribe_catalog_changes() -> broadcast::Receiver<CatalogDiff> {
+        CATALOG_CHANGES.subscribe()
+    }
+
+    /// Swaps the global catalog for `models`, publishing the differences to
+    /// catalog subscribers. Returns the computed diff.
+    pub async fn replace_catalog(models: Vec<Model>) -> CatalogDiff {
+        let models = models
+            .into_iter()
+            .map(|model| (model.get_path(), model))
+            .collect();
+        let mut factory = FACTORY.write().await;
+        factory.swap_models(models)
+    }
+
+    /// Adds or replaces a single model, publishing the change like a refresh.
+    pub async fn register_model(model: Model) -> CatalogDiff {
+        let mut factory = FACTORY.write().await;
+        let mut models = factory.models.clone();
+        models.insert(model.get_path(), model);
+        factory.swap_models(models)
+    }
+
+    pub async fn export_catalog() -> CatalogSnapshot {
+        let factory = FACTORY.read().await;
+        CatalogSnapshot::new(factory.models.values().cloned().collect())
+    }
+
+    /// Replaces the catalog with a previously exported snapshot instead of
+    /// fetching models.dev.
+    pub async fn import_catalog(snapshot: CatalogSnapshot) -> Result<CatalogDiff> {
+        snapshot.validate()?;
+        Ok(Self::replace_catalog(snapshot.models).await)
+    }
+
+    fn swap_models(&mut self, models: HashMap<String, Model>) -> CatalogDiff {
+        let diff = CatalogDiff::between(&self.models, &models);
+        self.models = models;
+        if !diff.is_empty() {
+            let _ = CATALOG_CHANGES.send(diff.clone());
+        }
+        diff
+    }
+
     async fn fetch_modelsdev_api() -> Result<ModelsDevResponse> {
         let client = reqwest::Client::new();
         let response = client
@@ -43,14 +106,16 @@ impl AdapterFactory {
     }
 
     fn populate_from_modelsdev(&mut self, response: ModelsDevResponse) -> Result<()> {
+        let mut models = HashMap::new();
         for (provider_id, provider) in response.providers {
             for (model_id, model_info) in provider.models {
                 let model = self.convert_modelsdev_model(&provider_id, &model_id, &model_info)?;
 
                 let path = model.get_path();
-                self.models.insert(path, model);
+                models.insert(path, model);
             }
         }
+        self.swap_models(models);
         Ok(())
     }
 
@@ -66,11 +131,15 @@ impl AdapterFactory {
 
         let mut capabilities = defaults.capabilities.clone();
         capabilities.supports_vision = model_info.modalities.input.contains(&"image".to_string());
+        capabilities.supports_video = model_info.modalities.input.contains(&"video".to_string());
         capabilities.supports_tools = model_info.tool_call;
         capabilities.supports_temperature = model_info.temperature;
 
         let cost = if let Some(cost_info) = &model_info.cost {
-            Cost::from_modelsdev(cost_info.input, cost_info.output)
+            Cost::from_modelsdev(cost_info.input, cost_info.output).with_cache_pricing(
+                cost_info.cache_read.map(|price| price / 1_000_000.0),
+                cost_info.cache_write.map(|price| price / 1_000_000.0),
+            )
         } else {
             Cost::default()
         };
@@ -120,6 +189,43 @@ impl AdapterFactory {
             .collect()
     }
 
+    /// Capabilities and costs of the catalog models matching `filter`, see
+    /// `CapabilityMatrix::render` for a table.
+    pub async fn capability_matrix(filter: Option<ModelFilter>) -> CapabilityMatrix {
+        CapabilityMatrix::new(&Self::get_supported_models(filter).await)
+    }
+
+    /// Picks a catalog model for `conversation`: one with vision or video
+    /// when it has images or videos, enough context for its length, tools
+    /// and JSON output when needed and within the cost ceiling, ranked by
+    /// `constraints.scorer`.
+    pub async fn auto_select(
+        conversation: &Conversation,
+        constraints: &SelectionConstraints,
+    ) -> Result<Model> {
+        select_model(
+            &Self::get_supported_models(None).await,
+            conversation,
+            constraints,
+        )
+    }
+
+    /// The catalog as an OpenAI `/v1/models` response, for clients that
+    /// discover models through the OpenAI SDK.
+    pub async fn to_openai_models_list() -> OpenAIModelsList {
+        OpenAIModelsList::new(&Self::get_supported_models(None).await)
+    }
+
+    pub async fn record_limits(provider: &str, limits: RateLimitInfo) {
+        let mut factory = FACTORY.write().await;
+        factory.limits.insert(provider.to_string(), limits);
+    }
+
+    pub async fn current_limits(provider: &str) -> Option<RateLimitInfo> {
+        let factory = FACTORY.read().await;
+        factory.limits.get(provider).cloned()
+    }
+
     pub async fn list_providers() -> Vec<String> {
         let factory = FACTORY.read().await;
         let mut providers: Vec<String> = factory
@@ -137,9 +243,13 @@ impl AdapterFactory {
 pub struct ModelFilter {
     pub supports_streaming: Option<bool>,
     pub supports_vision: Option<bool>,
+    pub supports_video: Option<bool>,
     pub supports_tools: Option<bool>,
     pub supports_temperature: Option<bool>,
     pub provider: Option<String>,
+    pub nsfw: Option<bool>,
+    /// ISO 639-1 code, see `ModelLanguages`.
+    pub language: Option<String>,
 }
 
 impl ModelFilter {
@@ -158,6 +268,11 @@ impl ModelFilter {
         self
     }
 
+    pub fn with_video(mut self, value: bool) -> Self {
+        self.supports_video = Some(value);
+        self
+    }
+
     pub fn with_tools(mut self, value: bool) -> Self {
         self.supports_tools = Some(value);
         self
@@ -168,7 +283,21 @@ impl ModelFilter {
         self
     }
 
-    fn matches(&self, model: &Model) -> bool {
+    /// `true` keeps only NSFW-allowed models, for requests `check_policy`
+    /// would reject elsewhere.
+    pub fn with_nsfw(mut self, value: bool) -> Self {
+        self.nsfw = Some(value);
+        self
+    }
+
+    /// Keeps models listed as strong in `language`, e.g. the result of
+    /// `conversation_language`.
+    pub fn for_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub(crate) fn matches(&self, model: &Model) -> bool {
         if let Some(streaming) = self.supports_streaming {
             if model.capabilities.supports_streaming != streaming {
                 return false;
@@ -179,6 +308,11 @@ impl ModelFilter {
                 return false;
             }
         }
+        if let Some(video) = self.supports_video {
+            if model.capabilities.supports_video != video {
+                return false;
+            }
+        }
         if let Some(tools) = self.supports_tools {
             if model.capabilities.supports_tools != tools {
                 return false;
@@ -194,6 +328,16 @@ impl ModelFilter {
                 return false;
             }
         }
+        if let Some(nsfw) = self.nsfw {
+            if model.properties.is_nsfw != nsfw {
+                return false;
+            }
+        }
+        if let Some(ref language) = self.language {
+            if !ModelLanguages::is_strong_in(&model.name, language) {
+                return false;
+            }
+        }
         true
     }
 }
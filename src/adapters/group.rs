@@ -0,0 +1,244 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{AdapterChatCompletion, Conversation};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+#[derive(Debug)]
+pub struct GroupOutcome {
+    /// Position returned by `spawn`.
+    pub index: usize,
+    pub model: String,
+    pub result: Result<AdapterChatCompletion>,
+}
+
+#[derive(Debug, Default)]
+pub struct GroupResults {
+    /// Finished requests in completion order.
+    pub completed: Vec<GroupOutcome>,
+    /// Requests aborted by `cancel` or because the budget ran out.
+    pub cancelled: Vec<usize>,
+    pub spent: f64,
+}
+
+impl GroupResults {
+    pub fn successes(&self) -> impl Iterator<Item = (&GroupOutcome, &AdapterChatCompletion)> {
+        self.completed
+            .iter()
+            .filter_map(|outcome| outcome.result.as_ref().ok().map(|r| (outcome, r)))
+    }
+}
+
+/// Owns a set of in-flight requests. Dropping the group aborts whatever is
+/// still running, so requests never outlive the scope that started them.
+pub struct ExecutionGroup {
+    tasks: JoinSet<GroupOutcome>,
+    pending: Vec<usize>,
+    next_index: usize,
+    budget: Option<f64>,
+    spent: f64,
+}
+
+impl Default for ExecutionGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionGroup {
+    pub fn new() -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            pending: Vec::new(),
+            next_index: 0,
+            budget: None,
+            spent: 0.0,
+        }
+    }
+
+    /// Caps the total cost of the group's responses. Once the responses
+    /// received so far reach the cap, the remaining requests are cancelled
+    /// and `spawn` is refused.
+    pub fn with_budget(mut self, max_cost: f64) -> Self {
+        self.budget = Some(max_cost);
+        self
+    }
+
+    pub fn spent(&self) -> f64 {
+        self.spent
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn budget_exhausted(&self) -> bool {
+        self.budget.is_some_and(|budget| self.spent >= budget)
+    }
+
+    pub fn spawn(
+        &mut self,
+        adapter: Arc<dyn BaseAdapter>,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<usize> {
+        if let Some(limit) = self.budget.filter(|_| self.budget_exhausted()) {
+            return Err(AdapterError::BudgetExceeded {
+                limit,
+                spent: self.spent,
+            });
+        }
+
+        let index = self.next_index;
+        self.next_index += 1;
+        self.pending.push(index);
+
+        let conversation = conversation.clone();
+        let options = options.clone();
+        self.tasks.spawn(async move {
+            let result = adapter.execute(&conversation, &options).await;
+            GroupOutcome {
+                index,
+                model: adapter.get_model().get_path(),
+                result,
+            }
+        });
+        Ok(index)
+    }
+
+    /// Aborts every request still in flight and returns their indices.
+    pub fn cancel(&mut self) -> Vec<usize> {
+        self.tasks.abort_all();
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Waits for the next request to finish. Returns `None` once the group is
+    /// empty.
+    pub async fn next(&mut self) -> Option<GroupOutcome> {
+        loop {
+            let outcome = match self.tasks.join_next().await? {
+                Ok(outcome) => outcome,
+                // Aborted tasks were already removed from `pending`.
+                Err(err) if err.is_cancelled() => continue,
+                Err(err) => std::panic::resume_unwind(err.into_panic()),
+            };
+            self.pending.retain(|index| *index != outcome.index);
+            if let Ok(response) = &outcome.result {
+                self.spent += response.cost;
+            }
+            return Some(outcome);
+        }
+    }
+
+    /// Collects every outcome. Results that arrived before the budget ran
+    /// out are kept; the rest are reported as cancelled.
+    pub async fn join_all(mut self) -> GroupResults {
+        let mut results = GroupResults::default();
+        while let Some(outcome) = self.next().await {
+            results.completed.push(outcome);
+            if self.budget_exhausted() {
+                results.cancelled = self.cancel();
+                break;
+            }
+        }
+        results.spent = self.spent;
+        results
+    }
+
+    /// Returns the first successful response and cancels the others. When
+    /// every request fails, the last error is returned.
+    pub async fn first_success(mut self) -> Result<GroupOutcome> {
+        let mut last_error = None;
+        while let Some(outcome) = self.next().await {
+            if outcome.result.is_ok() {
+                self.cancel();
+                return Ok(outcome);
+            }
+            last_error = Some(outcome);
+        }
+        match last_error {
+            Some(outcome) => Err(outcome.result.unwrap_err()),
+            None => Err(AdapterError::ConfigError(
+                "execution group has no requests".to_string(),
+            )),
+        }
+    }
+
+    /// Sends `conversation` to every model and keeps the first success.
+    pub async fn race(
+        models: &[Arc<dyn BaseAdapter>],
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<GroupOutcome> {
+        let mut group = ExecutionGroup::new();
+        for model in models {
+            group.spawn(model.clone(), conversation, options)?;
+        }
+        group.first_success().await
+    }
+}
+
+fn is_acceptable(outcome: &GroupOutcome, options: &ExecuteOptions) -> bool {
+    let Ok(response) = &outcome.result else {
+        return false;
+    };
+    match &options.output_validation {
+        Some(validation) => {
+            let output = response
+                .choices
+                .first()
+                .and_then(|choice| choice.message.content.as_deref())
+                .unwrap_or_default();
+            validation.validator.validate(output).is_ok()
+        }
+        None => true,
+    }
+}
+
+/// Hedged request: `primary` is asked immediately and `hedger` only when no
+/// acceptable answer arrived within `delay` (or the primary already failed).
+/// The first acceptable answer wins and the other request is cancelled. An
+/// answer is acceptable when it succeeded and passes
+/// `options.output_validation`, if set. When neither answer is acceptable the
+/// first successful one is returned, otherwise the last error.
+pub async fn race_with_fallback(
+    primary: Arc<dyn BaseAdapter>,
+    hedger: Arc<dyn BaseAdapter>,
+    delay: Duration,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+) -> Result<GroupOutcome> {
+    let mut group = ExecutionGroup::new();
+    group.spawn(primary, conversation, options)?;
+
+    let mut fallback = None;
+    if let Ok(Some(outcome)) = tokio::time::timeout(delay, group.next()).await {
+        if is_acceptable(&outcome, options) {
+            return Ok(outcome);
+        }
+        fallback = Some(outcome);
+    }
+
+    group.spawn(hedger, conversation, options)?;
+    while let Some(outcome) = group.next().await {
+        if is_acceptable(&outcome, options) {
+            group.cancel();
+            return Ok(outcome);
+        }
+        let keep_previous = fallback
+            .as_ref()
+            .is_some_and(|previous| previous.result.is_ok());
+        if !keep_previous {
+            fallback = Some(outcome);
+        }
+    }
+
+    match fallback {
+        Some(outcome) if outcome.result.is_ok() => Ok(outcome),
+        Some(outcome) => Err(outcome.result.unwrap_err()),
+        None => Err(AdapterError::ConfigError(
+            "hedged request produced no outcome".to_string(),
+        )),
+    }
+}
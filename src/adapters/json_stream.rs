@@ -0,0 +1,466 @@
+use crate::adapters::AdapterStream;
+use crate::error::{AdapterError, Result};
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::{Map, Value};
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+
+pub type JsonEventStream = Pin<Box<dyn Stream<Item = Result<JsonPathEvent>> + Send>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{}", key.replace('~', "~0").replace('/', "~1")),
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// A value found at `path` while the document is still arriving. String
+/// values are reported with `partial: true` as they grow; scalars, closed
+/// strings and closed containers are reported once with `partial: false`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPathEvent {
+    pub path: Vec<PathSegment>,
+    pub value: Value,
+    pub partial: bool,
+}
+
+impl JsonPathEvent {
+    /// The path as an RFC 6901 pointer, usable with `Value::pointer`.
+    pub fn pointer(&self) -> String {
+        self.path
+            .iter()
+            .map(|segment| format!("/{}", segment))
+            .collect()
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Frame {
+    Object {
+        map: Map<String, Value>,
+        key: Option<String>,
+    },
+    Array(Vec<Value>),
+}
+
+#[derive(Debug, Clone)]
+enum Lexer {
+    Idle,
+    Str {
+        buf: String,
+        is_key: bool,
+        escape: bool,
+        unicode: Option<String>,
+        high_surrogate: Option<u16>,
+        reported: usize,
+    },
+    Literal(String),
+}
+
+/// Incremental parser for a single JSON document delivered in arbitrary
+/// fragments. Text before the opening `{`/`[` and after the matching close
+/// is ignored, which tolerates code fences around JSON-mode output.
+#[derive(Debug, Clone)]
+pub struct PartialJsonParser {
+    stack: Vec<Frame>,
+    lexer: Lexer,
+    root: Option<Value>,
+}
+
+impl Default for PartialJsonParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialJsonParser {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            lexer: Lexer::Idle,
+            root: None,
+        }
+    }
+
+    pub fn push(&mut self, text: &str) -> Result<Vec<JsonPathEvent>> {
+        let mut events = Vec::new();
+        for c in text.chars() {
+            self.push_char(c, &mut events)?;
+        }
+
+        let path = self.current_path();
+        if let Lexer::Str {
+            buf,
+            is_key: false,
+            reported,
+            ..
+        } = &mut self.lexer
+        {
+            if buf.len() > *reported {
+                *reported = buf.len();
+                events.push(JsonPathEvent {
+                    path,
+                    value: Value::String(buf.clone()),
+                    partial: true,
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    /// Flushes a trailing number/literal and fails if the document is still
+    /// open.
+    pub fn finish(&mut self) -> Result<Vec<JsonPathEvent>> {
+        let mut events = Vec::new();
+        if let Lexer::Literal(_) = self.lexer {
+            self.end_literal(&mut events)?;
+        }
+        if self.root.is_none() {
+            return Err(AdapterError::StreamError(
+                "JSON document ended before it was complete".to_string(),
+            ));
+        }
+        Ok(events)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.root.is_some()
+    }
+
+    pub fn value(&self) -> Option<&Value> {
+        self.root.as_ref()
+    }
+
+    /// Best-effort view of the document so far, with every open container and
+    /// string closed at the current position.
+    pub fn snapshot(&self) -> Option<Value> {
+        if let Some(root) = &self.root {
+            return Some(root.clone());
+        }
+
+        let mut pending = match &self.lexer {
+            Lexer::Str {
+                buf, is_key: false, ..
+            } => Some(Value::String(buf.clone())),
+            Lexer::Literal(buf) => serde_json::from_str(buf).ok(),
+            _ => None,
+        };
+        for frame in self.stack.iter().rev() {
+            let mut frame = frame.clone();
+            if let Some(value) = pending.take() {
+                attach(&mut frame, value);
+            }
+            pending = Some(close(frame));
+        }
+        pending
+    }
+
+    fn push_char(&mut self, c: char, events: &mut Vec<JsonPathEvent>) -> Result<()> {
+        if self.root.is_some() {
+            return Ok(());
+        }
+
+        match &mut self.lexer {
+            Lexer::Str { .. } => return self.push_string_char(c, events),
+            Lexer::Literal(buf) => {
+                if !(c.is_whitespace() || matches!(c, ',' | '}' | ']')) {
+                    buf.push(c);
+                    return Ok(());
+                }
+                self.end_literal(events)?;
+            }
+            Lexer::Idle => {}
+        }
+
+        if self.stack.is_empty() && !matches!(c, '{' | '[') {
+            return Ok(());
+        }
+
+        match c {
+            '{' => self.stack.push(Frame::Object {
+                map: Map::new(),
+                key: None,
+            }),
+            '[' => self.stack.push(Frame::Array(Vec::new())),
+            '}' | ']' => {
+                let frame = self.stack.pop().ok_or_else(|| unexpected(c))?;
+                let matches = matches!(
+                    (&frame, c),
+                    (Frame::Object { .. }, '}') | (Frame::Array(_), ']')
+                );
+                if !matches {
+                    return Err(unexpected(c));
+                }
+                self.complete_value(close(frame), events);
+            }
+            '"' => {
+                let is_key = matches!(self.stack.last(), Some(Frame::Object { key: None, .. }));
+                self.lexer = Lexer::Str {
+                    buf: String::new(),
+                    is_key,
+                    escape: false,
+                    unicode: None,
+                    high_surrogate: None,
+                    reported: 0,
+                };
+            }
+            ',' | ':' => {}
+            c if c.is_whitespace() => {}
+            c => self.lexer = Lexer::Literal(c.to_string()),
+        }
+        Ok(())
+    }
+
+    fn push_string_char(&mut self, c: char, events: &mut Vec<JsonPathEvent>) -> Result<()> {
+        let Lexer::Str {
+            buf,
+            is_key,
+            escape,
+            unicode,
+            high_surrogate,
+            ..
+        } = &mut self.lexer
+        else {
+            return Ok(());
+        };
+
+        if let Some(hex) = unicode {
+            hex.push(c);
+            if hex.len() < 4 {
+                return Ok(());
+            }
+            let code = u16::from_str_radix(hex, 16)
+                .map_err(|_| AdapterError::StreamError(format!("invalid \\u escape: {}", hex)))?;
+            *unicode = None;
+            match (high_surrogate.take(), code) {
+                (None, 0xD800..=0xDBFF) => *high_surrogate = Some(code),
+                (Some(high), 0xDC00..=0xDFFF) => {
+                    let combined =
+                        0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(code) - 0xDC00);
+                    buf.push(char::from_u32(combined).unwrap_or('\u{FFFD}'));
+                }
+                (_, code) => buf.push(char::from_u32(u32::from(code)).unwrap_or('\u{FFFD}')),
+            }
+            return Ok(());
+        }
+
+        if *escape {
+            *escape = false;
+            match c {
+                'n' => buf.push('\n'),
+                't' => buf.push('\t'),
+                'r' => buf.push('\r'),
+                'b' => buf.push('\u{0008}'),
+                'f' => buf.push('\u{000C}'),
+                'u' => *unicode = Some(String::new()),
+                other => buf.push(other),
+            }
+            return Ok(());
+        }
+
+        match c {
+            '\\' => *escape = true,
+            '"' => {
+                let text = std::mem::take(buf);
+                let is_key = *is_key;
+                self.lexer = Lexer::Idle;
+                if is_key {
+                    if let Some(Frame::Object { key, .. }) = self.stack.last_mut() {
+                        *key = Some(text);
+                    }
+                } else {
+                    self.complete_value(Value::String(text), events);
+                }
+            }
+            c => buf.push(c),
+        }
+        Ok(())
+    }
+
+    fn end_literal(&mut self, events: &mut Vec<JsonPathEvent>) -> Result<()> {
+        let Lexer::Literal(buf) = std::mem::replace(&mut self.lexer, Lexer::Idle) else {
+            return Ok(());
+        };
+        let value: Value = serde_json::from_str(&buf)?;
+        self.complete_value(value, events);
+        Ok(())
+    }
+
+    fn complete_value(&mut self, value: Value, events: &mut Vec<JsonPathEvent>) {
+        events.push(JsonPathEvent {
+            path: self.current_path(),
+            value: value.clone(),
+            partial: false,
+        });
+        match self.stack.last_mut() {
+            Some(frame) => attach(frame, value),
+            None => self.root = Some(value),
+        }
+    }
+
+    fn current_path(&self) -> Vec<PathSegment> {
+        self.stack
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Object { key, .. } => key.clone().map(PathSegment::Key),
+                Frame::Array(items) => Some(PathSegment::Index(items.len())),
+            })
+            .collect()
+    }
+}
+
+fn attach(frame: &mut Frame, value: Value) {
+    match frame {
+        Frame::Object { map, key } => {
+            if let Some(key) = key.take() {
+                map.insert(key, value);
+            }
+        }
+        Frame::Array(items) => items.push(value),
+    }
+}
+
+fn close(frame: Frame) -> Value {
+    match frame {
+        Frame::Object { map, .. } => Value::Object(map),
+        Frame::Array(items) => Value::Array(items),
+    }
+}
+
+fn unexpected(c: char) -> AdapterError {
+    AdapterError::StreamError(format!("unexpected '{}' in streamed JSON", c))
+}
+
+/// Feeds the content deltas of the first choice through a
+/// `PartialJsonParser`, yielding path events as they become available.
+pub fn json_event_stream(stream: AdapterStream) -> JsonEventStream {
+    struct State {
+        inner: AdapterStream,
+        parser: PartialJsonParser,
+        pending: VecDeque<JsonPathEvent>,
+        done: bool,
+    }
+
+    let state = State {
+        inner: stream,
+        parser: PartialJsonParser::new(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                return Some((Ok(event), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            let parsed = match state.inner.next().await {
+                Some(Ok(chunk)) => {
+                    let content: String = chunk
+                        .choices
+                        .iter()
+                        .filter(|choice| choice.index == 0)
+                        .filter_map(|choice| choice.delta.content.as_deref())
+                        .collect();
+                    state.parser.push(&content)
+                }
+                Some(Err(err)) => Err(err),
+                None => {
+                    state.done = true;
+                    state.parser.finish()
+                }
+            };
+
+            match parsed {
+                Ok(events) => state.pending.extend(events),
+                Err(err) => {
+                    state.done = true;
+                    state.pending.clear();
+                    return Some((Err(err), state));
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse_all(fragments: &[&str]) -> (Vec<JsonPathEvent>, PartialJsonParser) {
+        let mut parser = PartialJsonParser::new();
+        let mut events = Vec::new();
+        for fragment in fragments {
+            events.extend(parser.push(fragment).unwrap());
+        }
+        events.extend(parser.finish().unwrap());
+        (events, parser)
+    }
+
+    #[test]
+    fn test_partial_strings_and_completed_values() {
+        let (events, parser) = parse_all(&[
+            r#"{"title": "Hel"#,
+            r#"lo", "tags": ["a", 4"#,
+            r#"2], "done": true}"#,
+        ]);
+
+        let summary: Vec<_> = events
+            .iter()
+            .map(|event| (event.pointer(), event.value.clone(), event.partial))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("/title".to_string(), json!("Hel"), true),
+                ("/title".to_string(), json!("Hello"), false),
+                ("/tags/0".to_string(), json!("a"), false),
+                ("/tags/1".to_string(), json!(42), false),
+                ("/tags".to_string(), json!(["a", 42]), false),
+                ("/done".to_string(), json!(true), false),
+                (
+                    String::new(),
+                    json!({"title": "Hello", "tags": ["a", 42], "done": true}),
+                    false
+                ),
+            ]
+        );
+        assert!(parser.is_complete());
+    }
+
+    #[test]
+    fn test_snapshot_closes_open_containers() {
+        let mut parser = PartialJsonParser::new();
+        parser
+            .push("```json\n{\"user\": {\"name\": \"Ad\\u00e9l\", \"age\": 3")
+            .unwrap();
+        assert_eq!(
+            parser.snapshot(),
+            Some(json!({"user": {"name": "Adél", "age": 3}}))
+        );
+        assert!(parser.finish().is_err());
+    }
+
+    #[test]
+    fn test_mismatched_close_is_an_error() {
+        let mut parser = PartialJsonParser::new();
+        assert!(parser.push(r#"{"a": [1}"#).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions, ProviderCredentials};
+use crate::config::EnvConfig;
+use crate::error::{AdapterError, Result};
+use crate::models::{AdapterChatCompletion, Conversation, Model, RateLimitType};
+use async_trait::async_trait;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KeyUsage {
+    pub requests: u64,
+    pub rate_limited: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyStats {
+    /// The last four characters of the key, for logs and dashboards.
+    pub key_hint: String,
+    pub usage: KeyUsage,
+    /// Time left until the key is used again after a rate limit.
+    pub cooldown_remaining: Option<Duration>,
+}
+
+/// Upper bound on a key's cooldown, for `retry-after` values too large to
+/// add to an `Instant`.
+const MAX_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct KeyState {
+    key: String,
+    cooldown_until: Option<Instant>,
+    usage: KeyUsage,
+}
+
+/// Several API keys for one provider. Requests go to the keys in turn; a key
+/// that hits a rate limit is skipped for `cooldown` and the request moves on
+/// to the next key.
+pub struct KeyPool {
+    provider: String,
+    keys: Mutex<Vec<KeyState>>,
+    cursor: Mutex<usize>,
+    cooldown: Duration,
+}
+
+impl std::fmt::Debug for KeyPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyPool")
+            .field("provider", &self.provider)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl KeyPool {
+    pub fn new(provider: impl Into<String>, keys: Vec<String>) -> Result<Self> {
+        let provider = provider.into();
+        if keys.is_empty() {
+            return Err(AdapterError::ApiKeyNotFound(provider));
+        }
+        Ok(Self {
+            provider,
+            keys: Mutex::new(
+                keys.into_iter()
+                    .map(|key| KeyState {
+                        key,
+                        cooldown_until: None,
+                        usage: KeyUsage::default(),
+                    })
+                    .collect(),
+            ),
+            cursor: Mutex::new(0),
+            cooldown: Duration::from_secs(60),
+        })
+    }
+
+    /// Keys from `<PROVIDER>_API_KEYS` (comma separated), or the single
+    /// `<PROVIDER>_API_KEY`.
+    pub fn from_env(provider: &str) -> Result<Self> {
+        let keys = EnvConfig::get_api_keys(provider)
+            .or_else(|| EnvConfig::get_api_key(provider).map(|key| vec![key]))
+            .unwrap_or_default();
+        Self::new(provider, keys)
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<KeyState>> {
+        self.keys
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The next key that is not cooling down, as `(index, key)`.
+    fn next_key(&self) -> Option<(usize, String)> {
+        let keys = self.lock();
+        let mut cursor = self
+            .cursor
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        (0..keys.len())
+            .map(|offset| (*cursor + offset) % keys.len())
+            .find(|&index| keys[index].cooldown_until.is_none_or(|until| until <= now))
+            .map(|index| {
+                *cursor = index + 1;
+                (index, keys[index].key.clone())
+            })
+    }
+
+    /// How long until some key is usable again; zero when one is available.
+    pub fn next_available_in(&self) -> Duration {
+        let now = Instant::now();
+        self.lock()
+            .iter()
+            .map(|state| {
+                state
+                    .cooldown_until
+                    .map_or(Duration::ZERO, |until| until.saturating_duration_since(now))
+            })
+            .min()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    fn record(
+        &self,
+        index: usize,
+        outcome: std::result::Result<Option<(u32, u32)>, &AdapterError>,
+    ) {
+        let mut keys = self.lock();
+        let state = &mut keys[index];
+        state.usage.requests += 1;
+        match outcome {
+            Ok(tokens) => {
+                if let Some((prompt, completion)) = tokens {
+                    state.usage.prompt_tokens += u64::from(prompt);
+                    state.usage.completion_tokens += u64::from(completion);
+                }
+            }
+            Err(err) if err.is_rate_limited() => {
+                state.usage.rate_limited += 1;
+                let cooldown = err.retry_after().unwrap_or(self.cooldown);
+                let now = Instant::now();
+                state.cooldown_until = Some(
+                    now.checked_add(cooldown)
+                        .unwrap_or_else(|| now + MAX_COOLDOWN),
+                );
+            }
+            Err(_) => state.usage.failures += 1,
+        }
+    }
+
+    pub fn stats(&self) -> Vec<KeyStats> {
+        let now = Instant::now();
+        self.lock()
+            .iter()
+            .map(|state| {
+                let hint_start = state
+                    .key
+                    .char_indices()
+                    .rev()
+                    .nth(3)
+                    .map_or(0, |(index, _)| index);
+                KeyStats {
+                    key_hint: state.key[hint_start..].to_string(),
+                    usage: state.usage.clone(),
+                    cooldown_remaining: state
+                        .cooldown_until
+                        .map(|until| until.saturating_duration_since(now))
+                        .filter(|remaining| !remaining.is_zero()),
+                }
+            })
+            .collect()
+    }
+
+    /// Runs `call` with options whose credentials carry a pooled key,
+    /// rotating to the next key while the provider answers with a rate limit.
+    /// Each key is tried at most once per call; fails with the last
+    /// rate-limit error once every key is cooling down or has been tried.
+    pub async fn run<T, F, Fut>(&self, options: &ExecuteOptions, call: F) -> Result<T>
+    where
+        F: Fn(ExecuteOptions) -> Fut,
+        Fut: Future<Output = Result<T>>,
+        T: PooledOutput,
+    {
+        let mut last_error = None;
+        for _ in 0..self.len() {
+            let Some((index, key)) = self.next_key() else {
+                break;
+            };
+            let mut options = options.clone();
+            let credentials = options.credentials.take().unwrap_or_default();
+            options.credentials = Some(ProviderCredentials {
+                api_key: Some(key),
+                ..credentials
+            });
+
+            match call(options).await {
+                Ok(output) => {
+                    self.record(index, Ok(output.token_counts()));
+                    return Ok(output);
+                }
+                Err(err) => {
+                    self.record(index, Err(&err));
+                    if !err.is_rate_limited() {
+                        return Err(err);
+                    }
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(
+            last_error.unwrap_or_else(|| AdapterError::RateLimitExceeded {
+                provider: self.provider.clone(),
+                retry_after: Some(self.next_available_in()),
+                limit_type: RateLimitType::Requests,
+                remaining: Some(0),
+            }),
+        )
+    }
+
+    pub async fn execute<A: BaseAdapter + ?Sized>(
+        &self,
+        adapter: &A,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        self.run(options, |options| async move {
+            adapter.execute(conversation, &options).await
+        })
+        .await
+    }
+}
+
+/// Outputs of `KeyPool::run` that can report token usage.
+pub trait PooledOutput {
+    fn token_counts(&self) -> Option<(u32, u32)> {
+        None
+    }
+}
+
+impl PooledOutput for AdapterChatCompletion {
+    fn token_counts(&self) -> Option<(u32, u32)> {
+        self.usage
+            .as_ref()
+            .map(|usage| (usage.prompt_tokens, usage.completion_tokens))
+    }
+}
+
+impl PooledOutput for AdapterStream {}
+
+/// Wraps an adapter so that every request draws its API key from a
+/// `KeyPool`.
+pub struct KeyPooled<A> {
+    inner: A,
+    pool: Arc<KeyPool>,
+}
+
+impl<A: BaseAdapter> KeyPooled<A> {
+    pub fn new(adapter: A, pool: Arc<KeyPool>) -> Self {
+        Self {
+            inner: adapter,
+            pool,
+        }
+    }
+
+    pub fn pool(&self) -> &KeyPool {
+        &self.pool
+    }
+}
+
+#[async_trait]
+impl<A: BaseAdapter> BaseAdapter for KeyPooled<A> {
+    fn get_model(&self) -> &Model {
+        self.inner.get_model()
+    }
+
+    fn set_api_key(&mut self, api_key: String) -> Result<()> {
+        self.inner.set_api_key(api_key)
+    }
+
+    async fn execute(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        self.pool.execute(&self.inner, conversation, options).await
+    }
+
+    async fn execute_stream(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterStream> {
+        self.pool
+            .run(options, |options| async move {
+                self.inner.execute_stream(conversation, &options).await
+            })
+            .await
+    }
+}
@@ -1,5 +1,61 @@
 pub mod base;
+pub mod batch;
+pub mod budget;
+pub mod builtin;
+pub mod catalog;
+pub mod dedup;
+pub mod events;
 pub mod factory;
+pub mod group;
+pub mod injection;
+pub mod json_stream;
+pub mod keys;
+pub mod normalizer;
+pub mod policy;
+pub mod postprocess;
+pub mod prefix_cache;
+pub mod preflight;
+pub mod push;
+pub mod queue;
+pub mod resume;
+pub mod routing;
+pub mod sampling;
+pub mod selection;
+pub mod session;
+pub mod spec;
+pub mod stream;
+#[cfg(feature = "strict-validation")]
+pub mod strict;
+pub mod tools;
+pub mod validation;
 
 pub use base::*;
+pub use batch::*;
+pub use budget::*;
+pub use builtin::*;
+pub use catalog::*;
+pub use dedup::*;
+pub use events::*;
 pub use factory::*;
+pub use group::*;
+pub use injection::*;
+pub use json_stream::*;
+pub use keys::*;
+pub use normalizer::*;
+pub use policy::*;
+pub use postprocess::*;
+pub use prefix_cache::*;
+pub use preflight::*;
+pub use push::*;
+pub use queue::*;
+pub use resume::*;
+pub use routing::*;
+pub use sampling::*;
+pub use selection::*;
+pub use session::*;
+pub use spec::*;
+pub use stream::*;
+#[cfg(feature = "strict-validation")]
+pub use strict::*;
+pub use tools::*;
+pub use validation::*;
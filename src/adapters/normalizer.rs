@@ -0,0 +1,238 @@
+use crate::config::{ProviderQuirks, QuirkAction, QuirkRule};
+use crate::error::{AdapterError, Result};
+use crate::models::AdapterWarning;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Applies a provider's quirk rules, in order, to an outgoing request body.
+/// Rules come from `config/provider_quirks.toml` or are added with
+/// `with_rule`.
+#[derive(Debug, Clone, Default)]
+pub struct Normalizer {
+    rules: Vec<(QuirkRule, Option<Regex>)>,
+}
+
+impl Normalizer {
+    pub fn new(rules: Vec<QuirkRule>) -> Result<Self> {
+        rules
+            .into_iter()
+            .try_fold(Self::default(), |normalizer, rule| {
+                normalizer.with_rule(rule)
+            })
+    }
+
+    /// The built-in rules for `provider_id`. Unknown providers get none.
+    pub fn for_provider(provider_id: &str) -> Self {
+        Self::new(ProviderQuirks::for_provider(provider_id))
+            .expect("built-in provider quirks must have valid model patterns")
+    }
+
+    pub fn with_rule(mut self, rule: QuirkRule) -> Result<Self> {
+        let models = rule
+            .models
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|err| {
+                AdapterError::ConfigError(format!("invalid quirk model pattern: {}", err))
+            })?;
+        self.rules.push((rule, models));
+        Ok(self)
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &QuirkRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Rewrites `body` for `model` and returns the rules that changed it.
+    pub fn apply(&self, model: &str, body: &mut Value) -> Vec<&QuirkRule> {
+        self.rules
+            .iter()
+            .filter(|(_, models)| models.as_ref().is_none_or(|re| re.is_match(model)))
+            .filter(|(rule, _)| apply_action(&rule.action, body))
+            .map(|(rule, _)| rule)
+            .collect()
+    }
+
+    /// Like `apply`, reporting every parameter a `drop` or `map` rule
+    /// removed.
+    pub fn apply_with_warnings(&self, model: &str, body: &mut Value) -> Vec<AdapterWarning> {
+        let applied = self.apply(model, body);
+        let body = &*body;
+        applied
+            .into_iter()
+            .filter_map(|rule| match &rule.action {
+                QuirkAction::Map { field, .. } if has_field(body, field) => None,
+                QuirkAction::Drop { field } | QuirkAction::Map { field, .. } => {
+                    Some(AdapterWarning::DroppedParameter {
+                        parameter: field.clone(),
+                        model: model.to_string(),
+                        reason: rule
+                            .reason
+                            .clone()
+                            .unwrap_or_else(|| format!("{} does not support {}", model, field)),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Non-mutating `apply`.
+    pub fn normalize(&self, model: &str, body: &Value) -> Value {
+        let mut body = body.clone();
+        self.apply(model, &mut body);
+        body
+    }
+}
+
+/// Splits a field into its parent pointer and key. Plain names address
+/// top-level keys; names starting with `/` are JSON pointers.
+fn split_field(field: &str) -> (String, String) {
+    if !field.starts_with('/') {
+        return (String::new(), field.to_string());
+    }
+    let (parent, key) = field.rsplit_once('/').unwrap_or(("", field));
+    (
+        parent.to_string(),
+        key.replace("~1", "/").replace("~0", "~"),
+    )
+}
+
+fn parent_object<'a>(body: &'a mut Value, parent: &str) -> Option<&'a mut Map<String, Value>> {
+    body.pointer_mut(parent)?.as_object_mut()
+}
+
+fn has_field(body: &Value, field: &str) -> bool {
+    let (parent, key) = split_field(field);
+    body.pointer(&parent)
+        .and_then(Value::as_object)
+        .is_some_and(|object| object.contains_key(&key))
+}
+
+fn apply_action(action: &QuirkAction, body: &mut Value) -> bool {
+    match action {
+        QuirkAction::Rename { from, to } => {
+            let (from_parent, from_key) = split_field(from);
+            let Some(value) = parent_object(body, &from_parent).and_then(|o| o.remove(&from_key))
+            else {
+                return false;
+            };
+            let (to_parent, to_key) = split_field(to);
+            if let Some(object) = parent_object(body, &to_parent) {
+                // An explicit value for the new name wins.
+                object.entry(to_key).or_insert(value);
+            }
+            true
+        }
+        QuirkAction::Drop { field } => {
+            let (parent, key) = split_field(field);
+            parent_object(body, &parent)
+                .and_then(|object| object.remove(&key))
+                .is_some()
+        }
+        QuirkAction::Clamp { field, min, max } => {
+            let (parent, key) = split_field(field);
+            let Some(value) = parent_object(body, &parent).and_then(|o| o.get_mut(&key)) else {
+                return false;
+            };
+            let Some(number) = value.as_f64() else {
+                return false;
+            };
+            let clamped = number
+                .max(min.unwrap_or(f64::MIN))
+                .min(max.unwrap_or(f64::MAX));
+            if clamped == number {
+                return false;
+            }
+            *value = if value.is_f64() {
+                Value::from(clamped)
+            } else {
+                Value::from(clamped as i64)
+            };
+            true
+        }
+        QuirkAction::Default { field, value } => {
+            let (parent, key) = split_field(field);
+            let Some(object) = parent_object(body, &parent) else {
+                return false;
+            };
+            if object.contains_key(&key) {
+                return false;
+            }
+            object.insert(key, value.clone());
+            true
+        }
+        QuirkAction::Map { field, values } => {
+            let (parent, key) = split_field(field);
+            let Some(object) = parent_object(body, &parent) else {
+                return false;
+            };
+            let Some(current) = object.get(&key) else {
+                return false;
+            };
+            match current.as_str().and_then(|name| values.get(name)) {
+                Some(mapped) if mapped == current => false,
+                Some(mapped) => {
+                    object.insert(key, mapped.clone());
+                    true
+                }
+                None => {
+                    object.remove(&key);
+                    true
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtin_quirks() {
+        let body = json!({"max_tokens": 100, "temperature": 0.2, "messages": []});
+
+        let openai = Normalizer::for_provider("openai");
+        assert_eq!(
+            openai.normalize("o3-mini", &body),
+            json!({"max_completion_tokens": 100, "messages": []})
+        );
+        assert_eq!(openai.normalize("gpt-4o", &body), body);
+
+        let anthropic = Normalizer::for_provider("anthropic");
+        let mut body = json!({"n": 2, "temperature": 1.5, "stop": ["\n"]});
+        let applied = anthropic.apply("claude-sonnet", &mut body);
+        assert_eq!(body, json!({"temperature": 1.0, "stop_sequences": ["\n"]}));
+        assert_eq!(applied.len(), 3);
+
+        let gemini = Normalizer::for_provider("gemini");
+        assert_eq!(
+            gemini.normalize(
+                "gemini-2.0-flash",
+                &json!({"generationConfig": {"candidateCount": 12}})
+            ),
+            json!({"generationConfig": {"candidateCount": 8}})
+        );
+        assert_eq!(Normalizer::for_provider("unknown").rules().count(), 0);
+    }
+
+    #[test]
+    fn test_service_tier_mapping() {
+        let mut body = json!({"service_tier": "default"});
+        let warnings =
+            Normalizer::for_provider("anthropic").apply_with_warnings("claude-sonnet", &mut body);
+        assert_eq!(body, json!({"service_tier": "standard_only"}));
+        assert!(warnings.is_empty());
+
+        let mut body = json!({"service_tier": "priority"});
+        let warnings = Normalizer::for_provider("groq").apply_with_warnings("llama-3", &mut body);
+        assert_eq!(body, json!({}));
+        assert!(matches!(
+            &warnings[..],
+            [AdapterWarning::DroppedParameter { parameter, .. }] if parameter == "service_tier"
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{AdapterChatCompletion, Conversation, Model};
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+
+/// A moderation result for a request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModerationVerdict {
+    pub flagged: bool,
+    /// e.g. `sexual` or `violence`.
+    pub categories: Vec<String>,
+}
+
+impl ModerationVerdict {
+    pub fn allowed() -> Self {
+        Self::default()
+    }
+
+    pub fn flagged(categories: Vec<String>) -> Self {
+        Self {
+            flagged: true,
+            categories,
+        }
+    }
+}
+
+/// Classifies requests before they are dispatched, e.g. with a provider's
+/// moderation endpoint or a local classifier.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    async fn moderate(&self, conversation: &Conversation) -> Result<ModerationVerdict>;
+}
+
+/// Fails with `AdapterError::PolicyViolation` when a flagged request is
+/// about to go to a model that is not marked `is_nsfw`.
+pub fn check_policy(model: &Model, verdict: &ModerationVerdict) -> Result<()> {
+    if !verdict.flagged || model.properties.is_nsfw {
+        return Ok(());
+    }
+    Err(AdapterError::PolicyViolation {
+        model: model.get_path(),
+        categories: verdict.categories.clone(),
+    })
+}
+
+/// Wraps an adapter and moderates every request before it is sent;
+/// flagged requests only reach NSFW-allowed models.
+pub struct PolicyGate<A> {
+    inner: A,
+    moderator: Arc<dyn Moderator>,
+}
+
+impl<A: BaseAdapter> PolicyGate<A> {
+    pub fn new(adapter: A, moderator: Arc<dyn Moderator>) -> Self {
+        Self {
+            inner: adapter,
+            moderator,
+        }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    async fn gate(&self, conversation: &Conversation) -> Result<()> {
+        let verdict = self.moderator.moderate(conversation).await?;
+        check_policy(self.inner.get_model(), &verdict)
+    }
+}
+
+impl<A> fmt::Debug for PolicyGate<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyGate").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<A: BaseAdapter> BaseAdapter for PolicyGate<A> {
+    fn get_model(&self) -> &Model {
+        self.inner.get_model()
+    }
+
+    fn set_api_key(&mut self, api_key: String) -> Result<()> {
+        self.inner.set_api_key(api_key)
+    }
+
+    async fn execute(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        self.gate(conversation).await?;
+        self.inner.execute(conversation, options).await
+    }
+
+    async fn execute_stream(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterStream> {
+        self.gate(conversation).await?;
+        self.inner.execute_stream(conversation, options).await
+    }
+}
@@ -0,0 +1,161 @@
+use crate::models::{AdapterChatCompletion, AdapterWarning};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Content transform applied to every choice of a completion, in order.
+/// Serializable so gateways can keep the pipeline in an options profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum PostProcessor {
+    /// Unwraps a reply that is a single fenced code block.
+    StripCodeFences,
+    /// Cuts the reply at the first leaked stop sequence.
+    TrimStopSequences(Vec<String>),
+    /// Normalizes line endings, trailing spaces and runs of blank lines.
+    NormalizeWhitespace,
+    /// Truncates the reply to at most this many characters.
+    MaxLength(usize),
+}
+
+impl PostProcessor {
+    pub fn apply(&self, content: &str) -> String {
+        match self {
+            PostProcessor::StripCodeFences => strip_code_fence(content).to_string(),
+            PostProcessor::TrimStopSequences(stops) => {
+                let cut = stops
+                    .iter()
+                    .filter(|stop| !stop.is_empty())
+                    .filter_map(|stop| content.find(stop.as_str()))
+                    .min()
+                    .unwrap_or(content.len());
+                content[..cut].trim_end().to_string()
+            }
+            PostProcessor::NormalizeWhitespace => normalize_whitespace(content),
+            PostProcessor::MaxLength(max) => match content.char_indices().nth(*max) {
+                Some((end, _)) => content[..end].to_string(),
+                None => content.to_string(),
+            },
+        }
+    }
+}
+
+pub fn post_process(content: &str, processors: &[PostProcessor]) -> String {
+    processors
+        .iter()
+        .fold(content.to_string(), |content, processor| {
+            processor.apply(&content)
+        })
+}
+
+impl AdapterChatCompletion {
+    pub fn post_process(&mut self, processors: &[PostProcessor]) {
+        if processors.is_empty() {
+            return;
+        }
+        for choice in &mut self.choices {
+            if let Some(content) = &mut choice.message.content {
+                *content = post_process(content, processors);
+            }
+        }
+    }
+
+    /// For JSON-mode replies: makes choices that do not parse as JSON
+    /// parse, by unwrapping a code fence or cutting the prose around the
+    /// outermost object or array, and records a warning for each repair.
+    pub fn repair_json(&mut self) {
+        for choice in &mut self.choices {
+            let Some(content) = &mut choice.message.content else {
+                continue;
+            };
+            if serde_json::from_str::<Value>(content).is_ok() {
+                continue;
+            }
+            let Some((repaired, repair)) = repair_json_text(content) else {
+                continue;
+            };
+            *content = repaired;
+            self.warnings.push(AdapterWarning::RepairedJson {
+                choice_index: choice.index,
+                repair: repair.to_string(),
+            });
+        }
+    }
+}
+
+fn repair_json_text(content: &str) -> Option<(String, &'static str)> {
+    let parses = |text: &str| serde_json::from_str::<Value>(text).is_ok();
+    let unfenced = strip_code_fence(content);
+    if unfenced != content.trim() && parses(unfenced) {
+        return Some((unfenced.to_string(), "removed code fence"));
+    }
+    let start = content.find(['{', '['])?;
+    let end = content.rfind(['}', ']'])?;
+    let inner = content.get(start..=end)?;
+    parses(inner).then(|| (inner.to_string(), "removed text around the JSON value"))
+}
+
+pub(crate) fn strip_code_fence(content: &str) -> &str {
+    let trimmed = content.trim();
+    let Some(inner) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    let Some(inner) = inner.strip_suffix("```") else {
+        return trimmed;
+    };
+    if inner.contains("```") {
+        return trimmed;
+    }
+    match inner.split_once('\n') {
+        Some((language, body)) if !language.contains(' ') => body.trim(),
+        _ => inner.trim(),
+    }
+}
+
+fn normalize_whitespace(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    let mut blank_lines = 0;
+    for line in content.replace("\r\n", "\n").lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        normalized.push_str(line);
+        normalized.push('\n');
+    }
+    normalized.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_process_pipeline() {
+        let processors = vec![
+            PostProcessor::TrimStopSequences(vec!["<|im_end|>".to_string()]),
+            PostProcessor::StripCodeFences,
+            PostProcessor::NormalizeWhitespace,
+            PostProcessor::MaxLength(9),
+        ];
+        let raw = "```json\r\n{\"a\": 1}   \r\n\r\n\r\n{\"b\": 2}\n```<|im_end|>junk";
+        assert_eq!(post_process(raw, &processors), "{\"a\": 1}\n");
+        assert_eq!(
+            strip_code_fence("```a``` and ```b```"),
+            "```a``` and ```b```"
+        );
+        assert_eq!(strip_code_fence("```\nplain\n```"), "plain");
+        assert_eq!(
+            repair_json_text("Sure! Here it is: {\"a\": [1]} Hope that helps."),
+            Some((
+                "{\"a\": [1]}".to_string(),
+                "removed text around the JSON value"
+            ))
+        );
+        assert_eq!(repair_json_text("no json here"), None);
+    }
+}
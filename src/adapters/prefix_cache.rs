@@ -0,0 +1,203 @@
+use crate::utils::{estimate_tokens, stable_hash};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Anthropic does not cache prefixes shorter than this.
+const DEFAULT_MIN_TOKENS: u32 = 1024;
+const DEFAULT_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct Seen {
+    /// Prefix hash to the request counter it was last seen at.
+    prefixes: HashMap<u64, u64>,
+    requests: u64,
+}
+
+/// Remembers the message prefixes of recent requests and puts a
+/// `cache_control` breakpoint at the end of the longest prefix a request
+/// shares with an earlier one, so providers with explicit prompt caching
+/// such as Anthropic reuse it without the caller placing breakpoints.
+/// Share one across adapters to detect prefixes process-wide.
+#[derive(Debug)]
+pub struct PrefixCache {
+    seen: Mutex<Seen>,
+    min_tokens: u32,
+    capacity: usize,
+}
+
+impl Default for PrefixCache {
+    fn default() -> Self {
+        Self {
+            seen: Mutex::new(Seen::default()),
+            min_tokens: DEFAULT_MIN_TOKENS,
+            capacity: DEFAULT_CAPACITY,
+        }
+    }
+}
+
+impl PrefixCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Shared prefixes shorter than `min_tokens` are not marked.
+    pub fn with_min_tokens(mut self, min_tokens: u32) -> Self {
+        self.min_tokens = min_tokens;
+        self
+    }
+
+    /// Prefixes remembered before the least recently seen are forgotten.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Records the prefixes of `messages` and returns the length of the
+    /// longest one seen before that is at least `min_tokens` long.
+    pub fn observe(&self, messages: &[Value]) -> Option<usize> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.requests += 1;
+        let request = seen.requests;
+        let mut hash = 0;
+        let mut tokens = 0u32;
+        let mut shared = None;
+        for (index, message) in messages.iter().enumerate() {
+            hash = stable_hash(&(hash, message));
+            tokens = tokens.saturating_add(estimate_tokens(&message.to_string()));
+            if seen.prefixes.insert(hash, request).is_some() && tokens >= self.min_tokens {
+                shared = Some(index + 1);
+            }
+        }
+        if seen.prefixes.len() > self.capacity {
+            let mut ages: Vec<u64> = seen.prefixes.values().copied().collect();
+            ages.sort_unstable();
+            let cutoff = ages[ages.len() - self.capacity];
+            seen.prefixes.retain(|_, last_seen| *last_seen >= cutoff);
+        }
+        shared
+    }
+
+    /// Marks the shared prefix of `body[messages_field]`, treating a
+    /// top-level `system` field as the first message. Bodies that already
+    /// carry breakpoints are left alone. Returns how many messages,
+    /// counting `system`, the breakpoint covers.
+    pub fn annotate(&self, body: &mut Value, messages_field: &str) -> Option<usize> {
+        let messages = body[messages_field].as_array().into_iter().flatten();
+        if body.get("system").is_some_and(has_breakpoint)
+            || messages
+                .filter_map(|message| message.get("content"))
+                .any(has_breakpoint)
+        {
+            return None;
+        }
+        let system = body.get("system").cloned();
+        let mut messages: Vec<Value> = system.iter().cloned().collect();
+        messages.extend(body[messages_field].as_array().cloned().unwrap_or_default());
+        let shared = self.observe(&messages)?;
+
+        let last = match (system.is_some(), shared) {
+            (true, 1) => &mut body["system"],
+            (true, shared) => &mut body[messages_field][shared - 2],
+            (false, shared) => &mut body[messages_field][shared - 1],
+        };
+        let content = if last.get("content").is_some() {
+            &mut last["content"]
+        } else {
+            last
+        };
+        mark(content);
+        Some(shared)
+    }
+}
+
+/// Whether `content`, a string or an array of content blocks, already
+/// carries a caller-placed breakpoint.
+fn has_breakpoint(content: &Value) -> bool {
+    content.as_array().is_some_and(|blocks| {
+        blocks
+            .iter()
+            .any(|block| block.get("cache_control").is_some())
+    })
+}
+
+fn mark(content: &mut Value) {
+    let breakpoint = json!({"type": "ephemeral"});
+    match content {
+        Value::String(text) => {
+            *content = json!([{"type": "text", "text": text, "cache_control": breakpoint}]);
+        }
+        Value::Array(blocks) => {
+            if let Some(Value::Object(block)) = blocks.last_mut() {
+                block.insert("cache_control".to_string(), breakpoint);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_cache_marks_shared_prefix() {
+        let cache = PrefixCache::new().with_min_tokens(10);
+        let manual = "x".repeat(200);
+        let body = |question: &str| {
+            json!({
+                "system": manual,
+                "messages": [
+                    {"role": "user", "content": "Summarize the manual."},
+                    {"role": "assistant", "content": "It covers setup."},
+                    {"role": "user", "content": question},
+                ]
+            })
+        };
+
+        let mut first = body("How do I reset it?");
+        assert_eq!(cache.annotate(&mut first, "messages"), None);
+        assert!(!first.to_string().contains("cache_control"));
+
+        let mut second = body("Where is the serial number?");
+        assert_eq!(cache.annotate(&mut second, "messages"), Some(3));
+        assert_eq!(
+            second["messages"][1]["content"],
+            json!([{"type": "text", "text": "It covers setup.", "cache_control": {"type": "ephemeral"}}])
+        );
+        assert_eq!(second["system"], json!(manual));
+
+        let mut other = json!({"system": manual, "messages": [{"role": "user", "content": "Hi"}]});
+        assert_eq!(cache.annotate(&mut other, "messages"), Some(1));
+        assert_eq!(
+            other["system"][0]["cache_control"],
+            json!({"type": "ephemeral"})
+        );
+        assert_eq!(cache.annotate(&mut other, "messages"), None);
+    }
+
+    #[test]
+    fn test_prefix_cache_respects_existing_breakpoints_only() {
+        let cache = PrefixCache::new().with_min_tokens(1);
+        // Text that merely mentions the field is not a breakpoint.
+        let mentions = || {
+            json!({"messages": [
+                {"role": "user", "content": "What does \"cache_control\" do?"},
+                {"role": "user", "content": "Explain."},
+            ]})
+        };
+        cache.annotate(&mut mentions(), "messages");
+        assert_eq!(cache.annotate(&mut mentions(), "messages"), Some(2));
+
+        let placed = || {
+            json!({
+                "system": [{"type": "text", "text": "Be brief.", "cache_control": {"type": "ephemeral"}}],
+                "messages": [{"role": "user", "content": "Hi"}],
+            })
+        };
+        cache.annotate(&mut placed(), "messages");
+        let mut body = placed();
+        assert_eq!(cache.annotate(&mut body, "messages"), None);
+        assert_eq!(body, placed());
+    }
+}
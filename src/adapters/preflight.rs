@@ -0,0 +1,235 @@
+use crate::adapters::{AdapterFactory, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::memory::truncate_oldest;
+use crate::models::{AdapterWarning, AlternativeReason, Conversation, Model, ModelGraph};
+use serde::{Deserialize, Serialize};
+
+/// What `prepare_request` does with a conversation that does not fit the
+/// model's context window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextOverflowPolicy {
+    /// Fail with `AdapterError::ContextWindowExceeded`.
+    #[default]
+    Error,
+    /// Drop the oldest turns, see `truncate_oldest`.
+    Truncate,
+    /// Switch to a sibling model from the same vendor with a larger window.
+    SwitchModel,
+    /// Switch models if a sibling fits, truncate otherwise.
+    SwitchOrTruncate,
+}
+
+/// A request that fits its model's context window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedRequest {
+    pub model: Model,
+    pub conversation: Conversation,
+    pub estimated_tokens: u32,
+    /// Turns removed to make the conversation fit.
+    pub dropped_turns: usize,
+    /// Whether `model` differs from the one asked for.
+    pub switched_model: bool,
+    /// What was changed to make the request fit, for the response's
+    /// `warnings`.
+    pub warnings: Vec<AdapterWarning>,
+}
+
+/// Tokens the prompt may use once `max_tokens` is reserved for the reply.
+fn prompt_budget(model: &Model, options: &ExecuteOptions) -> u32 {
+    model
+        .context_length
+        .saturating_sub(options.max_tokens.unwrap_or(0))
+}
+
+fn can_replace(candidate: &Model, model: &Model, conversation: &Conversation) -> bool {
+    let needs_vision = conversation.turns.iter().any(|turn| turn.image_count() > 0);
+    candidate.vendor_name == model.vendor_name
+        && candidate.provider_name == model.provider_name
+        && candidate.get_path() != model.get_path()
+        && (!model.capabilities.supports_tools || candidate.capabilities.supports_tools)
+        && (!needs_vision || candidate.capabilities.supports_vision)
+}
+
+/// Like `prepare_request`, with sibling models taken from `candidates`
+/// instead of the global catalog.
+pub fn prepare_request_with(
+    model: &Model,
+    candidates: &[Model],
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+) -> Result<PreparedRequest> {
+    let tokens = conversation.estimated_tokens();
+    let budget = prompt_budget(model, options);
+    let fits = |tokens: u32, budget: u32| model.context_length == 0 || tokens <= budget;
+    if fits(tokens, budget) {
+        return Ok(PreparedRequest {
+            model: model.clone(),
+            conversation: conversation.clone(),
+            estimated_tokens: tokens,
+            dropped_turns: 0,
+            switched_model: false,
+            warnings: Vec::new(),
+        });
+    }
+
+    let policy = options.context_overflow.unwrap_or_default();
+    if matches!(
+        policy,
+        ContextOverflowPolicy::SwitchModel | ContextOverflowPolicy::SwitchOrTruncate
+    ) {
+        // A known variant of the model first, otherwise any sibling: the
+        // smallest window that fits, then the cheapest.
+        let mut models = candidates.to_vec();
+        if !models
+            .iter()
+            .any(|candidate| candidate.get_path() == model.get_path())
+        {
+            models.push(model.clone());
+        }
+        let graph = ModelGraph::infer(&models);
+        let reason = AlternativeReason::ContextOverflow {
+            min_context: tokens.saturating_add(options.max_tokens.unwrap_or(0)),
+        };
+        let sibling = model
+            .suggest_alternative(reason, &graph)
+            .filter(|candidate| can_replace(candidate, model, conversation))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .filter(|candidate| can_replace(candidate, model, conversation))
+                    .filter(|candidate| tokens <= prompt_budget(candidate, options))
+                    .min_by(|a, b| {
+                        a.context_length.cmp(&b.context_length).then(
+                            a.cost
+                                .prompt
+                                .total_cmp(&b.cost.prompt)
+                                .then(a.cost.completion.total_cmp(&b.cost.completion)),
+                        )
+                    })
+            });
+        if let Some(sibling) = sibling {
+            return Ok(PreparedRequest {
+                model: sibling.clone(),
+                conversation: conversation.clone(),
+                estimated_tokens: tokens,
+                dropped_turns: 0,
+                switched_model: true,
+                warnings: Vec::new(),
+            });
+        }
+    }
+
+    if matches!(
+        policy,
+        ContextOverflowPolicy::Truncate | ContextOverflowPolicy::SwitchOrTruncate
+    ) {
+        let truncated = truncate_oldest(conversation, budget);
+        let truncated_tokens = truncated.estimated_tokens();
+        if fits(truncated_tokens, budget) {
+            let dropped_turns = conversation.len() - truncated.len();
+            return Ok(PreparedRequest {
+                model: model.clone(),
+                dropped_turns,
+                conversation: truncated,
+                estimated_tokens: truncated_tokens,
+                switched_model: false,
+                warnings: vec![AdapterWarning::TruncatedConversation {
+                    model: model.get_path(),
+                    dropped_turns,
+                    tokens_before: tokens,
+                    tokens_after: truncated_tokens,
+                }],
+            });
+        }
+    }
+
+    Err(AdapterError::ContextWindowExceeded {
+        model: model.get_path(),
+        tokens,
+        limit: budget,
+        context_length: model.context_length,
+    })
+}
+
+/// Checks that `conversation` fits `model` before anything is sent and,
+/// following `options.context_overflow`, truncates it, picks a larger
+/// sibling model from the catalog, or fails with the exact token counts.
+/// `max_tokens` is reserved for the reply.
+pub async fn prepare_request(
+    model: &Model,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+) -> Result<PreparedRequest> {
+    let candidates = match options.context_overflow.unwrap_or_default() {
+        ContextOverflowPolicy::SwitchModel | ContextOverflowPolicy::SwitchOrTruncate => {
+            AdapterFactory::get_supported_models(None).await
+        }
+        _ => Vec::new(),
+    };
+    prepare_request_with(model, &candidates, conversation, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConversationRole, Turn, TurnType};
+
+    fn model(name: &str, context_length: u32) -> Model {
+        Model::test(name)
+            .with_provider("acme")
+            .with_context_length(context_length)
+            .with_cost(0.000001, 0.000002)
+    }
+
+    #[test]
+    fn test_prepare_request_policies() {
+        let turns = (0..20)
+            .map(|index| {
+                TurnType::Basic(Turn {
+                    role: if index % 2 == 0 {
+                        ConversationRole::User
+                    } else {
+                        ConversationRole::Assistant
+                    },
+                    content: "word ".repeat(40),
+                    name: None,
+                    metadata: None,
+                })
+            })
+            .collect();
+        let conversation = Conversation::with_turns(turns);
+        let small = model("small", 300);
+        let siblings = [model("large", 100_000), model("medium", 8_000)];
+
+        let err =
+            prepare_request_with(&small, &siblings, &conversation, &ExecuteOptions::default())
+                .unwrap_err();
+        assert!(matches!(
+            err,
+            AdapterError::ContextWindowExceeded { limit: 300, .. }
+        ));
+
+        let options = ExecuteOptions {
+            context_overflow: Some(ContextOverflowPolicy::SwitchModel),
+            ..ExecuteOptions::default()
+        };
+        let prepared = prepare_request_with(&small, &siblings, &conversation, &options).unwrap();
+        assert_eq!(prepared.model.name, "medium");
+        assert!(prepared.switched_model);
+
+        let options = ExecuteOptions {
+            context_overflow: Some(ContextOverflowPolicy::Truncate),
+            max_tokens: Some(100),
+            ..ExecuteOptions::default()
+        };
+        let prepared = prepare_request_with(&small, &siblings, &conversation, &options).unwrap();
+        assert!(prepared.dropped_turns > 0);
+        assert!(prepared.estimated_tokens <= 200);
+        assert!(matches!(
+            prepared.warnings[..],
+            [AdapterWarning::TruncatedConversation { dropped_turns, .. }]
+                if dropped_turns == prepared.dropped_turns
+        ));
+    }
+}
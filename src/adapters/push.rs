@@ -0,0 +1,171 @@
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, ConversationRole, Turn, TurnType};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use futures::stream;
+use futures::{Sink, SinkExt, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A piece of user input pushed while the model may still be answering.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputPiece {
+    Text(String),
+    Audio {
+        data: Vec<u8>,
+        mime_type: String,
+    },
+    /// Ends the user's turn and asks for a reply.
+    Commit,
+}
+
+pub type InputSink = Pin<Box<dyn Sink<InputPiece, Error = AdapterError> + Send>>;
+
+/// Duplex sessions: input is pushed into the sink while output arrives on
+/// the stream. Realtime and live APIs can implement this natively; any
+/// other adapter gets it through [`Buffered`].
+#[async_trait]
+pub trait PushSession: Send + Sync {
+    async fn open(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<(InputSink, AdapterStream)>;
+}
+
+/// Buffers pushed text until `InputPiece::Commit` (or the sink closing)
+/// and then streams a normal completion. Replies are appended to the
+/// session's conversation, so later turns see earlier ones. Audio is
+/// reported as unsupported on the output stream.
+pub struct Buffered {
+    adapter: Arc<dyn BaseAdapter>,
+    buffer: usize,
+}
+
+impl Buffered {
+    pub fn new(adapter: Arc<dyn BaseAdapter>) -> Self {
+        Self {
+            adapter,
+            buffer: 32,
+        }
+    }
+
+    pub fn with_buffer(mut self, buffer: usize) -> Self {
+        self.buffer = buffer;
+        self
+    }
+}
+
+struct BufferedState {
+    adapter: Arc<dyn BaseAdapter>,
+    conversation: Conversation,
+    options: ExecuteOptions,
+    input: mpsc::Receiver<InputPiece>,
+    pending: String,
+    reply: Option<(AdapterStream, String)>,
+    closed: bool,
+}
+
+impl BufferedState {
+    fn finish_reply(&mut self, content: String) {
+        self.conversation.add_turn(TurnType::Basic(Turn {
+            role: ConversationRole::Assistant,
+            content,
+            name: None,
+            metadata: None,
+        }));
+    }
+
+    async fn start_reply(&mut self) -> Result<()> {
+        self.conversation.add_turn(TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: std::mem::take(&mut self.pending),
+            name: None,
+            metadata: None,
+        }));
+        let stream = self
+            .adapter
+            .execute_stream(&self.conversation, &self.options)
+            .await?;
+        self.reply = Some((stream, String::new()));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PushSession for Buffered {
+    async fn open(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<(InputSink, AdapterStream)> {
+        let (sender, input) = mpsc::channel(self.buffer);
+        let sink = sender.sink_map_err(|err| AdapterError::StreamError(err.to_string()));
+        let state = BufferedState {
+            adapter: self.adapter.clone(),
+            conversation: conversation.clone(),
+            options: options.clone(),
+            input,
+            pending: String::new(),
+            reply: None,
+            closed: false,
+        };
+
+        let output = stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some((stream, content)) = &mut state.reply {
+                    match stream.next().await {
+                        Some(Ok(chunk)) => {
+                            if let Some(delta) = chunk
+                                .choices
+                                .first()
+                                .and_then(|choice| choice.delta.content.as_deref())
+                            {
+                                content.push_str(delta);
+                            }
+                            return Some((Ok(chunk), state));
+                        }
+                        Some(Err(err)) => {
+                            state.reply = None;
+                            return Some((Err(err), state));
+                        }
+                        None => {
+                            let (_, content) = state.reply.take()?;
+                            state.finish_reply(content);
+                            continue;
+                        }
+                    }
+                }
+                if state.closed {
+                    return None;
+                }
+                let piece = state.input.next().await;
+                let commit = match piece {
+                    Some(InputPiece::Text(text)) => {
+                        state.pending.push_str(&text);
+                        false
+                    }
+                    Some(InputPiece::Audio { mime_type, .. }) => {
+                        let err = AdapterError::UnsupportedFeature {
+                            model: state.adapter.get_model().get_path(),
+                            feature: format!("pushed {} audio", mime_type),
+                        };
+                        return Some((Err(err), state));
+                    }
+                    Some(InputPiece::Commit) => true,
+                    None => {
+                        state.closed = true;
+                        true
+                    }
+                };
+                if commit && !state.pending.is_empty() {
+                    if let Err(err) = state.start_reply().await {
+                        return Some((Err(err), state));
+                    }
+                }
+            }
+        });
+        Ok((Box::pin(sink), Box::pin(output)))
+    }
+}
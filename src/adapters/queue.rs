@@ -0,0 +1,391 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::Result;
+use crate::models::{AdapterChatCompletion, Conversation};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    Interactive,
+    Batch,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    pub default_concurrency: usize,
+    pub provider_concurrency: HashMap<String, usize>,
+    /// Number of interactive requests admitted for every batch request while
+    /// both classes are waiting, so batch traffic is slowed but never starved.
+    pub interactive_weight: u32,
+    /// When set, per-provider limits start at `initial` and are tuned from
+    /// observed rate limiting and latency instead of staying fixed.
+    pub adaptive: Option<AdaptiveConcurrency>,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            default_concurrency: 16,
+            provider_concurrency: HashMap::new(),
+            interactive_weight: 4,
+            adaptive: None,
+        }
+    }
+}
+
+/// AIMD tuning: every healthy response adds `increase / limit` to the limit
+/// (about `increase` per round of requests), every rate-limited or slow
+/// response multiplies it by `decrease_factor`.
+#[derive(Debug, Clone)]
+pub struct AdaptiveConcurrency {
+    pub initial: usize,
+    pub min: usize,
+    pub max: usize,
+    pub increase: f64,
+    pub decrease_factor: f64,
+    pub latency_target: Option<Duration>,
+}
+
+impl Default for AdaptiveConcurrency {
+    fn default() -> Self {
+        Self {
+            initial: 4,
+            min: 1,
+            max: 256,
+            increase: 1.0,
+            decrease_factor: 0.5,
+            latency_target: None,
+        }
+    }
+}
+
+impl QueueConfig {
+    pub fn with_provider_concurrency(mut self, provider: &str, limit: usize) -> Self {
+        self.provider_concurrency
+            .insert(provider.to_string(), limit.max(1));
+        self
+    }
+
+    pub fn with_adaptive(mut self, adaptive: AdaptiveConcurrency) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    fn initial_limit(&self, provider: &str) -> f64 {
+        match &self.adaptive {
+            Some(adaptive) => adaptive.initial.clamp(adaptive.min.max(1), adaptive.max) as f64,
+            None => self
+                .provider_concurrency
+                .get(provider)
+                .copied()
+                .unwrap_or(self.default_concurrency)
+                .max(1) as f64,
+        }
+    }
+}
+
+struct ProviderQueue {
+    active: usize,
+    limit: f64,
+    interactive: VecDeque<oneshot::Sender<QueuePermit>>,
+    batch: VecDeque<oneshot::Sender<QueuePermit>>,
+    interactive_streak: u32,
+}
+
+impl ProviderQueue {
+    fn new(limit: f64) -> Self {
+        Self {
+            active: 0,
+            limit,
+            interactive: VecDeque::new(),
+            batch: VecDeque::new(),
+            interactive_streak: 0,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        (self.limit.floor() as usize).max(1)
+    }
+
+    fn has_waiters(&self) -> bool {
+        !self.interactive.is_empty() || !self.batch.is_empty()
+    }
+
+    fn next_waiter(&mut self, weight: u32) -> Option<oneshot::Sender<QueuePermit>> {
+        let batch_turn = self.interactive_streak >= weight && !self.batch.is_empty();
+        if !batch_turn {
+            if let Some(waiter) = self.interactive.pop_front() {
+                self.interactive_streak += 1;
+                return Some(waiter);
+            }
+        }
+        self.interactive_streak = 0;
+        self.batch.pop_front()
+    }
+}
+
+struct QueueState {
+    config: QueueConfig,
+    providers: HashMap<String, ProviderQueue>,
+}
+
+/// Admission control in front of adapters: caps in-flight requests per
+/// provider and schedules waiting requests by priority class.
+#[derive(Clone)]
+pub struct RequestQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+/// A slot in the queue. The slot is handed to the next waiter when dropped.
+pub struct QueuePermit {
+    state: Arc<Mutex<QueueState>>,
+    provider: String,
+    armed: bool,
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        if self.armed {
+            release(&self.state, &self.provider);
+        }
+    }
+}
+
+fn release(state: &Arc<Mutex<QueueState>>, provider: &str) {
+    let mut guard = state.lock().unwrap();
+    if let Some(queue) = guard.providers.get_mut(provider) {
+        queue.active = queue.active.saturating_sub(1);
+    }
+    admit_waiters(state, &mut guard, provider);
+}
+
+fn admit_waiters(state: &Arc<Mutex<QueueState>>, guard: &mut QueueState, provider: &str) {
+    let QueueState { config, providers } = guard;
+    let Some(queue) = providers.get_mut(provider) else {
+        return;
+    };
+
+    while queue.active < queue.capacity() {
+        let Some(waiter) = queue.next_waiter(config.interactive_weight) else {
+            return;
+        };
+        let permit = QueuePermit {
+            state: state.clone(),
+            provider: provider.to_string(),
+            armed: true,
+        };
+        queue.active += 1;
+        if let Err(mut unclaimed) = waiter.send(permit) {
+            unclaimed.armed = false;
+            queue.active -= 1;
+        }
+    }
+}
+
+impl RequestQueue {
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                config,
+                providers: HashMap::new(),
+            })),
+        }
+    }
+
+    pub async fn acquire(&self, provider: &str, priority: Priority) -> QueuePermit {
+        let receiver = {
+            let mut guard = self.state.lock().unwrap();
+            let QueueState { config, providers } = &mut *guard;
+            let queue = providers
+                .entry(provider.to_string())
+                .or_insert_with(|| ProviderQueue::new(config.initial_limit(provider)));
+
+            if queue.active < queue.capacity() && !queue.has_waiters() {
+                queue.active += 1;
+                return QueuePermit {
+                    state: self.state.clone(),
+                    provider: provider.to_string(),
+                    armed: true,
+                };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            match priority {
+                Priority::Interactive => queue.interactive.push_back(sender),
+                Priority::Batch => queue.batch.push_back(sender),
+            }
+            receiver
+        };
+
+        receiver
+            .await
+            .expect("request queue dropped a waiter without a permit")
+    }
+
+    pub async fn execute<A: BaseAdapter + ?Sized>(
+        &self,
+        adapter: &A,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        priority: Priority,
+    ) -> Result<AdapterChatCompletion> {
+        let provider = adapter.get_model().provider_name.clone();
+        let _permit = self.acquire(&provider, priority).await;
+        let started = Instant::now();
+        let result = adapter.execute(conversation, options).await;
+        let rate_limited = matches!(&result, Err(err) if err.is_rate_limited());
+        self.record_outcome(&provider, started.elapsed(), rate_limited);
+        result
+    }
+
+    /// Feeds one request outcome into the adaptive limiter. A no-op unless
+    /// `QueueConfig::adaptive` is configured.
+    pub fn record_outcome(&self, provider: &str, latency: Duration, rate_limited: bool) {
+        let mut guard = self.state.lock().unwrap();
+        let Some(adaptive) = guard.config.adaptive.clone() else {
+            return;
+        };
+        let Some(queue) = guard.providers.get_mut(provider) else {
+            return;
+        };
+
+        let too_slow = adaptive
+            .latency_target
+            .is_some_and(|target| latency > target);
+        let min = adaptive.min.max(1) as f64;
+        let max = adaptive.max.max(adaptive.min.max(1)) as f64;
+        queue.limit = if rate_limited || too_slow {
+            (queue.limit * adaptive.decrease_factor).max(min)
+        } else {
+            (queue.limit + adaptive.increase / queue.limit).min(max)
+        };
+
+        admit_waiters(&self.state, &mut guard, provider);
+    }
+
+    pub fn current_limit(&self, provider: &str) -> Option<usize> {
+        let guard = self.state.lock().unwrap();
+        guard.providers.get(provider).map(ProviderQueue::capacity)
+    }
+
+    pub fn active(&self, provider: &str) -> usize {
+        let guard = self.state.lock().unwrap();
+        guard.providers.get(provider).map_or(0, |q| q.active)
+    }
+
+    pub fn waiting(&self, provider: &str) -> usize {
+        let guard = self.state.lock().unwrap();
+        guard
+            .providers
+            .get(provider)
+            .map_or(0, |q| q.interactive.len() + q.batch.len())
+    }
+}
+
+impl Default for RequestQueue {
+    fn default() -> Self {
+        Self::new(QueueConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::task::JoinHandle;
+
+    type Order = Arc<Mutex<Vec<&'static str>>>;
+
+    async fn spawn_waiter(
+        queue: &RequestQueue,
+        order: &Order,
+        label: &'static str,
+        priority: Priority,
+    ) -> JoinHandle<()> {
+        let waiting = queue.waiting("openai");
+        let task_queue = queue.clone();
+        let order = order.clone();
+        let handle = tokio::spawn(async move {
+            let _permit = task_queue.acquire("openai", priority).await;
+            order.lock().unwrap().push(label);
+        });
+        while queue.waiting("openai") == waiting {
+            tokio::task::yield_now().await;
+        }
+        handle
+    }
+
+    #[tokio::test]
+    async fn test_interactive_requests_jump_ahead_of_batch() {
+        let queue =
+            RequestQueue::new(QueueConfig::default().with_provider_concurrency("openai", 1));
+        let order = Order::default();
+
+        let held = queue.acquire("openai", Priority::Batch).await;
+        let handles = vec![
+            spawn_waiter(&queue, &order, "batch", Priority::Batch).await,
+            spawn_waiter(&queue, &order, "interactive", Priority::Interactive).await,
+        ];
+
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["interactive", "batch"]);
+        assert_eq!(queue.active("openai"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_not_starved() {
+        let config = QueueConfig {
+            interactive_weight: 1,
+            ..QueueConfig::default()
+        }
+        .with_provider_concurrency("openai", 1);
+        let queue = RequestQueue::new(config);
+        let order = Order::default();
+
+        let held = queue.acquire("openai", Priority::Interactive).await;
+        let handles = vec![
+            spawn_waiter(&queue, &order, "i1", Priority::Interactive).await,
+            spawn_waiter(&queue, &order, "i2", Priority::Interactive).await,
+            spawn_waiter(&queue, &order, "b1", Priority::Batch).await,
+        ];
+
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["i1", "b1", "i2"]);
+    }
+
+    #[tokio::test]
+    async fn test_adaptive_limit_backs_off_and_recovers() {
+        let config = QueueConfig::default().with_adaptive(AdaptiveConcurrency {
+            initial: 8,
+            min: 1,
+            max: 10,
+            latency_target: Some(Duration::from_secs(2)),
+            ..AdaptiveConcurrency::default()
+        });
+        let queue = RequestQueue::new(config);
+        drop(queue.acquire("openai", Priority::Interactive).await);
+        assert_eq!(queue.current_limit("openai"), Some(8));
+
+        queue.record_outcome("openai", Duration::from_millis(100), true);
+        assert_eq!(queue.current_limit("openai"), Some(4));
+        queue.record_outcome("openai", Duration::from_secs(5), false);
+        assert_eq!(queue.current_limit("openai"), Some(2));
+
+        for _ in 0..20 {
+            queue.record_outcome("openai", Duration::from_millis(100), false);
+        }
+        assert_eq!(queue.current_limit("openai"), Some(6));
+
+        for _ in 0..200 {
+            queue.record_outcome("openai", Duration::from_millis(100), false);
+        }
+        assert_eq!(queue.current_limit("openai"), Some(10));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{AdapterChatCompletionChunk, Conversation};
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+struct ResumeState {
+    adapter: Arc<dyn BaseAdapter>,
+    conversation: Conversation,
+    options: ExecuteOptions,
+    inner: AdapterStream,
+    partial: BTreeMap<u32, String>,
+    resumes_left: u32,
+    resumed: bool,
+    done: bool,
+}
+
+impl ResumeState {
+    fn record(&mut self, chunk: &mut AdapterChatCompletionChunk) {
+        for choice in &mut chunk.choices {
+            if self.resumed {
+                choice.delta.role = None;
+            }
+            if let Some(content) = &choice.delta.content {
+                self.partial
+                    .entry(choice.index)
+                    .or_default()
+                    .push_str(content);
+            }
+        }
+    }
+
+    /// Only single-choice text streams can be continued: the accumulated text
+    /// becomes the assistant prefix of a new request.
+    fn can_resume(&self) -> bool {
+        self.resumes_left > 0
+            && self.adapter.get_model().capabilities.supports_prefill
+            && self.partial.keys().all(|index| *index == 0)
+    }
+
+    fn continuation(&self) -> Conversation {
+        let partial = self.partial.get(&0).cloned().unwrap_or_default();
+        let mut conversation = self.conversation.clone();
+        match conversation.assistant_prefill().map(str::to_string) {
+            Some(prefill) => {
+                conversation.turns.pop();
+                conversation.with_assistant_prefill(prefill + &partial)
+            }
+            None => conversation.with_assistant_prefill(partial),
+        }
+    }
+
+    fn interrupted(&self, err: &AdapterError) -> AdapterError {
+        AdapterError::StreamInterrupted {
+            partial: self.partial.get(&0).cloned().unwrap_or_default(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+/// Network-level failures after the stream started; provider error frames
+/// are not retried.
+fn is_connection_drop(err: &AdapterError) -> bool {
+    match err {
+        AdapterError::HttpError(_) | AdapterError::StreamError(_) => true,
+        AdapterError::Shared(inner) => is_connection_drop(inner),
+        _ => false,
+    }
+}
+
+/// Streams a completion and recovers from dropped connections. Models with
+/// prefill support are re-requested up to `max_resumes` times with the text
+/// received so far as the assistant prefix, and the stream continues where
+/// it stopped. Otherwise the stream ends with
+/// `AdapterError::StreamInterrupted` carrying the partial content.
+pub async fn resumable_stream(
+    adapter: Arc<dyn BaseAdapter>,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+    max_resumes: u32,
+) -> Result<AdapterStream> {
+    let inner = adapter.execute_stream(conversation, options).await?;
+    let state = ResumeState {
+        adapter,
+        conversation: conversation.clone(),
+        options: options.clone(),
+        inner,
+        partial: BTreeMap::new(),
+        resumes_left: max_resumes,
+        resumed: false,
+        done: false,
+    };
+
+    Ok(Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if state.done {
+                return None;
+            }
+            match state.inner.next().await {
+                Some(Ok(mut chunk)) => {
+                    state.record(&mut chunk);
+                    return Some((Ok(chunk), state));
+                }
+                Some(Err(err)) if is_connection_drop(&err) => {
+                    if !state.can_resume() {
+                        state.done = true;
+                        let interrupted = state.interrupted(&err);
+                        return Some((Err(interrupted), state));
+                    }
+                    state.resumes_left -= 1;
+                    let continuation = state.continuation();
+                    match state
+                        .adapter
+                        .execute_stream(&continuation, &state.options)
+                        .await
+                    {
+                        Ok(inner) => {
+                            state.inner = inner;
+                            state.resumed = true;
+                        }
+                        Err(_) => {
+                            state.done = true;
+                            let interrupted = state.interrupted(&err);
+                            return Some((Err(interrupted), state));
+                        }
+                    }
+                }
+                Some(Err(err)) => {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+                None => return None,
+            }
+        }
+    })))
+}
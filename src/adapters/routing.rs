@@ -0,0 +1,361 @@
+use crate::adapters::ExecuteOptions;
+use crate::error::{AdapterError, Result};
+use crate::models::{ContentEntryData, Conversation, PromptReference, TurnType};
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Modality {
+    Text,
+    Image,
+    Video,
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// What routing rules know about a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingContext {
+    pub tenant: Option<String>,
+    pub region: Option<String>,
+    /// e.g. `zh`, as detected or declared by the caller.
+    pub language: Option<String>,
+    pub modalities: Vec<Modality>,
+    /// Unix seconds.
+    pub timestamp: u64,
+}
+
+impl RoutingContext {
+    /// The modalities of `conversation`, at the current time.
+    pub fn new(conversation: &Conversation) -> Self {
+        let mut modalities = Vec::new();
+        let mut add = |modality| {
+            if !modalities.contains(&modality) {
+                modalities.push(modality);
+            }
+        };
+        for turn in &conversation.turns {
+            match turn {
+                TurnType::Content(turn) => {
+                    for entry in &turn.content {
+                        add(match entry.data {
+                            ContentEntryData::Text { .. } => Modality::Text,
+                            ContentEntryData::Image { .. } => Modality::Image,
+                            ContentEntryData::Video { .. } => Modality::Video,
+                            ContentEntryData::File { .. } => Modality::File,
+                        });
+                    }
+                }
+                turn if !turn.text().is_empty() => add(Modality::Text),
+                _ => {}
+            }
+        }
+        Self {
+            tenant: None,
+            region: None,
+            language: None,
+            modalities,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    pub fn at(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    fn hour(&self) -> u8 {
+        (self.timestamp % 86_400 / 3600) as u8
+    }
+
+    fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday.
+        WEEKDAYS[((self.timestamp / 86_400 + 3) % 7) as usize]
+    }
+}
+
+/// Conditions of a rule; all that are set must hold. Lists match when the
+/// request's value is one of them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenants: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regions: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// Matches when the request uses any of these.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+    /// UTC hours as `start-end`, end exclusive; `22-6` wraps past midnight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hours: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekdays: Option<Vec<Weekday>>,
+}
+
+fn parse_hours(hours: &str) -> Option<(u8, u8)> {
+    let (start, end) = hours.split_once('-')?;
+    let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+    (start < 24 && end <= 24 && start != end).then_some((start, end))
+}
+
+fn listed(values: &Option<Vec<String>>, value: Option<&str>) -> bool {
+    values.as_ref().is_none_or(|values| {
+        value.is_some_and(|value| values.iter().any(|v| v.eq_ignore_ascii_case(value)))
+    })
+}
+
+impl RuleCondition {
+    pub fn matches(&self, context: &RoutingContext) -> bool {
+        let in_hours = self.hours.as_deref().is_none_or(|hours| {
+            let hour = context.hour();
+            match parse_hours(hours) {
+                Some((start, end)) if start < end => (start..end).contains(&hour),
+                Some((start, end)) => hour >= start || hour < end,
+                None => false,
+            }
+        });
+        listed(&self.tenants, context.tenant.as_deref())
+            && listed(&self.regions, context.region.as_deref())
+            && listed(&self.languages, context.language.as_deref())
+            && self.modalities.as_ref().is_none_or(|modalities| {
+                modalities
+                    .iter()
+                    .any(|modality| context.modalities.contains(modality))
+            })
+            && self
+                .weekdays
+                .as_ref()
+                .is_none_or(|weekdays| weekdays.contains(&context.weekday()))
+            && in_hours
+    }
+}
+
+/// Sends matching requests to `model` and/or layers `options` over theirs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pub name: String,
+    #[serde(default)]
+    pub when: RuleCondition,
+    /// A model path, e.g. `openai/openai/gpt-4o-mini`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<ExecuteOptions>,
+    /// A `PromptRegistry` reference such as `prompt://support@2`, so rules
+    /// can roll prompts out to a subset of traffic.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+/// The outcome of `RoutingRules::route`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingDecision {
+    /// The rule that matched, `None` when none did.
+    pub rule: Option<String>,
+    pub model: String,
+    pub options: ExecuteOptions,
+    pub prompt: Option<PromptReference>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    rule: Vec<RoutingRule>,
+}
+
+/// Declarative routing policy, evaluated per request; the first matching
+/// rule wins.
+///
+/// ```toml
+/// [[rule]]
+/// name = "cjk"
+/// model = "openai/openai/gpt-4o"
+/// when = { languages = ["zh", "ja"] }
+///
+/// [[rule]]
+/// name = "off-peak"
+/// model = "openai/openai/gpt-4o-mini"
+/// when = { hours = "22-6", tenants = ["acme"] }
+/// options = { temperature = 0.2 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutingRules {
+    rules: Vec<RoutingRule>,
+}
+
+impl RoutingRules {
+    pub fn new(rules: Vec<RoutingRule>) -> Result<Self> {
+        for rule in &rules {
+            let invalid = |reason: String| {
+                AdapterError::ConfigError(format!("routing rule {}: {}", rule.name, reason))
+            };
+            if let Some(hours) = &rule.when.hours {
+                parse_hours(hours).ok_or_else(|| invalid(format!("bad hours {}", hours)))?;
+            }
+            if let Some(model) = &rule.model {
+                if model.split('/').count() != 3 {
+                    return Err(invalid(format!(
+                        "model {} is not a provider/vendor/name path",
+                        model
+                    )));
+                }
+            }
+            if let Some(options) = &rule.options {
+                options.validate().map_err(|err| invalid(err.to_string()))?;
+            }
+            if let Some(prompt) = &rule.prompt {
+                PromptReference::parse(prompt)
+                    .ok_or_else(|| invalid(format!("bad prompt reference {}", prompt)))?;
+            }
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn from_toml(rules: &str) -> Result<Self> {
+        let file: RulesFile = toml::from_str(rules)?;
+        Self::new(file.rule)
+    }
+
+    pub fn rules(&self) -> &[RoutingRule] {
+        &self.rules
+    }
+
+    pub fn evaluate(&self, context: &RoutingContext) -> Option<&RoutingRule> {
+        self.rules.iter().find(|rule| rule.when.matches(context))
+    }
+
+    /// The model, options and prompt for a request for `model` with
+    /// `options`. A rule's options fill in what the request leaves unset.
+    pub fn route(
+        &self,
+        context: &RoutingContext,
+        model: &str,
+        options: &ExecuteOptions,
+    ) -> RoutingDecision {
+        let Some(rule) = self.evaluate(context) else {
+            return RoutingDecision {
+                rule: None,
+                model: model.to_string(),
+                options: options.clone(),
+                prompt: None,
+            };
+        };
+        RoutingDecision {
+            rule: Some(rule.name.clone()),
+            model: rule.model.clone().unwrap_or_else(|| model.to_string()),
+            options: match &rule.options {
+                Some(defaults) => options.clone().merge(defaults),
+                None => options.clone(),
+            },
+            prompt: rule.prompt.as_deref().and_then(PromptReference::parse),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConversationRole, Turn};
+
+    #[test]
+    fn test_routing_rules() {
+        let rules = RoutingRules::from_toml(
+            r#"
+            [[rule]]
+            name = "cjk"
+            model = "openai/openai/gpt-4o"
+            when = { languages = ["zh", "ja"] }
+
+            [[rule]]
+            name = "off-peak"
+            model = "openai/openai/gpt-4o-mini"
+            when = { hours = "22-6", weekdays = ["sat", "sun"] }
+            options = { temperature = 0.2 }
+            prompt = "prompt://concise@3"
+            "#,
+        )
+        .unwrap();
+        let conversation = Conversation::with_turns(vec![TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: "Hello".to_string(),
+            name: None,
+            metadata: None,
+        })]);
+        let options = ExecuteOptions {
+            max_tokens: Some(100),
+            ..ExecuteOptions::default()
+        };
+        // Saturday 2024-03-02, 23:00 UTC.
+        let context = RoutingContext::new(&conversation).at(1_709_420_400);
+        assert_eq!(context.modalities, vec![Modality::Text]);
+
+        let decision = rules.route(&context, "openai/openai/gpt-4o", &options);
+        assert_eq!(decision.rule.as_deref(), Some("off-peak"));
+        assert_eq!(decision.model, "openai/openai/gpt-4o-mini");
+        assert_eq!(decision.options.temperature, Some(0.2));
+        assert_eq!(decision.options.max_tokens, Some(100));
+        assert_eq!(
+            decision.prompt.map(|prompt| prompt.to_string()).as_deref(),
+            Some("prompt://concise@3")
+        );
+
+        let decision = rules.route(&context.clone().with_language("ZH"), "x/y/z", &options);
+        assert_eq!(decision.rule.as_deref(), Some("cjk"));
+
+        // Friday noon.
+        let decision = rules.route(&context.at(1_709_294_400), "x/y/z", &options);
+        assert_eq!(decision.rule, None);
+        assert_eq!(decision.model, "x/y/z");
+
+        assert!(
+            RoutingRules::from_toml("[[rule]]\nname = \"bad\"\nwhen = { hours = \"25-3\" }")
+                .is_err()
+        );
+        assert!(
+            RoutingRules::from_toml("[[rule]]\nname = \"bad\"\nprompt = \"support@2\"").is_err()
+        );
+    }
+}
@@ -0,0 +1,302 @@
+use crate::adapters::postprocess::strip_code_fence;
+use crate::adapters::{BaseAdapter, ExecuteOptions, ExecutionGroup};
+use crate::error::{AdapterError, Result};
+use crate::models::{
+    AdapterChatCompletion, Conversation, ConversationRole, CostBreakdown, TokenUsage, Turn,
+    TurnType,
+};
+use futures::future::join_all;
+use serde_json::Value;
+use std::sync::Arc;
+
+const DEFAULT_SAMPLING_TEMPERATURE: f64 = 0.7;
+
+#[derive(Debug, Clone)]
+pub struct Consensus<T> {
+    pub answer: T,
+    pub votes: usize,
+    /// Samples that produced a parseable answer.
+    pub samples: usize,
+    /// Samples that failed or could not be parsed.
+    pub failures: usize,
+    /// Share of parsed samples that agree with `answer`.
+    pub agreement: f64,
+    /// Every distinct answer with its vote count, most votes first.
+    pub distribution: Vec<(T, usize)>,
+    pub usage: Option<TokenUsage>,
+    pub cost: f64,
+    pub cost_breakdown: Option<CostBreakdown>,
+}
+
+/// Parses a reply as JSON, tolerating a surrounding code fence.
+pub fn json_answer(output: &str) -> Option<Value> {
+    serde_json::from_str(strip_code_fence(output)).ok()
+}
+
+/// Self-consistency sampling: runs the prompt `samples` times concurrently,
+/// each with its own seed (counting up from `options.seed`) and a sampling
+/// temperature of 0.7 unless `options.temperature` is set, parses every
+/// reply with `parse` and returns the most common answer. Ties go to the
+/// answer seen first.
+pub async fn majority_vote<A, T, F>(
+    adapter: &A,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+    samples: u32,
+    parse: F,
+) -> Result<Consensus<T>>
+where
+    A: BaseAdapter + ?Sized,
+    T: PartialEq + Clone,
+    F: Fn(&str) -> Option<T>,
+{
+    if samples == 0 {
+        return Err(AdapterError::ConfigError(
+            "majority vote needs at least one sample".to_string(),
+        ));
+    }
+
+    let base_seed = options.seed.unwrap_or(0);
+    let requests = (0..samples).map(|sample| {
+        let options = ExecuteOptions {
+            seed: Some(base_seed.wrapping_add(sample as u64)),
+            temperature: options.temperature.or(Some(DEFAULT_SAMPLING_TEMPERATURE)),
+            n: None,
+            ..options.clone()
+        };
+        async move { adapter.execute(conversation, &options).await }
+    });
+    let responses = join_all(requests).await;
+
+    let mut usage: Option<TokenUsage> = None;
+    let mut cost = 0.0;
+    let mut cost_breakdown: Option<CostBreakdown> = None;
+    let mut distribution: Vec<(T, usize)> = Vec::new();
+    let mut failures = 0;
+    let mut last_error = None;
+
+    for response in responses {
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                failures += 1;
+                last_error = Some(err);
+                continue;
+            }
+        };
+        if let Some(sample_usage) = &response.usage {
+            usage
+                .get_or_insert_with(|| TokenUsage::new(0, 0))
+                .accumulate(sample_usage);
+        }
+        cost += response.cost;
+        if let Some(breakdown) = &response.cost_breakdown {
+            cost_breakdown
+                .get_or_insert_with(CostBreakdown::default)
+                .accumulate(breakdown);
+        }
+
+        let parsed = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.as_deref())
+            .and_then(&parse);
+        match parsed {
+            Some(answer) => match distribution.iter_mut().find(|(seen, _)| *seen == answer) {
+                Some((_, votes)) => *votes += 1,
+                None => distribution.push((answer, 1)),
+            },
+            None => failures += 1,
+        }
+    }
+
+    // Stable sort keeps first-seen order among equal vote counts.
+    distribution.sort_by_key(|(_, votes)| std::cmp::Reverse(*votes));
+    let parsed: usize = distribution.iter().map(|(_, votes)| votes).sum();
+    let Some((answer, votes)) = distribution.first().cloned() else {
+        return Err(last_error.unwrap_or_else(|| {
+            AdapterError::Unknown(format!("none of the {} samples could be parsed", samples))
+        }));
+    };
+
+    Ok(Consensus {
+        answer,
+        votes,
+        samples: parsed,
+        failures,
+        agreement: votes as f64 / parsed as f64,
+        distribution,
+        usage,
+        cost,
+        cost_breakdown,
+    })
+}
+
+const RERANK_INSTRUCTION: &str = "You compare candidate answers to the conversation below. Score \
+every candidate from 0 to 10 for correctness and helpfulness. Reply with JSON only, for example \
+{\"scores\": [7, 9]}, listing one score per candidate in order.";
+
+#[derive(Debug, Clone)]
+pub struct RankedCandidate {
+    pub content: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BestOf {
+    pub chosen: AdapterChatCompletion,
+    /// Position of `chosen` in `candidates`.
+    pub chosen_index: usize,
+    pub candidates: Vec<RankedCandidate>,
+    /// Generation and reranking combined.
+    pub usage: Option<TokenUsage>,
+    pub cost: f64,
+}
+
+/// Samples `n` candidates from `generator` in parallel, asks `reranker` to
+/// score them and returns the highest scoring one. Failed samples are
+/// dropped; ties go to the earlier candidate.
+pub async fn best_of(
+    n: u32,
+    generator: Arc<dyn BaseAdapter>,
+    reranker: Arc<dyn BaseAdapter>,
+    conversation: &Conversation,
+    options: &ExecuteOptions,
+) -> Result<BestOf> {
+    let base_seed = options.seed.unwrap_or(0);
+    let mut group = ExecutionGroup::new();
+    for sample in 0..n {
+        let options = ExecuteOptions {
+            seed: Some(base_seed.wrapping_add(sample as u64)),
+            temperature: options.temperature.or(Some(DEFAULT_SAMPLING_TEMPERATURE)),
+            n: None,
+            ..options.clone()
+        };
+        group.spawn(generator.clone(), conversation, &options)?;
+    }
+
+    let mut samples: Vec<(usize, AdapterChatCompletion)> = Vec::new();
+    let mut last_error = None;
+    for outcome in group.join_all().await.completed {
+        match outcome.result {
+            Ok(response) => samples.push((outcome.index, response)),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    samples.sort_by_key(|(index, _)| *index);
+    if samples.is_empty() {
+        return Err(last_error.unwrap_or_else(|| {
+            AdapterError::ConfigError("best_of needs at least one sample".to_string())
+        }));
+    }
+
+    let mut usage: Option<TokenUsage> = None;
+    let mut cost = 0.0;
+    let mut accumulate = |response: &AdapterChatCompletion| {
+        if let Some(response_usage) = &response.usage {
+            usage
+                .get_or_insert_with(|| TokenUsage::new(0, 0))
+                .accumulate(response_usage);
+        }
+        cost += response.cost;
+    };
+    let contents: Vec<String> = samples
+        .iter()
+        .map(|(_, response)| {
+            accumulate(response);
+            response
+                .choices
+                .first()
+                .and_then(|choice| choice.message.content.clone())
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let verdict = reranker
+        .execute(
+            &rerank_request(conversation, &contents),
+            &ExecuteOptions {
+                temperature: Some(0.0),
+                ..ExecuteOptions::default()
+            },
+        )
+        .await?;
+    accumulate(&verdict);
+    let reply = verdict
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.as_deref())
+        .unwrap_or_default();
+    let scores = parse_scores(reply, contents.len()).ok_or_else(|| {
+        AdapterError::Unknown(format!("reranker reply has no usable scores: {}", reply))
+    })?;
+
+    let mut chosen_index = 0;
+    for (index, score) in scores.iter().enumerate() {
+        if *score > scores[chosen_index] {
+            chosen_index = index;
+        }
+    }
+    let candidates = contents
+        .into_iter()
+        .zip(scores)
+        .map(|(content, score)| RankedCandidate { content, score })
+        .collect();
+
+    Ok(BestOf {
+        chosen: samples.swap_remove(chosen_index).1,
+        chosen_index,
+        candidates,
+        usage,
+        cost,
+    })
+}
+
+fn rerank_request(conversation: &Conversation, candidates: &[String]) -> Conversation {
+    let transcript = conversation
+        .turns
+        .iter()
+        .map(|turn| format!("{}: {}", turn.role(), turn.text()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let candidates = candidates
+        .iter()
+        .enumerate()
+        .map(|(index, content)| {
+            format!(
+                "<candidate id=\"{}\">\n{}\n</candidate>",
+                index + 1,
+                content
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Conversation::with_turns(vec![
+        TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: RERANK_INSTRUCTION.to_string(),
+            name: None,
+            metadata: None,
+        }),
+        TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: format!(
+                "Conversation:\n{}\n\nCandidates:\n{}",
+                transcript, candidates
+            ),
+            name: None,
+            metadata: None,
+        }),
+    ])
+}
+
+fn parse_scores(reply: &str, expected: usize) -> Option<Vec<f64>> {
+    let scores: Vec<f64> = json_answer(reply)?
+        .get("scores")?
+        .as_array()?
+        .iter()
+        .map(Value::as_f64)
+        .collect::<Option<_>>()?;
+    (scores.len() == expected).then_some(scores)
+}
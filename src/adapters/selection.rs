@@ -0,0 +1,272 @@
+use crate::adapters::{Modality, ModelFilter, RoutingContext};
+use crate::config::ModelLanguages;
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, Model, TurnType};
+use crate::utils::conversation_language;
+use std::fmt;
+use std::sync::Arc;
+
+/// What `auto_select` read from a conversation and its constraints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestProfile {
+    pub estimated_tokens: u32,
+    /// Tokens reserved for the reply.
+    pub max_tokens: u32,
+    pub modalities: Vec<Modality>,
+    pub needs_tools: bool,
+    pub needs_json: bool,
+    /// Detected from the conversation, e.g. `zh`.
+    pub language: Option<String>,
+}
+
+impl RequestProfile {
+    /// What the request would cost on `model`.
+    pub fn estimated_cost(&self, model: &Model) -> f64 {
+        self.estimated_tokens as f64 * model.cost.prompt
+            + self.max_tokens as f64 * model.cost.completion
+            + model.cost.request
+    }
+
+    /// Whether `model` can serve the request at all.
+    pub fn fits(&self, model: &Model) -> bool {
+        let capabilities = &model.capabilities;
+        (model.context_length == 0
+            || self.estimated_tokens.saturating_add(self.max_tokens) <= model.context_length)
+            && (!self.modalities.contains(&Modality::Image) || capabilities.supports_vision)
+            && (!self.modalities.contains(&Modality::Video) || capabilities.supports_video)
+            && (!self.needs_tools || capabilities.supports_tools)
+            && (!self.needs_json || capabilities.supports_json_output)
+    }
+}
+
+/// Ranks the models that fit a request; the highest score wins. Implement
+/// it to plug in a trained selector.
+pub trait ModelScorer: Send + Sync {
+    fn score(&self, model: &Model, request: &RequestProfile) -> f64;
+}
+
+impl<F> ModelScorer for F
+where
+    F: Fn(&Model, &RequestProfile) -> f64 + Send + Sync,
+{
+    fn score(&self, model: &Model, request: &RequestProfile) -> f64 {
+        self(model, request)
+    }
+}
+
+/// The default scorer: the cheapest model, preferring ones strong in the
+/// conversation's language.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicScorer;
+
+impl ModelScorer for HeuristicScorer {
+    fn score(&self, model: &Model, request: &RequestProfile) -> f64 {
+        let language_bonus = match &request.language {
+            Some(language) if ModelLanguages::is_strong_in(&model.name, language) => 1.0,
+            _ => 0.0,
+        };
+        language_bonus - request.estimated_cost(model)
+    }
+}
+
+/// Requirements for `AdapterFactory::auto_select` beyond what the
+/// conversation implies.
+#[derive(Clone, Default)]
+pub struct SelectionConstraints {
+    /// Most the request may cost, in dollars.
+    pub max_cost: Option<f64>,
+    pub max_tokens: Option<u32>,
+    pub tools: bool,
+    pub json: bool,
+    pub filter: Option<ModelFilter>,
+    pub scorer: Option<Arc<dyn ModelScorer>>,
+}
+
+impl fmt::Debug for SelectionConstraints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SelectionConstraints")
+            .field("max_cost", &self.max_cost)
+            .field("max_tokens", &self.max_tokens)
+            .field("tools", &self.tools)
+            .field("json", &self.json)
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SelectionConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_cost(mut self, max_cost: f64) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_tools(mut self, tools: bool) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: ModelFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    pub fn with_scorer(mut self, scorer: impl ModelScorer + 'static) -> Self {
+        self.scorer = Some(Arc::new(scorer));
+        self
+    }
+
+    /// The profile of `conversation` under these constraints. Tool calls in
+    /// the history need a model with tools too.
+    pub fn profile(&self, conversation: &Conversation) -> RequestProfile {
+        let has_tool_turns = conversation.turns.iter().any(|turn| {
+            matches!(
+                turn,
+                TurnType::ToolCalls { .. } | TurnType::ToolOutput { .. }
+            )
+        });
+        RequestProfile {
+            estimated_tokens: conversation.estimated_tokens(),
+            max_tokens: self.max_tokens.unwrap_or(0),
+            modalities: RoutingContext::new(conversation).modalities,
+            needs_tools: self.tools || has_tool_turns,
+            needs_json: self.json,
+            language: conversation_language(conversation).map(str::to_string),
+        }
+    }
+}
+
+/// Like `AdapterFactory::auto_select`, choosing from `models`.
+pub fn select_model(
+    models: &[Model],
+    conversation: &Conversation,
+    constraints: &SelectionConstraints,
+) -> Result<Model> {
+    let profile = constraints.profile(conversation);
+    let scorer: &dyn ModelScorer = match &constraints.scorer {
+        Some(scorer) => scorer.as_ref(),
+        None => &HeuristicScorer,
+    };
+    models
+        .iter()
+        .filter(|model| {
+            constraints
+                .filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(model))
+        })
+        .filter(|model| profile.fits(model))
+        .filter(|model| {
+            constraints
+                .max_cost
+                .is_none_or(|max_cost| profile.estimated_cost(model) <= max_cost)
+        })
+        .map(|model| (scorer.score(model, &profile), model))
+        .max_by(|(a, model_a), (b, model_b)| {
+            a.total_cmp(b)
+                .then_with(|| model_b.get_path().cmp(&model_a.get_path()))
+        })
+        .map(|(_, model)| model.clone())
+        .ok_or_else(|| {
+            AdapterError::ModelNotFound(format!(
+                "no model fits {} tokens with modalities {:?}{}{}{}",
+                profile.estimated_tokens.saturating_add(profile.max_tokens),
+                profile.modalities,
+                if profile.needs_tools { ", tools" } else { "" },
+                if profile.needs_json {
+                    ", JSON output"
+                } else {
+                    ""
+                },
+                constraints
+                    .max_cost
+                    .map(|max_cost| format!(" under ${}", max_cost))
+                    .unwrap_or_default()
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentEntry, ContentTurn, ConversationRole, ImageUrl, ModelCapabilities};
+
+    fn model(name: &str, context_length: u32, prompt: f64, vision: bool) -> Model {
+        Model::test(name)
+            .with_provider("acme")
+            .with_context_length(context_length)
+            .with_cost(prompt, prompt * 4.0)
+            .with_capabilities(ModelCapabilities {
+                supports_vision: vision,
+                supports_tools: true,
+                ..ModelCapabilities::default()
+            })
+    }
+
+    #[test]
+    fn test_select_model() {
+        let models = [
+            model("tiny", 4_000, 0.0000001, false),
+            model("vision", 128_000, 0.000003, true),
+            model("long", 1_000_000, 0.000001, false),
+        ];
+        let text = |text: String| {
+            Conversation::with_turns(vec![TurnType::Content(ContentTurn {
+                role: ConversationRole::User,
+                content: vec![ContentEntry::text(text)],
+                name: None,
+                metadata: None,
+            })])
+        };
+        let constraints = SelectionConstraints::new().with_max_tokens(500);
+
+        let short = text("Hello".to_string());
+        assert_eq!(
+            select_model(&models, &short, &constraints).unwrap().name,
+            "tiny"
+        );
+        let long = text("word ".repeat(20_000));
+        assert_eq!(
+            select_model(&models, &long, &constraints).unwrap().name,
+            "long"
+        );
+
+        let mut image = short.clone();
+        if let TurnType::Content(turn) = &mut image.turns[0] {
+            turn.content.push(ContentEntry::image(ImageUrl {
+                url: "https://example.com/cat.png".to_string(),
+                detail: None,
+            }));
+        }
+        assert_eq!(
+            select_model(&models, &image, &constraints).unwrap().name,
+            "vision"
+        );
+        assert!(select_model(
+            &models,
+            &image,
+            &constraints.clone().with_max_cost(0.0000001)
+        )
+        .is_err());
+
+        let prefer_large = constraints
+            .with_scorer(|model: &Model, _: &RequestProfile| model.context_length as f64);
+        assert_eq!(
+            select_model(&models, &short, &prefer_large).unwrap().name,
+            "long"
+        );
+    }
+}
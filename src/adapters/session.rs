@@ -0,0 +1,403 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{
+    AdapterChatCompletion, Conversation, ConversationRole, TokenUsage, ToolCall, Turn, TurnType,
+};
+use crate::store::ConversationStore;
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Metadata key marking an assistant turn whose stream has not finished.
+pub const PARTIAL_TURN_KEY: &str = "partial";
+
+/// A conversation with one adapter that only accepts turns in an order the
+/// model can take, appends the model's replies and totals usage and cost.
+pub struct ChatSession {
+    adapter: Arc<dyn BaseAdapter>,
+    options: ExecuteOptions,
+    conversation: Conversation,
+    usage: TokenUsage,
+    cost: f64,
+    last_response: Option<AdapterChatCompletion>,
+    store: Option<(Arc<dyn ConversationStore>, String)>,
+    persist_every: usize,
+}
+
+impl ChatSession {
+    pub fn new(adapter: Arc<dyn BaseAdapter>) -> Self {
+        Self {
+            adapter,
+            options: ExecuteOptions::default(),
+            conversation: Conversation::new(),
+            usage: TokenUsage::new(0, 0),
+            cost: 0.0,
+            last_response: None,
+            store: None,
+            persist_every: 64,
+        }
+    }
+
+    /// Saves the transcript to `store` under `id` after every reply and,
+    /// while streaming, every `with_persist_every` characters.
+    pub fn with_store(mut self, store: Arc<dyn ConversationStore>, id: impl Into<String>) -> Self {
+        self.store = Some((store, id.into()));
+        self
+    }
+
+    pub fn with_persist_every(mut self, chars: usize) -> Self {
+        self.persist_every = chars.max(1);
+        self
+    }
+
+    /// Reopens the session saved under `id`, replaying its turns. A reply
+    /// that was cut off mid-stream stays in the transcript and is reported
+    /// by `partial_reply`.
+    pub async fn resume(
+        adapter: Arc<dyn BaseAdapter>,
+        store: Arc<dyn ConversationStore>,
+        id: impl Into<String>,
+    ) -> Result<Self> {
+        let id = id.into();
+        let conversation = store.load(&id).await?.unwrap_or_default();
+        Self::new(adapter)
+            .with_store(store, id)
+            .with_conversation(conversation)
+    }
+
+    /// Writes the transcript to the store, if there is one.
+    pub async fn save(&self) -> Result<()> {
+        match &self.store {
+            Some((store, id)) => store.save(id, &self.conversation).await,
+            None => Ok(()),
+        }
+    }
+
+    /// The text of the last reply if its stream never finished.
+    pub fn partial_reply(&self) -> Option<String> {
+        let last = self.conversation.turns.last()?;
+        let partial = last.metadata()?.get(PARTIAL_TURN_KEY) == Some(&Value::Bool(true));
+        partial.then(|| last.text())
+    }
+
+    pub fn with_options(mut self, options: ExecuteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Starts the session with a system prompt.
+    pub fn with_system(mut self, text: impl Into<String>) -> Result<Self> {
+        self.push(TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: text.into(),
+            name: None,
+            metadata: None,
+        }))?;
+        Ok(self)
+    }
+
+    /// Resumes an earlier conversation. Its turns are checked like pushed
+    /// ones.
+    pub fn with_conversation(mut self, conversation: Conversation) -> Result<Self> {
+        for turn in conversation.turns {
+            self.push(turn)?;
+        }
+        Ok(self)
+    }
+
+    pub fn conversation(&self) -> &Conversation {
+        &self.conversation
+    }
+
+    pub fn usage(&self) -> &TokenUsage {
+        &self.usage
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.cost
+    }
+
+    pub fn last_response(&self) -> Option<&AdapterChatCompletion> {
+        self.last_response.as_ref()
+    }
+
+    /// Roles the next turn may have. A tool output is only legal while a
+    /// tool call is still unanswered.
+    pub fn next_roles(&self) -> Vec<ConversationRole> {
+        let capabilities = &self.adapter.get_model().capabilities;
+        let turns = &self.conversation.turns;
+        if self.pending_tool_calls() > 0 {
+            return vec![ConversationRole::Tool];
+        }
+        let started = turns.iter().any(|turn| {
+            !matches!(
+                turn.role(),
+                ConversationRole::System | ConversationRole::Developer
+            )
+        });
+        let mut roles = Vec::new();
+        if !started && (turns.is_empty() || capabilities.supports_multiple_system) {
+            roles.push(ConversationRole::System);
+            roles.push(ConversationRole::Developer);
+        }
+        match turns.last().map(TurnType::role) {
+            Some(ConversationRole::User) => {
+                if capabilities.supports_repeating_roles {
+                    roles.push(ConversationRole::User);
+                }
+                roles.push(ConversationRole::Assistant);
+            }
+            Some(ConversationRole::Assistant) => {
+                roles.push(ConversationRole::User);
+                if capabilities.supports_repeating_roles {
+                    roles.push(ConversationRole::Assistant);
+                }
+            }
+            Some(ConversationRole::Tool) | Some(ConversationRole::Function) => {
+                roles.push(ConversationRole::User);
+                roles.push(ConversationRole::Assistant);
+            }
+            _ => roles.push(ConversationRole::User),
+        }
+        roles
+    }
+
+    fn pending_tool_calls(&self) -> usize {
+        self.unanswered_tool_calls().len()
+    }
+
+    /// Ids of the calls in the latest tool-calls turn that no tool output
+    /// has answered yet.
+    fn unanswered_tool_calls(&self) -> Vec<&str> {
+        let turns = &self.conversation.turns;
+        let Some(index) = turns
+            .iter()
+            .rposition(|turn| matches!(turn, TurnType::ToolCalls { .. }))
+        else {
+            return Vec::new();
+        };
+        let TurnType::ToolCalls { tool_calls, .. } = &turns[index] else {
+            return Vec::new();
+        };
+        let answered: Vec<&str> = turns[index + 1..]
+            .iter()
+            .filter_map(|turn| match turn {
+                TurnType::ToolOutput { tool_call_id, .. } => Some(tool_call_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        tool_calls
+            .iter()
+            .map(|call| call.id.as_str())
+            .filter(|id| !answered.contains(id))
+            .collect()
+    }
+
+    /// Appends `turn` if its role is one of `next_roles`.
+    pub fn push(&mut self, turn: TurnType) -> Result<()> {
+        let roles = self.next_roles();
+        if !roles.contains(turn.role()) {
+            let after = self
+                .conversation
+                .turns
+                .last()
+                .map(|last| last.role().to_string())
+                .unwrap_or_else(|| "the start".to_string());
+            return Err(AdapterError::ConfigError(format!(
+                "invalid turn order: {} cannot follow {}",
+                turn.role(),
+                after
+            )));
+        }
+        if let TurnType::ToolOutput { tool_call_id, .. } = &turn {
+            if !self
+                .unanswered_tool_calls()
+                .contains(&tool_call_id.as_str())
+            {
+                return Err(AdapterError::ConfigError(format!(
+                    "invalid turn order: no unanswered tool call with id {}",
+                    tool_call_id
+                )));
+            }
+        }
+        self.conversation.add_turn(turn);
+        Ok(())
+    }
+
+    /// Sends `text` as the next user turn and returns the reply. The reply,
+    /// including any tool calls, is appended; on failure the user turn is
+    /// taken back out.
+    pub async fn send(&mut self, text: impl Into<String>) -> Result<String> {
+        self.push(TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: text.into(),
+            name: None,
+            metadata: None,
+        }))?;
+        match self.complete().await {
+            Ok(reply) => {
+                self.save().await?;
+                Ok(reply)
+            }
+            Err(err) => {
+                self.conversation.turns.pop();
+                Err(err)
+            }
+        }
+    }
+
+    /// Like `send`, but streams the reply through `on_delta`. The partial
+    /// reply is saved as it grows, so a crash mid-stream leaves it in the
+    /// store marked with `PARTIAL_TURN_KEY`. If the stream fails after text
+    /// arrived, that text stays in the transcript. Chunks carry no usage,
+    /// so streamed replies are not added to `usage`.
+    pub async fn send_streaming<F>(
+        &mut self,
+        text: impl Into<String>,
+        mut on_delta: F,
+    ) -> Result<String>
+    where
+        F: FnMut(&str) + Send,
+    {
+        self.push(TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: text.into(),
+            name: None,
+            metadata: None,
+        }))?;
+        self.save().await?;
+        let mut stream = match self
+            .adapter
+            .execute_stream(&self.conversation, &self.options)
+            .await
+        {
+            Ok(stream) => stream,
+            Err(err) => {
+                self.conversation.turns.pop();
+                self.save().await?;
+                return Err(err);
+            }
+        };
+
+        self.conversation.add_turn(
+            TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: String::new(),
+                name: None,
+                metadata: None,
+            })
+            .with_metadata(PARTIAL_TURN_KEY, true),
+        );
+        let mut unsaved = 0;
+        let mut tool_calls: BTreeMap<u32, ToolCall> = BTreeMap::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(err) => {
+                    if self.partial_reply().is_some_and(|reply| reply.is_empty()) {
+                        self.conversation
+                            .turns
+                            .truncate(self.conversation.len() - 2);
+                    }
+                    self.save().await?;
+                    return Err(err);
+                }
+            };
+            let Some(choice) = chunk.choices.into_iter().find(|choice| choice.index == 0) else {
+                continue;
+            };
+            for delta in choice.delta.tool_calls.unwrap_or_default() {
+                let call = tool_calls
+                    .entry(delta.index)
+                    .or_insert_with(|| ToolCall::function("", "", String::new()));
+                let function = delta.function.unwrap_or_default();
+                if let Some(id) = delta.id {
+                    call.id = id;
+                }
+                if let Some(name) = function.name {
+                    call.function.name.push_str(&name);
+                }
+                if let Some(arguments) = function.arguments {
+                    call.function.arguments.push_str(&arguments);
+                }
+            }
+            let Some(content) = choice.delta.content.filter(|content| !content.is_empty()) else {
+                continue;
+            };
+            on_delta(&content);
+            if let Some(TurnType::Basic(turn)) = self.conversation.turns.last_mut() {
+                turn.content.push_str(&content);
+            }
+            unsaved += content.len();
+            if unsaved >= self.persist_every {
+                self.save().await?;
+                unsaved = 0;
+            }
+        }
+
+        let reply = self.partial_reply().unwrap_or_default();
+        self.conversation.turns.pop();
+        if tool_calls.is_empty() {
+            self.conversation.add_turn(TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: reply.clone(),
+                name: None,
+                metadata: None,
+            }));
+        } else {
+            self.conversation.add_turn(TurnType::ToolCalls {
+                role: ConversationRole::Assistant,
+                content: (!reply.is_empty()).then(|| reply.clone()),
+                tool_calls: tool_calls.into_values().collect(),
+                metadata: None,
+            });
+        }
+        self.save().await?;
+        Ok(reply)
+    }
+
+    /// Asks the model for its next turn, e.g. after tool outputs were pushed.
+    pub async fn complete(&mut self) -> Result<String> {
+        if !self.next_roles().contains(&ConversationRole::Assistant) {
+            return Err(AdapterError::ConfigError(
+                "invalid turn order: the model cannot reply here".to_string(),
+            ));
+        }
+        let response = self
+            .adapter
+            .execute(&self.conversation, &self.options)
+            .await?;
+        let Some(choice) = response.choices.first() else {
+            return Err(AdapterError::Unknown(
+                "model returned no choices".to_string(),
+            ));
+        };
+        let content = choice.message.content.clone().unwrap_or_default();
+        match choice
+            .message
+            .tool_calls
+            .clone()
+            .filter(|calls| !calls.is_empty())
+        {
+            Some(tool_calls) => self.conversation.add_turn(TurnType::ToolCalls {
+                role: ConversationRole::Assistant,
+                content: choice.message.content.clone(),
+                tool_calls,
+                metadata: None,
+            }),
+            None => self.conversation.add_turn(TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: content.clone(),
+                name: None,
+                metadata: None,
+            })),
+        }
+        if let Some(usage) = &response.usage {
+            self.usage.accumulate(usage);
+        }
+        self.cost += response.cost;
+        self.last_response = Some(response);
+        Ok(content)
+    }
+}
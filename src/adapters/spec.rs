@@ -0,0 +1,900 @@
+use crate::adapters::normalizer::Normalizer;
+use crate::adapters::stream::{stream_error, SseDecoder, SseEvent};
+use crate::adapters::{AdapterStream, BaseAdapter, ExecuteOptions, PrefixCache, ResponseFormat};
+use crate::config::{EnvConfig, QuirkRule};
+use crate::error::{AdapterError, Result};
+use crate::http::{HttpClient, UnixEndpoint};
+use crate::models::{
+    AdapterChatCompletion, AdapterChatCompletionChunk, AdapterWarning, Choice, ChunkChoice,
+    CitationFormat, Citations, CodeExecution, ComputerCall, Conversation, ConversationRole, Delta,
+    Message, Model, RateLimitInfo, ReproducibilityRecord, TokenUsage, ToolCall, ToolCallDelta,
+};
+use crate::utils::delete_none_values;
+use async_trait::async_trait;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How the API key is sent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum AuthScheme {
+    #[default]
+    Bearer,
+    Header {
+        name: String,
+    },
+    Query {
+        name: String,
+    },
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestSpec {
+    pub model_field: String,
+    pub messages_field: String,
+    /// Set to `true` on streaming requests; unset for providers that select
+    /// streaming by URL.
+    pub stream_field: Option<String>,
+    /// Renames `ExecuteOptions` fields, e.g. `max_tokens = "max_output_tokens"`.
+    /// An empty name drops the field.
+    pub fields: HashMap<String, String>,
+    /// Merged into every body unless the request sets the same field.
+    pub extra: Map<String, Value>,
+}
+
+impl Default for RequestSpec {
+    fn default() -> Self {
+        Self {
+            model_field: "model".to_string(),
+            messages_field: "messages".to_string(),
+            stream_field: Some("stream".to_string()),
+            fields: HashMap::new(),
+            extra: Map::new(),
+        }
+    }
+}
+
+/// JSON pointers into the provider's response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResponseSpec {
+    pub id: String,
+    pub content: String,
+    pub finish_reason: String,
+    /// An OpenAI-shaped `tool_calls` array.
+    pub tool_calls: String,
+    pub prompt_tokens: String,
+    pub completion_tokens: String,
+    /// The model version the provider served, for `ReproducibilityRecord`.
+    pub model: String,
+    pub system_fingerprint: String,
+    /// Predicted-output token counts, see `ExecuteOptions::prediction`.
+    pub accepted_prediction_tokens: String,
+    pub rejected_prediction_tokens: String,
+    /// The tier that served the request; inferred from the model's
+    /// provider when unset, see [`service_tier_pointer`].
+    pub service_tier: Option<String>,
+    /// Grounding or citation metadata to surface on the choice; inferred
+    /// from the model's provider when unset.
+    pub citations: Option<CitationFormat>,
+}
+
+impl Default for ResponseSpec {
+    fn default() -> Self {
+        Self {
+            id: "/id".to_string(),
+            content: "/choices/0/message/content".to_string(),
+            finish_reason: "/choices/0/finish_reason".to_string(),
+            tool_calls: "/choices/0/message/tool_calls".to_string(),
+            prompt_tokens: "/usage/prompt_tokens".to_string(),
+            completion_tokens: "/usage/completion_tokens".to_string(),
+            model: "/model".to_string(),
+            system_fingerprint: "/system_fingerprint".to_string(),
+            accepted_prediction_tokens:
+                "/usage/completion_tokens_details/accepted_prediction_tokens".to_string(),
+            rejected_prediction_tokens:
+                "/usage/completion_tokens_details/rejected_prediction_tokens".to_string(),
+            service_tier: None,
+            citations: None,
+        }
+    }
+}
+
+/// Where `provider` reports the tier that served a request: inside `usage`
+/// for Anthropic, top-level for OpenAI and compatible APIs.
+pub fn service_tier_pointer(provider: &str) -> &'static str {
+    match provider {
+        "anthropic" => "/usage/service_tier",
+        _ => "/service_tier",
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamFraming {
+    #[default]
+    Sse,
+    /// One JSON document per line.
+    JsonLines,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamSpec {
+    pub framing: StreamFraming,
+    /// URL used for streaming requests; defaults to the chat URL.
+    pub url: Option<String>,
+    pub id: String,
+    pub content: String,
+    /// An OpenAI-shaped array of tool call fragments.
+    pub tool_calls: String,
+    pub finish_reason: String,
+    /// Frame payload that ends the stream.
+    pub done: Option<String>,
+}
+
+impl Default for StreamSpec {
+    fn default() -> Self {
+        Self {
+            framing: StreamFraming::Sse,
+            url: None,
+            id: "/id".to_string(),
+            content: "/choices/0/delta/content".to_string(),
+            tool_calls: "/choices/0/delta/tool_calls".to_string(),
+            finish_reason: "/choices/0/finish_reason".to_string(),
+            done: Some("[DONE]".to_string()),
+        }
+    }
+}
+
+/// Describes an HTTP chat provider declaratively. The defaults match the
+/// OpenAI chat completions API, so OpenAI-compatible providers only need a
+/// name and a base URL:
+///
+/// ```toml
+/// name = "example"
+/// base_url = "https://api.example.com/v1"
+/// ```
+///
+/// URLs may contain `{base_url}` and `{model}` placeholders.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderSpec {
+    pub name: String,
+    pub base_url: String,
+    #[serde(default = "default_chat_url")]
+    pub chat_url: String,
+    /// Environment variable holding the API key; `<NAME>_API_KEY` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+    #[serde(default)]
+    pub auth: AuthScheme,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Header carrying the organization, e.g. `OpenAI-Organization`. The
+    /// value comes from the request's `ProviderCredentials`, the adapter or
+    /// `<NAME>_ORG_ID`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_header: Option<String>,
+    /// Header carrying the project, e.g. `OpenAI-Project`, resolved like
+    /// `org_header` with `<NAME>_PROJECT_ID`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_header: Option<String>,
+    /// Whether the provider deduplicates retries that carry the same
+    /// `Idempotency-Key` header, as OpenAI does. When set,
+    /// `ExecuteOptions::idempotency_key` is sent in that header.
+    #[serde(default)]
+    pub idempotency: bool,
+    #[serde(default)]
+    pub request: RequestSpec,
+    #[serde(default)]
+    pub response: ResponseSpec,
+    #[serde(default)]
+    pub stream: StreamSpec,
+    /// Applied to the request body after field mapping, see `Normalizer`.
+    #[serde(default)]
+    pub quirks: Vec<QuirkRule>,
+    /// Bundled request schema, e.g. `openai_chat`, that request bodies are
+    /// checked against when the `strict-validation` feature is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+}
+
+fn default_chat_url() -> String {
+    "{base_url}/chat/completions".to_string()
+}
+
+impl ProviderSpec {
+    pub fn from_toml(spec: &str) -> Result<Self> {
+        Ok(toml::from_str(spec)?)
+    }
+
+    pub fn from_json(spec: &str) -> Result<Self> {
+        Ok(serde_json::from_str(spec)?)
+    }
+
+    /// Reads a `.toml` or `.json` spec file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let spec = std::fs::read_to_string(path).map_err(|err| {
+            AdapterError::ConfigError(format!("failed to read {}: {}", path.display(), err))
+        })?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json(&spec),
+            _ => Self::from_toml(&spec),
+        }
+    }
+
+    /// Loads every `.toml` and `.json` spec in `dir`, sorted by file name.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let dir = dir.as_ref();
+        let entries = std::fs::read_dir(dir).map_err(|err| {
+            AdapterError::ConfigError(format!("failed to read {}: {}", dir.display(), err))
+        })?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("toml" | "json")
+                )
+            })
+            .collect();
+        paths.sort();
+        paths.iter().map(Self::from_path).collect()
+    }
+
+    fn url(&self, template: &str, base_url: Option<&str>, model: &str) -> String {
+        let base_url = base_url.unwrap_or(&self.base_url);
+        template
+            .replace("{base_url}", base_url.trim_end_matches('/'))
+            .replace("{model}", model)
+    }
+}
+
+/// A `BaseAdapter` driven entirely by a `ProviderSpec`.
+pub struct SpecAdapter {
+    spec: Arc<ProviderSpec>,
+    model: Model,
+    api_key: String,
+    org: Option<String>,
+    project: Option<String>,
+    normalizer: Normalizer,
+    http: HttpClient,
+    on_warning: Option<WarningCallback>,
+    prefix_cache: Option<Arc<PrefixCache>>,
+}
+
+type WarningCallback = Arc<dyn Fn(&AdapterWarning) + Send + Sync>;
+
+impl std::fmt::Debug for SpecAdapter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpecAdapter")
+            .field("provider", &self.spec.name)
+            .field("model", &self.model.get_path())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SpecAdapter {
+    /// A `unix://` base URL (see `UnixEndpoint`) sends every request over
+    /// that socket.
+    pub fn new(mut spec: ProviderSpec, model: Model, api_key: impl Into<String>) -> Result<Self> {
+        let (http, base_url) = HttpClient::for_base_url(&spec.base_url)?;
+        spec.base_url = base_url;
+        Ok(Self {
+            normalizer: Normalizer::new(spec.quirks.clone())?,
+            org: EnvConfig::get_org_id(&spec.name),
+            project: EnvConfig::get_project_id(&spec.name),
+            spec: Arc::new(spec),
+            model,
+            api_key: api_key.into(),
+            http,
+            on_warning: None,
+            prefix_cache: None,
+        })
+    }
+
+    /// Sent in the spec's `org_header` unless a request overrides it.
+    pub fn with_org(mut self, org: impl Into<String>) -> Self {
+        self.org = Some(org.into());
+        self
+    }
+
+    /// Sent in the spec's `project_header` unless a request overrides it.
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    /// Replaces the client built from the spec's base URL, e.g. with one
+    /// using a custom transport.
+    pub fn with_http_client(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Called with every warning as it is raised, including for streamed
+    /// requests, whose warnings have no response to be collected into.
+    pub fn on_warning<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&AdapterWarning) + Send + Sync + 'static,
+    {
+        self.on_warning = Some(Arc::new(callback));
+        self
+    }
+
+    /// Marks prefixes shared with earlier requests for prompt caching, see
+    /// `PrefixCache`. Only for providers that honor `cache_control`.
+    pub fn with_prefix_cache(mut self, cache: Arc<PrefixCache>) -> Self {
+        self.prefix_cache = Some(cache);
+        self
+    }
+
+    pub fn from_env(spec: ProviderSpec, model: Model) -> Result<Self> {
+        let api_key = match &spec.api_key_env {
+            Some(name) => std::env::var(name).ok(),
+            None => EnvConfig::get_api_key(&spec.name),
+        };
+        let api_key = match (&spec.auth, api_key) {
+            (AuthScheme::None, api_key) => api_key.unwrap_or_default(),
+            (_, Some(api_key)) => api_key,
+            (_, None) => return Err(AdapterError::ApiKeyNotFound(spec.name.clone())),
+        };
+        Self::new(spec, model, api_key)
+    }
+
+    pub fn spec(&self) -> &ProviderSpec {
+        &self.spec
+    }
+
+    /// The JSON body sent for `conversation`.
+    pub fn build_body(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        stream: bool,
+    ) -> Result<Value> {
+        self.build_request(conversation, options, stream)
+            .map(|(body, _)| body)
+    }
+
+    /// `build_body` along with the warnings raised while building it.
+    fn build_request(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        stream: bool,
+    ) -> Result<(Value, Vec<AdapterWarning>)> {
+        let request = &self.spec.request;
+        // Structured output without a limit gets one sized to the schema
+        // rather than the provider maximum.
+        let estimated = options
+            .max_tokens
+            .is_none()
+            .then(|| options.response_format.as_ref()?.estimated_max_tokens())
+            .flatten()
+            .map(|max_tokens| match self.model.completion_length {
+                Some(limit) if limit > 0 => max_tokens.min(limit),
+                _ => max_tokens,
+            });
+        let sized;
+        let options = match estimated {
+            Some(max_tokens) => {
+                sized = ExecuteOptions {
+                    max_tokens: Some(max_tokens),
+                    ..options.clone()
+                };
+                &sized
+            }
+            None => options,
+        };
+        let builtin_tools = options.builtin_tools.as_deref().unwrap_or_default();
+        let prediction = options.prediction.as_deref();
+        let mut options = serde_json::to_value(options)?;
+        delete_none_values(&mut options);
+        if let Value::Object(options) = &mut options {
+            options.remove("builtin_tools");
+            if !self.model.capabilities.supports_min_tokens {
+                options.remove("min_tokens");
+            }
+        }
+
+        let mut body = Map::new();
+        body.insert(
+            request.model_field.clone(),
+            Value::String(self.model.name.clone()),
+        );
+        body.insert(
+            request.messages_field.clone(),
+            Value::Array(conversation.to_openai_messages()),
+        );
+        if let Value::Object(options) = options {
+            for (field, value) in options {
+                match request.fields.get(&field) {
+                    Some(renamed) if renamed.is_empty() => {}
+                    Some(renamed) => {
+                        body.insert(renamed.clone(), value);
+                    }
+                    None => {
+                        body.insert(field, value);
+                    }
+                }
+            }
+        }
+        let mut dropped = Vec::new();
+        if let Some(prediction) = prediction {
+            if self.model.capabilities.supports_prediction {
+                body.insert(
+                    "prediction".to_string(),
+                    json!({"type": "content", "content": prediction}),
+                );
+            } else {
+                dropped.push(AdapterWarning::DroppedParameter {
+                    parameter: "prediction".to_string(),
+                    model: self.model.get_path(),
+                    reason: "the model does not support predicted outputs".to_string(),
+                });
+            }
+        }
+        for (field, value) in &request.extra {
+            body.entry(field.clone()).or_insert_with(|| value.clone());
+        }
+        if let (true, Some(field)) = (stream, &request.stream_field) {
+            body.insert(field.clone(), Value::Bool(true));
+        }
+
+        let mut body = Value::Object(body);
+        for tool in builtin_tools {
+            if !tool.is_supported_by(&self.model.capabilities) {
+                return Err(AdapterError::UnsupportedFeature {
+                    model: self.model.get_path(),
+                    feature: "computer use".to_string(),
+                });
+            }
+            tool.apply(&self.model.provider_name, &self.model.name, &mut body)?;
+        }
+        let mut warnings = self
+            .normalizer
+            .apply_with_warnings(&self.model.name, &mut body);
+        warnings.extend(dropped);
+        #[cfg(feature = "strict-validation")]
+        if let Some(schema) = &self.spec.schema {
+            crate::adapters::strict::validate_request(&self.spec.name, schema, &body)?;
+        }
+        if let Some(callback) = &self.on_warning {
+            warnings.iter().for_each(|warning| callback(warning));
+        }
+        Ok((body, warnings))
+    }
+
+    /// Marks the shared prefix of a body about to be sent. `build_body`
+    /// skips this so that inspecting a body does not record its prefixes.
+    fn cache_prefix(&self, body: &mut Value) {
+        if let Some(cache) = &self.prefix_cache {
+            cache.annotate(body, &self.spec.request.messages_field);
+        }
+    }
+
+    /// The request URL for `template`, honouring a per-request base URL.
+    fn request_url(&self, template: &str, options: &ExecuteOptions) -> Result<String> {
+        let base_url = options
+            .credentials
+            .as_ref()
+            .and_then(|credentials| credentials.base_url.as_deref());
+        if let Some(base_url) = base_url.filter(|url| UnixEndpoint::parse(url).is_some()) {
+            return Err(AdapterError::ConfigError(format!(
+                "unix:// base URLs cannot be set per request: {}",
+                base_url
+            )));
+        }
+        Ok(self.spec.url(template, base_url, &self.model.name))
+    }
+
+    async fn send(
+        &self,
+        url: &str,
+        body: &Value,
+        options: &ExecuteOptions,
+    ) -> Result<reqwest::Response> {
+        let credentials = options.credentials.as_ref();
+        let api_key = credentials
+            .and_then(|credentials| credentials.api_key.as_deref())
+            .unwrap_or(&self.api_key);
+        let org = credentials
+            .and_then(|credentials| credentials.org.as_ref())
+            .or(self.org.as_ref());
+        let project = credentials
+            .and_then(|credentials| credentials.project.as_ref())
+            .or(self.project.as_ref());
+        let mut request = self.http.inner().post(url).json(body);
+        request = match &self.spec.auth {
+            AuthScheme::Bearer => request.bearer_auth(api_key),
+            AuthScheme::Header { name } => request.header(name.as_str(), api_key),
+            AuthScheme::Query { name } => request.query(&[(name.as_str(), api_key)]),
+            AuthScheme::None => request,
+        };
+        for (name, value) in &self.spec.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let (Some(name), Some(org)) = (&self.spec.org_header, org) {
+            request = request.header(name.as_str(), org.as_str());
+        }
+        if let (Some(name), Some(project)) = (&self.spec.project_header, project) {
+            request = request.header(name.as_str(), project.as_str());
+        }
+        if let (true, Some((name, key))) = (self.spec.idempotency, options.idempotency_header()) {
+            request = request.header(name, key);
+        }
+
+        let response = self.http.send(request).await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let limits = RateLimitInfo::from_headers(response.headers());
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(AdapterError::rate_limited(
+                &self.spec.name,
+                limits.as_ref(),
+                &body,
+            ));
+        }
+        Err(
+            AdapterError::from_provider_error(&self.spec.name, &body).unwrap_or_else(|| {
+                AdapterError::ProviderError {
+                    provider: self.spec.name.clone(),
+                    error_type: None,
+                    message: format!("request failed with status {}", status),
+                }
+            }),
+        )
+    }
+
+    fn parse_response(&self, body: &Value) -> Result<AdapterChatCompletion> {
+        let paths = &self.spec.response;
+        let text = |pointer: &str| body.pointer(pointer).and_then(Value::as_str);
+        let tokens = |pointer: &str| body.pointer(pointer).and_then(Value::as_u64);
+
+        let tool_calls = body
+            .pointer(&paths.tool_calls)
+            .filter(|calls| !calls.is_null())
+            .map(|calls| serde_json::from_value::<Vec<ToolCall>>(calls.clone()))
+            .transpose()?;
+        let content = text(&paths.content).map(str::to_string);
+        let computer_calls = ComputerCall::parse(&self.model.provider_name, body);
+        if content.is_none() && tool_calls.is_none() && computer_calls.is_none() {
+            return Err(AdapterError::ProviderError {
+                provider: self.spec.name.clone(),
+                error_type: None,
+                message: format!("response has no content at {}", paths.content),
+            });
+        }
+        let usage = match (
+            tokens(&paths.prompt_tokens),
+            tokens(&paths.completion_tokens),
+        ) {
+            (Some(prompt), Some(completion)) => Some(TokenUsage {
+                accepted_prediction_tokens: tokens(&paths.accepted_prediction_tokens)
+                    .map(|count| count as u32),
+                rejected_prediction_tokens: tokens(&paths.rejected_prediction_tokens)
+                    .map(|count| count as u32),
+                ..TokenUsage::new(prompt as u32, completion as u32)
+            }),
+            _ => None,
+        };
+
+        let mut response = AdapterChatCompletion {
+            id: text(&paths.id).unwrap_or_default().to_string(),
+            object: "chat.completion".to_string(),
+            created: unix_now(),
+            model: self.model.name.clone(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: ConversationRole::Assistant,
+                    content,
+                    tool_calls,
+                    refusal: None,
+                    code_executions: CodeExecution::parse(&self.model.provider_name, body),
+                    computer_calls,
+                },
+                finish_reason: text(&paths.finish_reason).map(str::to_string),
+                citations: paths
+                    .citations
+                    .or_else(|| CitationFormat::for_provider(&self.model.provider_name))
+                    .and_then(|format| Citations::parse(format, body)),
+            }],
+            usage,
+            cost: 0.0,
+            cost_breakdown: None,
+            rate_limit: None,
+            warnings: Vec::new(),
+            reproducibility: None,
+            service_tier: text(
+                paths
+                    .service_tier
+                    .as_deref()
+                    .unwrap_or_else(|| service_tier_pointer(&self.model.provider_name)),
+            )
+            .map(str::to_string),
+        };
+        response.apply_cost(&self.model.cost);
+        Ok(response)
+    }
+
+    async fn execute_once(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        let (mut body, warnings) = self.build_request(conversation, options, false)?;
+        self.cache_prefix(&mut body);
+        let url = self.request_url(&self.spec.chat_url, options)?;
+        let raw: Value = self.send(&url, &body, options).await?.json().await?;
+        let mut response = self.parse_response(&raw)?;
+        response.warnings = warnings;
+        if let Some(seed) = options.seed {
+            let text = |pointer: &str| {
+                raw.pointer(pointer)
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            let mut record = ReproducibilityRecord::new(
+                &self.spec.name,
+                self.model.get_path(),
+                seed,
+                &body,
+                &self.spec.request.messages_field,
+            );
+            record.model_version = text(&self.spec.response.model);
+            record.system_fingerprint = text(&self.spec.response.system_fingerprint);
+            response.reproducibility = Some(record);
+        }
+        if options
+            .response_format
+            .as_ref()
+            .is_some_and(ResponseFormat::is_json)
+        {
+            response.repair_json();
+        }
+        let drift = options.usage_drift_threshold.and_then(|threshold| {
+            let drift = response.usage_drift(conversation)?;
+            drift.exceeds(threshold).then_some(drift)
+        });
+        if let Some(drift) = drift {
+            let warning = AdapterWarning::UsageDrift {
+                model: response.model.clone(),
+                drift,
+            };
+            if let Some(callback) = &self.on_warning {
+                callback(&warning);
+            }
+            response.warnings.push(warning);
+        }
+        Ok(response)
+    }
+
+    /// `n` single-choice requests, with seeds counting up from
+    /// `options.seed` so the choices differ, merged into one response.
+    async fn execute_emulating_n(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+        n: u32,
+    ) -> Result<AdapterChatCompletion> {
+        let requests = (0..n).map(|index| {
+            let options = ExecuteOptions {
+                n: None,
+                seed: options.seed.map(|seed| seed.wrapping_add(index as u64)),
+                ..options.clone()
+            };
+            async move { self.execute_once(conversation, &options).await }
+        });
+        let mut responses = futures::future::try_join_all(requests).await?;
+        let mut response = responses.remove(0);
+        for next in responses {
+            response.absorb(next);
+        }
+        let warning = AdapterWarning::EmulatedFeature {
+            feature: "n".to_string(),
+            model: self.model.get_path(),
+            detail: format!("sent {} single-choice requests", n),
+        };
+        if let Some(callback) = &self.on_warning {
+            callback(&warning);
+        }
+        response.warnings.push(warning);
+        Ok(response)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[async_trait]
+impl BaseAdapter for SpecAdapter {
+    fn get_model(&self) -> &Model {
+        &self.model
+    }
+
+    fn set_api_key(&mut self, api_key: String) -> Result<()> {
+        self.api_key = api_key;
+        Ok(())
+    }
+
+    async fn execute(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterChatCompletion> {
+        match options.n {
+            Some(n) if n > 1 && !self.model.capabilities.supports_n => {
+                self.execute_emulating_n(conversation, options, n).await
+            }
+            _ => self.execute_once(conversation, options).await,
+        }
+    }
+
+    async fn execute_stream(
+        &self,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<AdapterStream> {
+        let (mut body, _) = self.build_request(conversation, options, true)?;
+        self.cache_prefix(&mut body);
+        let template = self.spec.stream.url.as_ref().unwrap_or(&self.spec.chat_url);
+        let url = self.request_url(template, options)?;
+        let response = self.send(&url, &body, options).await?;
+
+        Ok(spec_chunk_stream(
+            self.spec.clone(),
+            self.model.name.clone(),
+            response.bytes_stream(),
+        ))
+    }
+}
+
+type ByteStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>;
+
+struct SpecStreamState {
+    inner: ByteStream,
+    spec: Arc<ProviderSpec>,
+    model: String,
+    decoder: SseDecoder,
+    lines: Vec<u8>,
+    pending: VecDeque<SseEvent>,
+    done: bool,
+}
+
+impl SpecStreamState {
+    fn push(&mut self, bytes: &[u8]) {
+        match self.spec.stream.framing {
+            StreamFraming::Sse => {
+                let events = self.decoder.push(bytes);
+                self.pending.extend(events);
+            }
+            StreamFraming::JsonLines => {
+                self.lines.extend_from_slice(bytes);
+                while let Some(newline) = self.lines.iter().position(|&byte| byte == b'\n') {
+                    let line: Vec<u8> = self.lines.drain(..=newline).collect();
+                    self.push_line(&line);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        match self.spec.stream.framing {
+            StreamFraming::Sse => self.pending.extend(self.decoder.finish()),
+            StreamFraming::JsonLines => {
+                let line = std::mem::take(&mut self.lines);
+                self.push_line(&line);
+            }
+        }
+        self.done = true;
+    }
+
+    fn push_line(&mut self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let data = line.trim();
+        if !data.is_empty() {
+            self.pending.push_back(SseEvent {
+                event: None,
+                data: data.to_string(),
+            });
+        }
+    }
+
+    fn chunk(&self, event: &SseEvent) -> Result<AdapterChatCompletionChunk> {
+        let paths = &self.spec.stream;
+        let frame: Value = serde_json::from_str(&event.data)?;
+        let text = |pointer: &str| frame.pointer(pointer).and_then(Value::as_str);
+        let tool_calls = frame
+            .pointer(&paths.tool_calls)
+            .filter(|calls| !calls.is_null())
+            .map(|calls| serde_json::from_value::<Vec<ToolCallDelta>>(calls.clone()))
+            .transpose()?;
+        Ok(AdapterChatCompletionChunk {
+            id: text(&paths.id).unwrap_or_default().to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: unix_now(),
+            model: self.model.clone(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta: Delta {
+                    role: None,
+                    content: text(&paths.content).map(str::to_string),
+                    tool_calls,
+                },
+                finish_reason: text(&paths.finish_reason).map(str::to_string),
+            }],
+        })
+    }
+}
+
+fn spec_chunk_stream<S, B, E>(spec: Arc<ProviderSpec>, model: String, bytes: S) -> AdapterStream
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + 'static,
+    B: AsRef<[u8]>,
+    E: Into<AdapterError>,
+{
+    let state = SpecStreamState {
+        inner: Box::pin(bytes.map(|bytes| {
+            bytes
+                .map(|bytes| bytes.as_ref().to_vec())
+                .map_err(Into::into)
+        })),
+        spec,
+        model,
+        decoder: SseDecoder::new(),
+        lines: Vec::new(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(event) = state.pending.pop_front() {
+                if state.spec.stream.done.as_deref() == Some(event.data.trim()) {
+                    return None;
+                }
+                if let Some(err) = stream_error(&state.spec.name, &event) {
+                    state.pending.clear();
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+                let chunk = state.chunk(&event);
+                if chunk.is_err() {
+                    state.pending.clear();
+                    state.done = true;
+                }
+                return Some((chunk, state));
+            }
+            if state.done {
+                return None;
+            }
+
+            match state.inner.next().await {
+                Some(Ok(bytes)) => state.push(&bytes),
+                Some(Err(err)) => {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+                None => state.finish(),
+            }
+        }
+    }))
+}
@@ -0,0 +1,781 @@
+use crate::adapters::AdapterStream;
+use crate::error::{AdapterError, Result};
+use crate::models::{
+    AdapterChatCompletion, AdapterChatCompletionChunk, ChunkChoice, ConversationRole, Delta,
+    FunctionCallDelta, ToolCallDelta,
+};
+use crate::utils::percentile;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+impl SseEvent {
+    pub fn is_done(&self) -> bool {
+        self.data.trim() == "[DONE]"
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes after the last newline; a line is decoded once it is complete,
+    /// so characters split across reads survive.
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if let Some(event) = self.take_event() {
+                    events.push(event);
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data.push(value.trim_start().to_string());
+            } else if let Some(value) = line.strip_prefix("event:") {
+                self.event = Some(value.trim().to_string());
+            }
+        }
+        events
+    }
+
+    pub fn finish(&mut self) -> Option<SseEvent> {
+        if !self.buffer.is_empty() {
+            self.buffer.push(b'\n');
+            self.push(&[]);
+        }
+        self.take_event()
+    }
+
+    fn take_event(&mut self) -> Option<SseEvent> {
+        if self.data.is_empty() {
+            self.event = None;
+            return None;
+        }
+        Some(SseEvent {
+            event: self.event.take(),
+            data: std::mem::take(&mut self.data).join("\n"),
+        })
+    }
+}
+
+/// Returns the provider error carried by an SSE frame, if any. Covers OpenAI
+/// `{"error": ...}` payloads and Anthropic `event: error` frames.
+pub fn stream_error(provider: &str, event: &SseEvent) -> Option<AdapterError> {
+    let is_error_event = event.event.as_deref() == Some("error");
+    match serde_json::from_str::<serde_json::Value>(&event.data) {
+        Ok(body) => AdapterError::from_provider_error(provider, &body).or_else(|| {
+            is_error_event.then(|| AdapterError::ProviderError {
+                provider: provider.to_string(),
+                error_type: None,
+                message: event.data.clone(),
+            })
+        }),
+        Err(_) if is_error_event => Some(AdapterError::ProviderError {
+            provider: provider.to_string(),
+            error_type: None,
+            message: event.data.clone(),
+        }),
+        Err(_) => None,
+    }
+}
+
+struct SseState<S> {
+    inner: S,
+    provider: String,
+    decoder: SseDecoder,
+    pending: VecDeque<SseEvent>,
+    done: bool,
+}
+
+/// Decodes an OpenAI-compatible SSE byte stream into chunks. Error frames
+/// received after the response started terminate the stream with an `Err`.
+pub fn openai_chunk_stream<S, B, E>(provider: &str, bytes: S) -> AdapterStream
+where
+    S: Stream<Item = std::result::Result<B, E>> + Send + Unpin + 'static,
+    B: AsRef<[u8]>,
+    E: Into<AdapterError>,
+{
+    let state = SseState {
+        inner: bytes,
+        provider: provider.to_string(),
+        decoder: SseDecoder::new(),
+        pending: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if state.done {
+                return None;
+            }
+
+            if let Some(event) = state.pending.pop_front() {
+                if event.is_done() {
+                    return None;
+                }
+                if let Some(err) = stream_error(&state.provider, &event) {
+                    state.done = true;
+                    return Some((Err(err), state));
+                }
+                let chunk = serde_json::from_str::<AdapterChatCompletionChunk>(&event.data)
+                    .map_err(AdapterError::from);
+                if chunk.is_err() {
+                    state.done = true;
+                }
+                return Some((chunk, state));
+            }
+
+            match state.inner.next().await {
+                Some(Ok(bytes)) => {
+                    let events = state.decoder.push(bytes.as_ref());
+                    state.pending.extend(events);
+                }
+                Some(Err(err)) => {
+                    state.done = true;
+                    return Some((Err(err.into()), state));
+                }
+                None => match state.decoder.finish() {
+                    Some(event) => state.pending.push_back(event),
+                    None => return None,
+                },
+            }
+        }
+    }))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions {
+    pub coalesce_ms: Option<u64>,
+    pub max_chunk_chars: Option<usize>,
+}
+
+impl StreamOptions {
+    pub fn is_passthrough(&self) -> bool {
+        self.coalesce_ms.is_none() && self.max_chunk_chars.is_none()
+    }
+}
+
+struct CoalesceState {
+    inner: AdapterStream,
+    options: StreamOptions,
+    pending: Option<AdapterChatCompletionChunk>,
+    deadline: Option<Instant>,
+    ready: VecDeque<Result<AdapterChatCompletionChunk>>,
+    done: bool,
+}
+
+impl CoalesceState {
+    fn flush(&mut self) {
+        self.deadline = None;
+        if let Some(chunk) = self.pending.take() {
+            self.ready.push_back(Ok(chunk));
+        }
+    }
+
+    fn accept(&mut self, chunk: AdapterChatCompletionChunk) {
+        let has_tool_calls = chunk.choices.iter().any(|c| c.delta.tool_calls.is_some());
+        if has_tool_calls {
+            self.flush();
+            self.ready.push_back(Ok(chunk));
+            return;
+        }
+
+        let finished = chunk.choices.iter().any(|c| c.finish_reason.is_some());
+        match &mut self.pending {
+            Some(pending) => merge_chunk(pending, chunk),
+            None => {
+                self.deadline = self
+                    .options
+                    .coalesce_ms
+                    .map(|ms| Instant::now() + Duration::from_millis(ms));
+                self.pending = Some(chunk);
+            }
+        }
+
+        let too_large = match (self.options.max_chunk_chars, &self.pending) {
+            (Some(max), Some(pending)) => content_chars(pending) >= max,
+            _ => false,
+        };
+        if finished || too_large {
+            self.flush();
+        }
+    }
+}
+
+fn merge_chunk(pending: &mut AdapterChatCompletionChunk, chunk: AdapterChatCompletionChunk) {
+    for choice in chunk.choices {
+        match pending.choices.iter_mut().find(|c| c.index == choice.index) {
+            Some(existing) => {
+                if let Some(content) = choice.delta.content {
+                    existing
+                        .delta
+                        .content
+                        .get_or_insert_with(String::new)
+                        .push_str(&content);
+                }
+                if existing.delta.role.is_none() {
+                    existing.delta.role = choice.delta.role;
+                }
+                if choice.finish_reason.is_some() {
+                    existing.finish_reason = choice.finish_reason;
+                }
+            }
+            None => pending.choices.push(choice),
+        }
+    }
+}
+
+fn content_chars(chunk: &AdapterChatCompletionChunk) -> usize {
+    chunk
+        .choices
+        .iter()
+        .filter_map(|c| c.delta.content.as_ref())
+        .map(|content| content.chars().count())
+        .sum()
+}
+
+/// Batches consecutive content deltas into larger chunks. A batch is emitted
+/// once `coalesce_ms` has passed since its first delta, once it holds
+/// `max_chunk_chars` characters, or when a choice finishes. Tool-call deltas
+/// and errors are never merged.
+pub fn coalesce_stream(stream: AdapterStream, options: StreamOptions) -> AdapterStream {
+    if options.is_passthrough() {
+        return stream;
+    }
+
+    let state = CoalesceState {
+        inner: stream,
+        options,
+        pending: None,
+        deadline: None,
+        ready: VecDeque::new(),
+        done: false,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.ready.pop_front() {
+                return Some((item, state));
+            }
+            if state.done {
+                return None;
+            }
+
+            let next = match (state.deadline, state.pending.is_some()) {
+                (Some(deadline), true) => {
+                    match tokio::time::timeout_at(deadline, state.inner.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            state.flush();
+                            continue;
+                        }
+                    }
+                }
+                _ => state.inner.next().await,
+            };
+
+            match next {
+                Some(Ok(chunk)) => state.accept(chunk),
+                Some(Err(err)) => {
+                    state.flush();
+                    state.ready.push_back(Err(err));
+                    state.done = true;
+                }
+                None => {
+                    state.flush();
+                    state.done = true;
+                }
+            }
+        }
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedStreamOptions {
+    pub chunk_chars: usize,
+    pub interval: Duration,
+}
+
+impl Default for SimulatedStreamOptions {
+    fn default() -> Self {
+        Self {
+            chunk_chars: 16,
+            interval: Duration::from_millis(20),
+        }
+    }
+}
+
+/// Re-emits a finished completion as a stream of deltas, `chunk_chars`
+/// characters at a time with `interval` between chunks. Tool calls are sent
+/// whole in a single delta, and each choice ends with its finish reason.
+pub fn simulate_stream(
+    response: AdapterChatCompletion,
+    options: SimulatedStreamOptions,
+) -> AdapterStream {
+    let chunk_chars = options.chunk_chars.max(1);
+    let chunk = |choice: ChunkChoice| AdapterChatCompletionChunk {
+        id: response.id.clone(),
+        object: "chat.completion.chunk".to_string(),
+        created: response.created,
+        model: response.model.clone(),
+        choices: vec![choice],
+    };
+
+    let mut chunks = Vec::new();
+    for choice in &response.choices {
+        let content: Vec<char> = choice
+            .message
+            .content
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .collect();
+        let mut role = Some(ConversationRole::Assistant);
+        for piece in content.chunks(chunk_chars) {
+            chunks.push(chunk(ChunkChoice {
+                index: choice.index,
+                delta: Delta {
+                    role: role.take(),
+                    content: Some(piece.iter().collect()),
+                    tool_calls: None,
+                },
+                finish_reason: None,
+            }));
+        }
+
+        if let Some(tool_calls) = &choice.message.tool_calls {
+            let deltas = tool_calls
+                .iter()
+                .enumerate()
+                .map(|(index, call)| ToolCallDelta {
+                    index: index as u32,
+                    id: Some(call.id.clone()),
+                    call_type: Some(call.call_type.clone()),
+                    function: Some(FunctionCallDelta {
+                        name: Some(call.function.name.clone()),
+                        arguments: Some(call.function.arguments.clone()),
+                    }),
+                })
+                .collect();
+            chunks.push(chunk(ChunkChoice {
+                index: choice.index,
+                delta: Delta {
+                    role: role.take(),
+                    content: None,
+                    tool_calls: Some(deltas),
+                },
+                finish_reason: None,
+            }));
+        }
+
+        chunks.push(chunk(ChunkChoice {
+            index: choice.index,
+            delta: Delta {
+                role: role.take(),
+                content: None,
+                tool_calls: None,
+            },
+            finish_reason: Some(
+                choice
+                    .finish_reason
+                    .clone()
+                    .unwrap_or_else(|| "stop".to_string()),
+            ),
+        }));
+    }
+
+    let interval = options.interval;
+    Box::pin(stream::iter(chunks.into_iter().enumerate()).then(
+        move |(position, chunk)| async move {
+            if position > 0 && !interval.is_zero() {
+                tokio::time::sleep(interval).await;
+            }
+            Ok(chunk)
+        },
+    ))
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamLatency {
+    pub time_to_first_token: Option<Duration>,
+    pub total_duration: Option<Duration>,
+    pub token_chunks: usize,
+    pub mean_inter_token: Option<Duration>,
+    pub p50_inter_token: Option<Duration>,
+    pub p95_inter_token: Option<Duration>,
+    pub max_inter_token: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StreamSummary {
+    pub latency: StreamLatency,
+}
+
+#[derive(Debug)]
+struct MetricsState {
+    started: Instant,
+    first_token: Option<Instant>,
+    last_token: Option<Instant>,
+    finished: Option<Instant>,
+    gaps: Vec<Duration>,
+}
+
+impl MetricsState {
+    fn record_chunk(&mut self, chunk: &AdapterChatCompletionChunk) {
+        let has_token = chunk.choices.iter().any(|c| {
+            c.delta.content.as_deref().is_some_and(|s| !s.is_empty())
+                || c.delta.tool_calls.is_some()
+        });
+        if !has_token {
+            return;
+        }
+
+        let now = Instant::now();
+        if let Some(last) = self.last_token {
+            self.gaps.push(now - last);
+        }
+        self.first_token.get_or_insert(now);
+        self.last_token = Some(now);
+    }
+
+    fn latency(&self) -> StreamLatency {
+        let mut sorted = self.gaps.clone();
+        sorted.sort();
+        let mean =
+            (!sorted.is_empty()).then(|| sorted.iter().sum::<Duration>() / sorted.len() as u32);
+
+        StreamLatency {
+            time_to_first_token: self.first_token.map(|t| t - self.started),
+            total_duration: self.finished.map(|t| t - self.started),
+            token_chunks: self.first_token.map_or(0, |_| self.gaps.len() + 1),
+            mean_inter_token: mean,
+            p50_inter_token: percentile(&sorted, 0.5),
+            p95_inter_token: percentile(&sorted, 0.95),
+            max_inter_token: sorted.last().copied(),
+        }
+    }
+}
+
+/// Handle to the latency measurements of an instrumented stream. The numbers
+/// are updated live as the stream is polled.
+#[derive(Debug, Clone)]
+pub struct StreamMetrics {
+    state: Arc<Mutex<MetricsState>>,
+}
+
+impl StreamMetrics {
+    pub fn latency(&self) -> StreamLatency {
+        self.state.lock().unwrap().latency()
+    }
+
+    pub fn summary(&self) -> StreamSummary {
+        StreamSummary {
+            latency: self.latency(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished.is_some()
+    }
+}
+
+pub fn instrument_stream(stream: AdapterStream) -> (AdapterStream, StreamMetrics) {
+    instrument_stream_since(stream, Instant::now())
+}
+
+pub fn instrument_stream_since(
+    stream: AdapterStream,
+    started: Instant,
+) -> (AdapterStream, StreamMetrics) {
+    let metrics = StreamMetrics {
+        state: Arc::new(Mutex::new(MetricsState {
+            started,
+            first_token: None,
+            last_token: None,
+            finished: None,
+            gaps: Vec::new(),
+        })),
+    };
+
+    let recorder = metrics.clone();
+    let instrumented = stream::unfold((stream, recorder), |(mut inner, recorder)| async move {
+        let next = inner.next().await;
+        let mut state = recorder.state.lock().unwrap();
+        match &next {
+            Some(Ok(chunk)) => state.record_chunk(chunk),
+            Some(Err(_)) | None => {
+                state.finished.get_or_insert_with(Instant::now);
+            }
+        }
+        drop(state);
+        next.map(|item| (item, (inner, recorder)))
+    });
+
+    (Box::pin(instrumented), metrics)
+}
+
+pub async fn collect_chunks(mut stream: AdapterStream) -> Result<Vec<AdapterChatCompletionChunk>> {
+    let mut chunks = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        chunks.push(chunk?);
+    }
+    Ok(chunks)
+}
+
+pub trait AdapterStreamExt {
+    /// Forwards the stream into a bounded channel from a spawned task. The
+    /// task stops after the first error, when the receiver is dropped (even
+    /// while waiting for a chunk) or when aborted through the handle;
+    /// dropping the handle does not stop it.
+    fn into_channel(
+        self,
+        buffer: usize,
+    ) -> (
+        mpsc::Receiver<Result<AdapterChatCompletionChunk>>,
+        AbortHandle,
+    );
+}
+
+impl AdapterStreamExt for AdapterStream {
+    fn into_channel(
+        mut self,
+        buffer: usize,
+    ) -> (
+        mpsc::Receiver<Result<AdapterChatCompletionChunk>>,
+        AbortHandle,
+    ) {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        let task = tokio::spawn(async move {
+            loop {
+                let item = tokio::select! {
+                    _ = sender.closed() => return,
+                    item = self.next() => item,
+                };
+                let Some(item) = item else {
+                    return;
+                };
+                let failed = item.is_err();
+                if sender.send(item).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+        (receiver, task.abort_handle())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ChunkChoice, Delta};
+
+    fn content_chunk(content: &str, finish_reason: Option<&str>) -> AdapterChatCompletionChunk {
+        AdapterChatCompletionChunk {
+            id: "c1".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 1,
+            model: "gpt-4o".to_string(),
+            choices: vec![ChunkChoice {
+                index: 0,
+                delta: Delta {
+                    role: None,
+                    content: Some(content.to_string()),
+                    tool_calls: None,
+                },
+                finish_reason: finish_reason.map(str::to_string),
+            }],
+        }
+    }
+
+    fn chunk_stream(chunks: Vec<AdapterChatCompletionChunk>) -> AdapterStream {
+        Box::pin(stream::iter(chunks.into_iter().map(Ok)))
+    }
+
+    fn byte_stream(frames: &[&str]) -> impl Stream<Item = Result<Vec<u8>>> + Send + Unpin {
+        let frames: Vec<Result<Vec<u8>>> =
+            frames.iter().map(|f| Ok(f.as_bytes().to_vec())).collect();
+        stream::iter(frames)
+    }
+
+    const CHUNK: &str = r#"data: {"id":"c1","object":"chat.completion.chunk","created":1,"model":"gpt-4o","choices":[{"index":0,"delta":{"content":"Hel"},"finish_reason":null}]}"#;
+
+    #[test]
+    fn test_sse_decoder_keeps_split_characters() {
+        let frame = "data: 你好 👋\n\n".as_bytes();
+        let mut decoder = SseDecoder::new();
+        assert!(decoder.push(&frame[..8]).is_empty());
+        let events = decoder.push(&frame[8..]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "你好 👋");
+    }
+
+    #[tokio::test]
+    async fn test_openai_chunk_stream_until_done() {
+        let frames = [CHUNK, "\n\n", "data: [DONE]\n\n"];
+        let chunks = collect_chunks(openai_chunk_stream("openai", byte_stream(&frames)))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("Hel"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_error_frame_terminates_stream() {
+        let frames = [
+            CHUNK,
+            "\n\n",
+            "data: {\"error\":{\"message\":\"The server had an error\",\"type\":\"server_error\"}}\n\n",
+            CHUNK,
+            "\n\n",
+        ];
+        let mut stream = openai_chunk_stream("openai", byte_stream(&frames));
+
+        assert!(stream.next().await.unwrap().is_ok());
+        match stream.next().await {
+            Some(Err(AdapterError::ProviderError {
+                provider,
+                error_type,
+                message,
+            })) => {
+                assert_eq!(provider, "openai");
+                assert_eq!(error_type.as_deref(), Some("server_error"));
+                assert_eq!(message, "The server had an error");
+            }
+            other => panic!("expected provider error, got {:?}", other),
+        }
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_error_event_split_across_reads() {
+        let frames = [
+            "event: error\ndata: {\"type\":\"error\",",
+            "\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        ];
+        let mut stream = openai_chunk_stream("anthropic", byte_stream(&frames));
+
+        match stream.next().await {
+            Some(Err(AdapterError::ProviderError { error_type, .. })) => {
+                assert_eq!(error_type.as_deref(), Some("overloaded_error"));
+            }
+            other => panic!("expected provider error, got {:?}", other),
+        }
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_merges_deltas_until_finish() {
+        let chunks = vec![
+            content_chunk("He", None),
+            content_chunk("llo", None),
+            content_chunk(" wor", None),
+            content_chunk("ld", Some("stop")),
+        ];
+        let options = StreamOptions {
+            coalesce_ms: Some(1_000),
+            max_chunk_chars: None,
+        };
+
+        let merged = collect_chunks(coalesce_stream(chunk_stream(chunks), options))
+            .await
+            .unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].choices[0].delta.content.as_deref(),
+            Some("Hello world")
+        );
+        assert_eq!(merged[0].choices[0].finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_respects_max_chunk_chars() {
+        let chunks = vec![
+            content_chunk("ab", None),
+            content_chunk("cd", None),
+            content_chunk("ef", None),
+        ];
+        let options = StreamOptions {
+            coalesce_ms: None,
+            max_chunk_chars: Some(4),
+        };
+
+        let merged = collect_chunks(coalesce_stream(chunk_stream(chunks), options))
+            .await
+            .unwrap();
+        let contents: Vec<_> = merged
+            .iter()
+            .map(|c| c.choices[0].delta.content.clone().unwrap())
+            .collect();
+        assert_eq!(contents, vec!["abcd", "ef"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_instrumented_stream_records_latency() {
+        let started = Instant::now();
+        let delayed = stream::iter(vec![10u64, 20, 40]).then(|ms| async move {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(content_chunk("tok", None))
+        });
+        let (stream, metrics) = instrument_stream_since(Box::pin(delayed), started);
+
+        collect_chunks(stream).await.unwrap();
+
+        let latency = metrics.latency();
+        assert!(metrics.is_finished());
+        assert_eq!(latency.token_chunks, 3);
+        assert_eq!(latency.time_to_first_token, Some(Duration::from_millis(10)));
+        assert_eq!(latency.max_inter_token, Some(Duration::from_millis(40)));
+        assert_eq!(latency.mean_inter_token, Some(Duration::from_millis(30)));
+        assert_eq!(latency.total_duration, Some(Duration::from_millis(70)));
+    }
+
+    #[tokio::test]
+    async fn test_into_channel_stops_when_receiver_drops() {
+        let (mut receiver, _abort) = chunk_stream(vec![
+            content_chunk("a", None),
+            content_chunk("b", Some("stop")),
+        ])
+        .into_channel(1);
+        assert_eq!(
+            receiver.recv().await.unwrap().unwrap().choices[0]
+                .delta
+                .content,
+            Some("a".to_string())
+        );
+        assert!(receiver.recv().await.unwrap().is_ok());
+        assert!(receiver.recv().await.is_none());
+
+        let (sender, pending) = tokio::sync::oneshot::channel::<()>();
+        let never: AdapterStream = Box::pin(stream::once(async move {
+            let _sender = sender;
+            std::future::pending::<Result<AdapterChatCompletionChunk>>().await
+        }));
+        let (receiver, _abort) = never.into_channel(4);
+        drop(receiver);
+        // The stream, and the sender it holds, is dropped once the task ends.
+        assert!(pending.await.is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{AdapterError, Result};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+static REQUEST_SCHEMAS: Lazy<Value> = Lazy::new(|| {
+    serde_json::from_str(include_str!("../../config/request_schemas.json"))
+        .expect("bundled request schemas are valid JSON")
+});
+
+/// Names of the bundled request schemas, for `ProviderSpec::schema`.
+pub fn request_schema_names() -> Vec<&'static str> {
+    REQUEST_SCHEMAS
+        .as_object()
+        .map(|schemas| schemas.keys().map(String::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Every way `body` breaks the bundled schema `name`, including fields the
+/// provider does not know and would reject or silently drop.
+pub fn request_violations(name: &str, body: &Value) -> Result<Vec<String>> {
+    let schema = REQUEST_SCHEMAS.get(name).ok_or_else(|| {
+        AdapterError::ConfigError(format!(
+            "unknown request schema {}, expected one of {}",
+            name,
+            request_schema_names().join(", ")
+        ))
+    })?;
+    let mut violations = Vec::new();
+    collect(schema, body, "$", &mut violations);
+    Ok(violations)
+}
+
+pub(crate) fn validate_request(provider: &str, name: &str, body: &Value) -> Result<()> {
+    let violations = request_violations(name, body)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(AdapterError::SchemaViolation {
+        provider: provider.to_string(),
+        schema: name.to_string(),
+        violations,
+    })
+}
+
+fn collect(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            _ => true,
+        };
+        if !matches {
+            violations.push(format!("{} must be of type {}", path, expected));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            violations.push(format!(
+                "{} must be one of {}",
+                path,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                violations.push(format!("{} must be at least {}", path, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                violations.push(format!("{} must be at most {}", path, maximum));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for key in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(key) {
+                violations.push(format!("{} is missing required field {}", path, key));
+            }
+        }
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+        for (key, field) in object {
+            let field_path = format!("{}.{}", path, key);
+            match properties.and_then(|properties| properties.get(key)) {
+                Some(property) => collect(property, field, &field_path, violations),
+                None if closed => violations.push(format!("{} is not a known field", field_path)),
+                None => {}
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            collect(items, item, &format!("{}[{}]", path, index), violations);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_violations() {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "temperature": 0.2
+        });
+        assert!(request_violations("openai_chat", &body).unwrap().is_empty());
+
+        let body = json!({
+            "model": "claude-sonnet-4",
+            "messages": [{"role": "system", "content": "Hi"}],
+            "temprature": 0.2
+        });
+        let violations = request_violations("anthropic_messages", &body).unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                "$ is missing required field max_tokens",
+                "$.messages[0].role must be one of [\"user\",\"assistant\"]",
+                "$.temprature is not a known field",
+            ]
+        );
+        assert!(request_violations("cohere", &body).is_err());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{
+    AdapterChatCompletion, Conversation, ConversationRole, CostBreakdown, TokenUsage, ToolCall,
+    ToolResultContent, Turn, TurnType,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[async_trait]
+pub trait ToolHandler: Send + Sync {
+    async fn call(&self, arguments: Value) -> Result<ToolResultContent>;
+}
+
+struct FnHandler<F>(F);
+
+#[async_trait]
+impl<F> ToolHandler for FnHandler<F>
+where
+    F: Fn(Value) -> Result<ToolResultContent> + Send + Sync,
+{
+    async fn call(&self, arguments: Value) -> Result<ToolResultContent> {
+        (self.0)(arguments)
+    }
+}
+
+/// Executes the model's tool calls with registered handlers and feeds the
+/// results back until the model answers without calling a tool.
+#[derive(Clone)]
+pub struct ToolRunner {
+    handlers: HashMap<String, Arc<dyn ToolHandler>>,
+    max_rounds: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    pub response: AdapterChatCompletion,
+    /// The input conversation followed by every tool call, tool output and
+    /// the final assistant reply.
+    pub conversation: Conversation,
+    pub rounds: u32,
+}
+
+impl Default for ToolRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolRunner {
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            max_rounds: 8,
+        }
+    }
+
+    pub fn with_max_rounds(mut self, max_rounds: u32) -> Self {
+        self.max_rounds = max_rounds.max(1);
+        self
+    }
+
+    pub fn register(&mut self, name: impl Into<String>, handler: Arc<dyn ToolHandler>) {
+        self.handlers.insert(name.into(), handler);
+    }
+
+    pub fn register_fn<F>(&mut self, name: impl Into<String>, handler: F)
+    where
+        F: Fn(Value) -> Result<ToolResultContent> + Send + Sync + 'static,
+    {
+        self.register(name, Arc::new(FnHandler(handler)));
+    }
+
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Runs one tool call. Unknown tools and handler failures are reported
+    /// back to the model as the tool output instead of aborting the run.
+    pub async fn call(&self, tool_call: &ToolCall) -> ToolResultContent {
+        let Some(handler) = self.handlers.get(&tool_call.function.name) else {
+            return ToolResultContent::Text(format!(
+                "error: unknown tool {}",
+                tool_call.function.name
+            ));
+        };
+        let outcome = match tool_call.parsed_arguments() {
+            Ok(arguments) => handler.call(arguments).await,
+            Err(err) => Err(err),
+        };
+        outcome.unwrap_or_else(|err| ToolResultContent::Text(format!("error: {}", err)))
+    }
+
+    pub async fn run<A: BaseAdapter + ?Sized>(
+        &self,
+        adapter: &A,
+        conversation: &Conversation,
+        options: &ExecuteOptions,
+    ) -> Result<ToolRun> {
+        let mut conversation = conversation.clone();
+        let mut usage = None;
+        let mut cost = 0.0;
+        let mut breakdown: Option<CostBreakdown> = None;
+
+        for round in 1..=self.max_rounds {
+            let mut response = adapter.execute(&conversation, options).await?;
+            if let Some(round_usage) = &response.usage {
+                usage
+                    .get_or_insert_with(|| TokenUsage::new(0, 0))
+                    .accumulate(round_usage);
+            }
+            cost += response.cost;
+            if let Some(round_breakdown) = &response.cost_breakdown {
+                breakdown
+                    .get_or_insert_with(CostBreakdown::default)
+                    .accumulate(round_breakdown);
+            }
+
+            let Some(choice) = response.choices.first() else {
+                return Err(AdapterError::Unknown(
+                    "model returned no choices".to_string(),
+                ));
+            };
+            let tool_calls = choice.message.tool_calls.clone().unwrap_or_default();
+            if tool_calls.is_empty() {
+                if let Some(content) = &choice.message.content {
+                    conversation.add_turn(TurnType::Basic(Turn {
+                        role: ConversationRole::Assistant,
+                        content: content.clone(),
+                        name: None,
+                        metadata: None,
+                    }));
+                }
+                response.usage = usage;
+                response.cost = cost;
+                response.cost_breakdown = breakdown;
+                return Ok(ToolRun {
+                    response,
+                    conversation,
+                    rounds: round,
+                });
+            }
+
+            conversation.add_turn(TurnType::ToolCalls {
+                role: ConversationRole::Assistant,
+                content: choice.message.content.clone(),
+                tool_calls: tool_calls.clone(),
+                metadata: None,
+            });
+            for tool_call in &tool_calls {
+                let output = self.call(tool_call).await;
+                conversation.add_turn(TurnType::ToolOutput {
+                    role: ConversationRole::Tool,
+                    content: Some(output),
+                    tool_call_id: tool_call.id.clone(),
+                    metadata: None,
+                });
+            }
+        }
+
+        Err(AdapterError::Unknown(format!(
+            "tool loop did not finish within {} rounds",
+            self.max_rounds
+        )))
+    }
+}
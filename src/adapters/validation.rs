@@ -0,0 +1,203 @@
+use crate::adapters::postprocess::strip_code_fence;
+use crate::error::{AdapterError, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+
+const CORRECTION_INSTRUCTION: &str =
+    "Your previous reply failed validation and must be rewritten. Problem:";
+
+/// Checks a completion's text. The error string is shown to the model in the
+/// corrective retry and returned to the caller once retries run out.
+pub trait OutputValidator: Send + Sync {
+    fn validate(&self, output: &str) -> std::result::Result<(), String>;
+}
+
+/// Requires the output to match a regular expression.
+#[derive(Debug, Clone)]
+pub struct RegexValidator {
+    pattern: Regex,
+}
+
+impl RegexValidator {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let pattern = Regex::new(pattern).map_err(|err| {
+            AdapterError::ConfigError(format!("invalid validation pattern: {}", err))
+        })?;
+        Ok(Self { pattern })
+    }
+}
+
+impl OutputValidator for RegexValidator {
+    fn validate(&self, output: &str) -> std::result::Result<(), String> {
+        if self.pattern.is_match(output) {
+            Ok(())
+        } else {
+            Err(format!(
+                "the reply must match the pattern {}",
+                self.pattern.as_str()
+            ))
+        }
+    }
+}
+
+/// Requires the output to be JSON matching a schema. Supports the commonly
+/// used subset of JSON Schema: `type`, `properties`, `required`, `items` and
+/// `enum`. Code fences around the JSON are tolerated.
+#[derive(Debug, Clone)]
+pub struct JsonSchemaValidator {
+    schema: Value,
+}
+
+impl JsonSchemaValidator {
+    pub fn new(schema: Value) -> Self {
+        Self { schema }
+    }
+}
+
+impl OutputValidator for JsonSchemaValidator {
+    fn validate(&self, output: &str) -> std::result::Result<(), String> {
+        let text = strip_code_fence(output);
+        let value: Value = serde_json::from_str(text)
+            .map_err(|err| format!("the reply is not valid JSON: {}", err))?;
+        check_schema(&self.schema, &value, "$")
+    }
+}
+
+fn check_schema(schema: &Value, value: &Value, path: &str) -> std::result::Result<(), String> {
+    if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+        let matches = match expected {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "number" => value.is_number(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !matches {
+            return Err(format!("{} must be of type {}", path, expected));
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return Err(format!(
+                "{} must be one of {}",
+                path,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(key) {
+                    return Err(format!("{} is missing required field {}", path, key));
+                }
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (key, property) in properties {
+                if let Some(field) = object.get(key) {
+                    check_schema(property, field, &format!("{}.{}", path, key))?;
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, item) in array.iter().enumerate() {
+            check_schema(items, item, &format!("{}[{}]", path, index))?;
+        }
+    }
+
+    Ok(())
+}
+
+struct FnValidator<F>(F);
+
+impl<F> OutputValidator for FnValidator<F>
+where
+    F: Fn(&str) -> std::result::Result<(), String> + Send + Sync,
+{
+    fn validate(&self, output: &str) -> std::result::Result<(), String> {
+        (self.0)(output)
+    }
+}
+
+/// Validator attached to `ExecuteOptions::output_validation`, with the number
+/// of corrective retries allowed after the first attempt.
+#[derive(Clone)]
+pub struct OutputValidation {
+    pub validator: Arc<dyn OutputValidator>,
+    pub max_retries: u32,
+}
+
+impl OutputValidation {
+    pub fn new(validator: impl OutputValidator + 'static, max_retries: u32) -> Self {
+        Self {
+            validator: Arc::new(validator),
+            max_retries,
+        }
+    }
+
+    pub fn from_fn<F>(validator: F, max_retries: u32) -> Self
+    where
+        F: Fn(&str) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        Self::new(FnValidator(validator), max_retries)
+    }
+
+    pub fn correction(&self, reason: &str) -> String {
+        format!("{} {}", CORRECTION_INSTRUCTION, reason)
+    }
+}
+
+impl fmt::Debug for OutputValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutputValidation")
+            .field("max_retries", &self.max_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for OutputValidation {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.validator, &other.validator) && self.max_retries == other.max_retries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_schema_validator() {
+        let validator = JsonSchemaValidator::new(json!({
+            "type": "object",
+            "required": ["name", "tags"],
+            "properties": {
+                "name": {"type": "string"},
+                "tags": {"type": "array", "items": {"enum": ["a", "b"]}}
+            }
+        }));
+
+        assert!(validator
+            .validate("```json\n{\"name\": \"x\", \"tags\": [\"a\"]}\n```")
+            .is_ok());
+        assert_eq!(
+            validator.validate(r#"{"name": "x"}"#).unwrap_err(),
+            "$ is missing required field tags"
+        );
+        assert!(validator
+            .validate(r#"{"name": "x", "tags": ["c"]}"#)
+            .unwrap_err()
+            .starts_with("$.tags[0] must be one of"));
+        assert!(validator.validate("not json").is_err());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod threads;
+
+pub use threads::*;
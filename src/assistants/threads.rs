@@ -0,0 +1,210 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions, ToolRunner};
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, ConversationRole, TokenUsage, Turn, TurnType};
+use crate::store::ConversationStore;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct Assistant {
+    pub instructions: Option<String>,
+    pub options: ExecuteOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Queued,
+    InProgress,
+    Completed,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, RunStatus::Completed | RunStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub id: String,
+    pub thread_id: String,
+    pub status: RunStatus,
+    pub usage: Option<TokenUsage>,
+    pub cost: f64,
+    pub error: Option<String>,
+}
+
+/// Assistants-style flow (create thread, add message, run, poll) on top of a
+/// `ConversationStore` and the `ToolRunner`, for any provider adapter.
+pub struct Threads {
+    store: Arc<dyn ConversationStore>,
+    tools: Arc<ToolRunner>,
+    runs: Arc<DashMap<String, Run>>,
+}
+
+impl Threads {
+    pub fn new(store: Arc<dyn ConversationStore>, tools: ToolRunner) -> Self {
+        Self {
+            store,
+            tools: Arc::new(tools),
+            runs: Arc::new(DashMap::new()),
+        }
+    }
+
+    pub async fn create_thread(&self) -> Result<String> {
+        let thread_id = format!("thread_{}", uuid::Uuid::new_v4().simple());
+        self.store.save(&thread_id, &Conversation::new()).await?;
+        Ok(thread_id)
+    }
+
+    pub async fn add_message(
+        &self,
+        thread_id: &str,
+        role: ConversationRole,
+        content: impl Into<String>,
+    ) -> Result<()> {
+        let mut conversation = self.messages(thread_id).await?;
+        conversation.add_turn(TurnType::Basic(Turn {
+            role,
+            content: content.into(),
+            name: None,
+            metadata: None,
+        }));
+        self.store.save(thread_id, &conversation).await
+    }
+
+    pub async fn messages(&self, thread_id: &str) -> Result<Conversation> {
+        self.store
+            .load(thread_id)
+            .await?
+            .ok_or_else(|| AdapterError::ConfigError(format!("unknown thread {}", thread_id)))
+    }
+
+    pub async fn delete_thread(&self, thread_id: &str) -> Result<()> {
+        self.store.delete(thread_id).await
+    }
+
+    /// Starts a run in the background and returns immediately. The thread is
+    /// extended with tool calls, tool outputs and the reply once it finishes;
+    /// the assistant instructions are sent but never persisted.
+    pub async fn create_run(
+        &self,
+        thread_id: &str,
+        assistant: &Assistant,
+        adapter: Arc<dyn BaseAdapter>,
+    ) -> Result<Run> {
+        let conversation = self.messages(thread_id).await?;
+        let run = Run {
+            id: format!("run_{}", uuid::Uuid::new_v4().simple()),
+            thread_id: thread_id.to_string(),
+            status: RunStatus::Queued,
+            usage: None,
+            cost: 0.0,
+            error: None,
+        };
+        self.runs.insert(run.id.clone(), run.clone());
+
+        let store = self.store.clone();
+        let tools = self.tools.clone();
+        let runs = self.runs.clone();
+        let assistant = assistant.clone();
+        let run_id = run.id.clone();
+        let thread_id = thread_id.to_string();
+        let work = tokio::spawn(async move {
+            set_status(&runs, &run_id, RunStatus::InProgress);
+
+            let mut request = Conversation::new();
+            if let Some(instructions) = &assistant.instructions {
+                request.add_turn(TurnType::Basic(Turn {
+                    role: ConversationRole::System,
+                    content: instructions.clone(),
+                    name: None,
+                    metadata: None,
+                }));
+            }
+            request.turns.extend(conversation.turns);
+            let sent = request.len();
+
+            let outcome = match tools
+                .run(adapter.as_ref(), &request, &assistant.options)
+                .await
+            {
+                Ok(tool_run) => append_to_thread(
+                    store.as_ref(),
+                    &thread_id,
+                    tool_run.conversation.turns.into_iter().skip(sent),
+                )
+                .await
+                .map(|_| tool_run.response),
+                Err(err) => Err(err),
+            };
+
+            if let Some(mut run) = runs.get_mut(&run_id) {
+                match outcome {
+                    Ok(response) => {
+                        run.status = RunStatus::Completed;
+                        run.usage = response.usage;
+                        run.cost = response.cost;
+                    }
+                    Err(err) => {
+                        run.status = RunStatus::Failed;
+                        run.error = Some(err.to_string());
+                    }
+                }
+            }
+        });
+
+        // A run whose task panics would otherwise stay in progress forever.
+        let runs = self.runs.clone();
+        let run_id = run.id.clone();
+        tokio::spawn(async move {
+            let Err(err) = work.await else {
+                return;
+            };
+            if let Some(mut run) = runs.get_mut(&run_id) {
+                if !run.status.is_terminal() {
+                    run.status = RunStatus::Failed;
+                    run.error = Some(format!("run task ended unexpectedly: {}", err));
+                }
+            }
+        });
+
+        Ok(run)
+    }
+
+    pub fn get_run(&self, run_id: &str) -> Option<Run> {
+        self.runs.get(run_id).map(|run| run.clone())
+    }
+
+    pub async fn wait_for_run(&self, run_id: &str, poll_interval: Duration) -> Result<Run> {
+        loop {
+            let run = self
+                .get_run(run_id)
+                .ok_or_else(|| AdapterError::ConfigError(format!("unknown run {}", run_id)))?;
+            if run.status.is_terminal() {
+                return Ok(run);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+/// Appends to the stored thread rather than overwriting it, so messages added
+/// while the run was in progress are kept.
+async fn append_to_thread(
+    store: &dyn ConversationStore,
+    thread_id: &str,
+    turns: impl Iterator<Item = TurnType>,
+) -> Result<()> {
+    let mut thread = store.load(thread_id).await?.unwrap_or_default();
+    thread.turns.extend(turns);
+    store.save(thread_id, &thread).await
+}
+
+fn set_status(runs: &DashMap<String, Run>, run_id: &str, status: RunStatus) {
+    if let Some(mut run) = runs.get_mut(run_id) {
+        run.status = status;
+    }
+}
@@ -1,4 +1,6 @@
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
 
 pub struct EnvConfig;
 
@@ -8,6 +10,31 @@ impl EnvConfig {
         env::var(&key_name).ok()
     }
 
+    /// Comma-separated keys in `<PROVIDER>_API_KEYS`, for `KeyPool`.
+    pub fn get_api_keys(provider: &str) -> Option<Vec<String>> {
+        let key_name = format!("{}_API_KEYS", provider.to_uppercase().replace('-', "_"));
+        let keys: Vec<String> = env::var(&key_name)
+            .ok()?
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(str::to_string)
+            .collect();
+        (!keys.is_empty()).then_some(keys)
+    }
+
+    /// `<PROVIDER>_ORG_ID`, e.g. `OPENAI_ORG_ID`.
+    pub fn get_org_id(provider: &str) -> Option<String> {
+        let name = format!("{}_ORG_ID", provider.to_uppercase().replace('-', "_"));
+        env::var(&name).ok().filter(|value| !value.is_empty())
+    }
+
+    /// `<PROVIDER>_PROJECT_ID`, e.g. `OPENAI_PROJECT_ID`.
+    pub fn get_project_id(provider: &str) -> Option<String> {
+        let name = format!("{}_PROJECT_ID", provider.to_uppercase().replace('-', "_"));
+        env::var(&name).ok().filter(|value| !value.is_empty())
+    }
+
     pub fn get_override_base_url() -> Option<String> {
         env::var("_ADAPTERS_OVERRIDE_ALL_BASE_URLS_").ok()
     }
@@ -39,4 +66,34 @@ impl EnvConfig {
             .and_then(|s| s.parse().ok())
             .unwrap_or(5)
     }
+
+    pub fn get_client_cache_max_entries() -> usize {
+        env::var("ADAPTERS_CLIENT_CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(256)
+    }
+
+    /// Age after which cached HTTP clients are rebuilt; unset or `0` keeps
+    /// them for the life of the process.
+    pub fn get_client_cache_ttl() -> Option<Duration> {
+        env::var("ADAPTERS_CLIENT_CACHE_TTL")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// When set, `ImageUrl::from_bytes` removes EXIF and other metadata
+    /// before encoding.
+    pub fn get_strip_image_metadata() -> bool {
+        env::var("ADAPTERS_STRIP_IMAGE_METADATA")
+            .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false)
+    }
+
+    /// Directory whose TOML files override the compiled-in configuration.
+    pub fn get_config_dir() -> Option<PathBuf> {
+        env::var_os("ADAPTERS_CONFIG_DIR").map(PathBuf::from)
+    }
 }
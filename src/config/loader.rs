@@ -0,0 +1,132 @@
+use crate::config::model_languages::MODEL_LANGUAGES;
+use crate::config::provider_defaults::PROVIDER_DEFAULTS;
+use crate::config::provider_quirks::PROVIDER_QUIRKS;
+use crate::config::vendor_mappings::VENDOR_MAPPINGS;
+use crate::config::{validate, EnvConfig};
+use crate::error::{AdapterError, Result};
+use once_cell::sync::OnceCell;
+use serde::de::DeserializeOwned;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// A TOML config file that is read from `ADAPTERS_CONFIG_DIR` when present
+/// there and falls back to the copy compiled into the crate.
+pub(crate) struct ConfigFile<T> {
+    file_name: &'static str,
+    builtin: &'static str,
+    current: OnceCell<RwLock<Arc<T>>>,
+}
+
+impl<T: DeserializeOwned> ConfigFile<T> {
+    pub(crate) const fn new(file_name: &'static str, builtin: &'static str) -> Self {
+        Self {
+            file_name,
+            builtin,
+            current: OnceCell::new(),
+        }
+    }
+
+    pub(crate) fn path(&self) -> Option<PathBuf> {
+        let path = EnvConfig::get_config_dir()?.join(self.file_name);
+        path.is_file().then_some(path)
+    }
+
+    /// The file's label for diagnostics and its contents.
+    pub(crate) fn source(&self) -> Result<(String, String)> {
+        let Some(path) = self.path() else {
+            return Ok((self.file_name.to_string(), self.builtin.to_string()));
+        };
+        let config = std::fs::read_to_string(&path).map_err(|err| {
+            AdapterError::ConfigError(format!("failed to read {}: {}", path.display(), err))
+        })?;
+        Ok((path.display().to_string(), config))
+    }
+
+    pub(crate) fn load(&self) -> Result<T> {
+        let (file, config) = self.source()?;
+        toml::from_str(&config)
+            .map_err(|err| AdapterError::ConfigError(format!("failed to parse {}: {}", file, err)))
+    }
+
+    fn cell(&self) -> &RwLock<Arc<T>> {
+        self.current.get_or_init(|| {
+            // A broken override must not take the process down; the compiled
+            // copy is used until a reload succeeds. `AdapterFactory::init_config`
+            // reports the problem instead.
+            let config = self.load().ok().filter(|_| validate().is_empty());
+            let config = config.unwrap_or_else(|| {
+                toml::from_str(self.builtin)
+                    .unwrap_or_else(|err| panic!("built-in {} must parse: {}", self.file_name, err))
+            });
+            RwLock::new(Arc::new(config))
+        })
+    }
+
+    pub(crate) fn get(&self) -> Arc<T> {
+        self.cell()
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn set(&self, config: T) {
+        *self
+            .cell()
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(config);
+    }
+}
+
+/// Re-reads every config file. Either all files are replaced or, when
+/// `validate` reports a problem, none are.
+pub fn reload_config() -> Result<()> {
+    let diagnostics = validate();
+    if !diagnostics.is_empty() {
+        return Err(AdapterError::InvalidConfig(diagnostics));
+    }
+    let defaults = PROVIDER_DEFAULTS.load()?;
+    let mappings = VENDOR_MAPPINGS.load()?;
+    let quirks = PROVIDER_QUIRKS.load()?;
+    let languages = MODEL_LANGUAGES.load()?;
+    PROVIDER_DEFAULTS.set(defaults);
+    VENDOR_MAPPINGS.set(mappings);
+    PROVIDER_QUIRKS.set(quirks);
+    MODEL_LANGUAGES.set(languages);
+    Ok(())
+}
+
+fn modification_times() -> Vec<Option<SystemTime>> {
+    [
+        PROVIDER_DEFAULTS.path(),
+        VENDOR_MAPPINGS.path(),
+        PROVIDER_QUIRKS.path(),
+        MODEL_LANGUAGES.path(),
+    ]
+    .into_iter()
+    .map(|path| path.and_then(|path| path.metadata().ok()?.modified().ok()))
+    .collect()
+}
+
+/// Polls the config directory every `interval` and reloads when a file was
+/// added, changed or removed. `on_reload` receives the outcome of each
+/// reload; the returned task runs until it is aborted.
+pub fn watch_config<F>(interval: Duration, on_reload: F) -> JoinHandle<()>
+where
+    F: Fn(Result<()>) + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut seen = modification_times();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let current = modification_times();
+            if current != seen {
+                seen = current;
+                on_reload(reload_config());
+            }
+        }
+    })
+}
@@ -1,7 +1,15 @@
 pub mod env;
+pub mod loader;
+pub mod model_languages;
 pub mod provider_defaults;
+pub mod provider_quirks;
+pub mod validate;
 pub mod vendor_mappings;
 
 pub use env::*;
+pub use loader::*;
+pub use model_languages::*;
 pub use provider_defaults::*;
+pub use provider_quirks::*;
+pub use validate::*;
 pub use vendor_mappings::*;
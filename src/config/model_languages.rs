@@ -0,0 +1,32 @@
+use crate::config::loader::ConfigFile;
+use regex::Regex;
+use std::collections::HashMap;
+
+pub(crate) static MODEL_LANGUAGES: ConfigFile<HashMap<String, Vec<String>>> = ConfigFile::new(
+    "model_languages.toml",
+    include_str!("../../config/model_languages.toml"),
+);
+
+pub struct ModelLanguages;
+
+impl ModelLanguages {
+    /// Model name patterns for `language`, empty when it has no entry.
+    pub fn patterns(language: &str) -> Vec<String> {
+        MODEL_LANGUAGES
+            .get()
+            .get(&language.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether `model_name` is listed as strong in `language`. Every model
+    /// is for languages without an entry.
+    pub fn is_strong_in(model_name: &str, language: &str) -> bool {
+        let patterns = Self::patterns(language);
+        patterns.is_empty()
+            || patterns
+                .iter()
+                .filter_map(|pattern| Regex::new(pattern).ok())
+                .any(|regex| regex.is_match(model_name))
+    }
+}
@@ -1,6 +1,6 @@
+use crate::config::loader::ConfigFile;
 use crate::error::Result;
 use crate::models::ModelCapabilities;
-use once_cell::sync::Lazy;
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -12,14 +12,16 @@ pub struct ProviderDefaults {
     pub base_url: Option<String>,
 }
 
-static PROVIDER_DEFAULTS: Lazy<HashMap<String, ProviderDefaults>> = Lazy::new(|| {
-    let config_str = include_str!("../../config/provider_defaults.toml");
-    toml::from_str(config_str).expect("Failed to parse provider_defaults.toml")
-});
+pub(crate) static PROVIDER_DEFAULTS: ConfigFile<HashMap<String, ProviderDefaults>> =
+    ConfigFile::new(
+        "provider_defaults.toml",
+        include_str!("../../config/provider_defaults.toml"),
+    );
 
 impl ProviderDefaults {
     pub fn for_provider(provider_id: &str) -> ProviderDefaults {
         PROVIDER_DEFAULTS
+            .get()
             .get(provider_id)
             .cloned()
             .unwrap_or_else(|| ProviderDefaults {
@@ -29,6 +31,6 @@ impl ProviderDefaults {
     }
 
     pub fn get_all() -> Result<HashMap<String, ProviderDefaults>> {
-        Ok(PROVIDER_DEFAULTS.clone())
+        Ok(PROVIDER_DEFAULTS.get().as_ref().clone())
     }
 }
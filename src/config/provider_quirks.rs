@@ -0,0 +1,86 @@
+use crate::config::loader::ConfigFile;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum QuirkAction {
+    Rename {
+        from: String,
+        to: String,
+    },
+    Drop {
+        field: String,
+    },
+    Clamp {
+        field: String,
+        #[serde(default)]
+        min: Option<f64>,
+        #[serde(default)]
+        max: Option<f64>,
+    },
+    /// Sets `field` when the request leaves it out.
+    Default {
+        field: String,
+        value: Value,
+    },
+    /// Replaces a string value of `field` by its entry in `values` and drops
+    /// the field when there is none.
+    Map {
+        field: String,
+        values: HashMap<String, Value>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuirkRule {
+    #[serde(flatten)]
+    pub action: QuirkAction,
+    /// Regex on the model name; the rule applies to every model when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub models: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl QuirkRule {
+    pub fn new(action: QuirkAction) -> Self {
+        Self {
+            action,
+            models: None,
+            reason: None,
+        }
+    }
+
+    pub fn for_models(mut self, pattern: impl Into<String>) -> Self {
+        self.models = Some(pattern.into());
+        self
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+}
+
+pub(crate) static PROVIDER_QUIRKS: ConfigFile<HashMap<String, Vec<QuirkRule>>> = ConfigFile::new(
+    "provider_quirks.toml",
+    include_str!("../../config/provider_quirks.toml"),
+);
+
+pub struct ProviderQuirks;
+
+impl ProviderQuirks {
+    pub fn for_provider(provider_id: &str) -> Vec<QuirkRule> {
+        PROVIDER_QUIRKS
+            .get()
+            .get(provider_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_all() -> HashMap<String, Vec<QuirkRule>> {
+        PROVIDER_QUIRKS.get().as_ref().clone()
+    }
+}
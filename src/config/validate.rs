@@ -0,0 +1,212 @@
+use crate::config::model_languages::MODEL_LANGUAGES;
+use crate::config::provider_defaults::{ProviderDefaults, PROVIDER_DEFAULTS};
+use crate::config::provider_quirks::{QuirkRule, PROVIDER_QUIRKS};
+use crate::config::vendor_mappings::{VendorMappingsConfig, VENDOR_MAPPINGS};
+use crate::models::ModelCapabilities;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigDiagnostic {
+    /// Path of the override file, or the bare file name for the compiled-in
+    /// copy.
+    pub file: String,
+    /// Dotted key the problem was found at, when it is known.
+    pub key: Option<String>,
+    pub reason: String,
+}
+
+impl ConfigDiagnostic {
+    fn new(file: &str, key: Option<String>, reason: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            key,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(f, "{}: {}: {}", self.file, key, self.reason),
+            None => write!(f, "{}: {}", self.file, self.reason),
+        }
+    }
+}
+
+/// Loads every config file, honouring `ADAPTERS_CONFIG_DIR`, and reports
+/// syntax and type errors, unknown capability names, invalid regexes and
+/// references to unknown providers. An empty list means the configuration
+/// is valid.
+pub fn validate() -> Vec<ConfigDiagnostic> {
+    let mut diagnostics = Vec::new();
+    let providers = validate_provider_defaults(&mut diagnostics);
+    validate_vendor_mappings(providers.as_ref(), &mut diagnostics);
+    validate_provider_quirks(providers.as_ref(), &mut diagnostics);
+    validate_model_languages(&mut diagnostics);
+    diagnostics
+}
+
+fn parse<T: serde::de::DeserializeOwned>(
+    source: crate::error::Result<(String, String)>,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+    fallback_name: &str,
+) -> Option<(String, toml::Table, T)> {
+    let (file, config) = match source {
+        Ok(source) => source,
+        Err(err) => {
+            diagnostics.push(ConfigDiagnostic::new(fallback_name, None, err.to_string()));
+            return None;
+        }
+    };
+    let table = match config.parse::<toml::Table>() {
+        Ok(table) => table,
+        Err(err) => {
+            diagnostics.push(ConfigDiagnostic::new(&file, None, err.message()));
+            return None;
+        }
+    };
+    match toml::from_str::<T>(&config) {
+        Ok(typed) => Some((file, table, typed)),
+        Err(err) => {
+            diagnostics.push(ConfigDiagnostic::new(&file, None, err.message()));
+            None
+        }
+    }
+}
+
+fn validate_provider_defaults(diagnostics: &mut Vec<ConfigDiagnostic>) -> Option<HashSet<String>> {
+    let (file, table, _) = parse::<HashMap<String, ProviderDefaults>>(
+        PROVIDER_DEFAULTS.source(),
+        diagnostics,
+        "provider_defaults.toml",
+    )?;
+
+    let known = serde_json::to_value(ModelCapabilities::default())
+        .ok()
+        .and_then(|value| value.as_object().cloned())
+        .unwrap_or_default();
+    for (provider, capabilities) in &table {
+        let Some(capabilities) = capabilities.as_table() else {
+            continue;
+        };
+        for name in capabilities.keys() {
+            if !known.contains_key(name) {
+                diagnostics.push(ConfigDiagnostic::new(
+                    &file,
+                    Some(format!("{}.{}", provider, name)),
+                    "unknown capability",
+                ));
+            }
+        }
+    }
+    Some(table.keys().cloned().collect())
+}
+
+fn check_provider(
+    providers: Option<&HashSet<String>>,
+    provider: &str,
+    file: &str,
+    key: String,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    if providers.is_some_and(|providers| !providers.contains(provider)) {
+        diagnostics.push(ConfigDiagnostic::new(
+            file,
+            Some(key),
+            format!(
+                "provider {} is not defined in provider_defaults.toml",
+                provider
+            ),
+        ));
+    }
+}
+
+fn validate_vendor_mappings(
+    providers: Option<&HashSet<String>>,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    let Some((file, _, mappings)) = parse::<VendorMappingsConfig>(
+        VENDOR_MAPPINGS.source(),
+        diagnostics,
+        "vendor_mappings.toml",
+    ) else {
+        return;
+    };
+
+    for pattern in mappings.patterns.keys() {
+        if let Err(err) = Regex::new(pattern) {
+            diagnostics.push(ConfigDiagnostic::new(
+                &file,
+                Some(format!("patterns.\"{}\"", pattern)),
+                format!("invalid regex: {}", err),
+            ));
+        }
+    }
+    for provider in mappings.provider_defaults.keys() {
+        let key = format!("provider_defaults.{}", provider);
+        check_provider(providers, provider, &file, key, diagnostics);
+    }
+}
+
+fn validate_provider_quirks(
+    providers: Option<&HashSet<String>>,
+    diagnostics: &mut Vec<ConfigDiagnostic>,
+) {
+    let Some((file, _, quirks)) = parse::<HashMap<String, Vec<QuirkRule>>>(
+        PROVIDER_QUIRKS.source(),
+        diagnostics,
+        "provider_quirks.toml",
+    ) else {
+        return;
+    };
+
+    for (provider, rules) in &quirks {
+        check_provider(providers, provider, &file, provider.clone(), diagnostics);
+        for (index, rule) in rules.iter().enumerate() {
+            let Some(Err(err)) = rule.models.as_deref().map(Regex::new) else {
+                continue;
+            };
+            diagnostics.push(ConfigDiagnostic::new(
+                &file,
+                Some(format!("{}[{}].models", provider, index)),
+                format!("invalid regex: {}", err),
+            ));
+        }
+    }
+}
+
+fn validate_model_languages(diagnostics: &mut Vec<ConfigDiagnostic>) {
+    let Some((file, _, languages)) = parse::<HashMap<String, Vec<String>>>(
+        MODEL_LANGUAGES.source(),
+        diagnostics,
+        "model_languages.toml",
+    ) else {
+        return;
+    };
+
+    for (language, patterns) in &languages {
+        for (index, pattern) in patterns.iter().enumerate() {
+            if let Err(err) = Regex::new(pattern) {
+                diagnostics.push(ConfigDiagnostic::new(
+                    &file,
+                    Some(format!("{}[{}]", language, index)),
+                    format!("invalid regex: {}", err),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_config_is_valid() {
+        assert_eq!(validate(), Vec::new());
+    }
+}
@@ -1,24 +1,25 @@
-use once_cell::sync::Lazy;
+use crate::config::loader::ConfigFile;
 use regex::Regex;
 use serde::Deserialize;
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct VendorMappingsConfig {
     pub patterns: HashMap<String, String>,
     pub provider_defaults: HashMap<String, String>,
 }
 
-static VENDOR_MAPPINGS: Lazy<VendorMappingsConfig> = Lazy::new(|| {
-    let config_str = include_str!("../../config/vendor_mappings.toml");
-    toml::from_str(config_str).expect("Failed to parse vendor_mappings.toml")
-});
+pub(crate) static VENDOR_MAPPINGS: ConfigFile<VendorMappingsConfig> = ConfigFile::new(
+    "vendor_mappings.toml",
+    include_str!("../../config/vendor_mappings.toml"),
+);
 
 pub struct VendorMappings;
 
 impl VendorMappings {
     pub fn extract_vendor(model_id: &str, provider_id: &str) -> String {
-        for (pattern, vendor) in &VENDOR_MAPPINGS.patterns {
+        let mappings = VENDOR_MAPPINGS.get();
+        for (pattern, vendor) in &mappings.patterns {
             if let Ok(regex) = Regex::new(pattern) {
                 if regex.is_match(model_id) {
                     return vendor.clone();
@@ -26,7 +27,7 @@ impl VendorMappings {
             }
         }
 
-        VENDOR_MAPPINGS
+        mappings
             .provider_defaults
             .get(provider_id)
             .cloned()
@@ -1,3 +1,8 @@
+use crate::config::ConfigDiagnostic;
+use crate::models::{parse_reset, RateLimitInfo, RateLimitType};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,17 +28,478 @@ pub enum AdapterError {
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
-    #[error("Rate limit exceeded")]
-    RateLimitExceeded,
+    #[error("Transport error: {0}")]
+    TransportError(String),
+
+    #[error("Content blocked by {provider}: {}", categories.join(", "))]
+    ContentBlocked {
+        provider: String,
+        categories: Vec<String>,
+        raw: serde_json::Value,
+    },
+
+    #[error("Provider {provider} returned an error: {message}")]
+    ProviderError {
+        provider: String,
+        error_type: Option<String>,
+        message: String,
+    },
+
+    #[error("Request for {provider} breaks the {schema} schema: {}", violations.join("; "))]
+    SchemaViolation {
+        provider: String,
+        schema: String,
+        violations: Vec<String>,
+    },
+
+    #[error("Request blocked as possible prompt injection: {}", heuristics.join(", "))]
+    PromptInjection { score: f32, heuristics: Vec<String> },
+
+    #[error("Request flagged as {} cannot be sent to {model}, which is not NSFW-allowed", categories.join(", "))]
+    PolicyViolation {
+        model: String,
+        categories: Vec<String>,
+    },
+
+    #[error("Output failed validation after {attempts} attempts: {reason}")]
+    OutputValidationFailed {
+        attempts: u32,
+        reason: String,
+        output: String,
+    },
+
+    #[error("Budget of {limit} exceeded (spent {spent})")]
+    BudgetExceeded { limit: f64, spent: f64 },
+
+    #[error("Conversation of ~{tokens} tokens exceeds the {limit} available to {model} (context window {context_length})")]
+    ContextWindowExceeded {
+        model: String,
+        tokens: u32,
+        limit: u32,
+        context_length: u32,
+    },
+
+    #[error("Payload of {size} bytes exceeds the {limit} byte limit")]
+    TooLarge { size: usize, limit: usize },
+
+    #[error(
+        "Rate limit exceeded for {provider}{}",
+        retry_after.map(|wait| format!(", retry after {:.1}s", wait.as_secs_f64())).unwrap_or_default()
+    )]
+    RateLimitExceeded {
+        provider: String,
+        retry_after: Option<Duration>,
+        limit_type: RateLimitType,
+        remaining: Option<u64>,
+    },
 
     #[error("Stream error: {0}")]
     StreamError(String),
 
+    #[error("Stream interrupted after {} characters: {reason}", partial.chars().count())]
+    StreamInterrupted { partial: String, reason: String },
+
+    #[error("Invalid configuration: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidConfig(Vec<ConfigDiagnostic>),
+
     #[error("TOML parsing error: {0}")]
     TomlError(#[from] toml::de::Error),
 
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+
     #[error("Unknown error: {0}")]
     Unknown(String),
+
+    #[error(transparent)]
+    Shared(Arc<AdapterError>),
+}
+
+/// Coarse buckets for deciding what to do with a failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorClass {
+    /// Transient: rate limits, timeouts, dropped connections.
+    Retryable,
+    /// The request itself is wrong or was refused; retrying won't help.
+    UserError,
+    AuthError,
+    /// Quota, billing or budget exhausted.
+    QuotaError,
+    /// The provider failed or is overloaded; another provider may succeed.
+    ProviderOutage,
+    /// Unexpected failures inside the library.
+    Bug,
+}
+
+impl ErrorClass {
+    /// The status a gateway would answer with.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Self::Retryable => 503,
+            Self::UserError => 400,
+            Self::AuthError => 401,
+            Self::QuotaError => 429,
+            Self::ProviderOutage => 502,
+            Self::Bug => 500,
+        }
+    }
+}
+
+impl AdapterError {
+    pub fn classification(&self) -> ErrorClass {
+        match self {
+            AdapterError::ModelNotFound(_)
+            | AdapterError::ProviderNotSupported(_)
+            | AdapterError::UnsupportedFeature { .. }
+            | AdapterError::ConfigError(_)
+            | AdapterError::ContentBlocked { .. }
+            | AdapterError::PromptInjection { .. }
+            | AdapterError::PolicyViolation { .. }
+            | AdapterError::OutputValidationFailed { .. }
+            | AdapterError::TooLarge { .. }
+            | AdapterError::ContextWindowExceeded { .. }
+            | AdapterError::InvalidConfig(_)
+            | AdapterError::TomlError(_) => ErrorClass::UserError,
+            AdapterError::ApiKeyNotFound(_) => ErrorClass::AuthError,
+            AdapterError::BudgetExceeded { .. } => ErrorClass::QuotaError,
+            AdapterError::RateLimitExceeded { .. }
+            | AdapterError::TransportError(_)
+            | AdapterError::StreamError(_)
+            | AdapterError::StreamInterrupted { .. } => ErrorClass::Retryable,
+            AdapterError::HttpError(err) => match err.status() {
+                Some(status) => classify_status(status.as_u16()),
+                None if err.is_decode() => ErrorClass::ProviderOutage,
+                None if err.is_builder() => ErrorClass::Bug,
+                None => ErrorClass::Retryable,
+            },
+            AdapterError::ProviderError { error_type, .. } => match error_type.as_deref() {
+                Some(
+                    "rate_limit_exceeded"
+                    | "rate_limit_error"
+                    | "RESOURCE_EXHAUSTED"
+                    | "timeout"
+                    | "DEADLINE_EXCEEDED",
+                ) => ErrorClass::Retryable,
+                Some("insufficient_quota" | "billing_error" | "billing_hard_limit_reached") => {
+                    ErrorClass::QuotaError
+                }
+                Some(
+                    "authentication_error"
+                    | "invalid_api_key"
+                    | "permission_error"
+                    | "PERMISSION_DENIED"
+                    | "UNAUTHENTICATED",
+                ) => ErrorClass::AuthError,
+                Some(
+                    "invalid_request_error"
+                    | "not_found_error"
+                    | "request_too_large"
+                    | "INVALID_ARGUMENT"
+                    | "NOT_FOUND"
+                    | "FAILED_PRECONDITION",
+                ) => ErrorClass::UserError,
+                _ => ErrorClass::ProviderOutage,
+            },
+            AdapterError::SerializationError(_)
+            | AdapterError::SchemaViolation { .. }
+            | AdapterError::EncryptionError(_)
+            | AdapterError::Unknown(_) => ErrorClass::Bug,
+            AdapterError::Shared(inner) => inner.classification(),
+        }
+    }
+
+    /// Whether the same request may succeed later, here or on another
+    /// provider.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.classification(),
+            ErrorClass::Retryable | ErrorClass::ProviderOutage
+        )
+    }
+
+    /// The status to answer a proxied request with.
+    pub fn http_status(&self) -> u16 {
+        match self {
+            AdapterError::ModelNotFound(_) => 404,
+            AdapterError::TooLarge { .. } => 413,
+            AdapterError::HttpError(err) if err.status().is_some_and(|s| s.is_client_error()) => {
+                err.status().map_or(400, |status| status.as_u16())
+            }
+            AdapterError::Shared(inner) => inner.http_status(),
+            err if err.is_rate_limited() => 429,
+            err => err.classification().http_status(),
+        }
+    }
+
+    /// The error in OpenAI's format, `{"error": {"message", "type", "code",
+    /// "param"}}`, for returning to OpenAI SDK clients. Send it with
+    /// `http_status`.
+    pub fn to_openai_error_body(&self) -> serde_json::Value {
+        let (error_type, code, param) = match self {
+            AdapterError::Shared(inner) => return inner.to_openai_error_body(),
+            AdapterError::ModelNotFound(_) => (
+                "invalid_request_error",
+                Some("model_not_found"),
+                Some("model"),
+            ),
+            AdapterError::UnsupportedFeature { .. } => {
+                ("invalid_request_error", Some("unsupported_feature"), None)
+            }
+            AdapterError::ContentBlocked { .. } => {
+                ("invalid_request_error", Some("content_filter"), None)
+            }
+            AdapterError::PromptInjection { .. } => {
+                ("invalid_request_error", Some("prompt_injection"), None)
+            }
+            AdapterError::PolicyViolation { .. } => (
+                "invalid_request_error",
+                Some("content_policy_violation"),
+                Some("model"),
+            ),
+            AdapterError::TooLarge { .. } => {
+                ("invalid_request_error", Some("request_too_large"), None)
+            }
+            AdapterError::ContextWindowExceeded { .. } => (
+                "invalid_request_error",
+                Some("context_length_exceeded"),
+                Some("messages"),
+            ),
+            AdapterError::BudgetExceeded { .. } => {
+                ("insufficient_quota", Some("insufficient_quota"), None)
+            }
+            err if err.is_rate_limited() => ("rate_limit_error", Some("rate_limit_exceeded"), None),
+            err => match err.classification() {
+                ErrorClass::UserError => ("invalid_request_error", None, None),
+                ErrorClass::AuthError => ("authentication_error", Some("invalid_api_key"), None),
+                ErrorClass::QuotaError => ("insufficient_quota", Some("insufficient_quota"), None),
+                ErrorClass::Retryable => ("server_error", Some("service_unavailable"), None),
+                ErrorClass::ProviderOutage => ("server_error", Some("upstream_error"), None),
+                ErrorClass::Bug => ("server_error", None, None),
+            },
+        };
+        let message = match self {
+            AdapterError::ProviderError { message, .. } => message.clone(),
+            err => err.to_string(),
+        };
+        serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code,
+                "param": param,
+            }
+        })
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            AdapterError::RateLimitExceeded { .. } => true,
+            AdapterError::ProviderError { error_type, .. } => matches!(
+                error_type.as_deref(),
+                Some("rate_limit_exceeded" | "rate_limit_error" | "RESOURCE_EXHAUSTED")
+            ),
+            AdapterError::HttpError(err) => {
+                err.status() == Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+            }
+            AdapterError::Shared(inner) => inner.is_rate_limited(),
+            _ => false,
+        }
+    }
+
+    /// How long the provider asked to wait, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AdapterError::RateLimitExceeded { retry_after, .. } => *retry_after,
+            AdapterError::Shared(inner) => inner.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// A rate limit from a 429 response. Which limit was hit and when to
+    /// retry come from the limit headers, falling back to the error body:
+    /// OpenAI's `type: "tokens"`, token wording in the message, or Gemini's
+    /// `RetryInfo.retryDelay` and quota violations.
+    pub fn rate_limited(provider: &str, limits: Option<&RateLimitInfo>, body: &Value) -> Self {
+        let error = &body["error"];
+        let message = error["message"].as_str().unwrap_or_default().to_lowercase();
+        let mut tokens = error["type"].as_str() == Some("tokens")
+            || ["tokens per", "token rate", "input tokens", "output tokens"]
+                .iter()
+                .any(|phrase| message.contains(phrase));
+        let mut body_retry = None;
+        for detail in error["details"].as_array().into_iter().flatten() {
+            if let Some(delay) = detail["retryDelay"].as_str().and_then(parse_reset) {
+                body_retry = Some(delay);
+            }
+            tokens |= detail["violations"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|violation| {
+                    violation["quotaMetric"]
+                        .as_str()
+                        .is_some_and(|metric| metric.contains("token"))
+                });
+        }
+
+        let limits = limits.cloned().unwrap_or_default();
+        if limits.requests_remaining == Some(0) {
+            tokens = false;
+        } else if limits.tokens_remaining == Some(0) {
+            tokens = true;
+        }
+        let (limit_type, remaining, reset) = if tokens {
+            (
+                RateLimitType::Tokens,
+                limits.tokens_remaining,
+                limits.tokens_reset,
+            )
+        } else {
+            (
+                RateLimitType::Requests,
+                limits.requests_remaining,
+                limits.requests_reset,
+            )
+        };
+        AdapterError::RateLimitExceeded {
+            provider: provider.to_string(),
+            retry_after: limits.retry_after.or(body_retry).or(reset),
+            limit_type,
+            remaining,
+        }
+    }
+
+    pub fn from_provider_error(provider: &str, body: &serde_json::Value) -> Option<Self> {
+        if let Some(blocked) = crate::utils::detect_content_block(provider, body) {
+            return Some(blocked);
+        }
+
+        let error = body.get("error")?;
+        let error_type = error
+            .get("type")
+            .or_else(|| error.get("status"))
+            .or_else(|| error.get("code"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string);
+        let message = error
+            .get("message")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .or_else(|| error.as_str().map(str::to_string))
+            .unwrap_or_else(|| error.to_string());
+
+        Some(AdapterError::ProviderError {
+            provider: provider.to_string(),
+            error_type,
+            message,
+        })
+    }
+}
+
+fn classify_status(status: u16) -> ErrorClass {
+    match status {
+        401 | 403 => ErrorClass::AuthError,
+        402 => ErrorClass::QuotaError,
+        408 | 409 | 425 | 429 => ErrorClass::Retryable,
+        400..=499 => ErrorClass::UserError,
+        _ => ErrorClass::ProviderOutage,
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AdapterError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let provider_error = |error_type: &str| AdapterError::ProviderError {
+            provider: "openai".to_string(),
+            error_type: Some(error_type.to_string()),
+            message: String::new(),
+        };
+        assert_eq!(
+            provider_error("insufficient_quota").classification(),
+            ErrorClass::QuotaError
+        );
+        assert_eq!(
+            provider_error("invalid_api_key").classification(),
+            ErrorClass::AuthError
+        );
+        assert!(provider_error("overloaded_error").is_retryable());
+        assert!(!provider_error("invalid_request_error").is_retryable());
+
+        let shared = AdapterError::Shared(Arc::new(AdapterError::rate_limited(
+            "openai",
+            None,
+            &Value::Null,
+        )));
+        assert_eq!(shared.classification(), ErrorClass::Retryable);
+        assert_eq!(
+            AdapterError::ConfigError("bad".to_string())
+                .classification()
+                .http_status(),
+            400
+        );
+        assert_eq!(classify_status(503), ErrorClass::ProviderOutage);
+    }
+
+    #[test]
+    fn test_rate_limited_reads_headers_and_body() {
+        let limits = RateLimitInfo {
+            tokens_remaining: Some(0),
+            tokens_reset: Some(Duration::from_secs(6)),
+            requests_remaining: Some(40),
+            ..RateLimitInfo::default()
+        };
+        let err = AdapterError::rate_limited("openai", Some(&limits), &Value::Null);
+        assert!(matches!(
+            err,
+            AdapterError::RateLimitExceeded {
+                limit_type: RateLimitType::Tokens,
+                remaining: Some(0),
+                ..
+            }
+        ));
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(6)));
+
+        let body = serde_json::json!({"error": {
+            "status": "RESOURCE_EXHAUSTED",
+            "details": [
+                {"violations": [{"quotaMetric": "generativelanguage.googleapis.com/generate_content_input_token_count"}]},
+                {"@type": "type.googleapis.com/google.rpc.RetryInfo", "retryDelay": "27s"}
+            ]
+        }});
+        let err = AdapterError::rate_limited("gemini", None, &body);
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(27)));
+        assert!(err.to_string().ends_with("retry after 27.0s"));
+        assert!(matches!(
+            err,
+            AdapterError::RateLimitExceeded {
+                limit_type: RateLimitType::Tokens,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_openai_error_body() {
+        let err = AdapterError::ModelNotFound("openai/gpt-5".to_string());
+        assert_eq!(err.http_status(), 404);
+        let body = err.to_openai_error_body();
+        assert_eq!(body["error"]["code"], "model_not_found");
+        assert_eq!(body["error"]["param"], "model");
+
+        let err = AdapterError::ProviderError {
+            provider: "anthropic".to_string(),
+            error_type: Some("rate_limit_error".to_string()),
+            message: "Slow down".to_string(),
+        };
+        assert_eq!(err.http_status(), 429);
+        let body = err.to_openai_error_body();
+        assert_eq!(body["error"]["message"], "Slow down");
+        assert_eq!(body["error"]["type"], "rate_limit_error");
+        assert!(body["error"]["param"].is_null());
+    }
+}
@@ -0,0 +1,246 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::Result;
+use crate::models::Conversation;
+use crate::utils::{estimate_tokens, percentile};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    pub conversation: Conversation,
+    pub options: ExecuteOptions,
+    pub requests: usize,
+    pub concurrency: usize,
+    /// Streams every request so time to first token can be measured.
+    pub streaming: bool,
+}
+
+impl BenchConfig {
+    pub fn new(conversation: Conversation) -> Self {
+        Self {
+            conversation,
+            options: ExecuteOptions::default(),
+            requests: 20,
+            concurrency: 4,
+            streaming: true,
+        }
+    }
+
+    pub fn with_options(mut self, options: ExecuteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_requests(mut self, requests: usize) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchSample {
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttft_ms: Option<f64>,
+    pub completion_tokens: u32,
+    pub cost: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub mean: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl Percentiles {
+    fn from_values(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+        let at = |quantile: f64| percentile(&values, quantile).unwrap_or_default();
+        Self {
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            p50: at(0.50),
+            p95: at(0.95),
+            p99: at(0.99),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub model: String,
+    pub requests: usize,
+    pub concurrency: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub wall_time_ms: f64,
+    pub requests_per_sec: f64,
+    /// Completion tokens of all successful requests divided by wall time.
+    pub tokens_per_sec: f64,
+    pub latency_ms: Percentiles,
+    pub ttft_ms: Option<Percentiles>,
+    pub total_cost: f64,
+    pub samples: Vec<BenchSample>,
+}
+
+impl BenchReport {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per request.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("request,latency_ms,ttft_ms,completion_tokens,cost,error\n");
+        for (index, sample) in self.samples.iter().enumerate() {
+            let ttft = sample
+                .ttft_ms
+                .map(|ttft| format!("{:.3}", ttft))
+                .unwrap_or_default();
+            let error = sample
+                .error
+                .as_deref()
+                .map(|error| format!("\"{}\"", error.replace('"', "\"\"")))
+                .unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{:.3},{},{},{},{}\n",
+                index, sample.latency_ms, ttft, sample.completion_tokens, sample.cost, error
+            ));
+        }
+        csv
+    }
+}
+
+/// Fires `config.requests` requests at `adapter`, at most
+/// `config.concurrency` at a time. Streamed requests have no usage, so their
+/// tokens and cost are estimated from the streamed text.
+pub async fn bench(adapter: Arc<dyn BaseAdapter>, config: &BenchConfig) -> BenchReport {
+    let started = Instant::now();
+    let samples: Vec<BenchSample> = stream::iter(0..config.requests)
+        .map(|_| run_request(adapter.as_ref(), config))
+        .buffer_unordered(config.concurrency)
+        .collect()
+        .await;
+    let wall_time = started.elapsed().as_secs_f64().max(f64::EPSILON);
+
+    let succeeded: Vec<&BenchSample> = samples.iter().filter(|s| s.error.is_none()).collect();
+    let errors = samples.len() - succeeded.len();
+    let completion_tokens: u32 = succeeded.iter().map(|s| s.completion_tokens).sum();
+    let ttfts: Vec<f64> = succeeded.iter().filter_map(|s| s.ttft_ms).collect();
+
+    BenchReport {
+        model: adapter.get_model().get_path(),
+        requests: samples.len(),
+        concurrency: config.concurrency,
+        errors,
+        error_rate: errors as f64 / samples.len().max(1) as f64,
+        wall_time_ms: wall_time * 1000.0,
+        requests_per_sec: samples.len() as f64 / wall_time,
+        tokens_per_sec: completion_tokens as f64 / wall_time,
+        latency_ms: Percentiles::from_values(succeeded.iter().map(|s| s.latency_ms).collect()),
+        ttft_ms: (!ttfts.is_empty()).then(|| Percentiles::from_values(ttfts)),
+        total_cost: samples.iter().map(|s| s.cost).sum(),
+        samples,
+    }
+}
+
+async fn run_request(adapter: &dyn BaseAdapter, config: &BenchConfig) -> BenchSample {
+    let started = Instant::now();
+    let outcome = if config.streaming {
+        stream_request(adapter, config, started).await
+    } else {
+        adapter
+            .execute(&config.conversation, &config.options)
+            .await
+            .map(|response| {
+                let completion_tokens = response
+                    .usage
+                    .as_ref()
+                    .map(|usage| usage.completion_tokens)
+                    .unwrap_or(0);
+                (None, completion_tokens, response.cost)
+            })
+    };
+
+    let latency_ms = millis(started.elapsed());
+    match outcome {
+        Ok((ttft, completion_tokens, cost)) => BenchSample {
+            latency_ms,
+            ttft_ms: ttft.map(millis),
+            completion_tokens,
+            cost,
+            error: None,
+        },
+        Err(err) => BenchSample {
+            latency_ms,
+            ttft_ms: None,
+            completion_tokens: 0,
+            cost: 0.0,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+async fn stream_request(
+    adapter: &dyn BaseAdapter,
+    config: &BenchConfig,
+    started: Instant,
+) -> Result<(Option<Duration>, u32, f64)> {
+    let mut stream = adapter
+        .execute_stream(&config.conversation, &config.options)
+        .await?;
+    let mut first_token = None;
+    let mut text = String::new();
+    while let Some(chunk) = stream.next().await {
+        for choice in chunk?.choices {
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                first_token.get_or_insert_with(|| started.elapsed());
+                text.push_str(&content);
+            }
+        }
+    }
+
+    let completion_tokens = estimate_tokens(&text);
+    let prompt_tokens = config.conversation.estimated_tokens();
+    let cost = adapter
+        .get_model()
+        .cost
+        .calculate(prompt_tokens, completion_tokens);
+    Ok((first_token, completion_tokens, cost))
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let percentiles = Percentiles::from_values((1..=100).map(f64::from).collect());
+        assert_eq!(percentiles.p50, 50.0);
+        assert_eq!(percentiles.p95, 95.0);
+        assert_eq!(percentiles.p99, 99.0);
+        assert_eq!(percentiles.mean, 50.5);
+        assert_eq!(Percentiles::from_values(Vec::new()), Percentiles::default());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions, ResponseFormat};
+use crate::error::Result;
+use crate::models::{
+    AdapterChatCompletion, ContentEntry, ContentTurn, Conversation, ConversationRole, ImageUrl,
+    Model, Turn, TurnType,
+};
+use futures::StreamExt;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Largest completion the long output check asks for.
+const LONG_OUTPUT_TOKENS: u32 = 4096;
+
+const PIXEL_PNG: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConformanceCheck {
+    System,
+    Streaming,
+    Tools,
+    JsonMode,
+    MultipleChoices,
+    Vision,
+    LongOutput,
+}
+
+impl ConformanceCheck {
+    pub const ALL: [ConformanceCheck; 7] = [
+        Self::System,
+        Self::Streaming,
+        Self::Tools,
+        Self::JsonMode,
+        Self::MultipleChoices,
+        Self::Vision,
+        Self::LongOutput,
+    ];
+
+    /// The `ModelCapabilities` field, or `completion_length`, the check
+    /// puts to the test.
+    pub fn capability(&self) -> &'static str {
+        match self {
+            Self::System => "supports_system",
+            Self::Streaming => "supports_streaming",
+            Self::Tools => "supports_tools",
+            Self::JsonMode => "supports_json_output",
+            Self::MultipleChoices => "supports_n",
+            Self::Vision => "supports_vision",
+            Self::LongOutput => "completion_length",
+        }
+    }
+
+    fn claimed(&self, model: &Model) -> bool {
+        let capabilities = &model.capabilities;
+        match self {
+            Self::System => capabilities.supports_system,
+            Self::Streaming => capabilities.supports_streaming,
+            Self::Tools => capabilities.supports_tools,
+            Self::JsonMode => capabilities.supports_json_output,
+            Self::MultipleChoices => capabilities.supports_n,
+            Self::Vision => capabilities.supports_vision,
+            Self::LongOutput => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub check: ConformanceCheck,
+    pub capability: &'static str,
+    /// What the catalog says the model can do.
+    pub claimed: bool,
+    /// Whether the model actually did it.
+    pub observed: bool,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CheckResult {
+    pub fn diverges(&self) -> bool {
+        self.claimed != self.observed
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConformanceReport {
+    pub model: String,
+    pub results: Vec<CheckResult>,
+    pub total_cost: f64,
+}
+
+impl ConformanceReport {
+    /// Checks whose outcome disagrees with the catalog.
+    pub fn divergences(&self) -> Vec<&CheckResult> {
+        self.results
+            .iter()
+            .filter(|result| result.diverges())
+            .collect()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Runs `checks` one after another against the live model behind `adapter`
+/// and compares each outcome with the model's catalog entry. A failed
+/// request counts as the capability being absent.
+pub async fn run_conformance(
+    adapter: Arc<dyn BaseAdapter>,
+    checks: &[ConformanceCheck],
+) -> ConformanceReport {
+    let model = adapter.get_model();
+    let mut results = Vec::new();
+    let mut total_cost = 0.0;
+    for &check in checks {
+        let started = Instant::now();
+        let outcome = run_check(adapter.as_ref(), check).await;
+        let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let (observed, detail, error) = match outcome {
+            Ok(Probe {
+                observed,
+                detail,
+                cost,
+            }) => {
+                total_cost += cost;
+                (observed, detail, None)
+            }
+            Err(err) => (false, None, Some(err.to_string())),
+        };
+        results.push(CheckResult {
+            check,
+            capability: check.capability(),
+            claimed: check.claimed(model),
+            observed,
+            latency_ms,
+            detail,
+            error,
+        });
+    }
+    ConformanceReport {
+        model: model.get_path(),
+        results,
+        total_cost,
+    }
+}
+
+struct Probe {
+    observed: bool,
+    detail: Option<String>,
+    cost: f64,
+}
+
+impl Probe {
+    fn from_response(response: &AdapterChatCompletion, observed: bool) -> Self {
+        Self {
+            observed,
+            detail: None,
+            cost: response.cost,
+        }
+    }
+
+    fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+fn text_turn(role: ConversationRole, content: &str) -> TurnType {
+    TurnType::Basic(Turn {
+        role,
+        content: content.to_string(),
+        name: None,
+        metadata: None,
+    })
+}
+
+fn ask(content: &str) -> Conversation {
+    Conversation::with_turns(vec![text_turn(ConversationRole::User, content)])
+}
+
+fn first_content(response: &AdapterChatCompletion) -> &str {
+    response
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.as_deref())
+        .unwrap_or_default()
+}
+
+async fn run_check(adapter: &dyn BaseAdapter, check: ConformanceCheck) -> Result<Probe> {
+    match check {
+        ConformanceCheck::System => {
+            let conversation = Conversation::with_turns(vec![
+                text_turn(
+                    ConversationRole::System,
+                    "Whatever the user asks, answer with the single word PINEAPPLE.",
+                ),
+                text_turn(ConversationRole::User, "What is the capital of France?"),
+            ]);
+            let response = adapter
+                .execute(&conversation, &ExecuteOptions::default())
+                .await?;
+            let followed = first_content(&response)
+                .to_uppercase()
+                .contains("PINEAPPLE");
+            Ok(Probe::from_response(&response, followed))
+        }
+        ConformanceCheck::Streaming => {
+            let mut stream = adapter
+                .execute_stream(&ask("Say hello."), &ExecuteOptions::default())
+                .await?;
+            let mut chunks = 0;
+            let mut text = String::new();
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                chunks += 1;
+                if let Some(content) = chunk
+                    .choices
+                    .first()
+                    .and_then(|choice| choice.delta.content.as_deref())
+                {
+                    text.push_str(content);
+                }
+            }
+            Ok(Probe {
+                observed: !text.is_empty(),
+                detail: Some(format!("{} chunks", chunks)),
+                cost: 0.0,
+            })
+        }
+        ConformanceCheck::Tools => {
+            let options = ExecuteOptions {
+                tools: Some(vec![weather_tool()]),
+                ..ExecuteOptions::default()
+            };
+            let response = adapter
+                .execute(
+                    &ask("What is the weather in Paris? Use the get_weather tool."),
+                    &options,
+                )
+                .await?;
+            let called = response
+                .choices
+                .first()
+                .and_then(|choice| choice.message.tool_calls.as_ref())
+                .is_some_and(|calls| calls.iter().any(|call| call.function.name == "get_weather"));
+            Ok(Probe::from_response(&response, called))
+        }
+        ConformanceCheck::JsonMode => {
+            let options = ExecuteOptions {
+                response_format: Some(ResponseFormat::json()),
+                ..ExecuteOptions::default()
+            };
+            let response = adapter
+                .execute(
+                    &ask("Reply with a JSON object whose key \"ok\" is true."),
+                    &options,
+                )
+                .await?;
+            let object = serde_json::from_str::<Value>(first_content(&response))
+                .is_ok_and(|value| value.is_object());
+            Ok(Probe::from_response(&response, object))
+        }
+        ConformanceCheck::MultipleChoices => {
+            let options = ExecuteOptions {
+                n: Some(2),
+                ..ExecuteOptions::default()
+            };
+            let response = adapter.execute(&ask("Name a colour."), &options).await?;
+            let choices = response.choices.len();
+            Ok(Probe::from_response(&response, choices == 2)
+                .with_detail(format!("{} choices", choices)))
+        }
+        ConformanceCheck::Vision => {
+            let conversation = Conversation::with_turns(vec![TurnType::Content(ContentTurn {
+                role: ConversationRole::User,
+                content: vec![
+                    ContentEntry::text("Describe this image in one word."),
+                    ContentEntry::image(ImageUrl {
+                        url: PIXEL_PNG.to_string(),
+                        detail: None,
+                    }),
+                ],
+                name: None,
+                metadata: None,
+            })]);
+            let response = adapter
+                .execute(&conversation, &ExecuteOptions::default())
+                .await?;
+            let answered = !first_content(&response).trim().is_empty();
+            Ok(Probe::from_response(&response, answered))
+        }
+        ConformanceCheck::LongOutput => {
+            let target = adapter
+                .get_model()
+                .completion_length
+                .unwrap_or(LONG_OUTPUT_TOKENS)
+                .min(LONG_OUTPUT_TOKENS);
+            let options = ExecuteOptions {
+                max_tokens: Some(target),
+                ..ExecuteOptions::default()
+            };
+            let response = adapter
+                .execute(
+                    &ask("Count from 1 to 5000 in words, one number per line."),
+                    &options,
+                )
+                .await?;
+            let tokens = response
+                .usage
+                .as_ref()
+                .map(|usage| usage.completion_tokens)
+                .unwrap_or(0);
+            let truncated = response
+                .choices
+                .first()
+                .and_then(|choice| choice.finish_reason.as_deref())
+                == Some("length");
+            // Stopping at the limit, or close to it, shows the full budget is usable.
+            Ok(
+                Probe::from_response(&response, truncated || tokens >= target / 2)
+                    .with_detail(format!("{} of {} tokens", tokens, target)),
+            )
+        }
+    }
+}
+
+fn weather_tool() -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": "get_weather",
+            "description": "Current weather for a city",
+            "parameters": {
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"]
+            }
+        }
+    })
+}
@@ -0,0 +1,386 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions, OutputValidator};
+use crate::error::Result;
+use crate::models::{Conversation, ConversationRole, Turn, TurnType};
+use crate::utils::percentile;
+#[cfg(feature = "parquet")]
+use crate::utils::ParquetTable;
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+const JUDGE_INSTRUCTION: &str = "You grade answers produced by another assistant. Given the \
+rubric, the conversation and the answer, reply with a score from 0 to 10 on the first line \
+and a one-sentence justification on the second.";
+
+#[derive(Clone)]
+pub enum Grader {
+    /// Scores 1.0 when the trimmed output equals the expected text.
+    Exact(String),
+    /// Scores 1.0 when the output contains the expected text, ignoring case.
+    Contains(String),
+    Validator(Arc<dyn OutputValidator>),
+    /// LLM-as-judge: `judge` scores the output against `rubric` from 0 to 10.
+    Judge {
+        judge: Arc<dyn BaseAdapter>,
+        rubric: String,
+    },
+}
+
+impl fmt::Debug for Grader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Grader::Exact(expected) => f.debug_tuple("Exact").field(expected).finish(),
+            Grader::Contains(expected) => f.debug_tuple("Contains").field(expected).finish(),
+            Grader::Validator(_) => f.debug_tuple("Validator").finish_non_exhaustive(),
+            Grader::Judge { judge, rubric } => f
+                .debug_struct("Judge")
+                .field("judge", &judge.get_model().get_path())
+                .field("rubric", rubric)
+                .finish(),
+        }
+    }
+}
+
+impl Grader {
+    /// Returns a score between 0.0 and 1.0 and the judge's reasoning, if any.
+    pub async fn grade(
+        &self,
+        conversation: &Conversation,
+        output: &str,
+    ) -> Result<(f64, Option<String>)> {
+        let score = match self {
+            Grader::Exact(expected) => (output.trim() == expected.trim()) as u8 as f64,
+            Grader::Contains(expected) => {
+                output.to_lowercase().contains(&expected.to_lowercase()) as u8 as f64
+            }
+            Grader::Validator(validator) => {
+                return Ok(match validator.validate(output) {
+                    Ok(()) => (1.0, None),
+                    Err(reason) => (0.0, Some(reason)),
+                });
+            }
+            Grader::Judge { judge, rubric } => {
+                return judge_output(judge.as_ref(), rubric, conversation, output).await
+            }
+        };
+        Ok((score, None))
+    }
+}
+
+async fn judge_output(
+    judge: &dyn BaseAdapter,
+    rubric: &str,
+    conversation: &Conversation,
+    output: &str,
+) -> Result<(f64, Option<String>)> {
+    let transcript = conversation
+        .turns
+        .iter()
+        .map(|turn| format!("{}: {}", turn.role(), turn.text()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let request = Conversation::with_turns(vec![
+        TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: JUDGE_INSTRUCTION.to_string(),
+            name: None,
+            metadata: None,
+        }),
+        TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: format!(
+                "Rubric:\n{}\n\nConversation:\n{}\n\nAnswer:\n{}",
+                rubric, transcript, output
+            ),
+            name: None,
+            metadata: None,
+        }),
+    ]);
+    let options = ExecuteOptions {
+        temperature: Some(0.0),
+        ..ExecuteOptions::default()
+    };
+
+    let response = judge.execute(&request, &options).await?;
+    let verdict = response
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.clone())
+        .unwrap_or_default();
+    Ok((
+        parse_judge_score(&verdict),
+        Some(verdict.trim().to_string()),
+    ))
+}
+
+/// First number in the verdict, read as a 0-10 score.
+fn parse_judge_score(verdict: &str) -> f64 {
+    verdict
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|token| token.trim_matches('.').parse::<f64>().ok())
+        .map(|score| (score / 10.0).clamp(0.0, 1.0))
+        .unwrap_or(0.0)
+}
+
+#[derive(Debug, Clone)]
+pub struct EvalCase {
+    pub id: String,
+    pub conversation: Conversation,
+    pub grader: Grader,
+}
+
+impl EvalCase {
+    pub fn new(id: impl Into<String>, conversation: Conversation, grader: Grader) -> Self {
+        Self {
+            id: id.into(),
+            conversation,
+            grader,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseResult {
+    pub case_id: String,
+    pub model: String,
+    pub output: Option<String>,
+    pub score: f64,
+    pub passed: bool,
+    pub latency_ms: u64,
+    pub cost: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelReport {
+    pub model: String,
+    pub cases: usize,
+    pub passed: usize,
+    pub errors: usize,
+    pub accuracy: f64,
+    pub mean_score: f64,
+    pub mean_latency_ms: f64,
+    pub p95_latency_ms: u64,
+    pub total_cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub suite: String,
+    pub models: Vec<ModelReport>,
+    pub results: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    pub fn model(&self, path: &str) -> Option<&ModelReport> {
+        self.models.iter().find(|report| report.model == path)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per case result, tagged with the suite name.
+    #[cfg(feature = "parquet")]
+    pub fn to_parquet(&self) -> Result<Vec<u8>> {
+        let column = |value: fn(&CaseResult) -> Option<String>| {
+            self.results.iter().map(value).collect::<Vec<_>>()
+        };
+        ParquetTable::new()
+            .with_utf8("suite", vec![Some(self.suite.clone()); self.results.len()])
+            .with_utf8("case_id", column(|result| Some(result.case_id.clone())))
+            .with_utf8("model", column(|result| Some(result.model.clone())))
+            .with_utf8("output", column(|result| result.output.clone()))
+            .with_double(
+                "score",
+                self.results
+                    .iter()
+                    .map(|result| Some(result.score))
+                    .collect(),
+            )
+            .with_boolean(
+                "passed",
+                self.results
+                    .iter()
+                    .map(|result| Some(result.passed))
+                    .collect(),
+            )
+            .with_int64(
+                "latency_ms",
+                self.results
+                    .iter()
+                    .map(|result| Some(result.latency_ms as i64))
+                    .collect(),
+            )
+            .with_double(
+                "cost",
+                self.results
+                    .iter()
+                    .map(|result| Some(result.cost))
+                    .collect(),
+            )
+            .with_utf8("reasoning", column(|result| result.reasoning.clone()))
+            .with_utf8("error", column(|result| result.error.clone()))
+            .to_bytes()
+    }
+}
+
+/// A named set of cases run against every model in parallel. Failed
+/// requests score 0 and are counted as errors rather than aborting the run.
+#[derive(Debug, Clone)]
+pub struct EvalSuite {
+    pub name: String,
+    pub cases: Vec<EvalCase>,
+    pub options: ExecuteOptions,
+    pub concurrency: usize,
+    pub pass_threshold: f64,
+}
+
+impl EvalSuite {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+            options: ExecuteOptions::default(),
+            concurrency: 8,
+            pass_threshold: 0.5,
+        }
+    }
+
+    pub fn with_case(mut self, case: EvalCase) -> Self {
+        self.cases.push(case);
+        self
+    }
+
+    pub fn with_options(mut self, options: ExecuteOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub async fn run(&self, models: &[Arc<dyn BaseAdapter>]) -> EvalReport {
+        let jobs = models
+            .iter()
+            .flat_map(|model| self.cases.iter().map(move |case| (model.clone(), case)));
+        let mut results: Vec<CaseResult> = stream::iter(jobs)
+            .map(|(model, case)| self.run_case(model, case))
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let order = |result: &CaseResult| {
+            let model = models
+                .iter()
+                .position(|model| model.get_model().get_path() == result.model);
+            let case = self.cases.iter().position(|case| case.id == result.case_id);
+            (model, case)
+        };
+        results.sort_by_key(order);
+
+        let models = models
+            .iter()
+            .map(|model| {
+                let path = model.get_model().get_path();
+                let model_results: Vec<&CaseResult> = results
+                    .iter()
+                    .filter(|result| result.model == path)
+                    .collect();
+                summarize(path, &model_results)
+            })
+            .collect();
+
+        EvalReport {
+            suite: self.name.clone(),
+            models,
+            results,
+        }
+    }
+
+    async fn run_case(&self, model: Arc<dyn BaseAdapter>, case: &EvalCase) -> CaseResult {
+        let started = Instant::now();
+        let outcome = model.execute(&case.conversation, &self.options).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        let mut result = CaseResult {
+            case_id: case.id.clone(),
+            model: model.get_model().get_path(),
+            output: None,
+            score: 0.0,
+            passed: false,
+            latency_ms,
+            cost: 0.0,
+            reasoning: None,
+            error: None,
+        };
+        let response = match outcome {
+            Ok(response) => response,
+            Err(err) => {
+                result.error = Some(err.to_string());
+                return result;
+            }
+        };
+
+        result.cost = response.cost;
+        let output = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.clone())
+            .unwrap_or_default();
+        match case.grader.grade(&case.conversation, &output).await {
+            Ok((score, reasoning)) => {
+                result.score = score;
+                result.passed = score >= self.pass_threshold;
+                result.reasoning = reasoning;
+            }
+            Err(err) => result.error = Some(format!("grading failed: {}", err)),
+        }
+        result.output = Some(output);
+        result
+    }
+}
+
+fn summarize(model: String, results: &[&CaseResult]) -> ModelReport {
+    let cases = results.len();
+    let count = cases.max(1) as f64;
+    let mut latencies: Vec<u64> = results.iter().map(|result| result.latency_ms).collect();
+    latencies.sort_unstable();
+    let p95_latency_ms = percentile(&latencies, 0.95).unwrap_or(0);
+    let passed = results.iter().filter(|result| result.passed).count();
+
+    ModelReport {
+        model,
+        cases,
+        passed,
+        errors: results
+            .iter()
+            .filter(|result| result.error.is_some())
+            .count(),
+        accuracy: passed as f64 / count,
+        mean_score: results.iter().map(|result| result.score).sum::<f64>() / count,
+        mean_latency_ms: latencies.iter().sum::<u64>() as f64 / count,
+        p95_latency_ms,
+        total_cost: results.iter().map(|result| result.cost).sum(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_judge_score() {
+        assert_eq!(parse_judge_score("8\nMostly correct."), 0.8);
+        assert_eq!(parse_judge_score("Score: 10/10"), 1.0);
+        assert_eq!(parse_judge_score("7.5."), 0.75);
+        assert_eq!(parse_judge_score("no score"), 0.0);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod bench;
+pub mod conformance;
+pub mod harness;
+
+pub use bench::*;
+pub use conformance::*;
+pub use harness::*;
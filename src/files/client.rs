@@ -0,0 +1,291 @@
+use crate::config::EnvConfig;
+use crate::error::{AdapterError, Result};
+use crate::files::gemini::GeminiFiles;
+use crate::http::HttpClient;
+use crate::models::FileReference;
+use reqwest::multipart::{Form, Part};
+use reqwest::RequestBuilder;
+use serde_json::Value;
+
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const ANTHROPIC_FILES_BETA: &str = "files-api-2025-04-14";
+
+pub const OPENAI_ORGANIZATION_HEADER: &str = "OpenAI-Organization";
+pub const OPENAI_PROJECT_HEADER: &str = "OpenAI-Project";
+
+/// Adds the OpenAI organization and project headers that are set.
+pub(crate) fn openai_scope(
+    mut request: RequestBuilder,
+    org: &Option<String>,
+    project: &Option<String>,
+) -> RequestBuilder {
+    if let Some(org) = org {
+        request = request.header(OPENAI_ORGANIZATION_HEADER, org);
+    }
+    if let Some(project) = project {
+        request = request.header(OPENAI_PROJECT_HEADER, project);
+    }
+    request
+}
+
+/// The default base URL of the OpenAI-compatible providers whose file and
+/// fine-tuning endpoints live under `/v1`.
+pub(crate) fn openai_compatible_base_url(provider: &str) -> Option<&'static str> {
+    match provider {
+        "openai" => Some("https://api.openai.com"),
+        "together" => Some("https://api.together.xyz"),
+        "fireworks" => Some("https://api.fireworks.ai/inference"),
+        _ => None,
+    }
+}
+
+/// Sends an authenticated `request` and returns its JSON body, or the
+/// provider's error. `kind` names the request in the fallback message, e.g.
+/// "file".
+pub(crate) async fn send_json(
+    http: &HttpClient,
+    request: RequestBuilder,
+    provider: &str,
+    kind: &str,
+) -> Result<Value> {
+    let response = http.send(request).await?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if !status.is_success() {
+        return Err(
+            AdapterError::from_provider_error(provider, &body).unwrap_or_else(|| {
+                AdapterError::ProviderError {
+                    provider: provider.to_string(),
+                    error_type: None,
+                    message: format!("{} request failed with status {}", kind, status),
+                }
+            }),
+        );
+    }
+    Ok(body)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilePurpose {
+    Assistants,
+    Batch,
+    FineTune,
+    UserData,
+    Vision,
+}
+
+impl FilePurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilePurpose::Assistants => "assistants",
+            FilePurpose::Batch => "batch",
+            FilePurpose::FineTune => "fine-tune",
+            FilePurpose::UserData => "user_data",
+            FilePurpose::Vision => "vision",
+        }
+    }
+
+    fn default_filename(&self) -> &'static str {
+        match self {
+            FilePurpose::Batch | FilePurpose::FineTune => "upload.jsonl",
+            _ => "upload.bin",
+        }
+    }
+}
+
+enum Backend {
+    OpenAi,
+    Anthropic,
+    Gemini(GeminiFiles),
+}
+
+/// Uploads, inspects and deletes files in a provider's file store. OpenAI and
+/// OpenAI-compatible providers, Anthropic and Gemini are supported.
+pub struct FileClient {
+    provider: String,
+    http: HttpClient,
+    api_key: String,
+    base_url: String,
+    backend: Backend,
+    org: Option<String>,
+    project: Option<String>,
+}
+
+impl FileClient {
+    pub fn new(provider: &str, api_key: impl Into<String>) -> Result<Self> {
+        let api_key = api_key.into();
+        let (backend, default_base_url) = match provider {
+            "anthropic" => (Backend::Anthropic, "https://api.anthropic.com"),
+            "gemini" | "google" => (
+                Backend::Gemini(GeminiFiles::new(api_key.clone())?),
+                "https://generativelanguage.googleapis.com",
+            ),
+            other => (
+                Backend::OpenAi,
+                openai_compatible_base_url(other)
+                    .ok_or_else(|| AdapterError::ProviderNotSupported(other.to_string()))?,
+            ),
+        };
+
+        Ok(Self {
+            provider: provider.to_string(),
+            http: HttpClient::new()?,
+            api_key,
+            base_url: EnvConfig::get_override_base_url()
+                .unwrap_or_else(|| default_base_url.to_string()),
+            backend,
+            org: EnvConfig::get_org_id(provider),
+            project: EnvConfig::get_project_id(provider),
+        })
+    }
+
+    pub fn from_env(provider: &str) -> Result<Self> {
+        let api_key = EnvConfig::get_api_key(provider)
+            .ok_or_else(|| AdapterError::ApiKeyNotFound(provider.to_string()))?;
+        Self::new(provider, api_key)
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        if let Backend::Gemini(files) = self.backend {
+            self.backend = Backend::Gemini(files.with_base_url(self.base_url.clone()));
+        }
+        self
+    }
+
+    /// Sent as `OpenAI-Organization` to OpenAI-compatible providers; defaults
+    /// to `<PROVIDER>_ORG_ID`.
+    pub fn with_org(mut self, org: impl Into<String>) -> Self {
+        self.org = Some(org.into());
+        self
+    }
+
+    /// Sent as `OpenAI-Project` to OpenAI-compatible providers; defaults to
+    /// `<PROVIDER>_PROJECT_ID`.
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        self.project = Some(project.into());
+        self
+    }
+
+    pub fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    pub async fn upload(&self, bytes: Vec<u8>, purpose: FilePurpose) -> Result<FileReference> {
+        self.upload_as(
+            bytes,
+            purpose.default_filename(),
+            "application/octet-stream",
+            purpose,
+        )
+        .await
+    }
+
+    pub async fn upload_as(
+        &self,
+        bytes: Vec<u8>,
+        filename: &str,
+        mime_type: &str,
+        purpose: FilePurpose,
+    ) -> Result<FileReference> {
+        if let Backend::Gemini(files) = &self.backend {
+            let file = files.upload(bytes, mime_type, Some(filename)).await?;
+            return Ok(file.to_file_reference(&self.provider));
+        }
+
+        let part = Part::bytes(bytes)
+            .file_name(filename.to_string())
+            .mime_str(mime_type)?;
+        let mut form = Form::new().part("file", part);
+        if matches!(self.backend, Backend::OpenAi) {
+            form = form.text("purpose", purpose.as_str());
+        }
+
+        let request = self
+            .http
+            .inner()
+            .post(format!("{}/v1/files", self.base_url))
+            .multipart(form);
+        let body = self.send(request).await?;
+        self.parse_file(&body)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<FileReference> {
+        if let Backend::Gemini(files) = &self.backend {
+            let file = files.get(id).await?;
+            return Ok(file.to_file_reference(&self.provider));
+        }
+
+        let request = self
+            .http
+            .inner()
+            .get(format!("{}/v1/files/{}", self.base_url, id));
+        let body = self.send(request).await?;
+        self.parse_file(&body)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        if let Backend::Gemini(files) = &self.backend {
+            return files.delete(id).await;
+        }
+
+        let request = self
+            .http
+            .inner()
+            .delete(format!("{}/v1/files/{}", self.base_url, id));
+        self.send(request).await?;
+        Ok(())
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let request = match self.backend {
+            Backend::Anthropic => request
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .header("anthropic-beta", ANTHROPIC_FILES_BETA),
+            _ => openai_scope(request.bearer_auth(&self.api_key), &self.org, &self.project),
+        };
+        send_json(&self.http, request, &self.provider, "file").await
+    }
+
+    fn parse_file(&self, body: &Value) -> Result<FileReference> {
+        let id =
+            body.get("id")
+                .and_then(Value::as_str)
+                .ok_or_else(|| AdapterError::ProviderError {
+                    provider: self.provider.clone(),
+                    error_type: None,
+                    message: "file response did not include an id".to_string(),
+                })?;
+
+        Ok(FileReference {
+            provider: self.provider.clone(),
+            id: id.to_string(),
+            uri: None,
+            filename: body
+                .get("filename")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            mime_type: body
+                .get("mime_type")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            size_bytes: body
+                .get("bytes")
+                .or_else(|| body.get("size_bytes"))
+                .and_then(Value::as_u64),
+        })
+    }
+}
+
+pub async fn upload(provider: &str, bytes: Vec<u8>, purpose: FilePurpose) -> Result<FileReference> {
+    FileClient::from_env(provider)?.upload(bytes, purpose).await
+}
+
+pub async fn get(file: &FileReference) -> Result<FileReference> {
+    FileClient::from_env(&file.provider)?.get(&file.id).await
+}
+
+pub async fn delete(file: &FileReference) -> Result<()> {
+    FileClient::from_env(&file.provider)?.delete(&file.id).await
+}
@@ -0,0 +1,186 @@
+use crate::config::EnvConfig;
+use crate::error::{AdapterError, Result};
+use crate::http::HttpClient;
+use crate::models::{FileReference, VideoInput};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+
+/// Gemini rejects requests above 20 MB, so larger media has to go through the
+/// File API and be referenced by URI.
+pub const GEMINI_INLINE_LIMIT: usize = 20 * 1024 * 1024;
+/// Room left in an inlining request for the prompt and the JSON around it.
+const INLINE_HEADROOM: usize = 1024 * 1024;
+
+/// Whether `len` bytes of media, base64-encoded, leave the request under
+/// `GEMINI_INLINE_LIMIT`.
+fn fits_inline(len: usize) -> bool {
+    len.div_ceil(3)
+        .saturating_mul(4)
+        .saturating_add(INLINE_HEADROOM)
+        <= GEMINI_INLINE_LIMIT
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeminiFile {
+    pub name: String,
+    pub uri: String,
+    pub mime_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+impl GeminiFile {
+    pub fn is_active(&self) -> bool {
+        self.state.as_deref().is_none_or(|state| state == "ACTIVE")
+    }
+
+    pub fn to_file_reference(&self, provider: &str) -> FileReference {
+        FileReference {
+            provider: provider.to_string(),
+            id: self.name.clone(),
+            uri: Some(self.uri.clone()),
+            filename: self.display_name.clone(),
+            mime_type: Some(self.mime_type.clone()),
+            size_bytes: self.size_bytes.as_ref().and_then(|size| size.parse().ok()),
+        }
+    }
+
+    pub fn to_video_input(&self) -> VideoInput {
+        VideoInput::from_uri(&self.uri, &self.mime_type)
+    }
+}
+
+pub struct GeminiFiles {
+    http: HttpClient,
+    api_key: String,
+    base_url: String,
+}
+
+impl GeminiFiles {
+    pub fn new(api_key: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            http: HttpClient::new()?,
+            api_key: api_key.into(),
+            base_url: EnvConfig::get_override_base_url()
+                .unwrap_or_else(|| GEMINI_BASE_URL.to_string()),
+        })
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = EnvConfig::get_api_key("gemini")
+            .ok_or_else(|| AdapterError::ApiKeyNotFound("gemini".to_string()))?;
+        Self::new(api_key)
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Uploads `bytes` with the resumable upload protocol (start + a single
+    /// upload/finalize request).
+    pub async fn upload(
+        &self,
+        bytes: Vec<u8>,
+        mime_type: &str,
+        display_name: Option<&str>,
+    ) -> Result<GeminiFile> {
+        let start = self
+            .http
+            .inner()
+            .post(format!("{}/upload/v1beta/files", self.base_url))
+            .header("x-goog-api-key", &self.api_key)
+            .header("X-Goog-Upload-Protocol", "resumable")
+            .header("X-Goog-Upload-Command", "start")
+            .header("X-Goog-Upload-Header-Content-Length", bytes.len())
+            .header("X-Goog-Upload-Header-Content-Type", mime_type)
+            .json(&json!({"file": {"display_name": display_name}}));
+        let start = self.http.send(start).await?.error_for_status()?;
+
+        let upload_url = start
+            .headers()
+            .get("x-goog-upload-url")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| AdapterError::ProviderError {
+                provider: "gemini".to_string(),
+                error_type: None,
+                message: "file upload did not return an upload URL".to_string(),
+            })?
+            .to_string();
+
+        let upload = self
+            .http
+            .inner()
+            .post(upload_url)
+            .header("x-goog-api-key", &self.api_key)
+            .header("X-Goog-Upload-Offset", 0)
+            .header("X-Goog-Upload-Command", "upload, finalize")
+            .body(bytes);
+        let body: Value = self
+            .http
+            .send(upload)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(serde_json::from_value(
+            body.get("file").cloned().unwrap_or(body),
+        )?)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<GeminiFile> {
+        let request = self
+            .http
+            .inner()
+            .get(format!("{}/v1beta/{}", self.base_url, name))
+            .header("x-goog-api-key", &self.api_key);
+        Ok(self
+            .http
+            .send(request)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        let request = self
+            .http
+            .inner()
+            .delete(format!("{}/v1beta/{}", self.base_url, name))
+            .header("x-goog-api-key", &self.api_key);
+        self.http.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Inlines videos small enough for the request to stay under
+    /// `GEMINI_INLINE_LIMIT` once base64-encoded and uploads the others.
+    pub async fn video_input(&self, bytes: Vec<u8>, mime_type: &str) -> Result<VideoInput> {
+        if fits_inline(bytes.len()) {
+            return Ok(VideoInput::inline(&bytes, mime_type));
+        }
+        Ok(self.upload(bytes, mime_type, None).await?.to_video_input())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_limit_counts_base64_growth() {
+        assert!(fits_inline(4 * 1024 * 1024));
+        assert!(fits_inline(14 * 1024 * 1024));
+        assert!(!fits_inline(15 * 1024 * 1024));
+        assert!(!fits_inline(19 * 1024 * 1024));
+        assert!(!fits_inline(usize::MAX));
+    }
+}
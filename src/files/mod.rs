@@ -0,0 +1,5 @@
+pub mod client;
+pub mod gemini;
+
+pub use client::*;
+pub use gemini::*;
@@ -0,0 +1,280 @@
+use crate::adapters::AdapterFactory;
+use crate::config::{EnvConfig, ProviderDefaults, VendorMappings};
+use crate::error::{AdapterError, Result};
+use crate::files::{openai_compatible_base_url, openai_scope, send_json, FileClient, FilePurpose};
+use crate::http::HttpClient;
+use crate::models::{Conversation, Cost, Model, ModelProperties};
+use reqwest::RequestBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FineTuneStatus {
+    ValidatingFiles,
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    #[serde(other)]
+    Unknown,
+}
+
+impl FineTuneStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            FineTuneStatus::Succeeded | FineTuneStatus::Failed | FineTuneStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FineTuneJob {
+    pub id: String,
+    pub model: String,
+    pub status: FineTuneStatus,
+    #[serde(default)]
+    pub fine_tuned_model: Option<String>,
+    #[serde(default)]
+    pub training_file: Option<String>,
+    #[serde(default)]
+    pub validation_file: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub finished_at: Option<u64>,
+    #[serde(default)]
+    pub trained_tokens: Option<u64>,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FineTuneCheckpoint {
+    pub id: String,
+    pub fine_tuned_model_checkpoint: String,
+    pub step_number: u64,
+    #[serde(default)]
+    pub metrics: Value,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FineTuneRequest {
+    pub base_model: String,
+    pub training: Vec<Conversation>,
+    pub validation: Option<Vec<Conversation>>,
+    pub suffix: Option<String>,
+    pub hyperparameters: Option<Value>,
+}
+
+/// One `{"messages": [...]}` line per conversation.
+pub fn training_jsonl(conversations: &[Conversation]) -> Result<String> {
+    let mut jsonl = String::new();
+    for conversation in conversations {
+        let line = json!({"messages": conversation.to_openai_messages()});
+        jsonl.push_str(&serde_json::to_string(&line)?);
+        jsonl.push('\n');
+    }
+    Ok(jsonl)
+}
+
+/// Client for OpenAI-compatible `/v1/fine_tuning/jobs` endpoints.
+pub struct FineTuneClient {
+    provider: String,
+    http: HttpClient,
+    files: FileClient,
+    api_key: String,
+    base_url: String,
+    org: Option<String>,
+    project: Option<String>,
+}
+
+impl FineTuneClient {
+    pub fn new(provider: &str, api_key: impl Into<String>) -> Result<Self> {
+        let default_base_url = openai_compatible_base_url(provider)
+            .ok_or_else(|| AdapterError::ProviderNotSupported(provider.to_string()))?;
+        let api_key = api_key.into();
+
+        Ok(Self {
+            provider: provider.to_string(),
+            http: HttpClient::new()?,
+            files: FileClient::new(provider, api_key.clone())?,
+            api_key,
+            base_url: EnvConfig::get_override_base_url()
+                .unwrap_or_else(|| default_base_url.to_string()),
+            org: EnvConfig::get_org_id(provider),
+            project: EnvConfig::get_project_id(provider),
+        })
+    }
+
+    pub fn from_env(provider: &str) -> Result<Self> {
+        let api_key = EnvConfig::get_api_key(provider)
+            .ok_or_else(|| AdapterError::ApiKeyNotFound(provider.to_string()))?;
+        Self::new(provider, api_key)
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self.files = self.files.with_base_url(self.base_url.clone());
+        self
+    }
+
+    /// See `FileClient::with_org`; also applies to the training file uploads.
+    pub fn with_org(mut self, org: impl Into<String>) -> Self {
+        let org = org.into();
+        self.files = self.files.with_org(org.clone());
+        self.org = Some(org);
+        self
+    }
+
+    pub fn with_project(mut self, project: impl Into<String>) -> Self {
+        let project = project.into();
+        self.files = self.files.with_project(project.clone());
+        self.project = Some(project);
+        self
+    }
+
+    /// Uploads the training (and validation) conversations as JSONL and
+    /// starts a job on `base_model`.
+    pub async fn create_job(&self, request: FineTuneRequest) -> Result<FineTuneJob> {
+        if request.training.is_empty() {
+            return Err(AdapterError::ConfigError(
+                "fine-tuning needs at least one training conversation".to_string(),
+            ));
+        }
+
+        let training_file = self.upload_jsonl(&request.training, "train.jsonl").await?;
+        let validation_file = match &request.validation {
+            Some(validation) if !validation.is_empty() => {
+                Some(self.upload_jsonl(validation, "validation.jsonl").await?)
+            }
+            _ => None,
+        };
+
+        let mut body = json!({
+            "model": request.base_model,
+            "training_file": training_file,
+        });
+        if let Some(validation_file) = validation_file {
+            body["validation_file"] = json!(validation_file);
+        }
+        if let Some(suffix) = request.suffix {
+            body["suffix"] = json!(suffix);
+        }
+        if let Some(hyperparameters) = request.hyperparameters {
+            body["hyperparameters"] = hyperparameters;
+        }
+
+        let request = self
+            .http
+            .inner()
+            .post(format!("{}/v1/fine_tuning/jobs", self.base_url))
+            .json(&body);
+        self.send(request).await
+    }
+
+    pub async fn get_job(&self, job_id: &str) -> Result<FineTuneJob> {
+        let request = self
+            .http
+            .inner()
+            .get(format!("{}/v1/fine_tuning/jobs/{}", self.base_url, job_id));
+        self.send(request).await
+    }
+
+    pub async fn cancel_job(&self, job_id: &str) -> Result<FineTuneJob> {
+        let request = self.http.inner().post(format!(
+            "{}/v1/fine_tuning/jobs/{}/cancel",
+            self.base_url, job_id
+        ));
+        self.send(request).await
+    }
+
+    pub async fn list_checkpoints(&self, job_id: &str) -> Result<Vec<FineTuneCheckpoint>> {
+        #[derive(Deserialize)]
+        struct Page {
+            data: Vec<FineTuneCheckpoint>,
+        }
+
+        let request = self.http.inner().get(format!(
+            "{}/v1/fine_tuning/jobs/{}/checkpoints",
+            self.base_url, job_id
+        ));
+        let page: Page = self.send(request).await?;
+        Ok(page.data)
+    }
+
+    /// Polls until the job reaches a terminal state. A succeeded job is
+    /// registered in the factory catalog before it is returned.
+    pub async fn wait_for_completion(
+        &self,
+        job_id: &str,
+        poll_interval: Duration,
+    ) -> Result<FineTuneJob> {
+        loop {
+            let job = self.get_job(job_id).await?;
+            if job.status.is_terminal() {
+                if job.status == FineTuneStatus::Succeeded {
+                    self.register_model(&job).await?;
+                }
+                return Ok(job);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// Adds the fine-tuned model to the catalog, inheriting capabilities,
+    /// limits and pricing from its base model when that is known.
+    pub async fn register_model(&self, job: &FineTuneJob) -> Result<Model> {
+        let name = job.fine_tuned_model.clone().ok_or_else(|| {
+            AdapterError::ConfigError(format!("fine-tuning job {} has no output model", job.id))
+        })?;
+
+        let base = AdapterFactory::get_supported_models(None)
+            .await
+            .into_iter()
+            .find(|model| model.provider_name == self.provider && model.name == job.model);
+
+        let model = match base {
+            Some(base) => Model { name, ..base },
+            None => Model {
+                vendor_name: VendorMappings::extract_vendor(&job.model, &self.provider),
+                name,
+                provider_name: self.provider.clone(),
+                cost: Cost::default(),
+                context_length: 0,
+                completion_length: None,
+                capabilities: ProviderDefaults::for_provider(&self.provider).capabilities,
+                properties: ModelProperties::default(),
+                knowledge_cutoff: None,
+                release_date: None,
+                last_updated: None,
+            },
+        };
+
+        AdapterFactory::register_model(model.clone()).await;
+        Ok(model)
+    }
+
+    async fn upload_jsonl(&self, conversations: &[Conversation], filename: &str) -> Result<String> {
+        let jsonl = training_jsonl(conversations)?;
+        let file = self
+            .files
+            .upload_as(
+                jsonl.into_bytes(),
+                filename,
+                "application/jsonl",
+                FilePurpose::FineTune,
+            )
+            .await?;
+        Ok(file.id)
+    }
+
+    async fn send<T: serde::de::DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let request = openai_scope(request.bearer_auth(&self.api_key), &self.org, &self.project);
+        let body = send_json(&self.http, request, &self.provider, "fine-tuning").await?;
+        Ok(serde_json::from_value(body)?)
+    }
+}
@@ -0,0 +1,187 @@
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, ConversationRole, Turn, TurnType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetFormat {
+    /// `{"conversations": [{"from": "human", "value": ...}]}`.
+    ShareGpt,
+    /// OpenAI chat fine-tuning lines, `{"messages": [...]}`.
+    OpenAiChat,
+    /// `{"instruction": ..., "input": ..., "output": ...}`.
+    Alpaca,
+}
+
+impl DatasetFormat {
+    /// Guesses the format from the fields of a record.
+    pub fn detect(record: &Value) -> Option<Self> {
+        if record.get("messages").is_some() {
+            Some(Self::OpenAiChat)
+        } else if record.get("conversations").is_some() {
+            Some(Self::ShareGpt)
+        } else if record.get("instruction").is_some() {
+            Some(Self::Alpaca)
+        } else {
+            None
+        }
+    }
+}
+
+fn invalid(index: usize, reason: impl std::fmt::Display) -> AdapterError {
+    AdapterError::ConfigError(format!("invalid dataset record {}: {}", index + 1, reason))
+}
+
+/// Records of a JSON array or of JSONL, one object per non-empty line.
+fn records(input: &str) -> Result<Vec<Value>> {
+    let trimmed = input.trim_start();
+    if trimmed.starts_with('[') {
+        return Ok(serde_json::from_str(trimmed)?);
+    }
+    input
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(index, line)| serde_json::from_str(line).map_err(|err| invalid(index, err)))
+        .collect()
+}
+
+fn text_turn(role: ConversationRole, content: impl Into<String>) -> TurnType {
+    TurnType::Basic(Turn {
+        role,
+        content: content.into(),
+        name: None,
+        metadata: None,
+    })
+}
+
+fn share_gpt(record: &Value, index: usize) -> Result<Conversation> {
+    let messages = record["conversations"]
+        .as_array()
+        .ok_or_else(|| invalid(index, "conversations is not an array"))?;
+    let mut conversation = Conversation::new();
+    if let Some(system) = record.get("system").and_then(Value::as_str) {
+        conversation.add_turn(text_turn(ConversationRole::System, system));
+    }
+    for message in messages {
+        let role = match message["from"].as_str() {
+            Some("system") => ConversationRole::System,
+            Some("human") | Some("user") => ConversationRole::User,
+            Some("gpt") | Some("assistant") | Some("chatgpt") | Some("function_call") => {
+                ConversationRole::Assistant
+            }
+            // Without call ids, tool results are kept as plain user turns.
+            Some("observation") | Some("tool") => ConversationRole::User,
+            other => return Err(invalid(index, format!("unknown speaker {:?}", other))),
+        };
+        let value = message["value"]
+            .as_str()
+            .ok_or_else(|| invalid(index, "message has no value"))?;
+        conversation.add_turn(text_turn(role, value));
+    }
+    Ok(conversation)
+}
+
+fn openai_chat(record: &Value, index: usize) -> Result<Conversation> {
+    let messages = record["messages"]
+        .as_array()
+        .ok_or_else(|| invalid(index, "messages is not an array"))?;
+    let turns = messages
+        .iter()
+        .map(|message| {
+            let mut message = message.clone();
+            if let Some(message) = message.as_object_mut() {
+                // Per-message training weights have no place in a turn.
+                message.remove("weight");
+            }
+            serde_json::from_value::<TurnType>(message).map_err(|err| invalid(index, err))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Conversation::with_turns(turns))
+}
+
+fn alpaca(record: &Value, index: usize) -> Result<Conversation> {
+    let field = |name: &str| {
+        record
+            .get(name)
+            .and_then(Value::as_str)
+            .filter(|text| !text.is_empty())
+    };
+    let instruction = field("instruction").ok_or_else(|| invalid(index, "no instruction"))?;
+    let output = field("output").ok_or_else(|| invalid(index, "no output"))?;
+    let prompt = match field("input") {
+        Some(input) => format!("{}\n\n{}", instruction, input),
+        None => instruction.to_string(),
+    };
+    let mut conversation = Conversation::new();
+    if let Some(system) = field("system") {
+        conversation.add_turn(text_turn(ConversationRole::System, system));
+    }
+    conversation.add_turn(text_turn(ConversationRole::User, prompt));
+    conversation.add_turn(text_turn(ConversationRole::Assistant, output));
+    Ok(conversation)
+}
+
+/// Parses a JSON array or JSONL dataset. Without `format` it is detected
+/// from the first record.
+pub fn parse_dataset(input: &str, format: Option<DatasetFormat>) -> Result<Vec<Conversation>> {
+    let records = records(input)?;
+    let Some(format) = format.or_else(|| records.first().and_then(DatasetFormat::detect)) else {
+        if records.is_empty() {
+            return Ok(Vec::new());
+        }
+        return Err(invalid(0, "unrecognised dataset format"));
+    };
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, record)| match format {
+            DatasetFormat::ShareGpt => share_gpt(record, index),
+            DatasetFormat::OpenAiChat => openai_chat(record, index),
+            DatasetFormat::Alpaca => alpaca(record, index),
+        })
+        .collect()
+}
+
+pub fn load_dataset(
+    path: impl AsRef<Path>,
+    format: Option<DatasetFormat>,
+) -> Result<Vec<Conversation>> {
+    let path = path.as_ref();
+    let input = std::fs::read_to_string(path).map_err(|err| {
+        AdapterError::ConfigError(format!("failed to read {}: {}", path.display(), err))
+    })?;
+    parse_dataset(&input, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finetune::training_jsonl;
+
+    #[test]
+    fn test_parse_dataset_formats() {
+        let share_gpt = r#"[{"conversations": [
+            {"from": "human", "value": "Hi"},
+            {"from": "gpt", "value": "Hello!"}
+        ]}]"#;
+        let conversations = parse_dataset(share_gpt, None).unwrap();
+        assert_eq!(conversations[0].len(), 2);
+        assert_eq!(
+            conversations[0].turns[1].role(),
+            &ConversationRole::Assistant
+        );
+
+        let jsonl = training_jsonl(&conversations).unwrap();
+        assert_eq!(parse_dataset(&jsonl, None).unwrap(), conversations);
+
+        let alpaca = r#"{"instruction": "Translate", "input": "chat", "output": "cat"}"#;
+        let conversations = parse_dataset(alpaca, Some(DatasetFormat::Alpaca)).unwrap();
+        assert_eq!(conversations[0].turns[0].text(), "Translate\n\nchat");
+
+        let err = parse_dataset("{\"messages\": []}\nnot json", None).unwrap_err();
+        assert!(err.to_string().contains("record 2"));
+    }
+}
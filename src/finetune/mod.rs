@@ -0,0 +1,5 @@
+pub mod client;
+pub mod dataset;
+
+pub use client::*;
+pub use dataset::*;
@@ -1,23 +1,135 @@
-use crate::http::HttpClient;
-use dashmap::DashMap;
+use crate::config::EnvConfig;
+use crate::error::Result;
+use crate::http::{HttpClient, HttpClientConfig};
 use once_cell::sync::Lazy;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 type CacheKey = (String, String);
 
-static CLIENT_CACHE: Lazy<DashMap<CacheKey, HttpClient>> = Lazy::new(DashMap::new);
+struct CachedClient {
+    client: HttpClient,
+    created: Instant,
+    last_used: u64,
+}
+
+/// Clients keyed by base URL and API key hash, bounded by `max_entries`
+/// (least recently used entries go first) and rebuilt once older than `ttl`.
+struct ClientPool {
+    entries: HashMap<CacheKey, CachedClient>,
+    configs: HashMap<String, HttpClientConfig>,
+    max_entries: usize,
+    ttl: Option<Duration>,
+    clock: u64,
+}
+
+impl ClientPool {
+    fn new(max_entries: usize, ttl: Option<Duration>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            configs: HashMap::new(),
+            max_entries,
+            ttl,
+            clock: 0,
+        }
+    }
+
+    fn get_or_create(&mut self, key: CacheKey) -> Result<HttpClient> {
+        self.clock += 1;
+        let now = Instant::now();
+        let ttl = self.ttl;
+        if let Some(entry) = self.entries.get_mut(&key) {
+            if ttl.is_none_or(|ttl| now.duration_since(entry.created) < ttl) {
+                entry.last_used = self.clock;
+                return Ok(entry.client.clone());
+            }
+        }
+
+        let client = match self.configs.get(&key.0) {
+            Some(config) => HttpClient::from_config(config)?,
+            None => HttpClient::new()?,
+        };
+        self.entries.remove(&key);
+        self.evict(self.max_entries.saturating_sub(1));
+        self.entries.insert(
+            key,
+            CachedClient {
+                client: client.clone(),
+                created: now,
+                last_used: self.clock,
+            },
+        );
+        Ok(client)
+    }
+
+    fn evict(&mut self, keep: usize) {
+        while self.entries.len() > keep {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+    }
+
+    fn configure(&mut self, base_url: &str, config: HttpClientConfig) {
+        self.entries.retain(|(url, _), _| url != base_url);
+        self.configs.insert(base_url.to_string(), config);
+    }
+}
+
+static CLIENT_CACHE: Lazy<Mutex<ClientPool>> = Lazy::new(|| {
+    Mutex::new(ClientPool::new(
+        EnvConfig::get_client_cache_max_entries(),
+        EnvConfig::get_client_cache_ttl(),
+    ))
+});
+
+fn pool() -> std::sync::MutexGuard<'static, ClientPool> {
+    CLIENT_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
 pub struct ClientCache;
 
 impl ClientCache {
-    pub fn get_or_create(base_url: &str, api_key: &str) -> HttpClient {
+    pub fn get_or_create(base_url: &str, api_key: &str) -> Result<HttpClient> {
         let key = Self::make_key(base_url, api_key);
+        pool().get_or_create(key)
+    }
 
-        CLIENT_CACHE
-            .entry(key)
-            .or_insert_with(|| HttpClient::new().expect("Failed to create HTTP client"))
-            .clone()
+    /// Uses `config` for every client created for `base_url` from now on.
+    /// Clients already cached for it are dropped.
+    pub fn configure(base_url: &str, config: HttpClientConfig) {
+        pool().configure(base_url, config);
+    }
+
+    /// Defaults to `ADAPTERS_CLIENT_CACHE_MAX_ENTRIES`.
+    pub fn set_max_entries(max_entries: usize) {
+        let mut pool = pool();
+        pool.max_entries = max_entries;
+        pool.evict(max_entries);
+    }
+
+    /// Defaults to `ADAPTERS_CLIENT_CACHE_TTL`.
+    pub fn set_ttl(ttl: Option<Duration>) {
+        pool().ttl = ttl;
+    }
+
+    pub fn len() -> usize {
+        pool().entries.len()
+    }
+
+    pub fn is_empty() -> bool {
+        Self::len() == 0
     }
 
     fn make_key(base_url: &str, api_key: &str) -> CacheKey {
@@ -29,6 +141,41 @@ impl ClientCache {
     }
 
     pub fn clear() {
-        CLIENT_CACHE.clear();
+        pool().entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(url: &str) -> CacheKey {
+        ClientCache::make_key(url, "key")
+    }
+
+    #[test]
+    fn test_pool_evicts_least_recently_used_and_expired_entries() {
+        let mut pool = ClientPool::new(2, None);
+        pool.get_or_create(key("a")).unwrap();
+        pool.get_or_create(key("b")).unwrap();
+        pool.get_or_create(key("a")).unwrap();
+        pool.get_or_create(key("c")).unwrap();
+
+        assert_eq!(pool.entries.len(), 2);
+        assert!(pool.entries.contains_key(&key("a")));
+        assert!(!pool.entries.contains_key(&key("b")));
+
+        let created = pool.entries[&key("a")].created;
+        pool.ttl = Some(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(2));
+        pool.get_or_create(key("a")).unwrap();
+        assert!(pool.entries[&key("a")].created > created);
+
+        pool.configure(
+            "c",
+            HttpClientConfig::default().with_timeout(Duration::from_secs(1)),
+        );
+        assert!(!pool.entries.contains_key(&key("c")));
+        assert_eq!(pool.entries.len(), 1);
     }
 }
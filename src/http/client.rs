@@ -1,40 +1,315 @@
 use crate::config::EnvConfig;
-use crate::error::Result;
-use reqwest::{Client, ClientBuilder};
+use crate::error::{AdapterError, Result};
+#[cfg(feature = "custom-transport")]
+use crate::http::Transport;
+use crate::http::{RequestSigner, StubEndpoint, UnixEndpoint};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response};
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Connection settings for an `HttpClient`. `Default` reads the process-wide
+/// `ADAPTERS_HTTP_*` environment variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(EnvConfig::get_http_timeout()),
+            connect_timeout: Duration::from_secs(EnvConfig::get_http_connect_timeout()),
+            pool_max_idle_per_host: EnvConfig::get_max_keepalive_connections(),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn with_pool_max_idle_per_host(mut self, pool_max_idle_per_host: usize) -> Self {
+        self.pool_max_idle_per_host = pool_max_idle_per_host;
+        self
+    }
+}
+
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
+    signer: Option<Arc<dyn RequestSigner>>,
+    stub: Option<Arc<StubEndpoint>>,
+    #[cfg(feature = "custom-transport")]
+    transport: Option<Transport>,
 }
 
 impl HttpClient {
     pub fn new() -> Result<Self> {
-        let timeout = EnvConfig::get_http_timeout();
-        let connect_timeout = EnvConfig::get_http_connect_timeout();
-
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(timeout))
-            .connect_timeout(Duration::from_secs(connect_timeout))
-            .pool_max_idle_per_host(EnvConfig::get_max_keepalive_connections())
-            .build()?;
-
-        Ok(Self { client })
+        Self::from_config(&HttpClientConfig::default())
     }
 
     pub fn with_timeout(timeout_secs: u64) -> Result<Self> {
-        let connect_timeout = EnvConfig::get_http_connect_timeout();
+        Self::from_config(
+            &HttpClientConfig::default().with_timeout(Duration::from_secs(timeout_secs)),
+        )
+    }
 
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(timeout_secs))
-            .connect_timeout(Duration::from_secs(connect_timeout))
-            .pool_max_idle_per_host(EnvConfig::get_max_keepalive_connections())
-            .build()?;
+    pub fn from_config(config: &HttpClientConfig) -> Result<Self> {
+        Self::builder().with_config(config.clone()).build()
+    }
 
-        Ok(Self { client })
+    pub fn builder() -> HttpClientBuilder {
+        HttpClientBuilder::default()
+    }
+
+    /// A client for `base_url` together with the URL requests should be
+    /// built against. `unix://` endpoints (see `UnixEndpoint`) get a client
+    /// bound to the socket and an `http://localhost` base URL; `stub://`
+    /// endpoints (see `StubEndpoint`) are answered in-process. Any other URL
+    /// is returned unchanged with a default client.
+    pub fn for_base_url(base_url: &str) -> Result<(Self, String)> {
+        if let Some(endpoint) = StubEndpoint::parse(base_url) {
+            let client = Self::builder().with_stub(endpoint?).build()?;
+            return Ok((client, "http://stub".to_string()));
+        }
+        match UnixEndpoint::parse(base_url) {
+            #[cfg(unix)]
+            Some(endpoint) => {
+                let client = Self::builder().with_unix_socket(&endpoint.socket).build()?;
+                Ok((client, endpoint.http_base_url()))
+            }
+            #[cfg(not(unix))]
+            Some(_) => Err(AdapterError::ConfigError(format!(
+                "unix sockets are not supported on this platform: {}",
+                base_url
+            ))),
+            None => Ok((Self::new()?, base_url.to_string())),
+        }
     }
 
     pub fn inner(&self) -> &Client {
         &self.client
     }
+
+    /// Signs and sends `request`, through the custom transport when one is
+    /// set.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut request = request.build()?;
+        if let Some(signer) = &self.signer {
+            signer.sign(&mut request).await?;
+        }
+        if let Some(stub) = &self.stub {
+            return stub.respond(request).await;
+        }
+        #[cfg(feature = "custom-transport")]
+        if let Some(transport) = &self.transport {
+            return crate::http::send_via(transport, request).await;
+        }
+        Ok(self.client.execute(request).await?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    Rustls,
+    /// The platform TLS library; requires the `native-tls` feature.
+    #[cfg(feature = "native-tls")]
+    Native,
+}
+
+#[derive(Default)]
+pub struct HttpClientBuilder {
+    config: HttpClientConfig,
+    user_agent: Option<String>,
+    headers: Vec<(String, String)>,
+    tcp_keepalive: Option<Duration>,
+    tls_backend: Option<TlsBackend>,
+    unix_socket: Option<PathBuf>,
+    signer: Option<Arc<dyn RequestSigner>>,
+    stub: Option<Arc<StubEndpoint>>,
+    client: Option<Client>,
+    #[cfg(feature = "custom-transport")]
+    transport: Option<Transport>,
+}
+
+impl HttpClientBuilder {
+    pub fn with_config(mut self, config: HttpClientConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = timeout;
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.config.connect_timeout = connect_timeout;
+        self
+    }
+
+    pub fn with_pool_max_idle_per_host(mut self, pool_max_idle_per_host: usize) -> Self {
+        self.config.pool_max_idle_per_host = pool_max_idle_per_host;
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sent with every request. Invalid names or values are reported by
+    /// `build`.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    pub fn with_tls_backend(mut self, backend: TlsBackend) -> Self {
+        self.tls_backend = Some(backend);
+        self
+    }
+
+    /// Connects every request to the socket at `path` instead of over TCP.
+    #[cfg(unix)]
+    pub fn with_unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Runs `signer` on every request sent through `HttpClient::send`.
+    pub fn with_signer(mut self, signer: impl RequestSigner + 'static) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Answers every request from `endpoint` instead of the network.
+    pub fn with_stub(mut self, endpoint: StubEndpoint) -> Self {
+        self.stub = Some(Arc::new(endpoint));
+        self
+    }
+
+    /// Uses `client` as is; the other connection settings on the builder
+    /// are ignored.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Hands every request to `service` instead of the reqwest connection
+    /// pool. Timeouts and other settings on the builder do not apply.
+    #[cfg(feature = "custom-transport")]
+    pub fn with_transport<S>(mut self, service: S) -> Self
+    where
+        S: tower::Service<
+                http::Request<reqwest::Body>,
+                Response = http::Response<reqwest::Body>,
+                Error = Box<dyn std::error::Error + Send + Sync>,
+            > + Clone
+            + Send
+            + Sync
+            + 'static,
+        S::Future: Send + 'static,
+    {
+        self.transport = Some(Transport::new(service));
+        self
+    }
+
+    pub fn build(self) -> Result<HttpClient> {
+        if let Some(client) = self.client {
+            return Ok(HttpClient {
+                client,
+                signer: self.signer,
+                stub: self.stub,
+                #[cfg(feature = "custom-transport")]
+                transport: self.transport,
+            });
+        }
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| {
+                AdapterError::ConfigError(format!("invalid header name {}: {}", name, err))
+            })?;
+            let header_value = HeaderValue::from_str(value).map_err(|err| {
+                AdapterError::ConfigError(format!("invalid value for header {}: {}", name, err))
+            })?;
+            headers.insert(header_name, header_value);
+        }
+
+        let mut builder = ClientBuilder::new()
+            .timeout(self.config.timeout)
+            .connect_timeout(self.config.connect_timeout)
+            .pool_max_idle_per_host(self.config.pool_max_idle_per_host)
+            .default_headers(headers)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        #[cfg(unix)]
+        if let Some(path) = self.unix_socket {
+            builder = builder.unix_socket(path);
+        }
+        builder = match self.tls_backend {
+            Some(TlsBackend::Rustls) => builder.use_rustls_tls(),
+            #[cfg(feature = "native-tls")]
+            Some(TlsBackend::Native) => builder.use_native_tls(),
+            None => builder,
+        };
+
+        Ok(HttpClient {
+            client: builder.build()?,
+            signer: self.signer,
+            stub: self.stub,
+            #[cfg(feature = "custom-transport")]
+            transport: self.transport,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builder_sends_user_agent_and_default_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/")
+            .match_header("user-agent", "adapters-test/1.0")
+            .match_header("x-team", "search")
+            .create_async()
+            .await;
+
+        let http = HttpClient::builder()
+            .with_user_agent("adapters-test/1.0")
+            .with_header("x-team", "search")
+            .with_tcp_keepalive(Duration::from_secs(30))
+            .with_tls_backend(TlsBackend::Rustls)
+            .build()
+            .unwrap();
+        http.inner().get(server.url()).send().await.unwrap();
+        mock.assert_async().await;
+
+        assert!(matches!(
+            HttpClient::builder().with_header("bad header", "x").build(),
+            Err(AdapterError::ConfigError(_))
+        ));
+    }
 }
@@ -1,5 +1,13 @@
 pub mod cache;
 pub mod client;
+pub mod oauth;
+pub mod signing;
+pub mod stub;
+pub mod transport;
 
 pub use cache::*;
 pub use client::*;
+pub use oauth::*;
+pub use signing::*;
+pub use stub::*;
+pub use transport::*;
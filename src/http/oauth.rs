@@ -0,0 +1,287 @@
+use crate::error::{AdapterError, Result};
+use crate::http::{HttpClient, RequestSigner};
+use async_trait::async_trait;
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+pub const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+type AssertionFn = Arc<dyn Fn() -> Result<String> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum OAuthGrant {
+    ClientCredentials {
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        scope: Option<String>,
+    },
+    /// RFC 7523 JWT bearer exchange, as used by Google service accounts.
+    /// `assertion` returns a freshly signed JWT for every token request.
+    JwtBearer {
+        token_url: String,
+        assertion: AssertionFn,
+    },
+}
+
+impl OAuthGrant {
+    pub fn client_credentials(
+        token_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self::ClientCredentials {
+            token_url: token_url.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scope: None,
+        }
+    }
+
+    /// Client credentials against the Microsoft identity platform, e.g. with
+    /// scope `https://cognitiveservices.azure.com/.default` for Azure OpenAI.
+    pub fn azure_ad(
+        tenant_id: &str,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        scope: impl Into<String>,
+    ) -> Self {
+        Self::client_credentials(
+            format!(
+                "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+                tenant_id
+            ),
+            client_id,
+            client_secret,
+        )
+        .with_scope(scope)
+    }
+
+    pub fn jwt_bearer<F>(token_url: impl Into<String>, assertion: F) -> Self
+    where
+        F: Fn() -> Result<String> + Send + Sync + 'static,
+    {
+        Self::JwtBearer {
+            token_url: token_url.into(),
+            assertion: Arc::new(assertion),
+        }
+    }
+
+    /// Only affects client-credentials grants.
+    pub fn with_scope(mut self, value: impl Into<String>) -> Self {
+        if let Self::ClientCredentials { scope, .. } = &mut self {
+            *scope = Some(value.into());
+        }
+        self
+    }
+
+    fn token_url(&self) -> &str {
+        match self {
+            Self::ClientCredentials { token_url, .. } | Self::JwtBearer { token_url, .. } => {
+                token_url
+            }
+        }
+    }
+
+    fn form(&self) -> Result<Vec<(&'static str, String)>> {
+        Ok(match self {
+            Self::ClientCredentials {
+                client_id,
+                client_secret,
+                scope,
+                ..
+            } => {
+                let mut form = vec![
+                    ("grant_type", "client_credentials".to_string()),
+                    ("client_id", client_id.clone()),
+                    ("client_secret", client_secret.clone()),
+                ];
+                if let Some(scope) = scope {
+                    form.push(("scope", scope.clone()));
+                }
+                form
+            }
+            Self::JwtBearer { assertion, .. } => vec![
+                (
+                    "grant_type",
+                    "urn:ietf:params:oauth:grant-type:jwt-bearer".to_string(),
+                ),
+                ("assertion", assertion()?),
+            ],
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Option<Instant>,
+}
+
+/// Fetches OAuth2 access tokens and caches them until `refresh_margin`
+/// before they expire. Used as a `RequestSigner`, it sets the bearer
+/// `Authorization` header on every request.
+pub struct TokenManager {
+    grant: OAuthGrant,
+    http: HttpClient,
+    refresh_margin: Duration,
+    cached: Mutex<Option<CachedToken>>,
+}
+
+impl TokenManager {
+    pub fn new(grant: OAuthGrant) -> Result<Self> {
+        Ok(Self {
+            grant,
+            http: HttpClient::new()?,
+            refresh_margin: Duration::from_secs(60),
+            cached: Mutex::new(None),
+        })
+    }
+
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    pub fn with_http_client(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// A valid access token, fetching a new one when none is cached or the
+    /// cached one is about to expire.
+    pub async fn token(&self) -> Result<String> {
+        let mut cached = self.cached.lock().await;
+        if let Some(token) = cached.as_ref() {
+            // A margin too large to add to the clock outlasts any expiry.
+            let fresh = token.expires_at.is_none_or(|expires_at| {
+                Instant::now()
+                    .checked_add(self.refresh_margin)
+                    .is_some_and(|deadline| deadline < expires_at)
+            });
+            if fresh {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token = self.fetch().await?;
+        let access_token = token.access_token.clone();
+        *cached = Some(token);
+        Ok(access_token)
+    }
+
+    /// Drops the cached token, e.g. after the provider rejected it.
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+
+    async fn fetch(&self) -> Result<CachedToken> {
+        let requested_at = Instant::now();
+        let request = self
+            .http
+            .inner()
+            .post(self.grant.token_url())
+            .form(&self.grant.form()?);
+        let response = self.http.send(request).await?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            return Err(AdapterError::ProviderError {
+                provider: "oauth".to_string(),
+                error_type: body
+                    .get("error")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                message: body
+                    .get("error_description")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("token request failed with status {}", status)),
+            });
+        }
+
+        let token: TokenResponse = serde_json::from_value(body)?;
+        Ok(CachedToken {
+            access_token: token.access_token,
+            // An `expires_in` past what `Instant` can represent never expires.
+            expires_at: token
+                .expires_in
+                .and_then(|secs| requested_at.checked_add(Duration::from_secs(secs))),
+        })
+    }
+}
+
+#[async_trait]
+impl RequestSigner for TokenManager {
+    async fn sign(&self, request: &mut Request) -> Result<()> {
+        let token = self.token().await?;
+        let value = HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|err| AdapterError::ConfigError(format!("invalid access token: {}", err)))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_token_manager_caches_and_refreshes_before_expiry() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("grant_type".into(), "client_credentials".into()),
+                Matcher::UrlEncoded("client_id".into(), "app".into()),
+                Matcher::UrlEncoded("scope".into(), "llm".into()),
+            ]))
+            .with_body(json!({"access_token": "tok", "expires_in": 3600}).to_string())
+            .expect(2)
+            .create_async()
+            .await;
+
+        let grant =
+            OAuthGrant::client_credentials(format!("{}/token", server.url()), "app", "s3cret")
+                .with_scope("llm");
+        let manager = TokenManager::new(grant).unwrap();
+        assert_eq!(manager.token().await.unwrap(), "tok");
+        assert_eq!(manager.token().await.unwrap(), "tok");
+
+        let manager = manager.with_refresh_margin(Duration::from_secs(7200));
+        manager.token().await.unwrap();
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_token_manager_survives_huge_lifetimes() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/token")
+            .with_body(json!({"access_token": "tok", "expires_in": u64::MAX}).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let grant = OAuthGrant::client_credentials(format!("{}/token", server.url()), "app", "s");
+        let manager = TokenManager::new(grant)
+            .unwrap()
+            .with_refresh_margin(Duration::MAX);
+        assert_eq!(manager.token().await.unwrap(), "tok");
+        assert_eq!(manager.token().await.unwrap(), "tok");
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,318 @@
+use crate::error::{AdapterError, Result};
+use crate::utils::{estimate_tokens, stable_hash};
+use futures::stream;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::time::Duration;
+use url::Url;
+
+const LOREM: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+    "incididunt",
+    "ut",
+    "labore",
+    "et",
+    "dolore",
+    "magna",
+    "aliqua",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubReply {
+    /// `tokens` words of lorem ipsum.
+    Lorem,
+    /// The text of the last message, repeated back.
+    Echo,
+}
+
+/// Longest latency a stub URL may ask for, in milliseconds: one hour.
+const MAX_LATENCY_MS: f64 = 3_600_000.0;
+
+/// Delay before the response headers, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    Fixed(f64),
+    Uniform { min: f64, max: f64 },
+    Normal { mean: f64, std_dev: f64 },
+}
+
+impl LatencyDistribution {
+    /// `50` (fixed), `20-80` (uniform) or `200~50` (normal, mean~std dev).
+    /// Every number must be finite and at most [`MAX_LATENCY_MS`].
+    fn parse(value: &str) -> Option<Self> {
+        let number = |text: &str| {
+            text.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite() && (0.0..=MAX_LATENCY_MS).contains(n))
+        };
+        if let Some((mean, std_dev)) = value.split_once('~') {
+            return Some(Self::Normal {
+                mean: number(mean)?,
+                std_dev: number(std_dev)?,
+            });
+        }
+        if let Some((min, max)) = value.split_once('-') {
+            let (min, max) = (number(min)?, number(max)?);
+            return (min <= max).then_some(Self::Uniform { min, max });
+        }
+        number(value).map(Self::Fixed)
+    }
+
+    fn sample(&self, rng: &mut SplitMix) -> Duration {
+        let millis = match *self {
+            Self::Fixed(millis) => millis,
+            Self::Uniform { min, max } => min + (max - min) * rng.next_f64(),
+            Self::Normal { mean, std_dev } => {
+                // Box-Muller.
+                let u = rng.next_f64().max(f64::MIN_POSITIVE);
+                let v = rng.next_f64();
+                mean + std_dev * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+            }
+        };
+        // A normal tail can still land outside the range parse allows.
+        Duration::try_from_secs_f64(millis.clamp(0.0, MAX_LATENCY_MS) / 1000.0)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+struct SplitMix(u64);
+
+impl SplitMix {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// A base URL of the form `stub://<lorem|echo>[?options]` that answers
+/// OpenAI-compatible chat requests in-process, for load testing without a
+/// provider. Options:
+///
+/// - `latency`: see `LatencyDistribution`, default `0`
+/// - `token_ms`: delay between streamed tokens, default `0`
+/// - `tokens`: completion length of lorem replies, default `64`
+/// - `seed`: mixed with the request body, so equal requests get equal
+///   replies and latencies
+///
+/// e.g. `stub://lorem?latency=200~50&tokens=256&token_ms=5`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StubEndpoint {
+    pub reply: StubReply,
+    pub latency: LatencyDistribution,
+    pub token_delay: Duration,
+    pub tokens: usize,
+    pub seed: u64,
+}
+
+impl StubEndpoint {
+    /// `None` for other schemes; malformed stub URLs are an error.
+    pub fn parse(url: &str) -> Option<Result<Self>> {
+        url.starts_with("stub://").then(|| Self::parse_stub(url))
+    }
+
+    fn parse_stub(url: &str) -> Result<Self> {
+        let invalid = |reason: String| {
+            AdapterError::ConfigError(format!("invalid stub URL {}: {}", url, reason))
+        };
+        let parsed = Url::parse(url).map_err(|err| invalid(err.to_string()))?;
+        let reply = match parsed.host_str() {
+            Some("lorem") | None => StubReply::Lorem,
+            Some("echo") => StubReply::Echo,
+            Some(other) => return Err(invalid(format!("unknown reply {}", other))),
+        };
+        let mut endpoint = Self {
+            reply,
+            latency: LatencyDistribution::Fixed(0.0),
+            token_delay: Duration::ZERO,
+            tokens: 64,
+            seed: 0,
+        };
+        for (key, value) in parsed.query_pairs() {
+            let bad = || invalid(format!("bad {} {}", key, value));
+            match key.as_ref() {
+                "latency" => {
+                    endpoint.latency = LatencyDistribution::parse(&value).ok_or_else(bad)?
+                }
+                "token_ms" => {
+                    let millis = value.parse::<u64>().map_err(|_| bad())?;
+                    endpoint.token_delay = Duration::from_millis(millis);
+                }
+                "tokens" => endpoint.tokens = value.parse().map_err(|_| bad())?,
+                "seed" => endpoint.seed = value.parse().map_err(|_| bad())?,
+                _ => return Err(invalid(format!("unknown option {}", key))),
+            }
+        }
+        Ok(endpoint)
+    }
+
+    fn reply_words(&self, body: &Value, rng: &mut SplitMix) -> Vec<String> {
+        match self.reply {
+            StubReply::Lorem => (0..self.tokens)
+                .map(|_| LOREM[rng.next_u64() as usize % LOREM.len()].to_string())
+                .collect(),
+            StubReply::Echo => {
+                let last = body["messages"]
+                    .as_array()
+                    .and_then(|messages| messages.last())
+                    .map(message_text)
+                    .unwrap_or_default();
+                last.split_whitespace().map(str::to_string).collect()
+            }
+        }
+    }
+
+    /// Answers `request` as an OpenAI chat completions endpoint would,
+    /// after the sampled latency.
+    pub(crate) async fn respond(&self, request: reqwest::Request) -> Result<reqwest::Response> {
+        let bytes = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .unwrap_or_default();
+        let body: Value = serde_json::from_slice(bytes).unwrap_or(Value::Null);
+        let hash = stable_hash(&body) ^ self.seed;
+        let mut rng = SplitMix(hash);
+        tokio::time::sleep(self.latency.sample(&mut rng)).await;
+
+        let words = self.reply_words(&body, &mut rng);
+        let prompt_tokens: u32 = body["messages"]
+            .as_array()
+            .map(|messages| {
+                messages
+                    .iter()
+                    .map(|message| estimate_tokens(&message_text(message)))
+                    .sum()
+            })
+            .unwrap_or(0);
+        let id = format!("chatcmpl-stub-{:016x}", hash);
+        let model = body["model"].as_str().unwrap_or("stub").to_string();
+        let usage = json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": words.len(),
+            "total_tokens": prompt_tokens as usize + words.len(),
+        });
+
+        let response = http::Response::builder().status(200);
+        let response = if body["stream"].as_bool() == Some(true) {
+            let delay = self.token_delay;
+            let count = words.len();
+            let mut events: Vec<(Duration, String)> = words
+                .into_iter()
+                .enumerate()
+                .map(|(index, word)| {
+                    let content = if index + 1 < count { word + " " } else { word };
+                    let chunk = json!({
+                        "id": id,
+                        "object": "chat.completion.chunk",
+                        "model": model,
+                        "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}],
+                    });
+                    (if index == 0 { Duration::ZERO } else { delay }, chunk.to_string())
+                })
+                .collect();
+            let last = json!({
+                "id": id,
+                "object": "chat.completion.chunk",
+                "model": model,
+                "choices": [{"index": 0, "delta": {}, "finish_reason": "stop"}],
+                "usage": usage,
+            });
+            events.push((Duration::ZERO, last.to_string()));
+            events.push((Duration::ZERO, "[DONE]".to_string()));
+            let body = stream::unfold(events.into_iter(), |mut events| async move {
+                let (delay, data) = events.next()?;
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                Some((Ok::<_, Infallible>(format!("data: {}\n\n", data)), events))
+            });
+            response
+                .header("content-type", "text/event-stream")
+                .body(reqwest::Body::wrap_stream(body))
+        } else {
+            let completion = json!({
+                "id": id,
+                "object": "chat.completion",
+                "model": model,
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": words.join(" ")},
+                    "finish_reason": "stop",
+                }],
+                "usage": usage,
+            });
+            response
+                .header("content-type", "application/json")
+                .body(reqwest::Body::from(completion.to_string()))
+        };
+        let response = response.map_err(|err| AdapterError::TransportError(err.to_string()))?;
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+fn message_text(message: &Value) -> String {
+    match &message["content"] {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stub_endpoint() {
+        let endpoint = StubEndpoint::parse("stub://echo?latency=200~50&token_ms=5&seed=3")
+            .unwrap()
+            .unwrap();
+        assert_eq!(endpoint.reply, StubReply::Echo);
+        assert_eq!(
+            endpoint.latency,
+            LatencyDistribution::Normal {
+                mean: 200.0,
+                std_dev: 50.0
+            }
+        );
+        assert_eq!(endpoint.token_delay, Duration::from_millis(5));
+
+        let endpoint = StubEndpoint::parse("stub://lorem?latency=20-80")
+            .unwrap()
+            .unwrap();
+        let mut rng = SplitMix(1);
+        let delay = endpoint.latency.sample(&mut rng);
+        assert!(delay >= Duration::from_millis(20) && delay <= Duration::from_millis(80));
+
+        for latency in ["inf", "NaN", "1e300", "0~inf", "10-1e20"] {
+            let url = format!("stub://lorem?latency={}", latency);
+            assert!(StubEndpoint::parse(&url).unwrap().is_err(), "{}", latency);
+        }
+        assert!(StubEndpoint::parse("stub://lorem?latency=80-20")
+            .unwrap()
+            .is_err());
+        assert!(StubEndpoint::parse("https://api.openai.com").is_none());
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+/// A base URL of the form `unix://<socket path>[:<http path>]`, for local
+/// inference servers listening on a Unix domain socket, e.g.
+/// `unix:///run/llama.sock:/v1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnixEndpoint {
+    pub socket: PathBuf,
+    pub path: String,
+}
+
+impl UnixEndpoint {
+    pub fn parse(url: &str) -> Option<Self> {
+        let rest = url.strip_prefix("unix://")?;
+        let (socket, path) = match rest.find(":/") {
+            Some(index) => (&rest[..index], &rest[index + 1..]),
+            None => (rest, ""),
+        };
+        (!socket.is_empty()).then(|| Self {
+            socket: PathBuf::from(socket),
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The URL requests are addressed to once the socket is connected.
+    pub fn http_base_url(&self) -> String {
+        format!("http://localhost{}", self.path)
+    }
+}
+
+#[cfg(feature = "custom-transport")]
+pub use custom::*;
+
+#[cfg(feature = "custom-transport")]
+mod custom {
+    use crate::error::{AdapterError, Result};
+    use tower::util::BoxCloneSyncService;
+    use tower::ServiceExt;
+
+    type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+    /// A type-erased `tower::Service` that takes the place of the network for
+    /// an `HttpClient`, e.g. an in-process server or a sidecar connector.
+    #[derive(Clone)]
+    pub struct Transport(
+        BoxCloneSyncService<http::Request<reqwest::Body>, http::Response<reqwest::Body>, BoxError>,
+    );
+
+    impl Transport {
+        pub fn new<S>(service: S) -> Self
+        where
+            S: tower::Service<
+                    http::Request<reqwest::Body>,
+                    Response = http::Response<reqwest::Body>,
+                    Error = BoxError,
+                > + Clone
+                + Send
+                + Sync
+                + 'static,
+            S::Future: Send + 'static,
+        {
+            Self(BoxCloneSyncService::new(service))
+        }
+    }
+
+    pub(crate) async fn send_via(
+        transport: &Transport,
+        request: reqwest::Request,
+    ) -> Result<reqwest::Response> {
+        let request = http::Request::try_from(request)?;
+        let response = transport
+            .0
+            .clone()
+            .oneshot(request)
+            .await
+            .map_err(|err| AdapterError::TransportError(err.to_string()))?;
+        Ok(reqwest::Response::from(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_unix_endpoint() {
+        let endpoint = UnixEndpoint::parse("unix:///run/llama.sock:/v1/").unwrap();
+        assert_eq!(endpoint.socket, PathBuf::from("/run/llama.sock"));
+        assert_eq!(endpoint.http_base_url(), "http://localhost/v1");
+
+        let endpoint = UnixEndpoint::parse("unix:///tmp/vllm.sock").unwrap();
+        assert_eq!(endpoint.http_base_url(), "http://localhost");
+        assert!(UnixEndpoint::parse("http://localhost:8080").is_none());
+    }
+}
@@ -23,6 +23,8 @@
 //!     conversation.add_turn(martian_adapters::TurnType::Basic(Turn {
 //!         role: ConversationRole::User,
 //!         content: "Hello, how are you?".to_string(),
+//!         name: None,
+//!         metadata: None,
 //!     }));
 //!
 //!     // Get all models supporting vision
@@ -37,24 +39,103 @@
 //! ```
 
 pub mod adapters;
+#[cfg(feature = "assistants")]
+pub mod assistants;
 pub mod config;
 pub mod error;
+pub mod eval;
+pub mod files;
+pub mod finetune;
 pub mod http;
+#[cfg(feature = "mcp")]
+pub mod mcp;
+pub mod memory;
 pub mod models;
+pub mod store;
 pub mod utils;
 
 pub use adapters::{
-    AdapterFactory, AdapterStream, BaseAdapter, ExecuteOptions, ModelFilter, ResponseFormat,
+    best_of, check_policy, json_answer, json_event_stream, majority_vote, openai_chunk_stream,
+    post_process, prepare_request, prepare_request_with, race_with_fallback, resumable_stream,
+    select_model, service_tier_pointer, simulate_stream, stream_events, AdapterFactory,
+    AdapterStream, AdapterStreamExt, AdaptiveConcurrency, AuthScheme, BaseAdapter, BatchOutcome,
+    BestOf, BudgetEvent, BudgetRouter, BudgetState, Buffered, BuiltinTool, CatalogDiff,
+    CatalogSnapshot, ChatSession, ChoiceSummary, CodeInterpreterOptions, ComputerUseOptions,
+    Consensus, ContextOverflowPolicy, Deduplicated, ExecuteOptions, ExecuteOptionsBuilder,
+    ExecutionGroup, FailureSummary, FinishSummary, GroupOutcome, GroupResults, HeuristicScorer,
+    InjectionAction, InjectionGuard, InjectionHeuristic, InjectionMatch, InjectionReport,
+    InjectionScanner, InputPiece, InputSink, JsonEventStream, JsonPathEvent, JsonSchemaFormat,
+    JsonSchemaValidator, KeyPool, KeyPooled, KeyStats, KeyUsage, Modality, ModelFilter,
+    ModelScorer, ModerationVerdict, Moderator, Normalizer, OutputValidation, OutputValidator,
+    PartialJsonParser, PathSegment, PolicyGate, PooledOutput, PostProcessor, PrefixCache,
+    PreparedRequest, Priority, ProviderCredentials, ProviderSpec, PushSession, QueueConfig,
+    RankedCandidate, RegexValidator, RequestProfile, RequestQueue, RequestSpec, ResponseFormat,
+    ResponseSpec, RoutingContext, RoutingDecision, RoutingRule, RoutingRules, RuleCondition,
+    SelectionConstraints, ServiceTier, SimulatedStreamOptions, SpecAdapter, SseDecoder, SseEvent,
+    StreamEvent, StreamEventStream, StreamFraming, StreamLatency, StreamMetrics, StreamOptions,
+    StreamSpec, StreamSummary, ToolHandler, ToolRun, ToolRunner, UserLocation, WebSearchOptions,
+    Weekday, PARTIAL_TURN_KEY,
+};
+#[cfg(feature = "strict-validation")]
+pub use adapters::{request_schema_names, request_violations};
+#[cfg(feature = "assistants")]
+pub use assistants::{Assistant, Run, RunStatus, Threads};
+pub use config::{
+    reload_config, validate, watch_config, ConfigDiagnostic, EnvConfig, ModelLanguages,
+    ProviderDefaults, ProviderQuirks, QuirkAction, QuirkRule, VendorMappings,
+};
+pub use error::{AdapterError, ErrorClass, Result};
+pub use eval::{
+    bench, run_conformance, BenchConfig, BenchReport, BenchSample, CaseResult, CheckResult,
+    ConformanceCheck, ConformanceReport, EvalCase, EvalReport, EvalSuite, Grader, ModelReport,
+    Percentiles,
+};
+pub use files::{
+    FileClient, FilePurpose, GeminiFile, GeminiFiles, GEMINI_INLINE_LIMIT,
+    OPENAI_ORGANIZATION_HEADER, OPENAI_PROJECT_HEADER,
+};
+pub use finetune::{
+    load_dataset, parse_dataset, training_jsonl, DatasetFormat, FineTuneCheckpoint, FineTuneClient,
+    FineTuneJob, FineTuneRequest, FineTuneStatus,
+};
+#[cfg(feature = "custom-transport")]
+pub use http::Transport;
+pub use http::{
+    AwsSigV4Signer, ClientCache, HmacSigner, HttpClient, HttpClientBuilder, HttpClientConfig,
+    LatencyDistribution, OAuthGrant, RequestSigner, StubEndpoint, StubReply, TlsBackend,
+    TokenManager, UnixEndpoint, GOOGLE_TOKEN_URL,
+};
+#[cfg(feature = "mcp")]
+pub use mcp::{McpClient, McpTool, MCP_PROTOCOL_VERSION};
+pub use memory::{
+    extract_citations, truncate_oldest, ContextInjector, InjectedContext, MemoryStrategy,
+    RagResponse, RetrievedDocument, SUMMARY_PREFIX,
 };
-pub use config::{EnvConfig, ProviderDefaults, VendorMappings};
-pub use error::{AdapterError, Result};
-pub use http::{ClientCache, HttpClient};
 pub use models::{
-    AdapterChatCompletion, AdapterChatCompletionChunk, Choice, ChunkChoice, ContentEntry,
-    ContentEntryData, ContentTurn, Conversation, ConversationRole, Cost, Delta, FunctionCall,
-    ImageUrl, Message, Model, ModelCapabilities, ModelInfo, ModelProperties, ModelsDevResponse,
-    Provider, TokenUsage, ToolCall, Turn, TurnType,
+    AdapterChatCompletion, AdapterChatCompletionChunk, AdapterWarning, AlternativeReason,
+    CapabilityMatrix, CapabilityRow, Choice, ChunkChoice, CitationFormat, CitationSource,
+    Citations, CitedSegment, CodeExecution, CodeOutput, ComputerAction, ComputerCall, ContentEntry,
+    ContentEntryData, ContentTurn, Conversation, ConversationRole, ConversationStats, Cost,
+    CostBreakdown, CostRecord, CostRollup, CostTracker, Delta, FileReference, FunctionCall,
+    FunctionCallDelta, ImageUrl, Message, Model, ModelCapabilities, ModelEdge, ModelGraph,
+    ModelInfo, ModelProperties, ModelRelation, ModelsDevResponse, MouseButton, OpenAIModel,
+    OpenAIModelsList, Point, PromptReference, PromptRegistry, PromptTemplate, Provider,
+    RateLimitInfo, RateLimitType, RedactionPolicy, ReproducibilityRecord, RollupDimension,
+    TokenUsage, ToolCall, ToolCallDelta, ToolResultContent, Turn, TurnType, UsageDrift, VideoInput,
+};
+pub use store::{ConversationStore, InMemoryConversationStore};
+#[cfg(feature = "encryption")]
+pub use store::{EncryptedConversationStore, KeyProvider, StaticKeyProvider};
+#[cfg(feature = "parquet")]
+pub use utils::ParquetTable;
+pub use utils::{
+    anthropic_tool_calls, conversation_language, delete_none_values, delete_none_values_except,
+    detect_content_block, detect_language, encode_image_to_base64, estimate_schema_tokens,
+    estimate_tokens, extract_refusal, gemini_tool_calls, image_size_limit,
+    process_image_url_anthropic, sniff_image_mime, stable_hash, strip_metadata,
+    without_none_values, EMPTY_CONTENT, MAX_IMAGE_BYTES,
 };
+#[cfg(feature = "image-processing")]
 pub use utils::{
-    delete_none_values, encode_image_to_base64, process_image_url_anthropic, EMPTY_CONTENT,
+    encode_jpeg, provider_image_limits, resize_to_provider_limits, ImageLimits, ProcessedImage,
 };
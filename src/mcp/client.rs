@@ -0,0 +1,424 @@
+use crate::adapters::{ToolHandler, ToolRunner};
+use crate::error::{AdapterError, Result};
+use crate::http::HttpClient;
+use crate::models::{ContentEntry, ImageUrl, ToolResultContent};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+pub const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+const SESSION_HEADER: &str = "mcp-session-id";
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// JSON-RPC "method not found", for server requests the client does not
+/// implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// A tool exposed by an MCP server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub input_schema: Value,
+}
+
+impl McpTool {
+    /// The tool as an OpenAI-style function definition for
+    /// `ExecuteOptions::tools`.
+    pub fn to_openai_tool(&self) -> Value {
+        let mut function = json!({"name": self.name, "parameters": self.input_schema});
+        if let Some(description) = &self.description {
+            function["description"] = json!(description);
+        }
+        json!({"type": "function", "function": function})
+    }
+}
+
+struct StdioConnection {
+    // Kept so the server is killed when the client is dropped.
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+enum Connection {
+    Stdio(Box<Mutex<StdioConnection>>),
+    Http {
+        http: HttpClient,
+        url: String,
+        session: StdMutex<Option<String>>,
+    },
+}
+
+/// A JSON-RPC client for one MCP server, over stdio or streamable HTTP.
+pub struct McpClient {
+    name: String,
+    connection: Connection,
+    next_id: AtomicU64,
+    timeout: Duration,
+}
+
+impl McpClient {
+    /// Starts `command` and talks to it over its stdin and stdout.
+    pub async fn spawn(command: &str, args: &[&str]) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| {
+                AdapterError::TransportError(format!("failed to start {}: {}", command, err))
+            })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(AdapterError::TransportError(format!(
+                "{} has no stdio pipes",
+                command
+            )));
+        };
+        let connection = Connection::Stdio(Box::new(Mutex::new(StdioConnection {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })));
+        Self::initialize(command.to_string(), connection).await
+    }
+
+    /// Connects to a streamable HTTP endpoint such as `https://host/mcp`.
+    pub async fn connect(url: impl Into<String>) -> Result<Self> {
+        Self::connect_with(url, HttpClient::new()?).await
+    }
+
+    pub async fn connect_with(url: impl Into<String>, http: HttpClient) -> Result<Self> {
+        let url = url.into();
+        let connection = Connection::Http {
+            http,
+            url: url.clone(),
+            session: StdMutex::new(None),
+        };
+        Self::initialize(url, connection).await
+    }
+
+    async fn initialize(name: String, connection: Connection) -> Result<Self> {
+        let client = Self {
+            name,
+            connection,
+            next_id: AtomicU64::new(1),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": MCP_PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+                }),
+            )
+            .await?;
+        client.notify("notifications/initialized").await?;
+        Ok(client)
+    }
+
+    /// How long a stdio request waits for its response, one minute by
+    /// default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({"cursor": cursor}),
+                None => json!({}),
+            };
+            let mut result = self.request("tools/list", params).await?;
+            let page: Vec<McpTool> = serde_json::from_value(result["tools"].take())?;
+            tools.extend(page);
+            cursor = result["nextCursor"].as_str().map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    /// Calls `name` on the server. A result flagged `isError` is returned as
+    /// an error so `ToolRunner` reports it to the model.
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<ToolResultContent> {
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": arguments}))
+            .await?;
+        let content = tool_result_content(&result);
+        if result["isError"].as_bool() == Some(true) {
+            return Err(self.error(None, content.to_text()));
+        }
+        Ok(content)
+    }
+
+    /// Registers a handler for every server tool on `runner` and returns
+    /// their definitions for `ExecuteOptions::tools`.
+    pub async fn register_tools(self: &Arc<Self>, runner: &mut ToolRunner) -> Result<Vec<Value>> {
+        let tools = self.list_tools().await?;
+        for tool in &tools {
+            runner.register(
+                tool.name.clone(),
+                Arc::new(McpToolHandler {
+                    client: self.clone(),
+                    name: tool.name.clone(),
+                }),
+            );
+        }
+        Ok(tools.iter().map(McpTool::to_openai_tool).collect())
+    }
+
+    fn error(&self, code: Option<i64>, message: String) -> AdapterError {
+        AdapterError::ProviderError {
+            provider: format!("mcp:{}", self.name),
+            error_type: code.map(|code| code.to_string()),
+            message,
+        }
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let message = json!({"jsonrpc": "2.0", "method": method});
+        match &self.connection {
+            Connection::Stdio(connection) => {
+                write_line(&mut connection.lock().await.stdin, &message).await
+            }
+            Connection::Http { .. } => self.post(&message).await.map(drop),
+        }
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let response = match &self.connection {
+            Connection::Stdio(connection) => {
+                let mut connection = connection.lock().await;
+                write_line(&mut connection.stdin, &message).await?;
+                let read = async {
+                    loop {
+                        let mut line = String::new();
+                        let read = connection
+                            .stdout
+                            .read_line(&mut line)
+                            .await
+                            .map_err(|err| AdapterError::TransportError(err.to_string()))?;
+                        if read == 0 {
+                            return Err(AdapterError::TransportError(format!(
+                                "{} closed its stdout",
+                                self.name
+                            )));
+                        }
+                        // Server notifications are skipped.
+                        let Ok(response) = serde_json::from_str::<Value>(&line) else {
+                            continue;
+                        };
+                        if let Some(reply) = reply_to_server(&response) {
+                            write_line(&mut connection.stdin, &reply).await?;
+                        } else if response["id"] == json!(id) && response.get("method").is_none() {
+                            return Ok(response);
+                        }
+                    }
+                };
+                tokio::time::timeout(self.timeout, read)
+                    .await
+                    .map_err(|_| {
+                        AdapterError::TransportError(format!(
+                            "{} did not answer {} within {:?}",
+                            self.name, method, self.timeout
+                        ))
+                    })??
+            }
+            Connection::Http { .. } => {
+                let body = self.post(&message).await?;
+                for reply in server_messages(&body).iter().filter_map(reply_to_server) {
+                    self.post(&reply).await?;
+                }
+                response_for(&body, id).ok_or_else(|| {
+                    AdapterError::TransportError(format!(
+                        "{} sent no response to {}",
+                        self.name, method
+                    ))
+                })?
+            }
+        };
+        if let Some(error) = response.get("error") {
+            return Err(self.error(
+                error["code"].as_i64(),
+                error["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+                    .to_string(),
+            ));
+        }
+        Ok(response["result"].clone())
+    }
+
+    async fn post(&self, message: &Value) -> Result<String> {
+        let Connection::Http { http, url, session } = &self.connection else {
+            unreachable!("post is only used for HTTP connections");
+        };
+        let mut request = http
+            .inner()
+            .post(url)
+            .header("accept", "application/json, text/event-stream")
+            .json(message);
+        let current = session
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone();
+        if let Some(id) = current {
+            request = request.header(SESSION_HEADER, id);
+        }
+        let response = http.send(request).await?;
+        let status = response.status();
+        if let Some(id) = response.headers().get(SESSION_HEADER) {
+            if let Ok(id) = id.to_str() {
+                *session
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(id.to_string());
+            }
+        }
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(self.error(Some(status.as_u16().into()), body));
+        }
+        Ok(body)
+    }
+}
+
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stdin
+        .write_all(&line)
+        .await
+        .map_err(|err| AdapterError::TransportError(err.to_string()))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|err| AdapterError::TransportError(err.to_string()))
+}
+
+/// The answer to a request the server sent the client: an empty result
+/// for `ping`, "method not found" for anything else. `None` for responses
+/// and notifications.
+fn reply_to_server(message: &Value) -> Option<Value> {
+    let method = message.get("method")?.as_str()?;
+    let id = message.get("id")?;
+    Some(match method {
+        "ping" => json!({"jsonrpc": "2.0", "id": id, "result": {}}),
+        _ => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": METHOD_NOT_FOUND, "message": format!("method not found: {}", method)},
+        }),
+    })
+}
+
+/// The JSON-RPC messages in a plain JSON or `text/event-stream` body.
+fn server_messages(body: &str) -> Vec<Value> {
+    if let Ok(message) = serde_json::from_str::<Value>(body) {
+        return vec![message];
+    }
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        .collect()
+}
+
+/// The response with `id` in a plain JSON or `text/event-stream` body.
+fn response_for(body: &str, id: u64) -> Option<Value> {
+    if let Ok(response) = serde_json::from_str::<Value>(body) {
+        return Some(response);
+    }
+    server_messages(body)
+        .into_iter()
+        .find(|message| message["id"] == json!(id) && message.get("method").is_none())
+}
+
+fn tool_result_content(result: &Value) -> ToolResultContent {
+    let blocks = result["content"].as_array().cloned().unwrap_or_default();
+    if let [block] = blocks.as_slice() {
+        if let Some(text) = block["text"].as_str() {
+            return ToolResultContent::Text(text.to_string());
+        }
+    }
+    if blocks.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            return ToolResultContent::Json(structured.clone());
+        }
+    }
+    let entries = blocks
+        .iter()
+        .filter_map(|block| match block["type"].as_str()? {
+            "text" => Some(ContentEntry::text(block["text"].as_str()?)),
+            "image" => Some(ContentEntry::image(ImageUrl {
+                url: format!(
+                    "data:{};base64,{}",
+                    block["mimeType"].as_str()?,
+                    block["data"].as_str()?
+                ),
+                detail: None,
+            })),
+            _ => None,
+        })
+        .collect();
+    ToolResultContent::Blocks(entries)
+}
+
+struct McpToolHandler {
+    client: Arc<McpClient>,
+    name: String,
+}
+
+#[async_trait]
+impl ToolHandler for McpToolHandler {
+    async fn call(&self, arguments: Value) -> Result<ToolResultContent> {
+        self.client.call_tool(&self.name, arguments).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_result_content() {
+        let text = json!({"content": [{"type": "text", "text": "42"}]});
+        assert_eq!(
+            tool_result_content(&text),
+            ToolResultContent::Text("42".to_string())
+        );
+
+        let mixed = json!({"content": [
+            {"type": "text", "text": "chart:"},
+            {"type": "image", "data": "iVBORw0KGgo=", "mimeType": "image/png"}
+        ]});
+        let ToolResultContent::Blocks(entries) = tool_result_content(&mixed) else {
+            panic!("expected blocks");
+        };
+        assert_eq!(entries.len(), 2);
+
+        let sse = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":3,\"result\":{}}\n\n";
+        assert_eq!(response_for(sse, 3).unwrap()["result"], json!({}));
+    }
+}
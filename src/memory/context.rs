@@ -0,0 +1,193 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::Result;
+use crate::memory::strategy::leading_system_turns;
+use crate::models::{AdapterChatCompletion, Conversation, ConversationRole, Turn, TurnType};
+use crate::utils::estimate_tokens;
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_INSTRUCTION: &str = "Answer using the documents below when they are relevant. \
+Cite every document you use by its id in square brackets, for example [doc-1].";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrievedDocument {
+    pub id: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+impl RetrievedDocument {
+    pub fn new(id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            content: content.into(),
+            source: None,
+            score: None,
+        }
+    }
+
+    fn render(&self) -> String {
+        match &self.source {
+            Some(source) => format!(
+                "<document id=\"{}\" source=\"{}\">\n{}\n</document>",
+                self.id, source, self.content
+            ),
+            None => format!(
+                "<document id=\"{}\">\n{}\n</document>",
+                self.id, self.content
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InjectedContext {
+    pub conversation: Conversation,
+    pub included: Vec<String>,
+    pub omitted: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RagResponse {
+    pub response: AdapterChatCompletion,
+    pub included: Vec<String>,
+    /// Ids of included documents the reply cites, in order of first mention.
+    pub cited: Vec<String>,
+}
+
+/// Stuffs retrieved documents into a conversation as a delimited context
+/// turn and maps `[id]` citations in the reply back to those documents.
+#[derive(Debug, Clone)]
+pub struct ContextInjector {
+    max_tokens: u32,
+    instruction: String,
+}
+
+impl ContextInjector {
+    pub fn new(max_tokens: u32) -> Self {
+        Self {
+            max_tokens,
+            instruction: DEFAULT_INSTRUCTION.to_string(),
+        }
+    }
+
+    pub fn with_instruction(mut self, instruction: impl Into<String>) -> Self {
+        self.instruction = instruction.into();
+        self
+    }
+
+    /// Documents are taken in the given order (callers sort by relevance);
+    /// one that does not fit is skipped so smaller ones after it can still be
+    /// included.
+    pub fn select<'a>(
+        &self,
+        documents: &'a [RetrievedDocument],
+    ) -> (Vec<&'a RetrievedDocument>, Vec<&'a RetrievedDocument>) {
+        let mut used = estimate_tokens(&self.instruction);
+        let mut included = Vec::new();
+        let mut omitted = Vec::new();
+        for document in documents {
+            let tokens = estimate_tokens(&document.render());
+            if used + tokens <= self.max_tokens {
+                used += tokens;
+                included.push(document);
+            } else {
+                omitted.push(document);
+            }
+        }
+        (included, omitted)
+    }
+
+    /// Inserts the context turn after the leading system turns. The
+    /// conversation is returned unchanged when no document fits.
+    pub fn inject(
+        &self,
+        conversation: &Conversation,
+        documents: &[RetrievedDocument],
+    ) -> InjectedContext {
+        let (included, omitted) = self.select(documents);
+        let mut conversation = conversation.clone();
+
+        if !included.is_empty() {
+            let body = included
+                .iter()
+                .map(|document| document.render())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let position = leading_system_turns(&conversation);
+            conversation.turns.insert(
+                position,
+                TurnType::Basic(Turn {
+                    role: ConversationRole::System,
+                    content: format!("{}\n\n{}", self.instruction, body),
+                    name: None,
+                    metadata: None,
+                }),
+            );
+        }
+
+        InjectedContext {
+            conversation,
+            included: included
+                .iter()
+                .map(|document| document.id.clone())
+                .collect(),
+            omitted: omitted.iter().map(|document| document.id.clone()).collect(),
+        }
+    }
+
+    pub async fn execute<A: BaseAdapter + ?Sized>(
+        &self,
+        adapter: &A,
+        conversation: &Conversation,
+        documents: &[RetrievedDocument],
+        options: &ExecuteOptions,
+    ) -> Result<RagResponse> {
+        let injected = self.inject(conversation, documents);
+        let response = adapter.execute(&injected.conversation, options).await?;
+        let cited = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.as_deref())
+            .map(|text| extract_citations(text, &injected.included))
+            .unwrap_or_default();
+
+        Ok(RagResponse {
+            response,
+            included: injected.included,
+            cited,
+        })
+    }
+}
+
+/// Finds `[id]` markers (also `[id1, id2]`) that name one of `known`.
+pub fn extract_citations(text: &str, known: &[String]) -> Vec<String> {
+    let mut cited: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let Some(end) = rest[start..].find(']') else {
+            break;
+        };
+        for id in rest[start + 1..start + end].split(',').map(str::trim) {
+            if known.iter().any(|known| known == id) && !cited.iter().any(|seen| seen == id) {
+                cited.push(id.to_string());
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    cited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_citations() {
+        let known = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let text = "Paris [b]. It is large [a, b] and old [x]. See [c";
+        assert_eq!(extract_citations(text, &known), vec!["b", "a"]);
+    }
+}
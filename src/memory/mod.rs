@@ -0,0 +1,5 @@
+pub mod context;
+pub mod strategy;
+
+pub use context::*;
+pub use strategy::*;
@@ -0,0 +1,208 @@
+use crate::adapters::{BaseAdapter, ExecuteOptions};
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, ConversationRole, Turn, TurnType};
+use std::fmt;
+use std::sync::Arc;
+
+const SUMMARY_INSTRUCTION: &str = "Summarize the conversation below for use as context in a \
+continuing chat. Keep names, decisions, open questions and facts the user shared. Reply with \
+the summary only.";
+
+pub const SUMMARY_PREFIX: &str = "Summary of the earlier conversation:";
+
+/// How a conversation is shrunk once it no longer fits the context budget.
+/// Leading system/developer turns are always kept.
+#[derive(Clone, Default)]
+pub enum MemoryStrategy {
+    #[default]
+    Unbounded,
+    /// Drops the oldest turns until the estimate fits `max_tokens`.
+    TruncateOldest { max_tokens: u32 },
+    /// Replaces the oldest turns with a summary written by
+    /// `summarizer_model`, keeping as many recent turns verbatim as fit in
+    /// three quarters of `target_tokens`.
+    Summarize {
+        summarizer_model: Arc<dyn BaseAdapter>,
+        target_tokens: u32,
+    },
+}
+
+impl fmt::Debug for MemoryStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryStrategy::Unbounded => write!(f, "Unbounded"),
+            MemoryStrategy::TruncateOldest { max_tokens } => f
+                .debug_struct("TruncateOldest")
+                .field("max_tokens", max_tokens)
+                .finish(),
+            MemoryStrategy::Summarize {
+                summarizer_model,
+                target_tokens,
+            } => f
+                .debug_struct("Summarize")
+                .field("summarizer_model", &summarizer_model.get_model().get_path())
+                .field("target_tokens", target_tokens)
+                .finish(),
+        }
+    }
+}
+
+impl MemoryStrategy {
+    pub async fn apply(&self, conversation: &Conversation) -> Result<Conversation> {
+        match self {
+            MemoryStrategy::Unbounded => Ok(conversation.clone()),
+            MemoryStrategy::TruncateOldest { max_tokens } => {
+                Ok(truncate_oldest(conversation, *max_tokens))
+            }
+            MemoryStrategy::Summarize {
+                summarizer_model,
+                target_tokens,
+            } => summarize(summarizer_model.as_ref(), conversation, *target_tokens).await,
+        }
+    }
+}
+
+pub(crate) fn leading_system_turns(conversation: &Conversation) -> usize {
+    conversation
+        .turns
+        .iter()
+        .take_while(|turn| {
+            matches!(
+                turn.role(),
+                ConversationRole::System | ConversationRole::Developer
+            )
+        })
+        .count()
+}
+
+/// Index of the first turn to keep so that the kept tail (plus `reserved`
+/// tokens) fits into `budget`. The tail never starts with a tool output whose
+/// call would be cut off, and always keeps the last turn.
+fn split_point(conversation: &Conversation, start: usize, budget: u32, reserved: u32) -> usize {
+    let turns = &conversation.turns;
+    let mut used = reserved;
+    let mut split = turns.len();
+    for index in (start..turns.len()).rev() {
+        let tokens = turns[index].estimated_tokens();
+        if used + tokens > budget && split < turns.len() {
+            break;
+        }
+        used += tokens;
+        split = index;
+    }
+
+    while split < turns.len() - 1 && matches!(turns[split], TurnType::ToolOutput { .. }) {
+        split += 1;
+    }
+    split
+}
+
+pub fn truncate_oldest(conversation: &Conversation, max_tokens: u32) -> Conversation {
+    if conversation.estimated_tokens() <= max_tokens || conversation.is_empty() {
+        return conversation.clone();
+    }
+
+    let system = leading_system_turns(conversation);
+    let reserved = conversation.turns[..system]
+        .iter()
+        .map(TurnType::estimated_tokens)
+        .sum();
+    let split = split_point(conversation, system, max_tokens, reserved).max(system);
+
+    let mut turns = conversation.turns[..system].to_vec();
+    turns.extend_from_slice(&conversation.turns[split..]);
+    Conversation::with_turns(turns)
+}
+
+async fn summarize(
+    summarizer: &dyn BaseAdapter,
+    conversation: &Conversation,
+    target_tokens: u32,
+) -> Result<Conversation> {
+    if conversation.estimated_tokens() <= target_tokens {
+        return Ok(conversation.clone());
+    }
+
+    let system = leading_system_turns(conversation);
+    let reserved: u32 = conversation.turns[..system]
+        .iter()
+        .map(TurnType::estimated_tokens)
+        .sum();
+    let split = split_point(conversation, system, target_tokens * 3 / 4, reserved).max(system);
+    if split == system {
+        return Ok(conversation.clone());
+    }
+
+    let transcript = conversation.turns[system..split]
+        .iter()
+        .map(|turn| format!("{}: {}", turn.role(), turn.text()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let request = Conversation::with_turns(vec![
+        TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: SUMMARY_INSTRUCTION.to_string(),
+            name: None,
+            metadata: None,
+        }),
+        TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: transcript,
+            name: None,
+            metadata: None,
+        }),
+    ]);
+    let options = ExecuteOptions {
+        max_tokens: Some((target_tokens / 4).max(64)),
+        ..ExecuteOptions::default()
+    };
+
+    let response = summarizer.execute(&request, &options).await?;
+    let summary = response
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.clone())
+        .filter(|summary| !summary.trim().is_empty())
+        .ok_or_else(|| AdapterError::Unknown("summarizer returned no text".to_string()))?;
+
+    let mut turns = conversation.turns[..system].to_vec();
+    turns.push(TurnType::Basic(Turn {
+        role: ConversationRole::System,
+        content: format!("{}\n{}", SUMMARY_PREFIX, summary.trim()),
+        name: None,
+        metadata: None,
+    }));
+    turns.extend_from_slice(&conversation.turns[split..]);
+    Ok(Conversation::with_turns(turns))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(role: ConversationRole, content: &str) -> TurnType {
+        TurnType::Basic(Turn {
+            role,
+            content: content.to_string(),
+            name: None,
+            metadata: None,
+        })
+    }
+
+    #[test]
+    fn test_truncate_keeps_system_and_recent_turns() {
+        let long = "x".repeat(400);
+        let conversation = Conversation::with_turns(vec![
+            turn(ConversationRole::System, "Be brief."),
+            turn(ConversationRole::User, &long),
+            turn(ConversationRole::Assistant, &long),
+            turn(ConversationRole::User, "Last question?"),
+        ]);
+
+        let truncated = truncate_oldest(&conversation, 150);
+        assert_eq!(truncated.len(), 3);
+        assert_eq!(truncated.turns[0], conversation.turns[0]);
+        assert_eq!(truncated.turns[2], conversation.turns[3]);
+        assert_eq!(truncate_oldest(&conversation, 10_000), conversation);
+    }
+}
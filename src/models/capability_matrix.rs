@@ -0,0 +1,171 @@
+use crate::models::Model;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One model's row of a `CapabilityMatrix`. Costs are per million tokens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityRow {
+    pub model: String,
+    pub context_length: u32,
+    pub prompt_cost: f64,
+    pub completion_cost: f64,
+    /// Capability name without the `supports_` prefix, e.g. `vision`.
+    pub capabilities: BTreeMap<String, bool>,
+}
+
+/// Models × capabilities and costs, for dashboards and model comparisons.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilityMatrix {
+    /// The capability columns, in display order.
+    pub capabilities: Vec<String>,
+    pub rows: Vec<CapabilityRow>,
+}
+
+fn capabilities(model: &Model) -> BTreeMap<String, bool> {
+    let Ok(Value::Object(fields)) = serde_json::to_value(&model.capabilities) else {
+        return BTreeMap::new();
+    };
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let name = name.strip_prefix("supports_").unwrap_or(&name).to_string();
+            Some((name, value.as_bool()?))
+        })
+        .collect()
+}
+
+impl CapabilityMatrix {
+    /// Every capability of `models`, with rows sorted by model path.
+    pub fn new(models: &[Model]) -> Self {
+        let mut rows: Vec<CapabilityRow> = models
+            .iter()
+            .map(|model| CapabilityRow {
+                model: model.get_path(),
+                context_length: model.context_length,
+                prompt_cost: model.cost.prompt * 1_000_000.0,
+                completion_cost: model.cost.completion * 1_000_000.0,
+                capabilities: capabilities(model),
+            })
+            .collect();
+        rows.sort_by(|a, b| a.model.cmp(&b.model));
+        let capabilities = rows
+            .first()
+            .map(|row| row.capabilities.keys().cloned().collect())
+            .unwrap_or_default();
+        Self { capabilities, rows }
+    }
+
+    /// Keeps only the `names` columns, in that order, e.g.
+    /// `["vision", "tools", "streaming"]`.
+    pub fn with_capabilities(mut self, names: &[&str]) -> Self {
+        self.capabilities = names.iter().map(|name| name.to_string()).collect();
+        for row in &mut self.rows {
+            row.capabilities
+                .retain(|name, _| names.contains(&name.as_str()));
+        }
+        self
+    }
+
+    /// An ASCII table with one row per model.
+    pub fn render(&self) -> String {
+        let mut header = vec![
+            "model".to_string(),
+            "context".to_string(),
+            "$/M in".to_string(),
+            "$/M out".to_string(),
+        ];
+        header.extend(self.capabilities.iter().cloned());
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut cells = vec![
+                    row.model.clone(),
+                    row.context_length.to_string(),
+                    format!("{:.2}", row.prompt_cost),
+                    format!("{:.2}", row.completion_cost),
+                ];
+                cells.extend(self.capabilities.iter().map(|name| {
+                    match row.capabilities.get(name) {
+                        Some(true) => "yes",
+                        Some(false) => "no",
+                        None => "-",
+                    }
+                    .to_string()
+                }));
+                cells
+            })
+            .collect();
+
+        let widths: Vec<usize> = (0..header.len())
+            .map(|column| {
+                rows.iter()
+                    .map(|cells| cells[column].chars().count())
+                    .chain([header[column].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let separator = widths
+            .iter()
+            .map(|width| "-".repeat(width + 2))
+            .collect::<Vec<_>>()
+            .join("+");
+        let line = |cells: &[String]| {
+            cells
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(column, (cell, width))| {
+                    // Text left-aligned, numbers right-aligned.
+                    if (1..4).contains(&column) {
+                        format!(" {:>width$} ", cell, width = width)
+                    } else {
+                        format!(" {:<width$} ", cell, width = width)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+
+        let mut table = format!("+{}+\n|{}|\n+{}+\n", separator, line(&header), separator);
+        for cells in &rows {
+            table.push_str(&format!("|{}|\n", line(cells)));
+        }
+        table.push_str(&format!("+{}+\n", separator));
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModelCapabilities;
+
+    fn model(name: &str, vision: bool) -> Model {
+        Model::test(name)
+            .with_cost(0.0000025, 0.00001)
+            .with_capabilities(ModelCapabilities {
+                supports_vision: vision,
+                ..ModelCapabilities::default()
+            })
+    }
+
+    #[test]
+    fn test_capability_matrix() {
+        let matrix = CapabilityMatrix::new(&[model("gpt-4o", true), model("gpt-3.5", false)]);
+        assert_eq!(matrix.rows[0].model, "openai/openai/gpt-3.5");
+        assert!(matrix.capabilities.contains(&"computer_use".to_string()));
+        assert!(matrix.rows[1].capabilities["vision"]);
+
+        let matrix = matrix.with_capabilities(&["vision", "tools"]);
+        assert_eq!(matrix.rows[0].capabilities.len(), 2);
+        let table = matrix.render();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].contains("$/M in") && lines[1].contains("vision"));
+        assert!(lines[4].starts_with("| openai/openai/gpt-4o  |  128000 |   2.50 |   10.00 | yes"));
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+    }
+}
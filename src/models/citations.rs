@@ -0,0 +1,312 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Sources a search- or retrieval-backed answer was grounded on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Citations {
+    pub sources: Vec<CitationSource>,
+    /// Spans of the answer text and the sources that support them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<CitedSegment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub search_queries: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CitationSource {
+    pub uri: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CitedSegment {
+    /// Byte offsets into the answer text, end exclusive.
+    pub start_index: usize,
+    pub end_index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Indices into `Citations::sources`.
+    pub source_indices: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationFormat {
+    /// `url_citation` annotations on a chat completions message.
+    OpenAi,
+    /// `web_search_result_location` citations on Messages API text blocks.
+    Anthropic,
+    Gemini,
+    Perplexity,
+}
+
+impl CitationFormat {
+    pub fn for_provider(provider: &str) -> Option<Self> {
+        match provider {
+            "openai" | "azure" => Some(Self::OpenAi),
+            "anthropic" => Some(Self::Anthropic),
+            "gemini" | "google" | "vertex" => Some(Self::Gemini),
+            "perplexity" => Some(Self::Perplexity),
+            _ => None,
+        }
+    }
+}
+
+fn index(value: &Value, key: &str) -> usize {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0) as usize
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+impl Citations {
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty() && self.segments.is_empty() && self.search_queries.is_empty()
+    }
+
+    /// Reads `body` in the given provider format. Returns `None` when the
+    /// response carries no citation metadata.
+    pub fn parse(format: CitationFormat, body: &Value) -> Option<Self> {
+        match format {
+            CitationFormat::OpenAi => {
+                Self::from_openai_message(body.pointer("/choices/0/message")?)
+            }
+            CitationFormat::Anthropic => Self::from_anthropic_content(body.get("content")?),
+            CitationFormat::Gemini => Self::from_gemini_candidate(body.pointer("/candidates/0")?),
+            CitationFormat::Perplexity => Self::from_perplexity(body),
+        }
+    }
+
+    fn source_index(&mut self, uri: String, title: Option<String>) -> usize {
+        if let Some(index) = self.sources.iter().position(|source| source.uri == uri) {
+            return index;
+        }
+        self.sources.push(CitationSource { uri, title });
+        self.sources.len() - 1
+    }
+
+    pub fn from_openai_message(message: &Value) -> Option<Self> {
+        let mut citations = Self::default();
+        for annotation in message
+            .get("annotations")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let Some(citation) = annotation.get("url_citation") else {
+                continue;
+            };
+            let Some(uri) = string(citation, "url") else {
+                continue;
+            };
+            let source = citations.source_index(uri, string(citation, "title"));
+            citations.segments.push(CitedSegment {
+                start_index: index(citation, "start_index"),
+                end_index: index(citation, "end_index"),
+                text: None,
+                source_indices: vec![source],
+            });
+        }
+        (!citations.is_empty()).then_some(citations)
+    }
+
+    /// Web search results and text citations among Messages API content
+    /// blocks. Segment offsets refer to the concatenated text blocks.
+    pub fn from_anthropic_content(content: &Value) -> Option<Self> {
+        let mut citations = Self::default();
+        let mut offset = 0;
+        for block in content.as_array().into_iter().flatten() {
+            match block.get("type").and_then(Value::as_str) {
+                Some("server_tool_use") => {
+                    if let Some(query) = block.pointer("/input/query").and_then(Value::as_str) {
+                        citations.search_queries.push(query.to_string());
+                    }
+                }
+                Some("web_search_tool_result") => {
+                    for result in block
+                        .get("content")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let Some(uri) = string(result, "url") {
+                            citations.source_index(uri, string(result, "title"));
+                        }
+                    }
+                }
+                Some("text") => {
+                    let text = block.get("text").and_then(Value::as_str).unwrap_or("");
+                    for citation in block
+                        .get("citations")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        let Some(uri) = string(citation, "url") else {
+                            continue;
+                        };
+                        let source = citations.source_index(uri, string(citation, "title"));
+                        citations.segments.push(CitedSegment {
+                            start_index: offset,
+                            end_index: offset + text.len(),
+                            text: string(citation, "cited_text"),
+                            source_indices: vec![source],
+                        });
+                    }
+                    offset += text.len();
+                }
+                _ => {}
+            }
+        }
+        (!citations.is_empty()).then_some(citations)
+    }
+
+    /// Gemini `groundingMetadata` (Google Search grounding) and, failing
+    /// that, `citationMetadata` of a single candidate.
+    pub fn from_gemini_candidate(candidate: &Value) -> Option<Self> {
+        let mut citations = Self::default();
+
+        if let Some(grounding) = candidate.get("groundingMetadata") {
+            for chunk in grounding
+                .get("groundingChunks")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let source = chunk.get("web").or_else(|| chunk.get("retrievedContext"));
+                if let Some(uri) = source.and_then(|source| string(source, "uri")) {
+                    citations.sources.push(CitationSource {
+                        uri,
+                        title: source.and_then(|source| string(source, "title")),
+                    });
+                }
+            }
+            for support in grounding
+                .get("groundingSupports")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let segment = support.get("segment").unwrap_or(&Value::Null);
+                citations.segments.push(CitedSegment {
+                    start_index: index(segment, "startIndex"),
+                    end_index: index(segment, "endIndex"),
+                    text: string(segment, "text"),
+                    source_indices: support
+                        .get("groundingChunkIndices")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_u64)
+                        .map(|index| index as usize)
+                        .collect(),
+                });
+            }
+            citations.search_queries = grounding
+                .get("webSearchQueries")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect();
+        } else if let Some(sources) = candidate
+            .pointer("/citationMetadata/citationSources")
+            .and_then(Value::as_array)
+        {
+            for source in sources {
+                let Some(uri) = string(source, "uri") else {
+                    continue;
+                };
+                citations.segments.push(CitedSegment {
+                    start_index: index(source, "startIndex"),
+                    end_index: index(source, "endIndex"),
+                    text: None,
+                    source_indices: vec![citations.sources.len()],
+                });
+                citations.sources.push(CitationSource {
+                    uri,
+                    title: string(source, "title"),
+                });
+            }
+        }
+
+        (!citations.is_empty()).then_some(citations)
+    }
+
+    /// Perplexity-style top-level `search_results` (with titles) or the
+    /// plain `citations` URL list.
+    pub fn from_perplexity(body: &Value) -> Option<Self> {
+        let sources: Vec<CitationSource> =
+            match body.get("search_results").and_then(Value::as_array) {
+                Some(results) => results
+                    .iter()
+                    .filter_map(|result| {
+                        Some(CitationSource {
+                            uri: string(result, "url")?,
+                            title: string(result, "title"),
+                        })
+                    })
+                    .collect(),
+                None => body
+                    .get("citations")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|uri| CitationSource {
+                        uri: uri.to_string(),
+                        title: None,
+                    })
+                    .collect(),
+            };
+
+        (!sources.is_empty()).then(|| Self {
+            sources,
+            ..Self::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_gemini_grounding_metadata() {
+        let candidate = json!({
+            "content": {"parts": [{"text": "Spain won Euro 2024."}]},
+            "groundingMetadata": {
+                "webSearchQueries": ["euro 2024 winner"],
+                "groundingChunks": [
+                    {"web": {"uri": "https://uefa.com/euro", "title": "uefa.com"}},
+                    {"web": {"uri": "https://bbc.co.uk/sport", "title": "bbc.co.uk"}}
+                ],
+                "groundingSupports": [{
+                    "segment": {"startIndex": 0, "endIndex": 20, "text": "Spain won Euro 2024."},
+                    "groundingChunkIndices": [0, 1]
+                }]
+            }
+        });
+
+        let citations = Citations::from_gemini_candidate(&candidate).unwrap();
+        assert_eq!(citations.sources.len(), 2);
+        assert_eq!(citations.sources[1].title.as_deref(), Some("bbc.co.uk"));
+        assert_eq!(citations.segments[0].end_index, 20);
+        assert_eq!(citations.segments[0].source_indices, vec![0, 1]);
+        assert_eq!(citations.search_queries, vec!["euro 2024 winner"]);
+
+        assert!(Citations::from_gemini_candidate(&json!({"content": {}})).is_none());
+        let perplexity = json!({"citations": ["https://a.example", "https://b.example"]});
+        assert_eq!(
+            Citations::from_perplexity(&perplexity)
+                .unwrap()
+                .sources
+                .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Code the provider ran on its side through a code interpreter tool,
+/// together with what it produced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodeExecution {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub code: String,
+    #[serde(default)]
+    pub outputs: Vec<CodeOutput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CodeOutput {
+    Logs { text: String },
+    Error { text: String },
+    Image { url: String },
+}
+
+fn text(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+impl CodeExecution {
+    /// Executions in a raw `provider` response body, in the order they ran.
+    pub fn parse(provider: &str, body: &Value) -> Option<Vec<Self>> {
+        let executions = match provider {
+            "openai" | "azure" => Self::from_openai_output(body.get("output")?),
+            "anthropic" => Self::from_anthropic_content(body.get("content")?),
+            "gemini" | "google" | "vertex" => {
+                Self::from_gemini_parts(body.pointer("/candidates/0/content/parts")?)
+            }
+            _ => return None,
+        };
+        (!executions.is_empty()).then_some(executions)
+    }
+
+    /// `code_interpreter_call` items of a Responses API `output` array.
+    pub fn from_openai_output(output: &Value) -> Vec<Self> {
+        let items = output.as_array().into_iter().flatten();
+        items
+            .filter(|item| {
+                item.get("type").and_then(Value::as_str) == Some("code_interpreter_call")
+            })
+            .map(|item| Self {
+                language: Some("python".to_string()),
+                code: text(item, "code").unwrap_or_default(),
+                outputs: item
+                    .get("outputs")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|output| match output.get("type").and_then(Value::as_str) {
+                        Some("logs") => Some(CodeOutput::Logs {
+                            text: text(output, "logs")?,
+                        }),
+                        Some("image") => Some(CodeOutput::Image {
+                            url: text(output, "url")?,
+                        }),
+                        _ => None,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// `code_execution` server tool calls and their results among Messages
+    /// API content blocks.
+    pub fn from_anthropic_content(content: &Value) -> Vec<Self> {
+        let mut executions: Vec<Self> = Vec::new();
+        for block in content.as_array().into_iter().flatten() {
+            match block.get("type").and_then(Value::as_str) {
+                Some("server_tool_use")
+                    if block.get("name").and_then(Value::as_str) == Some("code_execution") =>
+                {
+                    executions.push(Self {
+                        language: Some("python".to_string()),
+                        code: block
+                            .pointer("/input/code")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        outputs: Vec::new(),
+                    });
+                }
+                Some("code_execution_tool_result") => {
+                    let (Some(execution), Some(result)) =
+                        (executions.last_mut(), block.get("content"))
+                    else {
+                        continue;
+                    };
+                    if let Some(stdout) = text(result, "stdout") {
+                        execution.outputs.push(CodeOutput::Logs { text: stdout });
+                    }
+                    if let Some(stderr) = text(result, "stderr") {
+                        execution.outputs.push(CodeOutput::Error { text: stderr });
+                    }
+                }
+                _ => {}
+            }
+        }
+        executions
+    }
+
+    /// `executableCode` parts, each followed by its `codeExecutionResult`.
+    pub fn from_gemini_parts(parts: &Value) -> Vec<Self> {
+        let mut executions: Vec<Self> = Vec::new();
+        for part in parts.as_array().into_iter().flatten() {
+            if let Some(code) = part.get("executableCode") {
+                executions.push(Self {
+                    language: text(code, "language").map(|language| language.to_lowercase()),
+                    code: text(code, "code").unwrap_or_default(),
+                    outputs: Vec::new(),
+                });
+            } else if let Some(result) = part.get("codeExecutionResult") {
+                let (Some(execution), Some(output)) =
+                    (executions.last_mut(), text(result, "output"))
+                else {
+                    continue;
+                };
+                execution
+                    .outputs
+                    .push(match result.get("outcome").and_then(Value::as_str) {
+                        Some("OUTCOME_OK") | None => CodeOutput::Logs { text: output },
+                        Some(_) => CodeOutput::Error { text: output },
+                    });
+            }
+        }
+        executions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_gemini_code_execution() {
+        let body = json!({"candidates": [{"content": {"parts": [
+            {"text": "Let me compute that."},
+            {"executableCode": {"language": "PYTHON", "code": "print(2 ** 10)"}},
+            {"codeExecutionResult": {"outcome": "OUTCOME_OK", "output": "1024\n"}},
+            {"text": "It is 1024."}
+        ]}}]});
+
+        let executions = CodeExecution::parse("gemini", &body).unwrap();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].language.as_deref(), Some("python"));
+        assert_eq!(executions[0].code, "print(2 ** 10)");
+        assert_eq!(
+            executions[0].outputs,
+            vec![CodeOutput::Logs {
+                text: "1024\n".to_string()
+            }]
+        );
+        assert_eq!(CodeExecution::parse("example", &body), None);
+    }
+}
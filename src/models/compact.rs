@@ -0,0 +1,389 @@
+use crate::error::{AdapterError, Result};
+use crate::models::Conversation;
+use base64::{engine::general_purpose, Engine as _};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde_json::{Map, Number, Value};
+use std::io::{Read, Write};
+
+/// Format marker and version, ahead of the deflated body.
+const MAGIC: &[u8; 4] = b"MCV\x01";
+
+/// Extension types for inline media, stored as raw bytes instead of base64.
+const EXT_DATA_URL: i8 = 1;
+const EXT_BASE64: i8 = 2;
+
+/// Nesting a decoded value may have; conversations need a handful of levels.
+const MAX_DEPTH: usize = 128;
+/// Inflated size `from_compact_bytes` accepts, against deflate bombs.
+const MAX_INFLATED_BYTES: u64 = 256 * 1024 * 1024;
+
+fn invalid(reason: &str) -> AdapterError {
+    AdapterError::SerializationError(<serde_json::Error as serde::de::Error>::custom(format!(
+        "invalid compact conversation: {}",
+        reason
+    )))
+}
+
+/// Bytes of `text` if it is canonical standard base64, so decoding and
+/// re-encoding gives back the same string.
+fn base64_bytes(text: &str) -> Option<Vec<u8>> {
+    if text.len() < 16 {
+        return None;
+    }
+    let bytes = general_purpose::STANDARD.decode(text).ok()?;
+    (general_purpose::STANDARD.encode(&bytes) == text).then_some(bytes)
+}
+
+fn write_length(
+    out: &mut Vec<u8>,
+    len: usize,
+    fix: Option<(u8, usize)>,
+    markers: [u8; 3],
+) -> Result<()> {
+    match fix {
+        Some((marker, limit)) if len < limit => out.push(marker | len as u8),
+        _ if len <= u8::MAX as usize && markers[0] != 0 => out.extend([markers[0], len as u8]),
+        _ if len <= u16::MAX as usize => {
+            out.push(markers[1]);
+            out.extend((len as u16).to_be_bytes());
+        }
+        _ => {
+            let len = u32::try_from(len).map_err(|_| invalid("value is over 4 GiB"))?;
+            out.push(markers[2]);
+            out.extend(len.to_be_bytes());
+        }
+    }
+    Ok(())
+}
+
+fn write_ext(out: &mut Vec<u8>, kind: i8, payload: &[u8]) -> Result<()> {
+    write_length(out, payload.len(), None, [0xc7, 0xc8, 0xc9])?;
+    out.push(kind as u8);
+    out.extend(payload);
+    Ok(())
+}
+
+/// MessagePack, with base64 media written as extension types. Written by
+/// hand rather than through `rmp-serde`: which strings become raw-byte
+/// extensions depends on their value and field name, which a serde
+/// serializer cannot see without a second pass over the tree, and the
+/// subset needed here fits in this file. The output is plain MessagePack
+/// that any reader with the two extension types can decode.
+fn encode(value: &Value, key: Option<&str>, out: &mut Vec<u8>) -> Result<()> {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Bool(false) => out.push(0xc2),
+        Value::Bool(true) => out.push(0xc3),
+        Value::Number(number) => {
+            if let Some(n) = number.as_u64() {
+                match n {
+                    0..=0x7f => out.push(n as u8),
+                    0x80..=0xffff_ffff => {
+                        out.push(0xce);
+                        out.extend((n as u32).to_be_bytes());
+                    }
+                    _ => {
+                        out.push(0xcf);
+                        out.extend(n.to_be_bytes());
+                    }
+                }
+            } else if let Some(n) = number.as_i64() {
+                out.push(0xd3);
+                out.extend(n.to_be_bytes());
+            } else {
+                out.push(0xcb);
+                out.extend(number.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(text) => {
+            let data_url = text
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+                .filter(|(mime, _)| mime.len() <= u8::MAX as usize)
+                .and_then(|(mime, data)| Some((mime, base64_bytes(data)?)));
+            if let Some((mime, bytes)) = data_url {
+                let mut payload = vec![mime.len() as u8];
+                payload.extend(mime.as_bytes());
+                payload.extend(bytes);
+                write_ext(out, EXT_DATA_URL, &payload)?;
+            } else if let Some(bytes) = (key == Some("data")).then(|| base64_bytes(text)).flatten()
+            {
+                write_ext(out, EXT_BASE64, &bytes)?;
+            } else {
+                write_length(out, text.len(), Some((0xa0, 32)), [0xd9, 0xda, 0xdb])?;
+                out.extend(text.as_bytes());
+            }
+        }
+        Value::Array(items) => {
+            write_length(out, items.len(), Some((0x90, 16)), [0, 0xdc, 0xdd])?;
+            for item in items {
+                encode(item, None, out)?;
+            }
+        }
+        Value::Object(fields) => {
+            write_length(out, fields.len(), Some((0x80, 16)), [0, 0xde, 0xdf])?;
+            for (name, field) in fields {
+                encode(&Value::String(name.clone()), None, out)?;
+                encode(field, Some(name), out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("unexpected end of data"));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn uint(&mut self, width: usize) -> Result<u64> {
+        Ok(self
+            .take(width)?
+            .iter()
+            .fold(0, |n, byte| n << 8 | u64::from(*byte)))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    fn ext(&mut self, len: usize) -> Result<Value> {
+        let kind = self.take(1)?[0] as i8;
+        let payload = self.take(len)?;
+        match kind {
+            EXT_DATA_URL => {
+                let (&mime_len, rest) =
+                    payload.split_first().ok_or_else(|| invalid("empty ext"))?;
+                if rest.len() < mime_len as usize {
+                    return Err(invalid("truncated data URL"));
+                }
+                let (mime, data) = rest.split_at(mime_len as usize);
+                let mime = std::str::from_utf8(mime).map_err(|_| invalid("bad mime type"))?;
+                Ok(Value::String(format!(
+                    "data:{};base64,{}",
+                    mime,
+                    general_purpose::STANDARD.encode(data)
+                )))
+            }
+            EXT_BASE64 => Ok(Value::String(general_purpose::STANDARD.encode(payload))),
+            _ => Err(invalid("unknown extension type")),
+        }
+    }
+
+    fn nested<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth == MAX_DEPTH {
+            return Err(invalid("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self, len: usize) -> Result<Value> {
+        self.nested(|decoder| {
+            (0..len)
+                .map(|_| decoder.value())
+                .collect::<Result<Vec<_>>>()
+                .map(Value::Array)
+        })
+    }
+
+    fn object(&mut self, len: usize) -> Result<Value> {
+        self.nested(|decoder| {
+            let mut fields = Map::new();
+            for _ in 0..len {
+                let Value::String(name) = decoder.value()? else {
+                    return Err(invalid("map key is not a string"));
+                };
+                fields.insert(name, decoder.value()?);
+            }
+            Ok(Value::Object(fields))
+        })
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let marker = self.take(1)?[0];
+        Ok(match marker {
+            0x00..=0x7f => Value::from(marker),
+            0x80..=0x8f => self.object((marker & 0x0f) as usize)?,
+            0x90..=0x9f => self.array((marker & 0x0f) as usize)?,
+            0xa0..=0xbf => Value::String(self.string((marker & 0x1f) as usize)?),
+            0xc0 => Value::Null,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            0xc7 => {
+                let len = self.uint(1)? as usize;
+                self.ext(len)?
+            }
+            0xc8 => {
+                let len = self.uint(2)? as usize;
+                self.ext(len)?
+            }
+            0xc9 => {
+                let len = self.uint(4)? as usize;
+                self.ext(len)?
+            }
+            0xcb => {
+                let n = f64::from_bits(self.uint(8)?);
+                Value::Number(Number::from_f64(n).ok_or_else(|| invalid("non-finite float"))?)
+            }
+            0xce => Value::from(self.uint(4)?),
+            0xcf => Value::from(self.uint(8)?),
+            0xd3 => Value::from(self.uint(8)? as i64),
+            0xd9 => {
+                let len = self.uint(1)? as usize;
+                Value::String(self.string(len)?)
+            }
+            0xda => {
+                let len = self.uint(2)? as usize;
+                Value::String(self.string(len)?)
+            }
+            0xdb => {
+                let len = self.uint(4)? as usize;
+                Value::String(self.string(len)?)
+            }
+            0xdc => {
+                let len = self.uint(2)? as usize;
+                self.array(len)?
+            }
+            0xdd => {
+                let len = self.uint(4)? as usize;
+                self.array(len)?
+            }
+            0xde => {
+                let len = self.uint(2)? as usize;
+                self.object(len)?
+            }
+            0xdf => {
+                let len = self.uint(4)? as usize;
+                self.object(len)?
+            }
+            _ => return Err(invalid("unsupported marker")),
+        })
+    }
+}
+
+fn inflate(body: &[u8], limit: u64) -> Result<Vec<u8>> {
+    let mut packed = Vec::new();
+    DeflateDecoder::new(body)
+        .take(limit + 1)
+        .read_to_end(&mut packed)
+        .map_err(|err| invalid(&err.to_string()))?;
+    if packed.len() as u64 > limit {
+        return Err(invalid("inflated size is over the limit"));
+    }
+    Ok(packed)
+}
+
+impl Conversation {
+    /// Deflated MessagePack for shipping conversations between services.
+    /// Inline images, videos and files are stored as raw bytes rather than
+    /// base64, so multimodal conversations are much smaller than as JSON.
+    pub fn to_compact_bytes(&self) -> Result<Vec<u8>> {
+        let mut packed = Vec::new();
+        encode(&serde_json::to_value(self)?, None, &mut packed)?;
+        let mut encoder = DeflateEncoder::new(MAGIC.to_vec(), Compression::default());
+        encoder
+            .write_all(&packed)
+            .and_then(|_| encoder.finish())
+            .map_err(|err| invalid(&err.to_string()))
+    }
+
+    pub fn from_compact_bytes(bytes: &[u8]) -> Result<Self> {
+        let body = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or_else(|| invalid("missing header"))?;
+        let packed = inflate(body, MAX_INFLATED_BYTES)?;
+        let mut decoder = Decoder {
+            bytes: &packed,
+            depth: 0,
+        };
+        let value = decoder.value()?;
+        if !decoder.bytes.is_empty() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        ContentEntry, ContentTurn, ConversationRole, ImageUrl, Turn, TurnType, VideoInput,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_compact_round_trip() {
+        let image: Vec<u8> = (0..4096u32).map(|n| (n * 31 % 251) as u8).collect();
+        let conversation = Conversation::with_turns(vec![
+            TurnType::Basic(Turn {
+                role: ConversationRole::System,
+                content: "Describe the media. ü".to_string(),
+                name: None,
+                metadata: Some(HashMap::from([
+                    ("attempt".to_string(), json!(-3)),
+                    ("score".to_string(), json!(0.25)),
+                    ("id".to_string(), json!(u64::MAX)),
+                ])),
+            }),
+            TurnType::Content(ContentTurn {
+                role: ConversationRole::User,
+                content: vec![
+                    ContentEntry::image(ImageUrl {
+                        url: format!(
+                            "data:image/png;base64,{}",
+                            general_purpose::STANDARD.encode(&image)
+                        ),
+                        detail: Some("high".to_string()),
+                    }),
+                    ContentEntry::video(VideoInput::inline(&image, "video/mp4")),
+                    ContentEntry::text("x".repeat(70_000)),
+                ],
+                name: None,
+                metadata: None,
+            }),
+        ]);
+
+        let compact = conversation.to_compact_bytes().unwrap();
+        let json = serde_json::to_vec(&conversation).unwrap();
+        assert!(compact.len() < json.len() / 2);
+        assert_eq!(
+            Conversation::from_compact_bytes(&compact).unwrap(),
+            conversation
+        );
+        assert!(Conversation::from_compact_bytes(&json).is_err());
+        assert!(Conversation::from_compact_bytes(&compact[..compact.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_compact_rejects_hostile_input() {
+        let mut encoder = DeflateEncoder::new(MAGIC.to_vec(), Compression::default());
+        encoder.write_all(&vec![0x91; 2 << 20]).unwrap();
+        let nested = encoder.finish().unwrap();
+        assert!(nested.len() < 64 * 1024);
+        let err = Conversation::from_compact_bytes(&nested).unwrap_err();
+        assert!(err.to_string().contains("nested too deeply"));
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0; 4096]).unwrap();
+        let bomb = encoder.finish().unwrap();
+        assert_eq!(inflate(&bomb, 4096).unwrap().len(), 4096);
+        assert!(inflate(&bomb, 4095).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::models::{ContentEntry, ImageUrl, ToolResultContent};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseButton {
+    #[default]
+    Left,
+    Right,
+    Middle,
+}
+
+/// What a computer-use model asks the client to do on its screen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ComputerAction {
+    Screenshot,
+    Click {
+        #[serde(flatten)]
+        at: Point,
+        #[serde(default)]
+        button: MouseButton,
+    },
+    DoubleClick {
+        #[serde(flatten)]
+        at: Point,
+    },
+    Move {
+        #[serde(flatten)]
+        to: Point,
+    },
+    Drag {
+        path: Vec<Point>,
+    },
+    Scroll {
+        #[serde(flatten)]
+        at: Point,
+        scroll_x: i64,
+        scroll_y: i64,
+    },
+    Type {
+        text: String,
+    },
+    /// Keys pressed together, such as `["ctrl", "s"]`.
+    Keypress {
+        keys: Vec<String>,
+    },
+    Wait,
+}
+
+fn point(value: Option<&Value>) -> Option<Point> {
+    let value = value?;
+    if let Some([x, y]) = value.as_array().map(Vec::as_slice) {
+        return Some(Point {
+            x: x.as_i64()?,
+            y: y.as_i64()?,
+        });
+    }
+    Some(Point {
+        x: value.get("x")?.as_i64()?,
+        y: value.get("y")?.as_i64()?,
+    })
+}
+
+fn string(value: &Value, key: &str) -> Option<String> {
+    value.get(key)?.as_str().map(str::to_string)
+}
+
+impl ComputerAction {
+    /// Parses the `input` of an Anthropic `computer` tool call.
+    pub fn from_anthropic(input: &Value) -> Option<Self> {
+        let at = || point(input.get("coordinate"));
+        Some(match input.get("action")?.as_str()? {
+            "screenshot" => Self::Screenshot,
+            "left_click" => Self::Click {
+                at: at()?,
+                button: MouseButton::Left,
+            },
+            "right_click" => Self::Click {
+                at: at()?,
+                button: MouseButton::Right,
+            },
+            "middle_click" => Self::Click {
+                at: at()?,
+                button: MouseButton::Middle,
+            },
+            "double_click" => Self::DoubleClick { at: at()? },
+            "mouse_move" => Self::Move { to: at()? },
+            "left_click_drag" => Self::Drag {
+                path: vec![point(input.get("start_coordinate"))?, at()?],
+            },
+            "scroll" => {
+                let amount = input.get("scroll_amount")?.as_i64()?;
+                let (scroll_x, scroll_y) = match input.get("scroll_direction")?.as_str()? {
+                    "up" => (0, -amount),
+                    "down" => (0, amount),
+                    "left" => (-amount, 0),
+                    "right" => (amount, 0),
+                    _ => return None,
+                };
+                Self::Scroll {
+                    at: at()?,
+                    scroll_x,
+                    scroll_y,
+                }
+            }
+            "type" => Self::Type {
+                text: string(input, "text")?,
+            },
+            "key" => Self::Keypress {
+                keys: string(input, "text")?
+                    .split('+')
+                    .map(str::to_string)
+                    .collect(),
+            },
+            "wait" => Self::Wait,
+            _ => return None,
+        })
+    }
+
+    /// Parses the `action` of an OpenAI `computer_call` output item.
+    pub fn from_openai(action: &Value) -> Option<Self> {
+        let at = || point(Some(action));
+        Some(match action.get("type")?.as_str()? {
+            "screenshot" => Self::Screenshot,
+            "click" => Self::Click {
+                at: at()?,
+                button: match action.get("button").and_then(Value::as_str) {
+                    Some("right") => MouseButton::Right,
+                    Some("wheel") | Some("middle") => MouseButton::Middle,
+                    _ => MouseButton::Left,
+                },
+            },
+            "double_click" => Self::DoubleClick { at: at()? },
+            "move" => Self::Move { to: at()? },
+            "drag" => Self::Drag {
+                path: action
+                    .get("path")?
+                    .as_array()?
+                    .iter()
+                    .map(|step| point(Some(step)))
+                    .collect::<Option<_>>()?,
+            },
+            "scroll" => Self::Scroll {
+                at: at()?,
+                scroll_x: action.get("scroll_x")?.as_i64()?,
+                scroll_y: action.get("scroll_y")?.as_i64()?,
+            },
+            "type" => Self::Type {
+                text: string(action, "text")?,
+            },
+            "keypress" => Self::Keypress {
+                keys: action
+                    .get("keys")?
+                    .as_array()?
+                    .iter()
+                    .map(|key| key.as_str().map(str::to_string))
+                    .collect::<Option<_>>()?,
+            },
+            "wait" => Self::Wait,
+            _ => return None,
+        })
+    }
+
+    pub fn to_anthropic(&self) -> Value {
+        let coordinate = |point: &Point| json!([point.x, point.y]);
+        match self {
+            Self::Screenshot => json!({"action": "screenshot"}),
+            Self::Click { at, button } => {
+                let action = match button {
+                    MouseButton::Left => "left_click",
+                    MouseButton::Right => "right_click",
+                    MouseButton::Middle => "middle_click",
+                };
+                json!({"action": action, "coordinate": coordinate(at)})
+            }
+            Self::DoubleClick { at } => {
+                json!({"action": "double_click", "coordinate": coordinate(at)})
+            }
+            Self::Move { to } => json!({"action": "mouse_move", "coordinate": coordinate(to)}),
+            Self::Drag { path } => json!({
+                "action": "left_click_drag",
+                "start_coordinate": path.first().map(coordinate),
+                "coordinate": path.last().map(coordinate),
+            }),
+            Self::Scroll {
+                at,
+                scroll_x,
+                scroll_y,
+            } => {
+                let (direction, amount) = match (*scroll_x, *scroll_y) {
+                    (0, y) if y < 0 => ("up", -y),
+                    (0, y) => ("down", y),
+                    (x, _) if x < 0 => ("left", -x),
+                    (x, _) => ("right", x),
+                };
+                json!({
+                    "action": "scroll",
+                    "coordinate": coordinate(at),
+                    "scroll_direction": direction,
+                    "scroll_amount": amount,
+                })
+            }
+            Self::Type { text } => json!({"action": "type", "text": text}),
+            Self::Keypress { keys } => json!({"action": "key", "text": keys.join("+")}),
+            Self::Wait => json!({"action": "wait"}),
+        }
+    }
+
+    pub fn to_openai(&self) -> Value {
+        match self {
+            Self::Screenshot => json!({"type": "screenshot"}),
+            Self::Click { at, button } => {
+                let button = match button {
+                    MouseButton::Left => "left",
+                    MouseButton::Right => "right",
+                    MouseButton::Middle => "wheel",
+                };
+                json!({"type": "click", "x": at.x, "y": at.y, "button": button})
+            }
+            Self::DoubleClick { at } => json!({"type": "double_click", "x": at.x, "y": at.y}),
+            Self::Move { to } => json!({"type": "move", "x": to.x, "y": to.y}),
+            Self::Drag { path } => json!({"type": "drag", "path": path}),
+            Self::Scroll {
+                at,
+                scroll_x,
+                scroll_y,
+            } => json!({
+                "type": "scroll",
+                "x": at.x,
+                "y": at.y,
+                "scroll_x": scroll_x,
+                "scroll_y": scroll_y,
+            }),
+            Self::Type { text } => json!({"type": "type", "text": text}),
+            Self::Keypress { keys } => json!({"type": "keypress", "keys": keys}),
+            Self::Wait => json!({"type": "wait"}),
+        }
+    }
+}
+
+/// A computer-use action requested by the model. Answer it with
+/// [`ComputerCall::screenshot_result`] once the action has been performed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComputerCall {
+    pub id: String,
+    #[serde(flatten)]
+    pub action: ComputerAction,
+}
+
+impl ComputerCall {
+    /// Computer calls in a raw `provider` response body.
+    pub fn parse(provider: &str, body: &Value) -> Option<Vec<Self>> {
+        let calls: Vec<Self> = match provider {
+            "openai" | "azure" => body
+                .get("output")?
+                .as_array()?
+                .iter()
+                .filter(|item| item.get("type").and_then(Value::as_str) == Some("computer_call"))
+                .filter_map(|item| {
+                    Some(Self {
+                        id: string(item, "call_id")?,
+                        action: ComputerAction::from_openai(item.get("action")?)?,
+                    })
+                })
+                .collect(),
+            "anthropic" => body
+                .get("content")?
+                .as_array()?
+                .iter()
+                .filter(|block| {
+                    block.get("type").and_then(Value::as_str) == Some("tool_use")
+                        && block.get("name").and_then(Value::as_str) == Some("computer")
+                })
+                .filter_map(|block| {
+                    Some(Self {
+                        id: string(block, "id")?,
+                        action: ComputerAction::from_anthropic(block.get("input")?)?,
+                    })
+                })
+                .collect(),
+            _ => return None,
+        };
+        (!calls.is_empty()).then_some(calls)
+    }
+
+    /// The tool result carrying the screen after the action, usually built
+    /// with `ImageUrl::from_bytes`.
+    pub fn screenshot_result(screenshot: ImageUrl) -> ToolResultContent {
+        ToolResultContent::Blocks(vec![ContentEntry::image(screenshot)])
+    }
+
+    /// The Responses API input item answering this call with a screenshot.
+    pub fn to_openai_output(&self, screenshot: &ImageUrl) -> Value {
+        json!({
+            "type": "computer_call_output",
+            "call_id": self.id,
+            "output": {"type": "computer_screenshot", "image_url": screenshot.url},
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_computer_actions_round_trip_between_providers() {
+        let anthropic = json!({
+            "action": "scroll",
+            "coordinate": [640, 400],
+            "scroll_direction": "down",
+            "scroll_amount": 3
+        });
+        let action = ComputerAction::from_anthropic(&anthropic).unwrap();
+        assert_eq!(
+            action,
+            ComputerAction::Scroll {
+                at: Point { x: 640, y: 400 },
+                scroll_x: 0,
+                scroll_y: 3
+            }
+        );
+        assert_eq!(action.to_anthropic(), anthropic);
+        assert_eq!(
+            ComputerAction::from_openai(&action.to_openai()),
+            Some(action)
+        );
+
+        let keys = ComputerAction::from_anthropic(&json!({"action": "key", "text": "ctrl+s"}));
+        assert_eq!(
+            keys.unwrap().to_openai(),
+            json!({"type": "keypress", "keys": ["ctrl", "s"]})
+        );
+    }
+}
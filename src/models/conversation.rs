@@ -1,4 +1,15 @@
+use crate::config::EnvConfig;
+use crate::error::{AdapterError, Result};
+use crate::models::ModelCapabilities;
+use crate::utils::{
+    encode_image_to_base64, estimate_tokens, process_image_url_anthropic, sniff_image_mime,
+    stable_hash, strip_metadata, IMAGE_TOKENS, MAX_IMAGE_BYTES, MESSAGE_OVERHEAD_TOKENS,
+};
+use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -6,29 +17,50 @@ pub enum ConversationRole {
     User,
     Assistant,
     System,
+    Developer,
     Function,
     Tool,
 }
 
+impl ConversationRole {
+    /// The role to send to a model with the given capabilities. `Developer`
+    /// is downgraded to `System` for providers that do not know it.
+    pub fn for_capabilities(&self, capabilities: &ModelCapabilities) -> ConversationRole {
+        match self {
+            ConversationRole::Developer if !capabilities.supports_developer_role => {
+                ConversationRole::System
+            }
+            role => role.clone(),
+        }
+    }
+}
+
 impl std::fmt::Display for ConversationRole {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ConversationRole::User => write!(f, "user"),
             ConversationRole::Assistant => write!(f, "assistant"),
             ConversationRole::System => write!(f, "system"),
+            ConversationRole::Developer => write!(f, "developer"),
             ConversationRole::Function => write!(f, "function"),
             ConversationRole::Tool => write!(f, "tool"),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Turn {
     pub role: ConversationRole,
     pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Client-side bookkeeping such as timestamps or message ids. Kept when
+    /// the conversation is serialized, never sent to a provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContentEntry {
     #[serde(rename = "type")]
     pub entry_type: String,
@@ -36,27 +68,233 @@ pub struct ContentEntry {
     pub data: ContentEntryData,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ContentEntryData {
     Text { text: String },
     Image { image_url: ImageUrl },
+    Video { video: VideoInput },
+    File { file: FileReference },
+}
+
+/// A file previously uploaded to a provider's file store, referenced from
+/// content instead of being sent inline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileReference {
+    pub provider: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+}
+
+impl FileReference {
+    pub fn to_openai_content(&self) -> Value {
+        json!({"type": "file", "file": {"file_id": self.id}})
+    }
+
+    pub fn to_anthropic_content(&self) -> Value {
+        let block_type = match self.mime_type.as_deref() {
+            Some(mime) if mime.starts_with("image/") => "image",
+            _ => "document",
+        };
+        json!({"type": block_type, "source": {"type": "file", "file_id": self.id}})
+    }
+
+    pub fn to_gemini_part(&self) -> Value {
+        json!({
+            "fileData": {
+                "mimeType": self.mime_type.as_deref().unwrap_or("application/octet-stream"),
+                "fileUri": self.uri.as_deref().unwrap_or(&self.id),
+            }
+        })
+    }
+}
+
+/// Video either referenced by `uri` (e.g. a Gemini File API upload) or
+/// carried inline as base64 `data`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoInput {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    pub mime_type: String,
+}
+
+impl VideoInput {
+    pub fn from_uri(uri: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            uri: Some(uri.into()),
+            data: None,
+            mime_type: mime_type.into(),
+        }
+    }
+
+    pub fn inline(bytes: &[u8], mime_type: impl Into<String>) -> Self {
+        Self {
+            uri: None,
+            data: Some(encode_image_to_base64(bytes)),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    pub fn to_gemini_part(&self) -> Result<Value> {
+        match (&self.uri, &self.data) {
+            (Some(uri), _) => Ok(json!({
+                "fileData": {"mimeType": self.mime_type, "fileUri": uri}
+            })),
+            (None, Some(data)) => Ok(json!({
+                "inlineData": {"mimeType": self.mime_type, "data": data}
+            })),
+            (None, None) => Err(AdapterError::ConfigError(
+                "video input needs either a uri or inline data".to_string(),
+            )),
+        }
+    }
+}
+
+impl ContentEntry {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            entry_type: "text".to_string(),
+            data: ContentEntryData::Text { text: text.into() },
+        }
+    }
+
+    pub fn image(image_url: ImageUrl) -> Self {
+        Self {
+            entry_type: "image_url".to_string(),
+            data: ContentEntryData::Image { image_url },
+        }
+    }
+
+    pub fn video(video: VideoInput) -> Self {
+        Self {
+            entry_type: "video".to_string(),
+            data: ContentEntryData::Video { video },
+        }
+    }
+
+    pub fn file(file: FileReference) -> Self {
+        Self {
+            entry_type: "file".to_string(),
+            data: ContentEntryData::File { file },
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ImageUrl {
     pub url: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ImageUrl {
+    /// Encodes `bytes` as a data URI. Without `mime_type` the format is
+    /// detected from the bytes. Images over `MAX_IMAGE_BYTES` are rejected;
+    /// use `ensure_within` for stricter provider limits. Metadata is
+    /// stripped first when `ADAPTERS_STRIP_IMAGE_METADATA` is set.
+    pub fn from_bytes(bytes: &[u8], mime_type: Option<&str>) -> Result<Self> {
+        let stripped;
+        let bytes = if EnvConfig::get_strip_image_metadata() {
+            stripped = strip_metadata(bytes)?;
+            &stripped[..]
+        } else {
+            bytes
+        };
+        if bytes.len() > MAX_IMAGE_BYTES {
+            return Err(AdapterError::TooLarge {
+                size: bytes.len(),
+                limit: MAX_IMAGE_BYTES,
+            });
+        }
+        let mime_type = match mime_type {
+            Some(mime_type) => mime_type,
+            None => sniff_image_mime(bytes).ok_or_else(|| {
+                AdapterError::ConfigError("unrecognized image format".to_string())
+            })?,
+        };
+        Ok(Self {
+            url: format!(
+                "data:{};base64,{}",
+                mime_type,
+                encode_image_to_base64(bytes)
+            ),
+            detail: None,
+        })
+    }
+
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|err| {
+            AdapterError::ConfigError(format!("failed to read image {}: {}", path.display(), err))
+        })?;
+        Self::from_bytes(&bytes, None)
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Decoded size of a data URI, `None` for remote URLs.
+    pub fn inline_size(&self) -> Option<usize> {
+        let (_, data) = self.url.strip_prefix("data:")?.split_once(',')?;
+        let padding = data.bytes().rev().take_while(|byte| *byte == b'=').count();
+        Some((data.len() / 4 * 3).saturating_sub(padding))
+    }
+
+    /// Re-encodes a data URI without EXIF and other metadata, see
+    /// `utils::strip_metadata`. Remote URLs are returned unchanged.
+    pub fn strip_metadata(&self) -> Result<Self> {
+        let Some((header, data)) = self
+            .url
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(','))
+        else {
+            return Ok(self.clone());
+        };
+        let bytes = general_purpose::STANDARD
+            .decode(data)
+            .map_err(|err| AdapterError::ConfigError(format!("invalid image data: {}", err)))?;
+        Ok(Self {
+            url: format!(
+                "data:{},{}",
+                header,
+                encode_image_to_base64(&strip_metadata(&bytes)?)
+            ),
+            detail: self.detail.clone(),
+        })
+    }
+
+    pub fn ensure_within(&self, limit: usize) -> Result<()> {
+        match self.inline_size() {
+            Some(size) if size > limit => Err(AdapterError::TooLarge { size, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ContentTurn {
     pub role: ConversationRole,
     pub content: Vec<ContentEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Client-side bookkeeping such as timestamps or message ids. Kept when
+    /// the conversation is serialized, never sent to a provider.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, Value>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     pub id: String,
     #[serde(rename = "type")]
@@ -64,30 +302,277 @@ pub struct ToolCall {
     pub function: FunctionCall,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub name: String,
     pub arguments: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ToolCall {
+    pub fn function(id: impl Into<String>, name: impl Into<String>, arguments: String) -> Self {
+        Self {
+            id: id.into(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: name.into(),
+                arguments,
+            },
+        }
+    }
+
+    /// Parses the stringified arguments. Empty arguments are treated as `{}`.
+    pub fn parsed_arguments(&self) -> Result<Value> {
+        if self.function.arguments.trim().is_empty() {
+            return Ok(json!({}));
+        }
+        Ok(serde_json::from_str(&self.function.arguments)?)
+    }
+
+    /// Converts an Anthropic `tool_use` content block.
+    pub fn from_anthropic_block(block: &Value) -> Option<Self> {
+        if block.get("type").and_then(Value::as_str) != Some("tool_use") {
+            return None;
+        }
+        let id = block.get("id")?.as_str()?;
+        let name = block.get("name")?.as_str()?;
+        let input = block.get("input").cloned().unwrap_or_else(|| json!({}));
+        Some(Self::function(id, name, input.to_string()))
+    }
+
+    /// Converts a Gemini `functionCall` part. Gemini usually sends no call id,
+    /// so one is derived from the call's position and content; the same
+    /// response therefore always yields the same ids.
+    pub fn from_gemini_part(part: &Value, position: usize) -> Option<Self> {
+        let call = part.get("functionCall")?;
+        let name = call.get("name")?.as_str()?;
+        let args = call.get("args").cloned().unwrap_or_else(|| json!({}));
+        let id = match call.get("id").and_then(Value::as_str) {
+            Some(id) => id.to_string(),
+            None => format!("call_{:016x}", stable_hash(&(position, name, &args))),
+        };
+        Some(Self::function(id, name, args.to_string()))
+    }
+
+    pub fn to_anthropic_block(&self) -> Result<Value> {
+        Ok(json!({
+            "type": "tool_use",
+            "id": self.id,
+            "name": self.function.name,
+            "input": self.parsed_arguments()?,
+        }))
+    }
+
+    pub fn to_gemini_part(&self) -> Result<Value> {
+        Ok(json!({
+            "functionCall": {
+                "name": self.function.name,
+                "args": self.parsed_arguments()?,
+            }
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolResultContent {
+    Text(String),
+    Blocks(Vec<ContentEntry>),
+    Json(Value),
+}
+
+impl ToolResultContent {
+    pub fn to_text(&self) -> String {
+        match self {
+            ToolResultContent::Text(text) => text.clone(),
+            ToolResultContent::Json(value) => value.to_string(),
+            ToolResultContent::Blocks(entries) => entries
+                .iter()
+                .filter_map(|entry| match &entry.data {
+                    ContentEntryData::Text { text } => Some(text.as_str()),
+                    ContentEntryData::Image { .. }
+                    | ContentEntryData::Video { .. }
+                    | ContentEntryData::File { .. } => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+
+    pub fn to_openai_content(&self) -> Value {
+        Value::String(self.to_text())
+    }
+
+    /// Gemini expects `functionResponse.response` to be an object, so
+    /// anything but a JSON object is wrapped as `{"content": ...}`.
+    pub fn to_gemini_response(&self, name: &str) -> Value {
+        let response = match self {
+            ToolResultContent::Json(value @ Value::Object(_)) => value.clone(),
+            ToolResultContent::Json(value) => json!({"content": value}),
+            _ => json!({"content": self.to_text()}),
+        };
+        json!({"functionResponse": {"name": name, "response": response}})
+    }
+
+    pub fn to_anthropic_content(&self) -> Result<Value> {
+        let ToolResultContent::Blocks(entries) = self else {
+            return Ok(Value::String(self.to_text()));
+        };
+
+        let mut blocks = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match &entry.data {
+                ContentEntryData::Text { text } => {
+                    blocks.push(json!({"type": "text", "text": text}));
+                }
+                ContentEntryData::Image { image_url } => {
+                    let (media_type, data) = process_image_url_anthropic(&image_url.url)?;
+                    blocks.push(json!({
+                        "type": "image",
+                        "source": {"type": "base64", "media_type": media_type, "data": data},
+                    }));
+                }
+                ContentEntryData::File { file } => blocks.push(file.to_anthropic_content()),
+                ContentEntryData::Video { .. } => {
+                    return Err(AdapterError::ConfigError(
+                        "Anthropic does not accept video content".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(Value::Array(blocks))
+    }
+}
+
+impl From<String> for ToolResultContent {
+    fn from(text: String) -> Self {
+        ToolResultContent::Text(text)
+    }
+}
+
+impl From<&str> for ToolResultContent {
+    fn from(text: &str) -> Self {
+        ToolResultContent::Text(text.to_string())
+    }
+}
+
+impl From<Value> for ToolResultContent {
+    fn from(value: Value) -> Self {
+        ToolResultContent::Json(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum TurnType {
-    Basic(Turn),
-    Content(ContentTurn),
     ToolOutput {
         role: ConversationRole,
-        content: Option<String>,
+        content: Option<ToolResultContent>,
         tool_call_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<HashMap<String, Value>>,
     },
     ToolCalls {
         role: ConversationRole,
         content: Option<String>,
         tool_calls: Vec<ToolCall>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<HashMap<String, Value>>,
     },
+    Basic(Turn),
+    Content(ContentTurn),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TurnType {
+    pub fn role(&self) -> &ConversationRole {
+        match self {
+            TurnType::Basic(turn) => &turn.role,
+            TurnType::Content(turn) => &turn.role,
+            TurnType::ToolCalls { role, .. } | TurnType::ToolOutput { role, .. } => role,
+        }
+    }
+
+    /// The textual content of the turn. Tool calls are rendered as
+    /// `name(arguments)` so they count towards token estimates.
+    pub fn text(&self) -> String {
+        match self {
+            TurnType::Basic(turn) => turn.content.clone(),
+            TurnType::Content(turn) => turn
+                .content
+                .iter()
+                .filter_map(|entry| match &entry.data {
+                    ContentEntryData::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            TurnType::ToolCalls {
+                content,
+                tool_calls,
+                ..
+            } => {
+                let calls = tool_calls
+                    .iter()
+                    .map(|call| format!("{}({})", call.function.name, call.function.arguments));
+                content
+                    .iter()
+                    .cloned()
+                    .chain(calls)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            TurnType::ToolOutput { content, .. } => content
+                .as_ref()
+                .map(ToolResultContent::to_text)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn image_count(&self) -> usize {
+        match self {
+            TurnType::Content(turn) => turn
+                .content
+                .iter()
+                .filter(|entry| matches!(entry.data, ContentEntryData::Image { .. }))
+                .count(),
+            _ => 0,
+        }
+    }
+
+    pub fn estimated_tokens(&self) -> u32 {
+        MESSAGE_OVERHEAD_TOKENS
+            + estimate_tokens(&self.text())
+            + self.image_count() as u32 * IMAGE_TOKENS
+    }
+
+    pub fn metadata(&self) -> Option<&HashMap<String, Value>> {
+        match self {
+            TurnType::Basic(turn) => turn.metadata.as_ref(),
+            TurnType::Content(turn) => turn.metadata.as_ref(),
+            TurnType::ToolCalls { metadata, .. } | TurnType::ToolOutput { metadata, .. } => {
+                metadata.as_ref()
+            }
+        }
+    }
+
+    pub fn metadata_mut(&mut self) -> &mut Option<HashMap<String, Value>> {
+        match self {
+            TurnType::Basic(turn) => &mut turn.metadata,
+            TurnType::Content(turn) => &mut turn.metadata,
+            TurnType::ToolCalls { metadata, .. } | TurnType::ToolOutput { metadata, .. } => {
+                metadata
+            }
+        }
+    }
+
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata_mut()
+            .get_or_insert_with(HashMap::new)
+            .insert(key.into(), value.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Conversation {
     pub turns: Vec<TurnType>,
 }
@@ -105,6 +590,115 @@ impl Conversation {
         self.turns.push(turn);
     }
 
+    pub fn with_assistant_prefill(mut self, text: impl Into<String>) -> Self {
+        self.turns.push(TurnType::Basic(Turn {
+            role: ConversationRole::Assistant,
+            content: text.into(),
+            name: None,
+            metadata: None,
+        }));
+        self
+    }
+
+    pub fn assistant_prefill(&self) -> Option<&str> {
+        match self.turns.last() {
+            Some(TurnType::Basic(turn)) if turn.role == ConversationRole::Assistant => {
+                Some(&turn.content)
+            }
+            _ => None,
+        }
+    }
+
+    /// A copy with EXIF and other metadata removed from every inline image.
+    pub fn strip_image_metadata(&self) -> Result<Conversation> {
+        let strip = |entries: &mut Vec<ContentEntry>| -> Result<()> {
+            for entry in entries {
+                if let ContentEntryData::Image { image_url } = &mut entry.data {
+                    *image_url = image_url.strip_metadata()?;
+                }
+            }
+            Ok(())
+        };
+        let mut conversation = self.clone();
+        for turn in &mut conversation.turns {
+            match turn {
+                TurnType::Content(turn) => strip(&mut turn.content)?,
+                TurnType::ToolOutput {
+                    content: Some(ToolResultContent::Blocks(entries)),
+                    ..
+                } => strip(entries)?,
+                _ => {}
+            }
+        }
+        Ok(conversation)
+    }
+
+    /// A copy with all turn metadata removed, i.e. what a provider sees.
+    pub fn without_metadata(&self) -> Conversation {
+        let mut conversation = self.clone();
+        for turn in &mut conversation.turns {
+            *turn.metadata_mut() = None;
+        }
+        conversation
+    }
+
+    /// Turn metadata is not part of the hash.
+    pub fn content_hash(&self) -> String {
+        format!("{:016x}", stable_hash(&self.without_metadata()))
+    }
+
+    /// OpenAI chat `messages`, as used by chat requests and fine-tuning JSONL.
+    pub fn to_openai_messages(&self) -> Vec<Value> {
+        self.turns
+            .iter()
+            .map(|turn| match turn {
+                TurnType::ToolOutput {
+                    role,
+                    content,
+                    tool_call_id,
+                    ..
+                } => json!({
+                    "role": role.to_string(),
+                    "content": content
+                        .as_ref()
+                        .map_or(Value::String(String::new()), ToolResultContent::to_openai_content),
+                    "tool_call_id": tool_call_id,
+                }),
+                other => {
+                    let mut message = serde_json::to_value(other).unwrap_or(Value::Null);
+                    if let Some(message) = message.as_object_mut() {
+                        message.remove("metadata");
+                    }
+                    message
+                }
+            })
+            .collect()
+    }
+
+    /// Rewrites roles the target model does not support, see
+    /// `ConversationRole::for_capabilities`.
+    pub fn for_capabilities(&self, capabilities: &ModelCapabilities) -> Conversation {
+        let mut conversation = self.clone();
+        for turn in &mut conversation.turns {
+            let role = match turn {
+                TurnType::Basic(turn) => &mut turn.role,
+                TurnType::Content(turn) => &mut turn.role,
+                TurnType::ToolCalls { role, .. } | TurnType::ToolOutput { role, .. } => role,
+            };
+            *role = role.for_capabilities(capabilities);
+        }
+        conversation
+    }
+
+    /// Finds an earlier assistant tool call by id, e.g. to recover the
+    /// function name Gemini needs alongside a tool result.
+    pub fn find_tool_call(&self, id: &str) -> Option<&ToolCall> {
+        self.turns.iter().find_map(|turn| match turn {
+            TurnType::ToolCalls { tool_calls, .. } => tool_calls.iter().find(|call| call.id == id),
+            _ => None,
+        })
+    }
+
     pub fn is_last_turn_vision_query(&self) -> bool {
         if let Some(TurnType::Content(content_turn)) = self.turns.last() {
             content_turn
@@ -116,6 +710,20 @@ impl Conversation {
         }
     }
 
+    pub fn has_video(&self) -> bool {
+        self.turns.iter().any(|turn| match turn {
+            TurnType::Content(content_turn) => content_turn
+                .content
+                .iter()
+                .any(|entry| matches!(entry.data, ContentEntryData::Video { .. })),
+            _ => false,
+        })
+    }
+
+    pub fn estimated_tokens(&self) -> u32 {
+        self.turns.iter().map(TurnType::estimated_tokens).sum()
+    }
+
     pub fn len(&self) -> usize {
         self.turns.len()
     }
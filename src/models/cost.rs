@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Cost {
     pub prompt: f64,
     pub completion: f64,
     pub request: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
 }
 
 impl Cost {
@@ -13,14 +18,52 @@ impl Cost {
             prompt,
             completion,
             request,
+            cache_read: None,
+            cache_write: None,
         }
     }
 
     pub fn from_modelsdev(input_per_million: f64, output_per_million: f64) -> Self {
-        Self {
-            prompt: input_per_million / 1_000_000.0,
-            completion: output_per_million / 1_000_000.0,
-            request: 0.0,
+        Self::new(
+            input_per_million / 1_000_000.0,
+            output_per_million / 1_000_000.0,
+            0.0,
+        )
+    }
+
+    pub fn with_cache_pricing(mut self, cache_read: Option<f64>, cache_write: Option<f64>) -> Self {
+        self.cache_read = cache_read;
+        self.cache_write = cache_write;
+        self
+    }
+
+    /// Splits the cost of a request by token class. Cached and reasoning
+    /// tokens are subsets of prompt and completion tokens respectively, so
+    /// they are priced separately and excluded from the plain buckets.
+    pub fn breakdown(&self, usage: &TokenUsage) -> CostBreakdown {
+        let cache_read_tokens = usage.cached_prompt_tokens.unwrap_or(0);
+        let cache_write_tokens = usage.cache_creation_tokens.unwrap_or(0);
+        let reasoning_tokens = usage.reasoning_tokens.unwrap_or(0);
+        let plain_prompt = usage
+            .prompt_tokens
+            .saturating_sub(cache_read_tokens)
+            .saturating_sub(cache_write_tokens);
+        let plain_completion = usage.completion_tokens.saturating_sub(reasoning_tokens);
+
+        let prompt = self.prompt * plain_prompt as f64;
+        let completion = self.completion * plain_completion as f64;
+        let cache_read = self.cache_read.unwrap_or(self.prompt) * cache_read_tokens as f64;
+        let cache_write = self.cache_write.unwrap_or(self.prompt) * cache_write_tokens as f64;
+        let reasoning = self.completion * reasoning_tokens as f64;
+
+        CostBreakdown {
+            prompt,
+            completion,
+            cache_read,
+            cache_write,
+            reasoning,
+            request: self.request,
+            total: prompt + completion + cache_read + cache_write + reasoning + self.request,
         }
     }
 
@@ -33,19 +76,52 @@ impl Cost {
 
 impl Default for Cost {
     fn default() -> Self {
-        Self {
-            prompt: 0.0,
-            completion: 0.0,
-            request: 0.0,
-        }
+        Self::new(0.0, 0.0, 0.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostBreakdown {
+    pub prompt: f64,
+    pub completion: f64,
+    pub cache_read: f64,
+    pub cache_write: f64,
+    pub reasoning: f64,
+    pub request: f64,
+    pub total: f64,
+}
+
+impl CostBreakdown {
+    pub fn accumulate(&mut self, other: &CostBreakdown) {
+        self.prompt += other.prompt;
+        self.completion += other.completion;
+        self.cache_read += other.cache_read;
+        self.cache_write += other.cache_write;
+        self.reasoning += other.reasoning;
+        self.request += other.request;
+        self.total += other.total;
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_prompt_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_tokens: Option<u32>,
+    /// Completion tokens that matched the request's `prediction`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accepted_prediction_tokens: Option<u32>,
+    /// Predicted tokens that were not used; billed as completion tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_prediction_tokens: Option<u32>,
 }
 
 impl TokenUsage {
@@ -54,6 +130,108 @@ impl TokenUsage {
             prompt_tokens,
             completion_tokens,
             total_tokens: prompt_tokens + completion_tokens,
+            cached_prompt_tokens: None,
+            cache_creation_tokens: None,
+            reasoning_tokens: None,
+            audio_tokens: None,
+            accepted_prediction_tokens: None,
+            rejected_prediction_tokens: None,
         }
     }
+
+    pub fn from_openai(usage: &Value) -> Option<Self> {
+        let prompt_tokens = token_count(usage, "/prompt_tokens")?;
+        let completion_tokens = token_count(usage, "/completion_tokens").unwrap_or(0);
+        let audio_tokens = sum_optional(
+            token_count(usage, "/prompt_tokens_details/audio_tokens"),
+            token_count(usage, "/completion_tokens_details/audio_tokens"),
+        );
+
+        Some(Self {
+            total_tokens: token_count(usage, "/total_tokens")
+                .unwrap_or(prompt_tokens + completion_tokens),
+            cached_prompt_tokens: token_count(usage, "/prompt_tokens_details/cached_tokens"),
+            reasoning_tokens: token_count(usage, "/completion_tokens_details/reasoning_tokens"),
+            audio_tokens,
+            accepted_prediction_tokens: token_count(
+                usage,
+                "/completion_tokens_details/accepted_prediction_tokens",
+            ),
+            rejected_prediction_tokens: token_count(
+                usage,
+                "/completion_tokens_details/rejected_prediction_tokens",
+            ),
+            ..Self::new(prompt_tokens, completion_tokens)
+        })
+    }
+
+    /// Anthropic reports cache reads and writes separately from
+    /// `input_tokens`; they are folded into `prompt_tokens` here so that the
+    /// cached counts are subsets of the prompt, as with OpenAI.
+    pub fn from_anthropic(usage: &Value) -> Option<Self> {
+        let input_tokens = token_count(usage, "/input_tokens")?;
+        let output_tokens = token_count(usage, "/output_tokens").unwrap_or(0);
+        let cache_read = token_count(usage, "/cache_read_input_tokens");
+        let cache_creation = token_count(usage, "/cache_creation_input_tokens");
+        let prompt_tokens = input_tokens + cache_read.unwrap_or(0) + cache_creation.unwrap_or(0);
+
+        Some(Self {
+            cached_prompt_tokens: cache_read,
+            cache_creation_tokens: cache_creation,
+            ..Self::new(prompt_tokens, output_tokens)
+        })
+    }
+
+    pub fn from_gemini(usage_metadata: &Value) -> Option<Self> {
+        let prompt_tokens = token_count(usage_metadata, "/promptTokenCount")?;
+        let thoughts = token_count(usage_metadata, "/thoughtsTokenCount");
+        let completion_tokens = token_count(usage_metadata, "/candidatesTokenCount").unwrap_or(0)
+            + thoughts.unwrap_or(0);
+
+        Some(Self {
+            total_tokens: token_count(usage_metadata, "/totalTokenCount")
+                .unwrap_or(prompt_tokens + completion_tokens),
+            cached_prompt_tokens: token_count(usage_metadata, "/cachedContentTokenCount"),
+            reasoning_tokens: thoughts,
+            ..Self::new(prompt_tokens, completion_tokens)
+        })
+    }
+
+    pub fn accumulate(&mut self, other: &TokenUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        add_optional(&mut self.cached_prompt_tokens, other.cached_prompt_tokens);
+        add_optional(&mut self.cache_creation_tokens, other.cache_creation_tokens);
+        add_optional(&mut self.reasoning_tokens, other.reasoning_tokens);
+        add_optional(&mut self.audio_tokens, other.audio_tokens);
+        add_optional(
+            &mut self.accepted_prediction_tokens,
+            other.accepted_prediction_tokens,
+        );
+        add_optional(
+            &mut self.rejected_prediction_tokens,
+            other.rejected_prediction_tokens,
+        );
+    }
+}
+
+fn token_count(usage: &Value, pointer: &str) -> Option<u32> {
+    usage
+        .pointer(pointer)
+        .and_then(Value::as_u64)
+        .map(|count| count as u32)
+}
+
+fn sum_optional(a: Option<u32>, b: Option<u32>) -> Option<u32> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    }
+}
+
+fn add_optional(total: &mut Option<u32>, value: Option<u32>) {
+    if let Some(value) = value {
+        *total = Some(total.unwrap_or(0) + value);
+    }
 }
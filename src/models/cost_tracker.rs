@@ -0,0 +1,364 @@
+use crate::error::Result;
+use crate::models::{AdapterChatCompletion, TokenUsage};
+use crate::utils::civil_from_days;
+#[cfg(feature = "parquet")]
+use crate::utils::ParquetTable;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+/// The usage and cost of one request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostRecord {
+    /// Unix seconds.
+    pub timestamp: u64,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub cost: f64,
+}
+
+/// What `CostTracker::rollup` groups records by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupDimension {
+    /// The UTC day, as `YYYY-MM-DD`.
+    Day,
+    Model,
+    Tenant,
+}
+
+/// Day, model and tenant of a group.
+type RollupKey = (Option<String>, Option<String>, Option<String>);
+
+/// Totals for one group; dimensions that were not grouped by are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostRollup {
+    pub day: Option<String>,
+    pub model: Option<String>,
+    pub tenant: Option<String>,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+/// Records the cost of every request and rolls it up per day, model and
+/// tenant for reporting. The retention period is the window tenant budgets
+/// apply to; records older than it are dropped by `prune`, which
+/// `prune_every` runs on a schedule.
+#[derive(Debug, Default)]
+pub struct CostTracker {
+    records: Mutex<Vec<CostRecord>>,
+    retention: Option<Duration>,
+    budgets: Mutex<HashMap<String, f64>>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+fn utc_day(timestamp: u64) -> String {
+    let (year, month, day) = civil_from_days((timestamp / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl CostTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    /// Records `response` at the current time.
+    pub fn record(&self, response: &AdapterChatCompletion, tenant: Option<&str>) {
+        let usage = response
+            .usage
+            .clone()
+            .unwrap_or_else(|| TokenUsage::new(0, 0));
+        self.record_entry(CostRecord {
+            timestamp: unix_now(),
+            model: response.model.clone(),
+            tenant: tenant.map(str::to_string),
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            cost: response.cost,
+        });
+    }
+
+    /// Adds `record`, which may be backdated or imported.
+    pub fn record_entry(&self, record: CostRecord) {
+        self.records.lock().unwrap().push(record);
+    }
+
+    /// The oldest timestamp still within the retention period as of `now`.
+    fn cutoff(&self, now: u64) -> Option<u64> {
+        Some(now.saturating_sub(self.retention?.as_secs()))
+    }
+
+    /// Drops records older than the retention period as of `now` (unix
+    /// seconds) and returns how many were removed.
+    pub fn prune(&self, now: u64) -> usize {
+        let Some(cutoff) = self.cutoff(now) else {
+            return 0;
+        };
+        let mut records = self.records.lock().unwrap();
+        let before = records.len();
+        records.retain(|record| record.timestamp >= cutoff);
+        before - records.len()
+    }
+
+    /// Prunes against the wall clock every `interval` until the tracker is
+    /// dropped or the returned task is aborted.
+    pub fn prune_every(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let tracker = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(tracker) = tracker.upgrade() else {
+                    return;
+                };
+                tracker.prune(unix_now());
+            }
+        })
+    }
+
+    /// Caps what `tenant` may spend, see `BudgetRouter`.
+    pub fn set_budget(&self, tenant: impl Into<String>, max_cost: f64) {
+        self.budgets.lock().unwrap().insert(tenant.into(), max_cost);
+    }
+
+    pub fn budget(&self, tenant: &str) -> Option<f64> {
+        self.budgets.lock().unwrap().get(tenant).copied()
+    }
+
+    /// What `tenant` has spent within the retention period, whether or not
+    /// older records were pruned yet.
+    pub fn spent_by(&self, tenant: &str) -> f64 {
+        let cutoff = self.cutoff(unix_now()).unwrap_or(0);
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| record.tenant.as_deref() == Some(tenant))
+            .filter(|record| record.timestamp >= cutoff)
+            .map(|record| record.cost)
+            .sum()
+    }
+
+    /// The share of its budget `tenant` has spent, `None` without a budget.
+    pub fn budget_used(&self, tenant: &str) -> Option<f64> {
+        let budget = self.budget(tenant)?;
+        let spent = self.spent_by(tenant);
+        Some(if budget > 0.0 {
+            spent / budget
+        } else {
+            f64::INFINITY
+        })
+    }
+
+    pub fn records(&self) -> Vec<CostRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.records.lock().unwrap().iter().map(|r| r.cost).sum()
+    }
+
+    /// Totals grouped by `dimensions`, sorted by day, model and tenant.
+    pub fn rollup(&self, dimensions: &[RollupDimension]) -> Vec<CostRollup> {
+        let by = |dimension| dimensions.contains(&dimension);
+        let mut groups: BTreeMap<RollupKey, CostRollup> = BTreeMap::new();
+        for record in self.records.lock().unwrap().iter() {
+            let key = (
+                by(RollupDimension::Day).then(|| utc_day(record.timestamp)),
+                by(RollupDimension::Model).then(|| record.model.clone()),
+                by(RollupDimension::Tenant)
+                    .then(|| record.tenant.clone())
+                    .flatten(),
+            );
+            let rollup = groups.entry(key.clone()).or_insert_with(|| CostRollup {
+                day: key.0,
+                model: key.1,
+                tenant: key.2,
+                requests: 0,
+                prompt_tokens: 0,
+                completion_tokens: 0,
+                cost: 0.0,
+            });
+            rollup.requests += 1;
+            rollup.prompt_tokens += u64::from(record.prompt_tokens);
+            rollup.completion_tokens += u64::from(record.completion_tokens);
+            rollup.cost += record.cost;
+        }
+        groups.into_values().collect()
+    }
+
+    pub fn export_json(&self, dimensions: &[RollupDimension]) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.rollup(dimensions))?)
+    }
+
+    /// One row per rollup; ungrouped dimensions are left empty.
+    pub fn export_csv(&self, dimensions: &[RollupDimension]) -> String {
+        let mut csv =
+            String::from("day,model,tenant,requests,prompt_tokens,completion_tokens,cost\n");
+        for rollup in self.rollup(dimensions) {
+            let field =
+                |value: &Option<String>| value.as_deref().map(csv_field).unwrap_or_default();
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{}\n",
+                field(&rollup.day),
+                field(&rollup.model),
+                field(&rollup.tenant),
+                rollup.requests,
+                rollup.prompt_tokens,
+                rollup.completion_tokens,
+                rollup.cost
+            ));
+        }
+        csv
+    }
+
+    /// Every retained record, one row each, for loading into a data
+    /// warehouse.
+    #[cfg(feature = "parquet")]
+    pub fn export_parquet(&self) -> Result<Vec<u8>> {
+        let records = self.records();
+        ParquetTable::new()
+            .with_int64(
+                "timestamp",
+                records
+                    .iter()
+                    .map(|record| Some(record.timestamp as i64))
+                    .collect(),
+            )
+            .with_utf8(
+                "day",
+                records
+                    .iter()
+                    .map(|record| Some(utc_day(record.timestamp)))
+                    .collect(),
+            )
+            .with_utf8(
+                "model",
+                records
+                    .iter()
+                    .map(|record| Some(record.model.clone()))
+                    .collect(),
+            )
+            .with_utf8(
+                "tenant",
+                records.iter().map(|record| record.tenant.clone()).collect(),
+            )
+            .with_int64(
+                "prompt_tokens",
+                records
+                    .iter()
+                    .map(|record| Some(record.prompt_tokens.into()))
+                    .collect(),
+            )
+            .with_int64(
+                "completion_tokens",
+                records
+                    .iter()
+                    .map(|record| Some(record.completion_tokens.into()))
+                    .collect(),
+            )
+            .with_double(
+                "cost",
+                records.iter().map(|record| Some(record.cost)).collect(),
+            )
+            .to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64, model: &str, tenant: &str, cost: f64) -> CostRecord {
+        CostRecord {
+            timestamp,
+            model: model.to_string(),
+            tenant: Some(tenant.to_string()),
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_cost_rollups() {
+        let tracker = CostTracker::new();
+        // 2024-03-01 and 2024-03-02, UTC.
+        tracker.record_entry(record(1_709_251_200, "gpt-4o", "acme", 0.5));
+        tracker.record_entry(record(1_709_254_800, "gpt-4o", "globex", 0.25));
+        tracker.record_entry(record(1_709_337_600, "claude-sonnet", "acme", 1.0));
+
+        let daily = tracker.rollup(&[RollupDimension::Day]);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].day.as_deref(), Some("2024-03-01"));
+        assert_eq!(daily[0].requests, 2);
+        assert_eq!(daily[0].cost, 0.75);
+
+        let csv = tracker.export_csv(&[RollupDimension::Model, RollupDimension::Tenant]);
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                "day,model,tenant,requests,prompt_tokens,completion_tokens,cost",
+                ",claude-sonnet,acme,1,10,5,1",
+                ",gpt-4o,acme,1,10,5,0.5",
+                ",gpt-4o,globex,1,10,5,0.25",
+            ]
+        );
+        let json: serde_json::Value =
+            serde_json::from_str(&tracker.export_json(&[RollupDimension::Tenant]).unwrap())
+                .unwrap();
+        assert_eq!(json[0]["tenant"], "acme");
+        assert_eq!(json[0]["cost"], 1.5);
+
+        let tracker = CostTracker::new().with_retention(Duration::from_secs(86_400));
+        tracker.record_entry(record(1_709_251_200, "gpt-4o", "acme", 0.5));
+        tracker.record_entry(record(1_709_337_600, "gpt-4o", "acme", 0.5));
+        assert_eq!(tracker.records().len(), 2);
+        assert_eq!(tracker.prune(1_709_337_600 + 3_600), 1);
+        assert_eq!(tracker.records().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cost_tracker_prunes_by_wall_clock() {
+        let tracker = Arc::new(CostTracker::new().with_retention(Duration::from_secs(3_600)));
+        tracker.set_budget("acme", 1.0);
+        tracker.record_entry(record(unix_now(), "gpt-4o", "acme", 0.25));
+        // An expired entry stops counting against the budget before it is
+        // pruned.
+        tracker.record_entry(record(unix_now() - 7_200, "gpt-4o", "acme", 0.5));
+        assert_eq!(tracker.records().len(), 2);
+        assert_eq!(tracker.spent_by("acme"), 0.25);
+
+        let pruner = tracker.prune_every(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        pruner.abort();
+        assert_eq!(tracker.records().len(), 1);
+        assert_eq!(tracker.spent_by("acme"), 0.25);
+    }
+}
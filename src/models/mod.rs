@@ -1,11 +1,42 @@
+pub mod capability_matrix;
+pub mod citations;
+pub mod code_execution;
+#[cfg(feature = "compact")]
+pub mod compact;
+pub mod computer_use;
 pub mod conversation;
 pub mod cost;
+pub mod cost_tracker;
 pub mod model;
+pub mod model_graph;
+pub mod models_list;
 pub mod modelsdev;
+pub mod prompt;
+pub mod rate_limit;
+pub mod redaction;
+pub mod reproducibility;
 pub mod response;
+pub mod stats;
+pub mod transcript;
+pub mod usage_drift;
+pub mod warning;
 
+pub use capability_matrix::*;
+pub use citations::*;
+pub use code_execution::*;
+pub use computer_use::*;
 pub use conversation::*;
 pub use cost::*;
+pub use cost_tracker::*;
 pub use model::*;
+pub use model_graph::*;
+pub use models_list::*;
 pub use modelsdev::*;
+pub use prompt::*;
+pub use rate_limit::*;
+pub use redaction::*;
+pub use reproducibility::*;
 pub use response::*;
+pub use stats::*;
+pub use usage_drift::*;
+pub use warning::*;
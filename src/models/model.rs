@@ -1,7 +1,7 @@
 use crate::models::cost::Cost;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ModelCapabilities {
     #[serde(default = "default_true")]
     pub supports_user: bool,
@@ -12,6 +12,8 @@ pub struct ModelCapabilities {
     #[serde(default)]
     pub supports_vision: bool,
     #[serde(default)]
+    pub supports_video: bool,
+    #[serde(default)]
     pub supports_tools: bool,
     #[serde(default = "default_true")]
     pub supports_n: bool,
@@ -39,6 +41,17 @@ pub struct ModelCapabilities {
     pub supports_only_system: bool,
     #[serde(default = "default_true")]
     pub supports_only_assistant: bool,
+    #[serde(default)]
+    pub supports_prefill: bool,
+    #[serde(default)]
+    pub supports_developer_role: bool,
+    #[serde(default)]
+    pub supports_computer_use: bool,
+    #[serde(default)]
+    pub supports_min_tokens: bool,
+    /// OpenAI predicted outputs.
+    #[serde(default)]
+    pub supports_prediction: bool,
 }
 
 fn default_true() -> bool {
@@ -52,6 +65,7 @@ impl Default for ModelCapabilities {
             supports_repeating_roles: true,
             supports_streaming: true,
             supports_vision: false,
+            supports_video: false,
             supports_tools: false,
             supports_n: true,
             supports_system: true,
@@ -66,11 +80,16 @@ impl Default for ModelCapabilities {
             supports_temperature: true,
             supports_only_system: true,
             supports_only_assistant: true,
+            supports_prefill: false,
+            supports_developer_role: false,
+            supports_computer_use: false,
+            supports_min_tokens: false,
+            supports_prediction: false,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ModelProperties {
     #[serde(default)]
     pub open_source: bool,
@@ -82,18 +101,7 @@ pub struct ModelProperties {
     pub is_nsfw: bool,
 }
 
-impl Default for ModelProperties {
-    fn default() -> Self {
-        Self {
-            open_source: false,
-            chinese: false,
-            gdpr_compliant: false,
-            is_nsfw: false,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Model {
     pub name: String,
     pub vendor_name: String,
@@ -116,3 +124,46 @@ impl Model {
         format!("{}/{}/{}", self.provider_name, self.vendor_name, self.name)
     }
 }
+
+#[cfg(test)]
+impl Model {
+    /// An OpenAI model for unit tests: a 128k window, $1/$4 per million
+    /// tokens and default capabilities.
+    pub(crate) fn test(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            vendor_name: "openai".to_string(),
+            provider_name: "openai".to_string(),
+            cost: Cost::new(0.000001, 0.000004, 0.0),
+            context_length: 128_000,
+            completion_length: None,
+            capabilities: ModelCapabilities::default(),
+            properties: ModelProperties::default(),
+            knowledge_cutoff: None,
+            release_date: None,
+            last_updated: None,
+        }
+    }
+
+    /// Sets both the vendor and the provider.
+    pub(crate) fn with_provider(mut self, provider: &str) -> Self {
+        self.vendor_name = provider.to_string();
+        self.provider_name = provider.to_string();
+        self
+    }
+
+    pub(crate) fn with_context_length(mut self, context_length: u32) -> Self {
+        self.context_length = context_length;
+        self
+    }
+
+    pub(crate) fn with_cost(mut self, prompt: f64, completion: f64) -> Self {
+        self.cost = Cost::new(prompt, completion, 0.0);
+        self
+    }
+
+    pub(crate) fn with_capabilities(mut self, capabilities: ModelCapabilities) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+}
@@ -0,0 +1,291 @@
+use crate::error::Result;
+use crate::models::Model;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelRelation {
+    /// The same model with a larger context window, e.g. `gpt-4` -> `gpt-4-32k`.
+    LargerContextVariant,
+    /// A later release of the same line, e.g. `claude-3-5-sonnet` ->
+    /// `claude-3-7-sonnet`.
+    NewerVersion,
+    /// A smaller, cheaper tier of the same line, e.g. `gpt-4o` -> `gpt-4o-mini`.
+    CheaperVariant,
+}
+
+/// Why `Model::suggest_alternative` is asked for a replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlternativeReason {
+    /// The prompt needs at least `min_context` tokens of context.
+    ContextOverflow { min_context: u32 },
+    /// The model is deprecated or unavailable.
+    Outdated,
+    /// The model is too expensive for the request.
+    TooExpensive,
+}
+
+/// One overlay entry; models are referred to by `Model::get_path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelEdge {
+    pub from: String,
+    pub relation: ModelRelation,
+    pub to: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Overlay {
+    #[serde(default)]
+    relation: Vec<ModelEdge>,
+}
+
+/// Relationships between catalog models, inferred from their names and
+/// extended or corrected with an overlay.
+#[derive(Debug, Clone, Default)]
+pub struct ModelGraph {
+    models: HashMap<String, Model>,
+    edges: HashMap<String, Vec<(ModelRelation, String)>>,
+}
+
+/// A model name split into its line (`claude-sonnet`), version numbers
+/// (`[3, 5]`) and whatever marks a dated snapshot or context size.
+#[derive(Debug, PartialEq)]
+struct NameParts {
+    family: String,
+    version: Vec<u32>,
+}
+
+fn name_parts(name: &str) -> NameParts {
+    let mut family = Vec::new();
+    let mut version = Vec::new();
+    let name = name.to_lowercase();
+    for token in name.split(['-', '_']) {
+        let is_number = !token.is_empty() && token.chars().all(|c| c.is_ascii_digit() || c == '.');
+        let is_context = token.len() > 1
+            && (token.ends_with('k') || token.ends_with('m'))
+            && token[..token.len() - 1].chars().all(|c| c.is_ascii_digit());
+        if matches!(token, "latest" | "preview" | "exp") || is_context {
+            continue;
+        }
+        if is_number {
+            // Dated snapshots: 20240620, 2024, 0613.
+            if token.len() >= 4 && !token.contains('.') {
+                continue;
+            }
+            version.extend(token.split('.').filter_map(|part| part.parse::<u32>().ok()));
+        } else {
+            family.push(token);
+        }
+    }
+    NameParts {
+        family: family.join("-"),
+        version,
+    }
+}
+
+fn infer_relation(from: &Model, to: &Model) -> Option<ModelRelation> {
+    if from.provider_name != to.provider_name
+        || from.vendor_name != to.vendor_name
+        || from.name == to.name
+    {
+        return None;
+    }
+    let (a, b) = (name_parts(&from.name), name_parts(&to.name));
+    if a.family == b.family {
+        if a.version == b.version {
+            return (to.context_length > from.context_length)
+                .then_some(ModelRelation::LargerContextVariant);
+        }
+        return (b.version > a.version).then_some(ModelRelation::NewerVersion);
+    }
+    let tier_of = b
+        .family
+        .strip_prefix(a.family.as_str())
+        .is_some_and(|rest| rest.starts_with('-'));
+    let same_line = a.family.split('-').next() == b.family.split('-').next()
+        && !a.version.is_empty()
+        && a.version == b.version;
+    let cheaper = to.cost.prompt < from.cost.prompt && to.cost.completion <= from.cost.completion;
+    ((tier_of || same_line) && cheaper).then_some(ModelRelation::CheaperVariant)
+}
+
+impl ModelGraph {
+    /// Infers relations between every pair of `models` from the same
+    /// provider and vendor.
+    pub fn infer(models: &[Model]) -> Self {
+        let mut graph = Self {
+            models: models
+                .iter()
+                .map(|model| (model.get_path(), model.clone()))
+                .collect(),
+            edges: HashMap::new(),
+        };
+        for from in models {
+            for to in models {
+                if let Some(relation) = infer_relation(from, to) {
+                    graph.add(&from.get_path(), relation, &to.get_path());
+                }
+            }
+        }
+        graph
+    }
+
+    fn add(&mut self, from: &str, relation: ModelRelation, to: &str) {
+        let edges = self.edges.entry(from.to_string()).or_default();
+        if !edges
+            .iter()
+            .any(|(r, target)| *r == relation && target == to)
+        {
+            edges.push((relation, to.to_string()));
+        }
+    }
+
+    pub fn with_relation(
+        mut self,
+        from: impl Into<String>,
+        relation: ModelRelation,
+        to: impl Into<String>,
+    ) -> Self {
+        self.add(&from.into(), relation, &to.into());
+        self
+    }
+
+    /// Adds the `[[relation]]` entries of a TOML overlay:
+    ///
+    /// ```toml
+    /// [[relation]]
+    /// from = "openai/openai/gpt-4o"
+    /// relation = "newer_version"
+    /// to = "openai/openai/gpt-4.1"
+    /// ```
+    pub fn with_overlay(mut self, overlay: &str) -> Result<Self> {
+        let overlay: Overlay = toml::from_str(overlay)?;
+        for edge in overlay.relation {
+            self.add(&edge.from, edge.relation, &edge.to);
+        }
+        Ok(self)
+    }
+
+    /// Targets of `relation` from the model at `path` that are in the graph.
+    pub fn related(&self, path: &str, relation: ModelRelation) -> Vec<&Model> {
+        self.edges
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter(|(r, _)| *r == relation)
+            .filter_map(|(_, target)| self.models.get(target))
+            .collect()
+    }
+
+    pub fn edges(&self) -> Vec<ModelEdge> {
+        let mut edges: Vec<ModelEdge> = self
+            .edges
+            .iter()
+            .flat_map(|(from, targets)| {
+                targets.iter().map(move |(relation, to)| ModelEdge {
+                    from: from.clone(),
+                    relation: *relation,
+                    to: to.clone(),
+                })
+            })
+            .collect();
+        edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        edges
+    }
+}
+
+impl Model {
+    /// The best related model for `reason`: the smallest window that fits
+    /// for context overflows, the newest version when outdated and the
+    /// cheapest tier when too expensive.
+    pub fn suggest_alternative<'a>(
+        &self,
+        reason: AlternativeReason,
+        graph: &'a ModelGraph,
+    ) -> Option<&'a Model> {
+        let path = self.get_path();
+        match reason {
+            AlternativeReason::ContextOverflow { min_context } => {
+                let mut candidates = graph.related(&path, ModelRelation::LargerContextVariant);
+                candidates.extend(graph.related(&path, ModelRelation::NewerVersion));
+                candidates
+                    .into_iter()
+                    .filter(|model| model.context_length >= min_context)
+                    .min_by_key(|model| model.context_length)
+            }
+            AlternativeReason::Outdated => graph
+                .related(&path, ModelRelation::NewerVersion)
+                .into_iter()
+                .max_by(|a, b| {
+                    name_parts(&a.name)
+                        .version
+                        .cmp(&name_parts(&b.name).version)
+                        .then_with(|| a.release_date.cmp(&b.release_date))
+                }),
+            AlternativeReason::TooExpensive => graph
+                .related(&path, ModelRelation::CheaperVariant)
+                .into_iter()
+                .min_by(|a, b| a.cost.prompt.total_cmp(&b.cost.prompt)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(name: &str, context_length: u32, prompt: f64) -> Model {
+        Model::test(name)
+            .with_context_length(context_length)
+            .with_cost(prompt, prompt * 2.0)
+    }
+
+    #[test]
+    fn test_inferred_relations_and_suggestions() {
+        let models = vec![
+            model("gpt-4", 8192, 30.0),
+            model("gpt-4-32k", 32768, 60.0),
+            model("gpt-4.5-preview", 128_000, 75.0),
+            model("gpt-4o", 128_000, 2.5),
+            model("gpt-4o-mini", 128_000, 0.15),
+        ];
+        let graph = ModelGraph::infer(&models);
+        let gpt4 = &models[0];
+
+        let variant = gpt4
+            .suggest_alternative(
+                AlternativeReason::ContextOverflow {
+                    min_context: 16_000,
+                },
+                &graph,
+            )
+            .unwrap();
+        assert_eq!(variant.name, "gpt-4-32k");
+        let newer = gpt4
+            .suggest_alternative(AlternativeReason::Outdated, &graph)
+            .unwrap();
+        assert_eq!(newer.name, "gpt-4.5-preview");
+        let cheaper = models[3]
+            .suggest_alternative(AlternativeReason::TooExpensive, &graph)
+            .unwrap();
+        assert_eq!(cheaper.name, "gpt-4o-mini");
+
+        let graph = graph
+            .with_overlay(
+                r#"
+                [[relation]]
+                from = "openai/openai/gpt-4"
+                relation = "newer_version"
+                to = "openai/openai/gpt-4o"
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            graph
+                .related("openai/openai/gpt-4", ModelRelation::NewerVersion)
+                .len(),
+            2
+        );
+    }
+}
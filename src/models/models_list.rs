@@ -0,0 +1,90 @@
+use crate::models::Model;
+use crate::utils::days_from_civil;
+use serde::{Deserialize, Serialize};
+
+/// An entry of the OpenAI `/v1/models` response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIModel {
+    /// The model path, which is what proxied requests name as `model`.
+    pub id: String,
+    pub object: String,
+    /// Unix seconds of the release date, `0` when unknown.
+    pub created: u64,
+    pub owned_by: String,
+}
+
+/// The OpenAI `/v1/models` response body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenAIModelsList {
+    pub object: String,
+    pub data: Vec<OpenAIModel>,
+}
+
+/// `YYYY-MM-DD` or `YYYY-MM`, as in the models.dev catalog.
+fn release_timestamp(date: &str) -> Option<u64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next().map_or(Some(1), |day| day.parse().ok())?;
+    u64::try_from(days_from_civil(year, month, day)?.checked_mul(86_400)?).ok()
+}
+
+impl From<&Model> for OpenAIModel {
+    fn from(model: &Model) -> Self {
+        Self {
+            id: model.get_path(),
+            object: "model".to_string(),
+            created: model
+                .release_date
+                .as_deref()
+                .and_then(release_timestamp)
+                .unwrap_or(0),
+            owned_by: model.vendor_name.clone(),
+        }
+    }
+}
+
+impl OpenAIModelsList {
+    /// Sorted by id.
+    pub fn new(models: &[Model]) -> Self {
+        let mut data: Vec<OpenAIModel> = models.iter().map(OpenAIModel::from).collect();
+        data.sort_by(|a, b| a.id.cmp(&b.id));
+        Self {
+            object: "list".to_string(),
+            data,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_openai_models_list() {
+        let model = |name: &str, release_date: Option<&str>| Model {
+            release_date: release_date.map(str::to_string),
+            ..Model::test(name)
+                .with_provider("anthropic")
+                .with_context_length(200_000)
+        };
+        let list = OpenAIModelsList::new(&[
+            model("claude-sonnet-4", Some("2025-05-22")),
+            model("claude-3-haiku", Some("2024-03")),
+            model("claude-instant", None),
+        ]);
+        assert_eq!(
+            serde_json::to_value(&list.data[0]).unwrap(),
+            json!({
+                "id": "anthropic/anthropic/claude-3-haiku",
+                "object": "model",
+                "created": 1_709_251_200,
+                "owned_by": "anthropic",
+            })
+        );
+        assert_eq!(list.object, "list");
+        assert_eq!(list.data[1].created, 0);
+        assert_eq!(list.data[2].created, 1_747_872_000);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, TurnType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::RwLock;
+
+const SCHEME: &str = "prompt://";
+
+/// One version of a named prompt. `{{variable}}` placeholders are filled in
+/// by `render`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: u32,
+    pub template: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// What changed since the previous version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+}
+
+impl PromptTemplate {
+    pub fn new(name: impl Into<String>, version: u32, template: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version,
+            template: template.into(),
+            author: None,
+            changelog: None,
+        }
+    }
+
+    pub fn with_author(mut self, author: impl Into<String>) -> Self {
+        self.author = Some(author.into());
+        self
+    }
+
+    pub fn with_changelog(mut self, changelog: impl Into<String>) -> Self {
+        self.changelog = Some(changelog.into());
+        self
+    }
+
+    pub fn reference(&self) -> PromptReference {
+        PromptReference {
+            name: self.name.clone(),
+            version: Some(self.version),
+        }
+    }
+
+    /// The template with every `{{variable}}` replaced; a placeholder
+    /// without a value is an error.
+    pub fn render(&self, variables: &HashMap<String, String>) -> Result<String> {
+        let mut rendered = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let variable = rest[start + 2..start + end].trim();
+            let value = variables.get(variable).ok_or_else(|| {
+                AdapterError::ConfigError(format!(
+                    "prompt {}@{} needs variable {}",
+                    self.name, self.version, variable
+                ))
+            })?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(value);
+            rest = &rest[start + end + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+/// `prompt://name@version`, or `prompt://name` for the active version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PromptReference {
+    pub name: String,
+    pub version: Option<u32>,
+}
+
+impl PromptReference {
+    /// `None` for text that is not a prompt reference.
+    pub fn parse(reference: &str) -> Option<Self> {
+        let reference = reference.trim().strip_prefix(SCHEME)?;
+        let (name, version) = match reference.split_once('@') {
+            Some((name, version)) => (name, Some(version.parse().ok()?)),
+            None => (reference, None),
+        };
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(Self {
+            name: name.to_string(),
+            version,
+        })
+    }
+}
+
+impl fmt::Display for PromptReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "{}{}@{}", SCHEME, self.name, version),
+            None => write!(f, "{}{}", SCHEME, self.name),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PromptsFile {
+    #[serde(default)]
+    prompt: Vec<PromptTemplate>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    versions: BTreeMap<u32, PromptTemplate>,
+    /// Set by `rollback`; the latest version is active otherwise.
+    pinned: Option<u32>,
+}
+
+impl Entry {
+    fn active(&self) -> Option<&PromptTemplate> {
+        match self.pinned {
+            Some(version) => self.versions.get(&version),
+            None => self.versions.values().next_back(),
+        }
+    }
+}
+
+/// Named, versioned prompt templates. Versions are immutable once
+/// registered; `rollback` pins an older one as active until `unpin`, so
+/// profiles and routing rules that refer to `prompt://name` follow it while
+/// ones pinned to `prompt://name@version` keep theirs, e.g. for A/B tests.
+#[derive(Debug, Default)]
+pub struct PromptRegistry {
+    prompts: RwLock<HashMap<String, Entry>>,
+}
+
+fn unknown(reference: impl fmt::Display) -> AdapterError {
+    AdapterError::ConfigError(format!("unknown prompt {}", reference))
+}
+
+impl PromptRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the `[[prompt]]` entries of a TOML file:
+    ///
+    /// ```toml
+    /// [[prompt]]
+    /// name = "support"
+    /// version = 2
+    /// author = "ana"
+    /// changelog = "Ask for the order number first"
+    /// template = "You are a support agent for {{company}}."
+    /// ```
+    pub fn from_toml(prompts: &str) -> Result<Self> {
+        let file: PromptsFile = toml::from_str(prompts)?;
+        let registry = Self::new();
+        for prompt in file.prompt {
+            registry.register(prompt)?;
+        }
+        Ok(registry)
+    }
+
+    /// Adds a version; registering the same name and version twice is an
+    /// error.
+    pub fn register(&self, prompt: PromptTemplate) -> Result<()> {
+        let mut prompts = self.prompts.write().unwrap();
+        let entry = prompts.entry(prompt.name.clone()).or_default();
+        if entry.versions.contains_key(&prompt.version) {
+            return Err(AdapterError::ConfigError(format!(
+                "prompt {} is already registered",
+                prompt.reference()
+            )));
+        }
+        entry.versions.insert(prompt.version, prompt);
+        Ok(())
+    }
+
+    /// A specific version, or the active one when `version` is `None`.
+    pub fn get(&self, name: &str, version: Option<u32>) -> Option<PromptTemplate> {
+        let prompts = self.prompts.read().unwrap();
+        let entry = prompts.get(name)?;
+        match version {
+            Some(version) => entry.versions.get(&version),
+            None => entry.active(),
+        }
+        .cloned()
+    }
+
+    pub fn versions(&self, name: &str) -> Vec<PromptTemplate> {
+        self.prompts
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|entry| entry.versions.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Makes `version` the active version of `name`.
+    pub fn rollback(&self, name: &str, version: u32) -> Result<()> {
+        let mut prompts = self.prompts.write().unwrap();
+        let entry = prompts
+            .get_mut(name)
+            .filter(|entry| entry.versions.contains_key(&version))
+            .ok_or_else(|| {
+                unknown(PromptReference {
+                    name: name.to_string(),
+                    version: Some(version),
+                })
+            })?;
+        entry.pinned = Some(version);
+        Ok(())
+    }
+
+    /// Makes the latest version active again.
+    pub fn unpin(&self, name: &str) {
+        if let Some(entry) = self.prompts.write().unwrap().get_mut(name) {
+            entry.pinned = None;
+        }
+    }
+
+    pub fn resolve(&self, reference: &PromptReference) -> Result<PromptTemplate> {
+        self.get(&reference.name, reference.version)
+            .ok_or_else(|| unknown(reference))
+    }
+
+    /// Parses and resolves `prompt://name@version`.
+    pub fn resolve_str(&self, reference: &str) -> Result<PromptTemplate> {
+        let parsed = PromptReference::parse(reference).ok_or_else(|| {
+            AdapterError::ConfigError(format!("invalid prompt reference {}", reference))
+        })?;
+        self.resolve(&parsed)
+    }
+
+    /// Replaces every basic turn whose content is a prompt reference with
+    /// the rendered template.
+    pub fn resolve_conversation(
+        &self,
+        conversation: &Conversation,
+        variables: &HashMap<String, String>,
+    ) -> Result<Conversation> {
+        let mut resolved = conversation.clone();
+        for turn in &mut resolved.turns {
+            if let TurnType::Basic(turn) = turn {
+                if let Some(reference) = PromptReference::parse(&turn.content) {
+                    turn.content = self.resolve(&reference)?.render(variables)?;
+                }
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConversationRole, Turn};
+
+    #[test]
+    fn test_prompt_registry_versions_and_rollback() {
+        let registry = PromptRegistry::from_toml(
+            r#"
+            [[prompt]]
+            name = "support"
+            version = 1
+            template = "You help {{company}} customers."
+
+            [[prompt]]
+            name = "support"
+            version = 2
+            author = "ana"
+            changelog = "Ask for the order number"
+            template = "You help {{ company }} customers. Ask for the order number."
+            "#,
+        )
+        .unwrap();
+        assert!(registry
+            .register(PromptTemplate::new("support", 2, "duplicate"))
+            .is_err());
+        assert_eq!(registry.resolve_str("prompt://support").unwrap().version, 2);
+
+        let conversation = Conversation::with_turns(vec![TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: "prompt://support".to_string(),
+            name: None,
+            metadata: None,
+        })]);
+        let variables = HashMap::from([("company".to_string(), "Acme".to_string())]);
+        registry.rollback("support", 1).unwrap();
+        let resolved = registry
+            .resolve_conversation(&conversation, &variables)
+            .unwrap();
+        assert_eq!(resolved.turns[0].text(), "You help Acme customers.");
+        assert_eq!(
+            registry.resolve_str("prompt://support@2").unwrap().author,
+            Some("ana".to_string())
+        );
+
+        registry.unpin("support");
+        assert_eq!(registry.get("support", None).unwrap().version, 2);
+        assert!(registry.rollback("support", 9).is_err());
+        assert!(registry
+            .resolve_conversation(&conversation, &HashMap::new())
+            .is_err());
+        assert_eq!(PromptReference::parse("prompt://a@x"), None);
+    }
+}
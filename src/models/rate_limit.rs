@@ -0,0 +1,264 @@
+use crate::utils::days_from_civil;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Which budget a rate limit was hit on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitType {
+    Requests,
+    Tokens,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_remaining: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_reset: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_limit: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_remaining: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens_reset: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitInfo {
+    /// Reads OpenAI-style `x-ratelimit-*` and Anthropic `anthropic-ratelimit-*`
+    /// headers. Returns `None` when the response carried no limit headers.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let info = Self {
+            requests_limit: first_number(
+                headers,
+                &[
+                    "x-ratelimit-limit-requests",
+                    "anthropic-ratelimit-requests-limit",
+                ],
+            ),
+            requests_remaining: first_number(
+                headers,
+                &[
+                    "x-ratelimit-remaining-requests",
+                    "anthropic-ratelimit-requests-remaining",
+                ],
+            ),
+            requests_reset: first_reset(
+                headers,
+                &[
+                    "x-ratelimit-reset-requests",
+                    "anthropic-ratelimit-requests-reset",
+                ],
+            ),
+            tokens_limit: first_number(
+                headers,
+                &[
+                    "x-ratelimit-limit-tokens",
+                    "anthropic-ratelimit-tokens-limit",
+                ],
+            ),
+            tokens_remaining: first_number(
+                headers,
+                &[
+                    "x-ratelimit-remaining-tokens",
+                    "anthropic-ratelimit-tokens-remaining",
+                ],
+            ),
+            tokens_reset: first_reset(
+                headers,
+                &[
+                    "x-ratelimit-reset-tokens",
+                    "anthropic-ratelimit-tokens-reset",
+                ],
+            ),
+            retry_after: header_str(headers, "retry-after").and_then(parse_reset),
+        };
+
+        (info != Self::default()).then_some(info)
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.requests_remaining == Some(0) || self.tokens_remaining == Some(0)
+    }
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn first_number(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names
+        .iter()
+        .find_map(|name| header_str(headers, name)?.trim().parse().ok())
+}
+
+fn first_reset(headers: &HeaderMap, names: &[&str]) -> Option<Duration> {
+    names
+        .iter()
+        .find_map(|name| parse_reset(header_str(headers, name)?))
+}
+
+/// Accepts plain seconds (`"30"`), Go-style durations (`"6m0s"`, `"20ms"`)
+/// and RFC 3339 timestamps, which are converted to the time left from now.
+pub(crate) fn parse_reset(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).ok();
+    }
+    if let Some(duration) = parse_go_duration(value) {
+        return Some(duration);
+    }
+    let reset_at = parse_rfc3339(value)?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+    Some(reset_at.saturating_sub(now))
+}
+
+fn parse_go_duration(value: &str) -> Option<Duration> {
+    let mut total = 0.0;
+    let mut rest = value;
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_len].parse().ok()?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            "us" | "µs" => 0.000_001,
+            "ns" => 0.000_000_001,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+        total += number * factor;
+    }
+
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// Minimal RFC 3339 parser returning the offset from the UNIX epoch.
+fn parse_rfc3339(value: &str) -> Option<Duration> {
+    let (date, time) = value.split_once(['T', 't', ' '])?;
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: i64 = date_parts.next()?.parse().ok()?;
+    let day: i64 = date_parts.next()?.parse().ok()?;
+
+    let (clock, offset_secs) = if let Some(clock) = time.strip_suffix(['Z', 'z']) {
+        (clock, 0)
+    } else {
+        let split = time.rfind(['+', '-'])?;
+        let (clock, offset) = time.split_at(split);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+        if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+            return None;
+        }
+        (clock, sign * (hours * 3600 + minutes * 60))
+    };
+
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let second: f64 = clock_parts.next()?.parse().ok()?;
+    if !(0..=23).contains(&hour) || !(0..=59).contains(&minute) || !(0.0..61.0).contains(&second) {
+        return None;
+    }
+
+    let seconds = days_from_civil(year, month, day)?
+        .checked_mul(86_400)?
+        .checked_add(hour * 3600 + minute * 60 - offset_secs)?;
+    let whole = Duration::from_secs(u64::try_from(seconds).ok()?);
+    whole.checked_add(Duration::try_from_secs_f64(second).ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_openai_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-limit-requests",
+            HeaderValue::from_static("500"),
+        );
+        headers.insert(
+            "x-ratelimit-remaining-requests",
+            HeaderValue::from_static("499"),
+        );
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            HeaderValue::from_static("120ms"),
+        );
+        headers.insert(
+            "x-ratelimit-remaining-tokens",
+            HeaderValue::from_static("0"),
+        );
+        headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("6m0s"));
+
+        let info = RateLimitInfo::from_headers(&headers).unwrap();
+        assert_eq!(info.requests_limit, Some(500));
+        assert_eq!(info.requests_remaining, Some(499));
+        assert_eq!(info.requests_reset, Some(Duration::from_millis(120)));
+        assert_eq!(info.tokens_reset, Some(Duration::from_secs(360)));
+        assert!(info.is_exhausted());
+    }
+
+    #[test]
+    fn test_no_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        assert_eq!(RateLimitInfo::from_headers(&headers), None);
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        assert_eq!(
+            parse_rfc3339("2024-03-01T12:00:30Z"),
+            Some(Duration::from_secs(1_709_294_430))
+        );
+        assert_eq!(
+            parse_rfc3339("2024-03-01T14:00:30+02:00"),
+            Some(Duration::from_secs(1_709_294_430))
+        );
+        assert_eq!(parse_reset("2000-01-01T00:00:00Z"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_out_of_range_resets() {
+        assert_eq!(parse_reset("1e400"), None);
+        assert_eq!(parse_reset("-5"), None);
+        assert_eq!(parse_reset("NaN"), None);
+        assert_eq!(parse_reset("99999999999999999999h"), None);
+        assert_eq!(parse_reset("2020-01-01T00:00:-5Z"), None);
+        assert_eq!(parse_reset("2020-01-01T00:00:infZ"), None);
+        assert_eq!(parse_reset("9223372036854775807-01-01T00:00:00Z"), None);
+        assert_eq!(parse_reset("2020-01-01T99:00:00Z"), None);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("1e400"));
+        headers.insert(
+            "anthropic-ratelimit-requests-reset",
+            HeaderValue::from_static("2020-01-01T00:00:-5Z"),
+        );
+        assert_eq!(RateLimitInfo::from_headers(&headers), None);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::error::{AdapterError, Result};
+use crate::models::{ContentEntry, ContentEntryData, Conversation, ToolResultContent, TurnType};
+use regex::Regex;
+
+const COMMON_PII_PATTERNS: &[(&str, &str)] = &[
+    (r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}", "[email]"),
+    (r"\b(sk|pk|rk)-[A-Za-z0-9_-]{16,}\b", "[api-key]"),
+    (r"\b(?:\d[ -]?){13,16}\b", "[card-number]"),
+    (
+        r"\+?\d{1,3}[ .-]?\(?\d{2,4}\)?[ .-]?\d{3,4}[ .-]?\d{3,4}\b",
+        "[phone]",
+    ),
+];
+
+/// What `Conversation::redacted` removes. By default media is replaced with
+/// placeholders and tool outputs are cut to 2000 characters; regex patterns
+/// are opt-in.
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    pub replace_media: bool,
+    pub max_tool_output_chars: Option<usize>,
+    pub patterns: Vec<(Regex, String)>,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            replace_media: true,
+            max_tool_output_chars: Some(2000),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_pattern(mut self, pattern: &str, replacement: impl Into<String>) -> Result<Self> {
+        let regex = Regex::new(pattern).map_err(|err| {
+            AdapterError::ConfigError(format!("invalid redaction pattern: {}", err))
+        })?;
+        self.patterns.push((regex, replacement.into()));
+        Ok(self)
+    }
+
+    /// Adds patterns for e-mail addresses, API keys, card and phone numbers.
+    pub fn with_common_pii(mut self) -> Self {
+        for (pattern, replacement) in COMMON_PII_PATTERNS {
+            let regex = Regex::new(pattern).expect("built-in redaction pattern must compile");
+            self.patterns.push((regex, replacement.to_string()));
+        }
+        self
+    }
+
+    pub fn with_max_tool_output_chars(mut self, max: Option<usize>) -> Self {
+        self.max_tool_output_chars = max;
+        self
+    }
+
+    pub fn keep_media(mut self) -> Self {
+        self.replace_media = false;
+        self
+    }
+
+    pub fn redact_text(&self, text: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(text.to_string(), |text, (regex, replacement)| {
+                regex.replace_all(&text, replacement.as_str()).into_owned()
+            })
+    }
+
+    fn redact_entry(&self, entry: &ContentEntry) -> ContentEntry {
+        let placeholder = match &entry.data {
+            ContentEntryData::Text { text } => return ContentEntry::text(self.redact_text(text)),
+            _ if !self.replace_media => return entry.clone(),
+            ContentEntryData::Image { .. } => "[image]",
+            ContentEntryData::Video { .. } => "[video]",
+            ContentEntryData::File { .. } => "[file]",
+        };
+        ContentEntry::text(placeholder)
+    }
+
+    fn redact_tool_output(&self, output: &ToolResultContent) -> ToolResultContent {
+        let output = match output {
+            ToolResultContent::Text(text) => ToolResultContent::Text(self.redact_text(text)),
+            ToolResultContent::Json(value) => {
+                let redacted = self.redact_text(&value.to_string());
+                serde_json::from_str(&redacted)
+                    .map(ToolResultContent::Json)
+                    .unwrap_or(ToolResultContent::Text(redacted))
+            }
+            ToolResultContent::Blocks(entries) => ToolResultContent::Blocks(
+                entries
+                    .iter()
+                    .map(|entry| self.redact_entry(entry))
+                    .collect(),
+            ),
+        };
+
+        let Some(max) = self.max_tool_output_chars else {
+            return output;
+        };
+        let text = output.to_text();
+        let total = text.chars().count();
+        if total <= max {
+            return output;
+        }
+        let kept: String = text.chars().take(max).collect();
+        ToolResultContent::Text(format!(
+            "{}... [{} characters truncated]",
+            kept,
+            total - max
+        ))
+    }
+
+    fn redact_turn(&self, turn: &TurnType) -> TurnType {
+        match turn {
+            TurnType::Basic(turn) => {
+                let mut turn = turn.clone();
+                turn.content = self.redact_text(&turn.content);
+                TurnType::Basic(turn)
+            }
+            TurnType::Content(turn) => {
+                let mut turn = turn.clone();
+                turn.content = turn
+                    .content
+                    .iter()
+                    .map(|entry| self.redact_entry(entry))
+                    .collect();
+                TurnType::Content(turn)
+            }
+            TurnType::ToolCalls {
+                role,
+                content,
+                tool_calls,
+                metadata,
+            } => TurnType::ToolCalls {
+                role: role.clone(),
+                content: content.as_deref().map(|content| self.redact_text(content)),
+                tool_calls: tool_calls
+                    .iter()
+                    .cloned()
+                    .map(|mut call| {
+                        call.function.arguments = self.redact_text(&call.function.arguments);
+                        call
+                    })
+                    .collect(),
+                metadata: metadata.clone(),
+            },
+            TurnType::ToolOutput {
+                role,
+                content,
+                tool_call_id,
+                metadata,
+            } => TurnType::ToolOutput {
+                role: role.clone(),
+                content: content
+                    .as_ref()
+                    .map(|output| self.redact_tool_output(output)),
+                tool_call_id: tool_call_id.clone(),
+                metadata: metadata.clone(),
+            },
+        }
+    }
+}
+
+impl Conversation {
+    /// A copy that is safe to log or attach to bug reports.
+    pub fn redacted(&self, policy: &RedactionPolicy) -> Conversation {
+        Conversation::with_turns(
+            self.turns
+                .iter()
+                .map(|turn| policy.redact_turn(turn))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentTurn, ConversationRole, ImageUrl, Turn};
+
+    #[test]
+    fn test_redacted_conversation() {
+        let conversation = Conversation::with_turns(vec![
+            TurnType::Content(ContentTurn {
+                role: ConversationRole::User,
+                content: vec![
+                    ContentEntry::text("Mail jane.doe@example.com about this"),
+                    ContentEntry {
+                        entry_type: "image_url".to_string(),
+                        data: ContentEntryData::Image {
+                            image_url: ImageUrl {
+                                url: "data:image/png;base64,AAAA".to_string(),
+                                detail: None,
+                            },
+                        },
+                    },
+                ],
+                name: None,
+                metadata: None,
+            }),
+            TurnType::ToolOutput {
+                role: ConversationRole::Tool,
+                content: Some(ToolResultContent::Text("x".repeat(50))),
+                tool_call_id: "call_1".to_string(),
+                metadata: None,
+            },
+            TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: "Use key sk-abcdefghijklmnopqrstuv".to_string(),
+                name: None,
+                metadata: None,
+            }),
+        ]);
+
+        let policy = RedactionPolicy::new()
+            .with_common_pii()
+            .with_max_tool_output_chars(Some(10));
+        let redacted = conversation.redacted(&policy);
+
+        let TurnType::Content(turn) = &redacted.turns[0] else {
+            panic!("expected a content turn");
+        };
+        assert_eq!(
+            turn.content[0],
+            ContentEntry::text("Mail [email] about this")
+        );
+        assert_eq!(turn.content[1], ContentEntry::text("[image]"));
+        assert_eq!(
+            redacted.turns[1].text(),
+            "xxxxxxxxxx... [40 characters truncated]"
+        );
+        assert_eq!(redacted.turns[2].text(), "Use key [api-key]");
+    }
+}
@@ -0,0 +1,115 @@
+use crate::utils::stable_hash;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Everything that determined a seeded completion, attached to the
+/// response so the experiment can be rerun and compared later. Providers
+/// only promise best-effort determinism while `system_fingerprint` stays
+/// the same, so that is recorded alongside the model version they served.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReproducibilityRecord {
+    pub provider: String,
+    /// The model asked for, as `Model::get_path`.
+    pub model: String,
+    /// The model the provider reports having served, e.g. a dated snapshot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+    pub seed: u64,
+    /// The request body as sent, without the messages.
+    pub parameters: Map<String, Value>,
+    /// `stable_hash` of the messages.
+    pub prompt_hash: u64,
+}
+
+impl ReproducibilityRecord {
+    /// Records the effective parameters of `body`, whose messages are
+    /// under `messages_field`.
+    pub fn new(
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        seed: u64,
+        body: &Value,
+        messages_field: &str,
+    ) -> Self {
+        let mut parameters = body.as_object().cloned().unwrap_or_default();
+        let messages = parameters.remove(messages_field).unwrap_or(Value::Null);
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+            model_version: None,
+            system_fingerprint: None,
+            seed,
+            parameters,
+            prompt_hash: stable_hash(&messages),
+        }
+    }
+
+    /// Names of the fields, and of the parameters as `parameters.<name>`,
+    /// that differ from `other`. Empty when a rerun should reproduce the
+    /// same output.
+    pub fn differences(&self, other: &ReproducibilityRecord) -> Vec<String> {
+        let mut differences = Vec::new();
+        let fields = [
+            ("provider", self.provider == other.provider),
+            ("model", self.model == other.model),
+            ("model_version", self.model_version == other.model_version),
+            (
+                "system_fingerprint",
+                self.system_fingerprint == other.system_fingerprint,
+            ),
+            ("seed", self.seed == other.seed),
+            ("prompt_hash", self.prompt_hash == other.prompt_hash),
+        ];
+        for (name, same) in fields {
+            if !same {
+                differences.push(name.to_string());
+            }
+        }
+        let mut names: Vec<&String> = self
+            .parameters
+            .keys()
+            .chain(other.parameters.keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        for name in names {
+            if self.parameters.get(name) != other.parameters.get(name) {
+                differences.push(format!("parameters.{}", name));
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reproducibility_differences() {
+        let body = json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "seed": 7,
+            "temperature": 0.2
+        });
+        let mut first =
+            ReproducibilityRecord::new("openai", "openai/openai/gpt-4o", 7, &body, "messages");
+        first.system_fingerprint = Some("fp_1".to_string());
+        assert!(!first.parameters.contains_key("messages"));
+        assert!(first.differences(&first.clone()).is_empty());
+
+        let mut body = body;
+        body["temperature"] = json!(0.7);
+        let mut second =
+            ReproducibilityRecord::new("openai", "openai/openai/gpt-4o", 7, &body, "messages");
+        second.system_fingerprint = Some("fp_2".to_string());
+        assert_eq!(
+            first.differences(&second),
+            vec!["system_fingerprint", "parameters.temperature"]
+        );
+    }
+}
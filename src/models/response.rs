@@ -1,7 +1,10 @@
-use crate::models::{ConversationRole, TokenUsage, ToolCall};
+use crate::models::{
+    AdapterWarning, Citations, CodeExecution, ComputerCall, ConversationRole, Cost, CostBreakdown,
+    RateLimitInfo, ReproducibilityRecord, TokenUsage, ToolCall,
+};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AdapterChatCompletion {
     pub id: String,
     pub object: String,
@@ -10,24 +13,94 @@ pub struct AdapterChatCompletion {
     pub choices: Vec<Choice>,
     pub usage: Option<TokenUsage>,
     pub cost: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_breakdown: Option<CostBreakdown>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitInfo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<AdapterWarning>,
+    /// Set on seeded requests, see `ReproducibilityRecord`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reproducibility: Option<ReproducibilityRecord>,
+    /// The processing tier the provider reports, for checking billing
+    /// against `ExecuteOptions::service_tier`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl AdapterChatCompletion {
+    pub fn apply_cost(&mut self, cost: &Cost) {
+        if let Some(usage) = &self.usage {
+            let breakdown = cost.breakdown(usage);
+            self.cost = breakdown.total;
+            self.cost_breakdown = Some(breakdown);
+        }
+    }
+
+    /// Appends the choices of `other`, renumbered after the existing ones,
+    /// and adds up usage, cost and warnings.
+    pub fn absorb(&mut self, other: AdapterChatCompletion) {
+        let offset = self.choices.len() as u32;
+        self.choices
+            .extend(other.choices.into_iter().map(|choice| Choice {
+                index: choice.index + offset,
+                ..choice
+            }));
+        if let Some(usage) = &other.usage {
+            match &mut self.usage {
+                Some(total) => total.accumulate(usage),
+                None => self.usage = Some(usage.clone()),
+            }
+        }
+        self.cost += other.cost;
+        if let Some(breakdown) = &other.cost_breakdown {
+            self.cost_breakdown
+                .get_or_insert_with(CostBreakdown::default)
+                .accumulate(breakdown);
+        }
+        for warning in other.warnings {
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Choice {
     pub index: u32,
     pub message: Message,
     pub finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub citations: Option<Citations>,
+}
+
+impl Choice {
+    pub fn is_refusal(&self) -> bool {
+        self.message.refusal.is_some()
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.finish_reason.as_deref() == Some("length")
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Message {
     pub role: ConversationRole,
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refusal: Option<String>,
+    /// Code run by a provider-side code interpreter while answering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_executions: Option<Vec<CodeExecution>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub computer_calls: Option<Vec<ComputerCall>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AdapterChatCompletionChunk {
     pub id: String,
     pub object: String,
@@ -36,19 +109,40 @@ pub struct AdapterChatCompletionChunk {
     pub choices: Vec<ChunkChoice>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChunkChoice {
     pub index: u32,
     pub delta: Delta,
     pub finish_reason: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Delta {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub role: Option<ConversationRole>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+/// A fragment of a streamed tool call. Only the first fragment of a call
+/// carries `id` and the function name; later ones append to `arguments`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallDelta {
+    pub index: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub call_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCallDelta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
 }
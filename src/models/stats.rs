@@ -0,0 +1,106 @@
+use crate::models::{Conversation, Model, TurnType};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A snapshot of how much of a model's context a conversation uses. Token
+/// counts are estimates, see `TurnType::estimated_tokens`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConversationStats {
+    pub turns: usize,
+    /// Number of turns per role, keyed by the role's wire name.
+    pub roles: BTreeMap<String, usize>,
+    pub tokens_per_turn: Vec<u32>,
+    pub estimated_tokens: u32,
+    pub image_count: usize,
+    pub tool_call_count: usize,
+    /// Serialized size of the conversation as sent to a provider.
+    pub size_bytes: usize,
+    pub context_length: u32,
+    pub remaining_tokens: u32,
+    /// `estimated_tokens / context_length`, may exceed 1.0.
+    pub context_usage: f64,
+}
+
+impl Conversation {
+    pub fn stats(&self, model: &Model) -> ConversationStats {
+        let mut roles = BTreeMap::new();
+        let mut image_count = 0;
+        let mut tool_call_count = 0;
+        let mut tokens_per_turn = Vec::with_capacity(self.turns.len());
+        for turn in &self.turns {
+            *roles.entry(turn.role().to_string()).or_insert(0) += 1;
+            image_count += turn.image_count();
+            if let TurnType::ToolCalls { tool_calls, .. } = turn {
+                tool_call_count += tool_calls.len();
+            }
+            tokens_per_turn.push(turn.estimated_tokens());
+        }
+
+        let estimated_tokens: u32 = tokens_per_turn.iter().sum();
+        let size_bytes = serde_json::to_vec(&self.without_metadata())
+            .map(|bytes| bytes.len())
+            .unwrap_or(0);
+        let context_usage = if model.context_length == 0 {
+            0.0
+        } else {
+            estimated_tokens as f64 / model.context_length as f64
+        };
+
+        ConversationStats {
+            turns: self.turns.len(),
+            roles,
+            tokens_per_turn,
+            estimated_tokens,
+            image_count,
+            tool_call_count,
+            size_bytes,
+            context_length: model.context_length,
+            remaining_tokens: model.context_length.saturating_sub(estimated_tokens),
+            context_usage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConversationRole, FunctionCall, ToolCall, Turn};
+
+    #[test]
+    fn test_conversation_stats() {
+        let model = Model::test("test-model").with_context_length(1000);
+        let conversation = Conversation::with_turns(vec![
+            TurnType::Basic(Turn {
+                role: ConversationRole::User,
+                content: "What is the weather in Paris?".to_string(),
+                name: None,
+                metadata: None,
+            }),
+            TurnType::ToolCalls {
+                role: ConversationRole::Assistant,
+                content: None,
+                tool_calls: vec![ToolCall {
+                    id: "call_1".to_string(),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "weather".to_string(),
+                        arguments: r#"{"city":"Paris"}"#.to_string(),
+                    },
+                }],
+                metadata: None,
+            },
+        ]);
+
+        let stats = conversation.stats(&model);
+        assert_eq!(stats.turns, 2);
+        assert_eq!(stats.roles["user"], 1);
+        assert_eq!(stats.roles["assistant"], 1);
+        assert_eq!(stats.tool_call_count, 1);
+        assert_eq!(stats.image_count, 0);
+        assert_eq!(stats.estimated_tokens, conversation.estimated_tokens());
+        assert_eq!(stats.tokens_per_turn.len(), 2);
+        assert_eq!(stats.remaining_tokens, 1000 - stats.estimated_tokens);
+        assert!(stats.size_bytes > 0);
+        assert!(stats.context_usage > 0.0 && stats.context_usage < 1.0);
+    }
+}
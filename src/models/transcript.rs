@@ -0,0 +1,242 @@
+use crate::models::{
+    ContentEntry, ContentEntryData, Conversation, ConversationRole, ToolResultContent, TurnType,
+};
+use serde_json::Value;
+
+const THUMBNAIL_WIDTH: u32 = 240;
+
+enum Block {
+    Text(String),
+    Image(String),
+    Attachment(String),
+    /// Tool arguments or output, shown collapsed.
+    Payload {
+        summary: String,
+        body: String,
+    },
+}
+
+fn pretty(raw: &str) -> String {
+    serde_json::from_str::<Value>(raw)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| raw.to_string())
+}
+
+fn entry_blocks(entries: &[ContentEntry]) -> Vec<Block> {
+    entries
+        .iter()
+        .map(|entry| match &entry.data {
+            ContentEntryData::Text { text } => Block::Text(text.clone()),
+            ContentEntryData::Image { image_url } => Block::Image(image_url.url.clone()),
+            ContentEntryData::Video { video } => Block::Attachment(format!(
+                "video ({})",
+                video.uri.as_deref().unwrap_or(&video.mime_type)
+            )),
+            ContentEntryData::File { file } => Block::Attachment(format!(
+                "file {}",
+                file.filename.as_deref().unwrap_or(&file.id)
+            )),
+        })
+        .collect()
+}
+
+fn turn_blocks(turn: &TurnType) -> (String, Vec<Block>) {
+    let heading = |role: &ConversationRole| {
+        let role = role.to_string();
+        let mut chars = role.chars();
+        chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    };
+    match turn {
+        TurnType::Basic(turn) => (heading(&turn.role), vec![Block::Text(turn.content.clone())]),
+        TurnType::Content(turn) => (heading(&turn.role), entry_blocks(&turn.content)),
+        TurnType::ToolCalls {
+            role,
+            content,
+            tool_calls,
+            ..
+        } => {
+            let mut blocks: Vec<Block> = content.iter().cloned().map(Block::Text).collect();
+            blocks.extend(tool_calls.iter().map(|call| Block::Payload {
+                summary: format!("Tool call: {} ({})", call.function.name, call.id),
+                body: pretty(&call.function.arguments),
+            }));
+            (heading(role), blocks)
+        }
+        TurnType::ToolOutput {
+            role,
+            content,
+            tool_call_id,
+            ..
+        } => {
+            let mut blocks = Vec::new();
+            let summary = format!("Tool result ({})", tool_call_id);
+            match content {
+                Some(ToolResultContent::Blocks(entries)) => {
+                    let entries = entry_blocks(entries);
+                    let (images, rest): (Vec<_>, Vec<_>) = entries
+                        .into_iter()
+                        .partition(|block| matches!(block, Block::Image(_)));
+                    let body = rest
+                        .into_iter()
+                        .filter_map(|block| match block {
+                            Block::Text(text) | Block::Attachment(text) => Some(text),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    blocks.push(Block::Payload { summary, body });
+                    blocks.extend(images);
+                }
+                Some(content) => blocks.push(Block::Payload {
+                    summary,
+                    body: pretty(&content.to_text()),
+                }),
+                None => blocks.push(Block::Payload {
+                    summary,
+                    body: String::new(),
+                }),
+            }
+            (heading(role), blocks)
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Conversation {
+    /// A readable transcript with one section per turn. Tool arguments and
+    /// outputs are collapsed and images are inlined as thumbnails.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        for turn in &self.turns {
+            let (heading, blocks) = turn_blocks(turn);
+            out.push_str(&format!("### {}\n\n", heading));
+            for block in blocks {
+                match block {
+                    Block::Text(text) => out.push_str(&format!("{}\n\n", text)),
+                    Block::Image(url) => out.push_str(&format!(
+                        "<img src=\"{}\" width=\"{}\" alt=\"image\">\n\n",
+                        escape_html(&url),
+                        THUMBNAIL_WIDTH
+                    )),
+                    Block::Attachment(name) => out.push_str(&format!("_[{}]_\n\n", name)),
+                    Block::Payload { summary, body } => out.push_str(&format!(
+                        "<details><summary>{}</summary>\n\n```\n{}\n```\n\n</details>\n\n",
+                        escape_html(&summary),
+                        body
+                    )),
+                }
+            }
+        }
+        out.trim_end().to_string() + "\n"
+    }
+
+    /// A standalone HTML page with the same layout as `to_markdown`.
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Conversation</title>\n\
+             <style>body{font-family:sans-serif;max-width:48rem;margin:auto}\
+             section{border-bottom:1px solid #ddd;padding:.5rem 0}\
+             pre{background:#f6f6f6;padding:.5rem;overflow-x:auto}</style>\n</head>\n<body>\n",
+        );
+        for turn in &self.turns {
+            let (heading, blocks) = turn_blocks(turn);
+            out.push_str(&format!(
+                "<section class=\"{}\">\n<h3>{}</h3>\n",
+                heading.to_lowercase(),
+                heading
+            ));
+            for block in blocks {
+                match block {
+                    Block::Text(text) => out.push_str(&format!(
+                        "<p>{}</p>\n",
+                        escape_html(&text).replace('\n', "<br>\n")
+                    )),
+                    Block::Image(url) => out.push_str(&format!(
+                        "<img src=\"{}\" width=\"{}\" alt=\"image\">\n",
+                        escape_html(&url),
+                        THUMBNAIL_WIDTH
+                    )),
+                    Block::Attachment(name) => {
+                        out.push_str(&format!("<p><em>[{}]</em></p>\n", escape_html(&name)))
+                    }
+                    Block::Payload { summary, body } => out.push_str(&format!(
+                        "<details><summary>{}</summary>\n<pre>{}</pre>\n</details>\n",
+                        escape_html(&summary),
+                        escape_html(&body)
+                    )),
+                }
+            }
+            out.push_str("</section>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentTurn, ImageUrl, ToolCall, Turn};
+
+    #[test]
+    fn test_transcript_exports() {
+        let conversation = Conversation::with_turns(vec![
+            TurnType::Content(ContentTurn {
+                role: ConversationRole::User,
+                content: vec![
+                    ContentEntry::text("What is <this>?"),
+                    ContentEntry::image(ImageUrl {
+                        url: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                        detail: None,
+                    }),
+                ],
+                name: None,
+                metadata: None,
+            }),
+            TurnType::ToolCalls {
+                role: ConversationRole::Assistant,
+                content: None,
+                tool_calls: vec![ToolCall::function(
+                    "call_1",
+                    "lookup",
+                    r#"{"q":"logo"}"#.to_string(),
+                )],
+                metadata: None,
+            },
+            TurnType::ToolOutput {
+                role: ConversationRole::Tool,
+                content: Some(ToolResultContent::Text("a logo".to_string())),
+                tool_call_id: "call_1".to_string(),
+                metadata: None,
+            },
+            TurnType::Basic(Turn {
+                role: ConversationRole::Assistant,
+                content: "It is a logo.".to_string(),
+                name: None,
+                metadata: None,
+            }),
+        ]);
+
+        let markdown = conversation.to_markdown();
+        assert!(markdown.starts_with("### User\n\nWhat is <this>?"));
+        assert!(markdown.contains("<img src=\"data:image/png;base64,iVBORw0KGgo=\""));
+        assert!(markdown.contains("<summary>Tool call: lookup (call_1)</summary>"));
+        assert!(markdown.contains("\"q\": \"logo\""));
+        assert!(markdown.ends_with("### Assistant\n\nIt is a logo.\n"));
+
+        let html = conversation.to_html();
+        assert!(html.contains("<p>What is &lt;this&gt;?</p>"));
+        assert!(html.contains("<section class=\"tool\">"));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
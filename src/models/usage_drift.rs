@@ -0,0 +1,151 @@
+use crate::models::{AdapterChatCompletion, AdapterWarning, Conversation};
+use crate::utils::estimate_tokens;
+use serde::{Deserialize, Serialize};
+
+/// Provider-reported usage next to the local estimates. Drift is the
+/// relative difference, `reported / estimated - 1`, so `0.5` means the
+/// provider counted 50% more tokens than expected.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UsageDrift {
+    pub reported_prompt: u32,
+    pub estimated_prompt: u32,
+    pub reported_completion: u32,
+    pub estimated_completion: u32,
+    pub prompt_drift: f64,
+    pub completion_drift: f64,
+}
+
+fn drift(reported: u32, estimated: u32) -> f64 {
+    reported as f64 / estimated.max(1) as f64 - 1.0
+}
+
+impl UsageDrift {
+    pub fn new(
+        reported_prompt: u32,
+        estimated_prompt: u32,
+        reported_completion: u32,
+        estimated_completion: u32,
+    ) -> Self {
+        Self {
+            reported_prompt,
+            estimated_prompt,
+            reported_completion,
+            estimated_completion,
+            prompt_drift: drift(reported_prompt, estimated_prompt),
+            completion_drift: drift(reported_completion, estimated_completion),
+        }
+    }
+
+    /// The larger of the two drifts, in either direction.
+    pub fn max_drift(&self) -> f64 {
+        self.prompt_drift.abs().max(self.completion_drift.abs())
+    }
+
+    pub fn exceeds(&self, threshold: f64) -> bool {
+        self.max_drift() > threshold
+    }
+}
+
+impl AdapterChatCompletion {
+    /// Compares the reported usage with local estimates for `conversation`
+    /// and the returned choices. `None` when the provider reported no usage.
+    pub fn usage_drift(&self, conversation: &Conversation) -> Option<UsageDrift> {
+        let usage = self.usage.as_ref()?;
+        let estimated_completion = self
+            .choices
+            .iter()
+            .map(|choice| {
+                let message = &choice.message;
+                let arguments: u32 = message
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|call| estimate_tokens(&call.function.arguments))
+                    .sum();
+                estimate_tokens(message.content.as_deref().unwrap_or_default()) + arguments
+            })
+            .sum();
+        Some(UsageDrift::new(
+            usage.prompt_tokens,
+            conversation.estimated_tokens(),
+            usage.completion_tokens,
+            estimated_completion,
+        ))
+    }
+
+    /// `usage_drift`, recording an `AdapterWarning::UsageDrift` when it
+    /// exceeds `threshold`.
+    pub fn reconcile_usage(
+        &mut self,
+        conversation: &Conversation,
+        threshold: f64,
+    ) -> Option<UsageDrift> {
+        let drift = self.usage_drift(conversation)?;
+        if drift.exceeds(threshold) {
+            self.warnings.push(AdapterWarning::UsageDrift {
+                model: self.model.clone(),
+                drift,
+            });
+        }
+        Some(drift)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Choice, ConversationRole, Message, TokenUsage, Turn, TurnType};
+
+    #[test]
+    fn test_usage_drift() {
+        let conversation = Conversation::with_turns(vec![TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: "word ".repeat(40),
+            name: None,
+            metadata: None,
+        })]);
+        let mut response = AdapterChatCompletion {
+            id: "chatcmpl-1".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "tiny-1".to_string(),
+            choices: vec![Choice {
+                index: 0,
+                message: Message {
+                    role: ConversationRole::Assistant,
+                    content: Some("a".repeat(40)),
+                    tool_calls: None,
+                    refusal: None,
+                    code_executions: None,
+                    computer_calls: None,
+                },
+                finish_reason: Some("stop".to_string()),
+                citations: None,
+            }],
+            usage: Some(TokenUsage::new(54, 30)),
+            cost: 0.0,
+            cost_breakdown: None,
+            rate_limit: None,
+            warnings: Vec::new(),
+            reproducibility: None,
+            service_tier: None,
+        };
+
+        let drift = response.reconcile_usage(&conversation, 0.5).unwrap();
+        assert_eq!(drift.estimated_prompt, 54);
+        assert_eq!(drift.prompt_drift, 0.0);
+        assert_eq!(drift.completion_drift, 2.0);
+        assert!(matches!(
+            response.warnings[..],
+            [AdapterWarning::UsageDrift { .. }]
+        ));
+
+        response.warnings.clear();
+        response.usage = Some(TokenUsage::new(54, 11));
+        assert!(!response
+            .reconcile_usage(&conversation, 0.5)
+            .unwrap()
+            .exceeds(0.5));
+        assert!(response.warnings.is_empty());
+    }
+}
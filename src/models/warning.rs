@@ -0,0 +1,35 @@
+use crate::models::UsageDrift;
+use serde::{Deserialize, Serialize};
+
+/// Something an adapter changed on the caller's behalf, reported on the
+/// response instead of happening silently.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AdapterWarning {
+    /// A request parameter the model does not support was removed.
+    DroppedParameter {
+        parameter: String,
+        model: String,
+        reason: String,
+    },
+    /// The oldest turns were removed to fit the context window.
+    TruncatedConversation {
+        model: String,
+        dropped_turns: usize,
+        /// Estimated prompt tokens before and after truncation.
+        tokens_before: u32,
+        tokens_after: u32,
+    },
+    /// A feature the model lacks, e.g. `n`, was provided by the adapter
+    /// instead, with `detail` describing how.
+    EmulatedFeature {
+        feature: String,
+        model: String,
+        detail: String,
+    },
+    /// A JSON-mode reply that did not parse was repaired.
+    RepairedJson { choice_index: u32, repair: String },
+    /// Reported usage is far from the local token estimates, which points
+    /// at a tokenizer mismatch or a billing anomaly.
+    UsageDrift { model: String, drift: UsageDrift },
+}
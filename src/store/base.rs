@@ -0,0 +1,13 @@
+use crate::error::Result;
+use crate::models::Conversation;
+use async_trait::async_trait;
+
+/// Persistence for conversations keyed by an opaque id (a thread or session).
+#[async_trait]
+pub trait ConversationStore: Send + Sync {
+    async fn load(&self, id: &str) -> Result<Option<Conversation>>;
+
+    async fn save(&self, id: &str, conversation: &Conversation) -> Result<()>;
+
+    async fn delete(&self, id: &str) -> Result<()>;
+}
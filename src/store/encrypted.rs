@@ -0,0 +1,334 @@
+use crate::error::{AdapterError, Result};
+use crate::models::{Conversation, ConversationRole, Turn, TurnType};
+use crate::store::ConversationStore;
+use async_trait::async_trait;
+use base64::{engine::general_purpose, Engine as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+const ENVELOPE_KEY: &str = "encryption";
+const ALGORITHM: &str = "AES-256-GCM";
+
+/// Supplies the keys `EncryptedConversationStore` encrypts with, e.g. from
+/// a KMS or secret manager. Keys are looked up by id so they can be
+/// rotated: new records use `current_key_id`, older ones keep the id they
+/// were written with.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    async fn current_key_id(&self) -> Result<String>;
+
+    /// The 256-bit key with `id`.
+    async fn key(&self, id: &str) -> Result<[u8; 32]>;
+}
+
+/// A single fixed key.
+pub struct StaticKeyProvider {
+    id: String,
+    key: [u8; 32],
+}
+
+impl StaticKeyProvider {
+    pub fn new(id: impl Into<String>, key: [u8; 32]) -> Self {
+        Self { id: id.into(), key }
+    }
+}
+
+impl fmt::Debug for StaticKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaticKeyProvider")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl KeyProvider for StaticKeyProvider {
+    async fn current_key_id(&self) -> Result<String> {
+        Ok(self.id.clone())
+    }
+
+    async fn key(&self, id: &str) -> Result<[u8; 32]> {
+        if id != self.id {
+            return Err(AdapterError::EncryptionError(format!("unknown key {}", id)));
+        }
+        Ok(self.key)
+    }
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key)
+        .map_err(|_| AdapterError::EncryptionError("invalid key".to_string()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Wraps a store and encrypts conversations with AES-256-GCM before they
+/// reach it. The inner store only sees an envelope: a single system turn
+/// whose content is the ciphertext and whose metadata holds the key id and
+/// the plaintext metadata fields chosen with `with_plaintext_metadata`, so
+/// records stay searchable without being decrypted. The conversation id,
+/// key id, algorithm and plaintext metadata are authenticated, so an
+/// envelope copied to another id or with edited metadata fails to decrypt.
+pub struct EncryptedConversationStore<S> {
+    inner: S,
+    keys: Arc<dyn KeyProvider>,
+    plaintext_metadata: Vec<String>,
+    rng: SystemRandom,
+}
+
+impl<S: ConversationStore> EncryptedConversationStore<S> {
+    pub fn new(inner: S, keys: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            inner,
+            keys,
+            plaintext_metadata: Vec::new(),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Turn metadata fields (e.g. `tenant` or `created_at`) copied to the
+    /// envelope unencrypted. The first turn that has a field provides it.
+    pub fn with_plaintext_metadata(mut self, fields: &[&str]) -> Self {
+        self.plaintext_metadata = fields.iter().map(|field| field.to_string()).collect();
+        self
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The plaintext metadata of the conversation `id`, checked against the
+    /// ciphertext so edited values are an error.
+    pub async fn metadata(&self, id: &str) -> Result<Option<HashMap<String, Value>>> {
+        let Some(envelope) = self.inner.load(id).await? else {
+            return Ok(None);
+        };
+        self.open(id, &envelope).await?;
+        let mut metadata = envelope_turn(&envelope)?
+            .metadata
+            .clone()
+            .unwrap_or_default();
+        metadata.remove(ENVELOPE_KEY);
+        Ok(Some(metadata))
+    }
+
+    /// The decrypted conversation JSON of `envelope`.
+    async fn open(&self, id: &str, envelope: &Conversation) -> Result<Vec<u8>> {
+        let turn = envelope_turn(envelope)?;
+        let metadata = turn.metadata.clone().unwrap_or_default();
+        let envelope_field = |field: &str| {
+            metadata[ENVELOPE_KEY][field]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| AdapterError::EncryptionError(format!("envelope has no {}", field)))
+        };
+        let algorithm = envelope_field("algorithm")?;
+        if algorithm != ALGORITHM {
+            return Err(AdapterError::EncryptionError(format!(
+                "unsupported algorithm {}",
+                algorithm
+            )));
+        }
+        let key_id = envelope_field("key_id")?;
+        let key = cipher(&self.keys.key(&key_id).await?)?;
+        let mut sealed = general_purpose::STANDARD
+            .decode(&turn.content)
+            .map_err(|err| AdapterError::EncryptionError(err.to_string()))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(AdapterError::EncryptionError(
+                "ciphertext is truncated".to_string(),
+            ));
+        }
+        let mut ciphertext = sealed.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&sealed)
+            .map_err(|_| AdapterError::EncryptionError("invalid nonce".to_string()))?;
+        let aad = associated_data(id, &key_id, &metadata)?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(aad), &mut ciphertext)
+            .map_err(|_| {
+                AdapterError::EncryptionError(format!("could not decrypt conversation {}", id))
+            })?;
+        let len = plaintext.len();
+        ciphertext.truncate(len);
+        Ok(ciphertext)
+    }
+
+    fn searchable(&self, conversation: &Conversation) -> HashMap<String, Value> {
+        let mut metadata = HashMap::new();
+        for field in &self.plaintext_metadata {
+            let value = conversation
+                .turns
+                .iter()
+                .find_map(|turn| turn.metadata()?.get(field).cloned());
+            if let Some(value) = value {
+                metadata.insert(field.clone(), value);
+            }
+        }
+        metadata
+    }
+}
+
+/// JSON with object keys in sorted order, whatever the map implementation.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut entries: Vec<_> = fields.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(name, field)| (name.clone(), canonical(field)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        other => other.clone(),
+    }
+}
+
+/// What the ciphertext authenticates besides itself: the conversation id,
+/// the algorithm, the key id and the plaintext metadata.
+fn associated_data(id: &str, key_id: &str, metadata: &HashMap<String, Value>) -> Result<Vec<u8>> {
+    let plaintext: Map<String, Value> = metadata
+        .iter()
+        .filter(|(name, _)| *name != ENVELOPE_KEY)
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    let data = json!({
+        "algorithm": ALGORITHM,
+        "id": id,
+        "key_id": key_id,
+        "metadata": plaintext,
+    });
+    Ok(serde_json::to_vec(&canonical(&data))?)
+}
+
+fn envelope_turn(envelope: &Conversation) -> Result<&Turn> {
+    match envelope.turns.as_slice() {
+        [TurnType::Basic(turn)]
+            if turn
+                .metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.contains_key(ENVELOPE_KEY)) =>
+        {
+            Ok(turn)
+        }
+        _ => Err(AdapterError::EncryptionError(
+            "stored conversation is not encrypted".to_string(),
+        )),
+    }
+}
+
+impl<S> fmt::Debug for EncryptedConversationStore<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedConversationStore")
+            .field("plaintext_metadata", &self.plaintext_metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<S: ConversationStore> ConversationStore for EncryptedConversationStore<S> {
+    async fn load(&self, id: &str) -> Result<Option<Conversation>> {
+        let Some(envelope) = self.inner.load(id).await? else {
+            return Ok(None);
+        };
+        let plaintext = self.open(id, &envelope).await?;
+        Ok(Some(serde_json::from_slice(&plaintext)?))
+    }
+
+    async fn save(&self, id: &str, conversation: &Conversation) -> Result<()> {
+        let key_id = self.keys.current_key_id().await?;
+        let key = cipher(&self.keys.key(&key_id).await?)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| AdapterError::EncryptionError("no randomness for nonce".to_string()))?;
+        let mut metadata = self.searchable(conversation);
+        let aad = associated_data(id, &key_id, &metadata)?;
+        let mut ciphertext = serde_json::to_vec(conversation)?;
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut ciphertext,
+        )
+        .map_err(|_| AdapterError::EncryptionError("encryption failed".to_string()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+
+        metadata.insert(
+            ENVELOPE_KEY.to_string(),
+            json!({"algorithm": ALGORITHM, "key_id": key_id}),
+        );
+        let envelope = Conversation::with_turns(vec![TurnType::Basic(Turn {
+            role: ConversationRole::System,
+            content: general_purpose::STANDARD.encode(sealed),
+            name: None,
+            metadata: Some(metadata),
+        })]);
+        self.inner.save(id, &envelope).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.inner.delete(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryConversationStore;
+
+    #[tokio::test]
+    async fn test_encrypted_round_trip() {
+        let keys = Arc::new(StaticKeyProvider::new("k1", [7u8; 32]));
+        let store = EncryptedConversationStore::new(InMemoryConversationStore::new(), keys)
+            .with_plaintext_metadata(&["tenant"]);
+        let conversation = Conversation::with_turns(vec![TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: "my card number is 4111".to_string(),
+            name: None,
+            metadata: Some(HashMap::from([("tenant".to_string(), json!("acme"))])),
+        })]);
+
+        store.save("t1", &conversation).await.unwrap();
+        assert_eq!(store.load("t1").await.unwrap(), Some(conversation));
+        assert_eq!(
+            store.metadata("t1").await.unwrap().unwrap()["tenant"],
+            json!("acme")
+        );
+
+        let envelope = store.inner().load("t1").await.unwrap().unwrap();
+        assert!(!envelope.turns[0].text().contains("4111"));
+        store.inner().save("t2", &envelope).await.unwrap();
+        assert!(matches!(
+            store.load("t2").await,
+            Err(AdapterError::EncryptionError(_))
+        ));
+
+        let mut forged = envelope.clone();
+        if let TurnType::Basic(turn) = &mut forged.turns[0] {
+            let metadata = turn.metadata.as_mut().unwrap();
+            metadata.insert("tenant".to_string(), json!("globex"));
+        }
+        store.inner().save("t1", &forged).await.unwrap();
+        assert!(store.load("t1").await.is_err());
+        assert!(store.metadata("t1").await.is_err());
+
+        let mut relabelled = envelope;
+        if let TurnType::Basic(turn) = &mut relabelled.turns[0] {
+            let metadata = turn.metadata.as_mut().unwrap();
+            metadata.insert(
+                ENVELOPE_KEY.to_string(),
+                json!({"algorithm": ALGORITHM, "key_id": "k2"}),
+            );
+        }
+        let keys = Arc::new(StaticKeyProvider::new("k2", [7u8; 32]));
+        let store = EncryptedConversationStore::new(store.inner, keys);
+        store.inner().save("t1", &relabelled).await.unwrap();
+        assert!(store.load("t1").await.is_err());
+    }
+}
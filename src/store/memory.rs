@@ -0,0 +1,42 @@
+use crate::error::Result;
+use crate::models::Conversation;
+use crate::store::ConversationStore;
+use async_trait::async_trait;
+use dashmap::DashMap;
+
+#[derive(Debug, Default)]
+pub struct InMemoryConversationStore {
+    conversations: DashMap<String, Conversation>,
+}
+
+impl InMemoryConversationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.conversations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conversations.is_empty()
+    }
+}
+
+#[async_trait]
+impl ConversationStore for InMemoryConversationStore {
+    async fn load(&self, id: &str) -> Result<Option<Conversation>> {
+        Ok(self.conversations.get(id).map(|entry| entry.clone()))
+    }
+
+    async fn save(&self, id: &str, conversation: &Conversation) -> Result<()> {
+        self.conversations
+            .insert(id.to_string(), conversation.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        self.conversations.remove(id);
+        Ok(())
+    }
+}
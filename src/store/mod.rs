@@ -0,0 +1,9 @@
+pub mod base;
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+pub mod memory;
+
+pub use base::*;
+#[cfg(feature = "encryption")]
+pub use encrypted::*;
+pub use memory::*;
@@ -0,0 +1,243 @@
+use crate::error::AdapterError;
+use serde_json::Value;
+
+const GEMINI_BLOCK_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "PROHIBITED_CONTENT",
+    "BLOCKLIST",
+    "SPII",
+    "RECITATION",
+];
+
+/// Inspects a raw provider response (or error body) and returns
+/// `AdapterError::ContentBlocked` when the provider refused to produce any
+/// output for policy reasons.
+pub fn detect_content_block(provider: &str, response: &Value) -> Option<AdapterError> {
+    let categories = gemini_prompt_block(response)
+        .or_else(|| gemini_candidate_block(response))
+        .or_else(|| azure_filter_error(response))
+        .or_else(|| filtered_choices(response))
+        .or_else(|| anthropic_refusal(response))?;
+
+    Some(AdapterError::ContentBlocked {
+        provider: provider.to_string(),
+        categories,
+        raw: response.clone(),
+    })
+}
+
+/// Returns the refusal text of an OpenAI-style message, if the model refused.
+pub fn extract_refusal(message: &Value) -> Option<String> {
+    message
+        .get("refusal")
+        .and_then(Value::as_str)
+        .filter(|refusal| !refusal.is_empty())
+        .map(str::to_string)
+}
+
+fn gemini_prompt_block(response: &Value) -> Option<Vec<String>> {
+    let feedback = response.get("promptFeedback")?;
+    let reason = feedback.get("blockReason")?.as_str()?;
+    let mut categories = blocked_safety_ratings(feedback);
+    if categories.is_empty() {
+        categories.push(reason.to_string());
+    }
+    Some(categories)
+}
+
+fn gemini_candidate_block(response: &Value) -> Option<Vec<String>> {
+    let candidates = response.get("candidates")?.as_array()?;
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let mut categories = Vec::new();
+    for candidate in candidates {
+        let reason = candidate.get("finishReason").and_then(Value::as_str)?;
+        if !GEMINI_BLOCK_FINISH_REASONS.contains(&reason) {
+            return None;
+        }
+        let blocked = blocked_safety_ratings(candidate);
+        if blocked.is_empty() {
+            categories.push(reason.to_string());
+        } else {
+            categories.extend(blocked);
+        }
+    }
+    categories.sort_unstable();
+    categories.dedup();
+    Some(categories)
+}
+
+fn blocked_safety_ratings(value: &Value) -> Vec<String> {
+    value
+        .get("safetyRatings")
+        .and_then(Value::as_array)
+        .map(|ratings| {
+            ratings
+                .iter()
+                .filter(|rating| rating.get("blocked").and_then(Value::as_bool) == Some(true))
+                .filter_map(|rating| rating.get("category").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn azure_filter_error(response: &Value) -> Option<Vec<String>> {
+    let error = response.get("error")?;
+    if error.get("code").and_then(Value::as_str) != Some("content_filter") {
+        return None;
+    }
+    let mut categories = error
+        .pointer("/innererror/content_filter_result")
+        .map(filtered_categories)
+        .unwrap_or_default();
+    if categories.is_empty() {
+        categories.push("content_filter".to_string());
+    }
+    Some(categories)
+}
+
+fn filtered_choices(response: &Value) -> Option<Vec<String>> {
+    let choices = response.get("choices")?.as_array()?;
+    if choices.is_empty() {
+        return None;
+    }
+
+    let mut categories = Vec::new();
+    for choice in choices {
+        if choice.get("finish_reason").and_then(Value::as_str) != Some("content_filter") {
+            return None;
+        }
+        let filtered = choice
+            .get("content_filter_results")
+            .map(filtered_categories)
+            .unwrap_or_default();
+        if filtered.is_empty() {
+            categories.push("content_filter".to_string());
+        } else {
+            categories.extend(filtered);
+        }
+    }
+    categories.sort_unstable();
+    categories.dedup();
+    Some(categories)
+}
+
+fn filtered_categories(results: &Value) -> Vec<String> {
+    results
+        .as_object()
+        .map(|map| {
+            map.iter()
+                .filter(|(_, result)| result.get("filtered").and_then(Value::as_bool) == Some(true))
+                .map(|(category, _)| category.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn anthropic_refusal(response: &Value) -> Option<Vec<String>> {
+    if response.get("stop_reason").and_then(Value::as_str) == Some("refusal") {
+        Some(vec!["refusal".to_string()])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_gemini_prompt_block() {
+        let response = json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [
+                    {"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH", "blocked": true},
+                    {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "NEGLIGIBLE"}
+                ]
+            }
+        });
+
+        match detect_content_block("gemini", &response) {
+            Some(AdapterError::ContentBlocked {
+                provider,
+                categories,
+                raw,
+            }) => {
+                assert_eq!(provider, "gemini");
+                assert_eq!(categories, vec!["HARM_CATEGORY_HARASSMENT"]);
+                assert_eq!(raw, response);
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_gemini_candidate_categories_are_deduplicated() {
+        let blocked = |categories: &[&str]| {
+            let ratings: Vec<Value> = categories
+                .iter()
+                .map(|category| json!({"category": category, "blocked": true}))
+                .collect();
+            json!({"finishReason": "SAFETY", "safetyRatings": ratings})
+        };
+        let response = json!({"candidates": [
+            blocked(&["HARM_CATEGORY_HARASSMENT", "HARM_CATEGORY_HATE_SPEECH"]),
+            blocked(&["HARM_CATEGORY_HARASSMENT"]),
+        ]});
+
+        match detect_content_block("gemini", &response) {
+            Some(AdapterError::ContentBlocked { categories, .. }) => assert_eq!(
+                categories,
+                vec!["HARM_CATEGORY_HARASSMENT", "HARM_CATEGORY_HATE_SPEECH"]
+            ),
+            other => panic!("expected ContentBlocked, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_detect_azure_content_filter() {
+        let response = json!({
+            "error": {
+                "code": "content_filter",
+                "message": "The response was filtered",
+                "innererror": {
+                    "code": "ResponsibleAIPolicyViolation",
+                    "content_filter_result": {
+                        "hate": {"filtered": false, "severity": "safe"},
+                        "violence": {"filtered": true, "severity": "high"}
+                    }
+                }
+            }
+        });
+
+        match detect_content_block("azure", &response) {
+            Some(AdapterError::ContentBlocked { categories, .. }) => {
+                assert_eq!(categories, vec!["violence"]);
+            }
+            other => panic!("expected ContentBlocked, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_regular_response_is_not_blocked() {
+        let response = json!({
+            "choices": [
+                {"index": 0, "finish_reason": "stop", "message": {"role": "assistant", "content": "Hi"}}
+            ]
+        });
+        assert!(detect_content_block("openai", &response).is_none());
+        assert_eq!(extract_refusal(&response["choices"][0]["message"]), None);
+
+        let refusal =
+            json!({"role": "assistant", "content": null, "refusal": "I can't help with that."});
+        assert_eq!(
+            extract_refusal(&refusal).as_deref(),
+            Some("I can't help with that.")
+        );
+    }
+}
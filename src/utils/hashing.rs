@@ -0,0 +1,28 @@
+use serde::Serialize;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// FNV-1a over the JSON encoding of `value`. Unlike `DefaultHasher` the result
+/// is stable across processes and Rust releases, and JSON object keys are
+/// serialized in sorted order, so equal content always hashes the same.
+pub fn stable_hash<T: Serialize + ?Sized>(value: &T) -> u64 {
+    let bytes = serde_json::to_vec(value).unwrap_or_default();
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stable_hash_ignores_key_order() {
+        let a: serde_json::Value = serde_json::from_str(r#"{"a": 1, "b": [true]}"#).unwrap();
+        let b: serde_json::Value = serde_json::from_str(r#"{"b": [true], "a": 1}"#).unwrap();
+        assert_eq!(stable_hash(&a), stable_hash(&b));
+        assert_ne!(stable_hash(&a), stable_hash(&json!({"a": 2, "b": [true]})));
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::{AdapterError, Result};
+use crate::models::ImageUrl;
+use crate::utils::{image_size_limit, sniff_image_mime};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use std::io::Cursor;
+
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+const MIN_JPEG_QUALITY: u8 = 45;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageLimits {
+    pub max_bytes: usize,
+    /// Longest edge in pixels. Larger images are downscaled by the provider
+    /// anyway, so sending them only costs bandwidth and latency.
+    pub max_dimension: u32,
+}
+
+pub fn provider_image_limits(provider: &str) -> ImageLimits {
+    let max_dimension = match provider {
+        "anthropic" | "bedrock" => 1568,
+        "gemini" | "vertex" => 3072,
+        _ => 2048,
+    };
+    ImageLimits {
+        max_bytes: image_size_limit(provider),
+        max_dimension,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessedImage {
+    pub bytes: Vec<u8>,
+    pub mime_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ProcessedImage {
+    pub fn to_image_url(&self) -> Result<ImageUrl> {
+        ImageUrl::from_bytes(&self.bytes, Some(self.mime_type))
+    }
+}
+
+fn decode(bytes: &[u8]) -> Result<DynamicImage> {
+    if sniff_image_mime(bytes) == Some("image/heic") {
+        return Err(AdapterError::ConfigError(
+            "HEIC images cannot be decoded without a HEIF decoder; convert them before upload"
+                .to_string(),
+        ));
+    }
+    image::load_from_memory(bytes)
+        .map_err(|err| AdapterError::ConfigError(format!("failed to decode image: {}", err)))
+}
+
+fn encode(image: &DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let result = match format {
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel.
+            let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+            rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, quality))
+        }
+        format => image.write_to(&mut Cursor::new(&mut bytes), format),
+    };
+    result.map_err(|err| AdapterError::ConfigError(format!("failed to encode image: {}", err)))?;
+    Ok(bytes)
+}
+
+fn processed(bytes: Vec<u8>, image: &DynamicImage, mime_type: &'static str) -> ProcessedImage {
+    let (width, height) = image.dimensions();
+    ProcessedImage {
+        bytes,
+        mime_type,
+        width,
+        height,
+    }
+}
+
+/// Re-encodes any supported image as JPEG. `quality` ranges from 1 to 100.
+pub fn encode_jpeg(bytes: &[u8], quality: u8) -> Result<ProcessedImage> {
+    let image = decode(bytes)?;
+    let bytes = encode(&image, ImageFormat::Jpeg, quality.clamp(1, 100))?;
+    Ok(processed(bytes, &image, "image/jpeg"))
+}
+
+/// Downscales the image to the provider's maximum dimension and, when it is
+/// still over the byte limit, re-encodes it as JPEG with decreasing quality
+/// and then smaller dimensions until it fits. Images already within limits
+/// are returned unchanged.
+pub fn resize_to_provider_limits(bytes: &[u8], provider: &str) -> Result<ProcessedImage> {
+    let limits = provider_image_limits(provider);
+    let mut image = decode(bytes)?;
+    let (width, height) = image.dimensions();
+    let oversized = width.max(height) > limits.max_dimension;
+
+    if !oversized && bytes.len() <= limits.max_bytes {
+        let mime_type = sniff_image_mime(bytes).unwrap_or("image/jpeg");
+        return Ok(processed(bytes.to_vec(), &image, mime_type));
+    }
+
+    if oversized {
+        image = image.resize(
+            limits.max_dimension,
+            limits.max_dimension,
+            FilterType::Lanczos3,
+        );
+    }
+    if sniff_image_mime(bytes) == Some("image/png") {
+        let png = encode(&image, ImageFormat::Png, DEFAULT_JPEG_QUALITY)?;
+        if png.len() <= limits.max_bytes {
+            return Ok(processed(png, &image, "image/png"));
+        }
+    }
+
+    loop {
+        let mut quality = DEFAULT_JPEG_QUALITY;
+        while quality >= MIN_JPEG_QUALITY {
+            let jpeg = encode(&image, ImageFormat::Jpeg, quality)?;
+            if jpeg.len() <= limits.max_bytes {
+                return Ok(processed(jpeg, &image, "image/jpeg"));
+            }
+            quality -= 10;
+        }
+
+        let (width, height) = image.dimensions();
+        if width.max(height) <= 64 {
+            return Err(AdapterError::TooLarge {
+                size: bytes.len(),
+                limit: limits.max_bytes,
+            });
+        }
+        image = image.resize(width / 2, height / 2, FilterType::Lanczos3);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbImage;
+
+    #[test]
+    fn test_resize_to_provider_limits() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_fn(3000, 1000, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        }));
+        let png = encode(&image, ImageFormat::Png, DEFAULT_JPEG_QUALITY).unwrap();
+
+        let resized = resize_to_provider_limits(&png, "anthropic").unwrap();
+        assert_eq!((resized.width, resized.height), (1568, 523));
+        assert!(resized.bytes.len() <= provider_image_limits("anthropic").max_bytes);
+
+        let small = encode(&image.thumbnail(100, 100), ImageFormat::Png, 0).unwrap();
+        let unchanged = resize_to_provider_limits(&small, "openai").unwrap();
+        assert_eq!(unchanged.bytes, small);
+        assert_eq!(unchanged.mime_type, "image/png");
+
+        let jpeg = encode_jpeg(&small, 70).unwrap();
+        assert_eq!(sniff_image_mime(&jpeg.bytes), Some("image/jpeg"));
+    }
+}
@@ -37,6 +37,145 @@ pub fn encode_image_to_base64(image_bytes: &[u8]) -> String {
     general_purpose::STANDARD.encode(image_bytes)
 }
 
+/// Removes EXIF, XMP, IPTC and text metadata (including GPS location) from
+/// JPEG, PNG and WebP images. Pixel data and color profiles are kept; other
+/// formats are returned unchanged.
+pub fn strip_metadata(bytes: &[u8]) -> Result<Vec<u8>> {
+    match sniff_image_mime(bytes) {
+        Some("image/jpeg") => strip_jpeg_metadata(bytes),
+        Some("image/png") => strip_png_metadata(bytes),
+        Some("image/webp") => strip_webp_metadata(bytes),
+        _ => Ok(bytes.to_vec()),
+    }
+}
+
+fn malformed(format: &str) -> AdapterError {
+    AdapterError::ConfigError(format!("malformed {} image", format))
+}
+
+fn strip_jpeg_metadata(bytes: &[u8]) -> Result<Vec<u8>> {
+    const APP1: u8 = 0xE1;
+    const APP13: u8 = 0xED;
+    const COMMENT: u8 = 0xFE;
+    const START_OF_SCAN: u8 = 0xDA;
+
+    let mut stripped = bytes[..2].to_vec();
+    let mut pos = 2;
+    while pos < bytes.len() {
+        if bytes[pos] != 0xFF || pos + 1 >= bytes.len() {
+            return Err(malformed("JPEG"));
+        }
+        let marker = bytes[pos + 1];
+        // Standalone markers carry no length.
+        if marker == 0x01 || (0xD0..=0xD9).contains(&marker) || marker == 0xFF {
+            stripped.extend_from_slice(&bytes[pos..pos + 2]);
+            pos += if marker == 0xFF { 1 } else { 2 };
+            continue;
+        }
+        let length = bytes
+            .get(pos + 2..pos + 4)
+            .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+            .ok_or_else(|| malformed("JPEG"))?;
+        let end = pos + 2 + length;
+        if length < 2 || end > bytes.len() {
+            return Err(malformed("JPEG"));
+        }
+        if marker == START_OF_SCAN {
+            // Entropy-coded data follows; nothing after it is metadata.
+            stripped.extend_from_slice(&bytes[pos..]);
+            return Ok(stripped);
+        }
+        if !matches!(marker, APP1 | APP13 | COMMENT) {
+            stripped.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+    Ok(stripped)
+}
+
+fn strip_png_metadata(bytes: &[u8]) -> Result<Vec<u8>> {
+    const METADATA_CHUNKS: [&[u8; 4]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+    let mut stripped = bytes[..8].to_vec();
+    let mut pos = 8;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| malformed("PNG"))?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        // Length, type, data and CRC.
+        let end = pos + 12 + length;
+        if end > bytes.len() {
+            return Err(malformed("PNG"));
+        }
+        if !METADATA_CHUNKS
+            .iter()
+            .any(|chunk| header[4..8] == chunk[..])
+        {
+            stripped.extend_from_slice(&bytes[pos..end]);
+        }
+        pos = end;
+    }
+    Ok(stripped)
+}
+
+fn strip_webp_metadata(bytes: &[u8]) -> Result<Vec<u8>> {
+    const VP8X_EXIF: u8 = 0x08;
+    const VP8X_XMP: u8 = 0x04;
+
+    let mut stripped = bytes[..12].to_vec();
+    let mut pos = 12;
+    while pos < bytes.len() {
+        let header = bytes.get(pos..pos + 8).ok_or_else(|| malformed("WebP"))?;
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        // Chunks are padded to an even size.
+        let end = (pos + 8 + size + (size & 1)).min(bytes.len());
+        if pos + 8 + size > bytes.len() {
+            return Err(malformed("WebP"));
+        }
+        match &header[..4] {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" if size > 0 => {
+                let flags = stripped.len() + 8;
+                stripped.extend_from_slice(&bytes[pos..end]);
+                stripped[flags] &= !(VP8X_EXIF | VP8X_XMP);
+            }
+            _ => stripped.extend_from_slice(&bytes[pos..end]),
+        }
+        pos = end;
+    }
+    let riff_size = (stripped.len() - 8) as u32;
+    stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Ok(stripped)
+}
+
+/// Largest inline image accepted by OpenAI and Gemini.
+pub const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+
+/// Largest inline image, before base64 encoding, the provider accepts.
+pub fn image_size_limit(provider: &str) -> usize {
+    match provider {
+        "anthropic" | "bedrock" => 5 * 1024 * 1024,
+        _ => MAX_IMAGE_BYTES,
+    }
+}
+
+/// Detects the image format from its magic bytes.
+pub fn sniff_image_mime(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..]
+            if [b"heic", b"heix", b"mif1", b"msf1"]
+                .iter()
+                .any(|heic| brand.starts_with(*heic)) =>
+        {
+            Some("image/heic")
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,6 +188,59 @@ mod tests {
         assert_eq!(result.1, "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==");
     }
 
+    #[test]
+    fn test_sniff_image_mime() {
+        assert_eq!(
+            sniff_image_mime(b"\x89PNG\r\n\x1a\n\0\0"),
+            Some("image/png")
+        );
+        assert_eq!(sniff_image_mime(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(sniff_image_mime(b"GIF89a"), Some("image/gif"));
+        assert_eq!(
+            sniff_image_mime(b"RIFF\0\0\0\0WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(sniff_image_mime(b"\0\0\0\x18ftypheic"), Some("image/heic"));
+        assert_eq!(sniff_image_mime(b"hello"), None);
+    }
+
+    #[test]
+    fn test_strip_metadata() {
+        let exif = [0xFF, 0xE1, 0x00, 0x08, b'E', b'x', b'i', b'f', 0x00, 0x00];
+        let jfif = [0xFF, 0xE0, 0x00, 0x04, 0x4A, 0x46];
+        let scan = [0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9];
+        let jpeg = [&[0xFF, 0xD8][..], &jfif, &exif, &scan].concat();
+        assert_eq!(
+            strip_metadata(&jpeg).unwrap(),
+            [&[0xFF, 0xD8][..], &jfif, &scan].concat()
+        );
+
+        let chunk = |kind: &[u8], data: &[u8]| {
+            [&(data.len() as u32).to_be_bytes()[..], kind, data, &[0; 4]].concat()
+        };
+        let signature = b"\x89PNG\r\n\x1a\n";
+        let png = [
+            &signature[..],
+            &chunk(b"IHDR", &[0; 13]),
+            &chunk(b"eXIf", b"GPS"),
+            &chunk(b"tEXt", b"Author"),
+            &chunk(b"IEND", &[]),
+        ]
+        .concat();
+        assert_eq!(
+            strip_metadata(&png).unwrap(),
+            [
+                &signature[..],
+                &chunk(b"IHDR", &[0; 13]),
+                &chunk(b"IEND", &[])
+            ]
+            .concat()
+        );
+
+        assert!(strip_metadata(&jpeg[..12]).is_err());
+        assert_eq!(strip_metadata(b"plain text").unwrap(), b"plain text");
+    }
+
     #[test]
     fn test_encode_image_to_base64() {
         let data = b"hello world";
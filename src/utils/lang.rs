@@ -0,0 +1,185 @@
+use crate::models::{Conversation, ConversationRole};
+
+/// Function words of the Latin-script languages `detect_language` tells
+/// apart.
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "that", "it", "you", "what", "how", "this",
+            "with", "for",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "que", "y", "es", "por", "para", "con", "una", "qué", "cómo",
+            "está",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "les", "des", "et", "est", "une", "pour", "dans", "pas", "vous", "je", "ce",
+            "qui",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "sie", "ein", "eine", "mit", "zu",
+            "auf", "wie", "was",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "os", "não", "uma", "com", "você", "como", "é", "em", "do", "da", "são",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "gli", "di", "che", "è", "non", "un", "per", "sono", "della", "questo",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Han,
+    Kana,
+    Hangul,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+}
+
+fn script(c: char) -> Option<Script> {
+    Some(match c as u32 {
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9D => Script::Kana,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => Script::Han,
+        0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => Script::Hangul,
+        0x0400..=0x04FF => Script::Cyrillic,
+        0x0370..=0x03FF => Script::Greek,
+        0x0600..=0x06FF => Script::Arabic,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0E00..=0x0E7F => Script::Thai,
+        _ if c.is_alphabetic() => Script::Latin,
+        _ => return None,
+    })
+}
+
+fn latin_language(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    FUNCTION_WORDS
+        .iter()
+        .map(|(language, function_words)| {
+            let hits = words
+                .iter()
+                .filter(|word| function_words.contains(&word.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        // The first language wins ties.
+        .fold(None, |best: Option<(&str, usize)>, candidate| match best {
+            Some(best) if best.1 >= candidate.1 => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|(language, _)| language)
+}
+
+/// The ISO 639-1 code of the dominant language of `text`, judged by its
+/// script and, for Latin script, by common function words. `None` when
+/// the text gives too little to go on. A heuristic for routing, not a
+/// classifier.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut counts: Vec<(Script, usize)> = Vec::new();
+    for script in text.chars().filter_map(script) {
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    let count = |script| {
+        counts
+            .iter()
+            .find(|(s, _)| *s == script)
+            .map_or(0, |(_, count)| *count)
+    };
+    // Japanese mixes kana with kanji.
+    let kana = count(Script::Kana);
+    if kana > 0 {
+        let japanese = kana + count(Script::Han);
+        if counts.iter().all(|(_, count)| japanese >= *count) {
+            return Some("ja");
+        }
+    }
+    let (dominant, _) = counts.iter().max_by_key(|(_, count)| *count)?;
+    match dominant {
+        Script::Latin => latin_language(text),
+        Script::Han => Some("zh"),
+        Script::Kana => Some("ja"),
+        Script::Hangul => Some("ko"),
+        Script::Cyrillic => Some("ru"),
+        Script::Greek => Some("el"),
+        Script::Arabic => Some("ar"),
+        Script::Hebrew => Some("he"),
+        Script::Devanagari => Some("hi"),
+        Script::Thai => Some("th"),
+    }
+}
+
+/// `detect_language` on the last user turn of `conversation`.
+pub fn conversation_language(conversation: &Conversation) -> Option<&'static str> {
+    conversation
+        .turns
+        .iter()
+        .rev()
+        .find(|turn| *turn.role() == ConversationRole::User)
+        .and_then(|turn| detect_language(&turn.text()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelLanguages;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect_language("请用中文回答这个问题"), Some("zh"));
+        assert_eq!(
+            detect_language("この問題について教えてください"),
+            Some("ja")
+        );
+        assert_eq!(detect_language("안녕하세요, 도와주세요"), Some("ko"));
+        assert_eq!(detect_language("Привет, как дела?"), Some("ru"));
+        assert_eq!(
+            detect_language("What is the capital of France?"),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("¿Cuál es la capital de Francia y por qué?"),
+            Some("es")
+        );
+        assert_eq!(
+            detect_language("Was ist die Hauptstadt von Frankreich?"),
+            Some("de")
+        );
+        assert_eq!(detect_language("12345 !!"), None);
+
+        assert!(ModelLanguages::is_strong_in("qwen-max", "zh"));
+        assert!(!ModelLanguages::is_strong_in("gpt-3.5-turbo", "zh"));
+        assert!(ModelLanguages::is_strong_in("gpt-3.5-turbo", "en"));
+    }
+}
@@ -1,7 +1,29 @@
+pub mod content_policy;
+pub mod dates;
+pub mod hashing;
+#[cfg(feature = "image-processing")]
+pub mod image_processing;
 pub mod images;
+pub mod lang;
 pub mod normalization;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod schema_tokens;
+pub mod stats;
+pub mod tokens;
 
+pub use content_policy::*;
+pub(crate) use dates::*;
+pub use hashing::*;
+#[cfg(feature = "image-processing")]
+pub use image_processing::*;
 pub use images::*;
+pub use lang::*;
 pub use normalization::*;
+#[cfg(feature = "parquet")]
+pub use parquet::*;
+pub use schema_tokens::*;
+pub use stats::*;
+pub use tokens::*;
 
 pub const EMPTY_CONTENT: &str = r#""""#;
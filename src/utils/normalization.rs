@@ -1,27 +1,132 @@
+use crate::models::ToolCall;
 use serde_json::Value;
 
+/// Removes `null` object members and array elements, recursively.
 pub fn delete_none_values(value: &mut Value) {
+    delete_none_values_except(value, &[]);
+}
+
+/// Like `delete_none_values`, but keeps nulls at the given JSON pointers
+/// (RFC 6901, e.g. `/logit_bias`), for parameters where an explicit null
+/// means "reset". Array indices in pointers refer to the original array.
+pub fn delete_none_values_except(value: &mut Value, keep: &[&str]) {
+    let mut pointer = String::new();
+    delete_nulls(value, keep, &mut pointer);
+}
+
+/// Non-mutating `delete_none_values_except`.
+pub fn without_none_values(value: &Value, keep: &[&str]) -> Value {
+    let mut value = value.clone();
+    delete_none_values_except(&mut value, keep);
+    value
+}
+
+fn delete_nulls(value: &mut Value, keep: &[&str], pointer: &mut String) {
+    let len = pointer.len();
     match value {
         Value::Object(map) => {
-            map.retain(|_, v| !v.is_null());
-            for v in map.values_mut() {
-                delete_none_values(v);
-            }
+            map.retain(|key, v| {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+                let retain = !v.is_null() || keep.contains(&pointer.as_str());
+                if retain {
+                    delete_nulls(v, keep, pointer);
+                }
+                pointer.truncate(len);
+                retain
+            });
         }
         Value::Array(arr) => {
-            for v in arr.iter_mut() {
-                delete_none_values(v);
-            }
+            let mut index = 0;
+            arr.retain_mut(|v| {
+                pointer.push_str(&format!("/{}", index));
+                index += 1;
+                let retain = !v.is_null() || keep.contains(&pointer.as_str());
+                if retain {
+                    delete_nulls(v, keep, pointer);
+                }
+                pointer.truncate(len);
+                retain
+            });
         }
         _ => {}
     }
 }
 
+/// Collects the `tool_use` blocks of an Anthropic message response.
+pub fn anthropic_tool_calls(response: &Value) -> Vec<ToolCall> {
+    response
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(ToolCall::from_anthropic_block)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Collects the `functionCall` parts of a Gemini candidate.
+pub fn gemini_tool_calls(candidate: &Value) -> Vec<ToolCall> {
+    candidate
+        .pointer("/content/parts")
+        .and_then(Value::as_array)
+        .map(|parts| {
+            parts
+                .iter()
+                .filter(|part| part.get("functionCall").is_some())
+                .enumerate()
+                .filter_map(|(position, part)| ToolCall::from_gemini_part(part, position))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_anthropic_tool_use_blocks() {
+        let response = json!({
+            "content": [
+                {"type": "text", "text": "Let me check."},
+                {"type": "tool_use", "id": "toolu_01", "name": "weather", "input": {"city": "Oslo"}},
+            ]
+        });
+
+        let calls = anthropic_tool_calls(&response);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].id, "toolu_01");
+        assert_eq!(calls[0].function.arguments, r#"{"city":"Oslo"}"#);
+        assert_eq!(
+            calls[0].to_anthropic_block().unwrap(),
+            response["content"][1]
+        );
+    }
+
+    #[test]
+    fn test_gemini_function_calls_get_stable_ids() {
+        let candidate = json!({
+            "content": {"parts": [
+                {"functionCall": {"name": "weather", "args": {"city": "Oslo"}}},
+                {"functionCall": {"name": "weather", "args": {"city": "Oslo"}}},
+            ]}
+        });
+
+        let calls = gemini_tool_calls(&candidate);
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0].id.starts_with("call_"));
+        assert_ne!(calls[0].id, calls[1].id);
+        assert_eq!(gemini_tool_calls(&candidate), calls);
+        assert_eq!(
+            calls[0].to_gemini_part().unwrap(),
+            candidate["content"]["parts"][0]
+        );
+    }
+
     #[test]
     fn test_delete_none_values() {
         let mut value = json!({
@@ -43,7 +148,7 @@ mod tests {
                 "c": {
                     "d": 2,
                 },
-                "f": [1, null, 3],
+                "f": [1, 3],
             })
         );
     }
@@ -0,0 +1,632 @@
+use crate::error::{AdapterError, Result};
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+// Parquet physical types, repetitions and encodings.
+const BOOLEAN: i32 = 0;
+const INT64: i32 = 2;
+const DOUBLE: i32 = 5;
+const BYTE_ARRAY: i32 = 6;
+const REQUIRED: i32 = 0;
+const OPTIONAL: i32 = 1;
+const UTF8: i32 = 0;
+const PLAIN: i32 = 0;
+const RLE: i32 = 3;
+
+// Thrift compact protocol element types.
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+/// Thrift compact protocol, the encoding of Parquet page headers and the
+/// file footer.
+#[derive(Default)]
+struct Thrift {
+    out: Vec<u8>,
+    last: Vec<i16>,
+    field: i16,
+}
+
+impl Thrift {
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.out.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        self.out.push(n as u8);
+    }
+
+    fn zigzag(&mut self, n: i64) {
+        self.varint(((n << 1) ^ (n >> 63)) as u64);
+    }
+
+    fn header(&mut self, id: i16, kind: u8) {
+        let delta = id - self.field;
+        if (1..=15).contains(&delta) {
+            self.out.push((delta as u8) << 4 | kind);
+        } else {
+            self.out.push(kind);
+            self.zigzag(id.into());
+        }
+        self.field = id;
+    }
+
+    fn i32(&mut self, id: i16, n: i32) {
+        self.header(id, T_I32);
+        self.zigzag(n.into());
+    }
+
+    fn i64(&mut self, id: i16, n: i64) {
+        self.header(id, T_I64);
+        self.zigzag(n);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.varint(bytes.len() as u64);
+        self.out.extend(bytes);
+    }
+
+    fn string(&mut self, id: i16, text: &str) {
+        self.header(id, T_BINARY);
+        self.bytes(text.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, kind: u8, len: usize) {
+        self.header(id, T_LIST);
+        if len < 15 {
+            self.out.push((len as u8) << 4 | kind);
+        } else {
+            self.out.push(0xf0 | kind);
+            self.varint(len as u64);
+        }
+    }
+
+    fn begin(&mut self) {
+        self.last.push(self.field);
+        self.field = 0;
+    }
+
+    fn struct_field(&mut self, id: i16) {
+        self.header(id, T_STRUCT);
+        self.begin();
+    }
+
+    fn end(&mut self) {
+        self.out.push(0);
+        self.field = self.last.pop().unwrap_or_default();
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ColumnData {
+    Boolean(Vec<Option<bool>>),
+    Int64(Vec<Option<i64>>),
+    Double(Vec<Option<f64>>),
+    Utf8(Vec<Option<String>>),
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    data: ColumnData,
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match &self.data {
+            ColumnData::Boolean(values) => values.len(),
+            ColumnData::Int64(values) => values.len(),
+            ColumnData::Double(values) => values.len(),
+            ColumnData::Utf8(values) => values.len(),
+        }
+    }
+
+    fn present(&self) -> Vec<bool> {
+        match &self.data {
+            ColumnData::Boolean(values) => values.iter().map(Option::is_some).collect(),
+            ColumnData::Int64(values) => values.iter().map(Option::is_some).collect(),
+            ColumnData::Double(values) => values.iter().map(Option::is_some).collect(),
+            ColumnData::Utf8(values) => values.iter().map(Option::is_some).collect(),
+        }
+    }
+
+    fn physical_type(&self) -> i32 {
+        match self.data {
+            ColumnData::Boolean(_) => BOOLEAN,
+            ColumnData::Int64(_) => INT64,
+            ColumnData::Double(_) => DOUBLE,
+            ColumnData::Utf8(_) => BYTE_ARRAY,
+        }
+    }
+
+    /// Columns without nulls are written as required, so they need no
+    /// definition levels.
+    fn optional(&self) -> bool {
+        self.present().contains(&false)
+    }
+
+    /// PLAIN-encoded non-null values.
+    fn values(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match &self.data {
+            ColumnData::Boolean(values) => {
+                let bits: Vec<bool> = values.iter().flatten().copied().collect();
+                for chunk in bits.chunks(8) {
+                    out.push(
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .fold(0u8, |byte, (bit, set)| byte | (u8::from(*set) << bit)),
+                    );
+                }
+            }
+            ColumnData::Int64(values) => {
+                for value in values.iter().flatten() {
+                    out.extend(value.to_le_bytes());
+                }
+            }
+            ColumnData::Double(values) => {
+                for value in values.iter().flatten() {
+                    out.extend(value.to_le_bytes());
+                }
+            }
+            ColumnData::Utf8(values) => {
+                for value in values.iter().flatten() {
+                    out.extend((value.len() as u32).to_le_bytes());
+                    out.extend(value.as_bytes());
+                }
+            }
+        }
+        out
+    }
+
+    /// The data page: definition levels for optional columns, as RLE runs
+    /// with a length prefix, followed by the values.
+    fn page(&self) -> Vec<u8> {
+        let mut page = Vec::new();
+        if self.optional() {
+            let mut levels = Thrift::default();
+            let present = self.present();
+            let mut index = 0;
+            while index < present.len() {
+                let run = present[index..]
+                    .iter()
+                    .take_while(|value| **value == present[index])
+                    .count();
+                levels.varint((run as u64) << 1);
+                levels.out.push(u8::from(present[index]));
+                index += run;
+            }
+            page.extend((levels.out.len() as u32).to_le_bytes());
+            page.extend(levels.out);
+        }
+        page.extend(self.values());
+        page
+    }
+}
+
+/// A single-row-group, uncompressed Parquet file built column by column,
+/// for Spark, DuckDB, pandas and other lakehouse tooling. Only flat columns
+/// of the four types below are supported; columns holding `None` values are
+/// written as optional.
+#[derive(Debug, Clone, Default)]
+pub struct ParquetTable {
+    columns: Vec<Column>,
+}
+
+impl ParquetTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(mut self, name: impl Into<String>, data: ColumnData) -> Self {
+        self.columns.push(Column {
+            name: name.into(),
+            data,
+        });
+        self
+    }
+
+    pub fn with_boolean(self, name: impl Into<String>, values: Vec<Option<bool>>) -> Self {
+        self.with(name, ColumnData::Boolean(values))
+    }
+
+    pub fn with_int64(self, name: impl Into<String>, values: Vec<Option<i64>>) -> Self {
+        self.with(name, ColumnData::Int64(values))
+    }
+
+    pub fn with_double(self, name: impl Into<String>, values: Vec<Option<f64>>) -> Self {
+        self.with(name, ColumnData::Double(values))
+    }
+
+    pub fn with_utf8(self, name: impl Into<String>, values: Vec<Option<String>>) -> Self {
+        self.with(name, ColumnData::Utf8(values))
+    }
+
+    /// The file contents. Every column must have the same number of rows.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let rows = self.columns.first().map(Column::len).unwrap_or(0);
+        if let Some(column) = self.columns.iter().find(|column| column.len() != rows) {
+            return Err(AdapterError::ConfigError(format!(
+                "parquet column {} has {} rows, expected {}",
+                column.name,
+                column.len(),
+                rows
+            )));
+        }
+
+        let mut file = MAGIC.to_vec();
+        // Offset and size of each column chunk.
+        let mut chunks = Vec::new();
+        for column in &self.columns {
+            let page = column.page();
+            let mut header = Thrift::default();
+            header.i32(1, 0); // DATA_PAGE
+            header.i32(2, page.len() as i32);
+            header.i32(3, page.len() as i32);
+            header.struct_field(5);
+            header.i32(1, rows as i32);
+            header.i32(2, PLAIN);
+            header.i32(3, RLE);
+            header.i32(4, RLE);
+            header.end();
+            header.out.push(0);
+            chunks.push((file.len() as i64, (header.out.len() + page.len()) as i64));
+            file.extend(header.out);
+            file.extend(page);
+        }
+
+        let mut footer = Thrift::default();
+        footer.i32(1, 1);
+        footer.list(2, T_STRUCT, self.columns.len() + 1);
+        footer.begin();
+        footer.string(4, "schema");
+        footer.i32(5, self.columns.len() as i32);
+        footer.end();
+        for column in &self.columns {
+            footer.begin();
+            footer.i32(1, column.physical_type());
+            footer.i32(
+                3,
+                if column.optional() {
+                    OPTIONAL
+                } else {
+                    REQUIRED
+                },
+            );
+            footer.string(4, &column.name);
+            if matches!(column.data, ColumnData::Utf8(_)) {
+                footer.i32(6, UTF8);
+            }
+            footer.end();
+        }
+        footer.i64(3, rows as i64);
+        footer.list(4, T_STRUCT, 1);
+        footer.begin();
+        footer.list(1, T_STRUCT, self.columns.len());
+        for (column, (offset, size)) in self.columns.iter().zip(&chunks) {
+            footer.begin();
+            footer.i64(2, *offset);
+            footer.struct_field(3);
+            footer.i32(1, column.physical_type());
+            footer.list(2, T_I32, 2);
+            footer.zigzag(PLAIN.into());
+            footer.zigzag(RLE.into());
+            footer.list(3, T_BINARY, 1);
+            footer.bytes(column.name.as_bytes());
+            footer.i32(4, 0); // UNCOMPRESSED
+            footer.i64(5, rows as i64);
+            footer.i64(6, *size);
+            footer.i64(7, *size);
+            footer.i64(9, *offset);
+            footer.end();
+            footer.end();
+        }
+        footer.i64(2, chunks.iter().map(|(_, size)| size).sum());
+        footer.i64(3, rows as i64);
+        footer.end();
+        footer.string(6, concat!("martian-adapters ", env!("CARGO_PKG_VERSION")));
+        footer.out.push(0);
+
+        file.extend(&footer.out);
+        file.extend((footer.out.len() as u32).to_le_bytes());
+        file.extend(MAGIC);
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+
+    /// A decoded Thrift compact value.
+    #[derive(Debug)]
+    enum Node {
+        Int(i64),
+        Binary(Vec<u8>),
+        List(Vec<Node>),
+        Struct(BTreeMap<i16, Node>),
+    }
+
+    impl Node {
+        fn field(&self, id: i16) -> &Node {
+            match self {
+                Node::Struct(fields) => &fields[&id],
+                other => panic!("expected a struct, got {:?}", other),
+            }
+        }
+
+        fn has(&self, id: i16) -> bool {
+            matches!(self, Node::Struct(fields) if fields.contains_key(&id))
+        }
+
+        fn int(&self, id: i16) -> i64 {
+            match self.field(id) {
+                Node::Int(n) => *n,
+                other => panic!("expected an integer, got {:?}", other),
+            }
+        }
+
+        fn text(&self, id: i16) -> String {
+            match self.field(id) {
+                Node::Binary(bytes) => String::from_utf8(bytes.clone()).unwrap(),
+                other => panic!("expected a string, got {:?}", other),
+            }
+        }
+
+        fn list(&self, id: i16) -> &[Node] {
+            match self.field(id) {
+                Node::List(items) => items,
+                other => panic!("expected a list, got {:?}", other),
+            }
+        }
+    }
+
+    /// A Thrift compact protocol reader written from the specification,
+    /// independent of `Thrift` above.
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl Reader<'_> {
+        fn byte(&mut self) -> u8 {
+            self.position += 1;
+            self.bytes[self.position - 1]
+        }
+
+        fn varint(&mut self) -> u64 {
+            let (mut n, mut shift) = (0u64, 0);
+            loop {
+                let byte = self.byte();
+                n |= u64::from(byte & 0x7f) << shift;
+                if byte < 0x80 {
+                    return n;
+                }
+                shift += 7;
+            }
+        }
+
+        fn zigzag(&mut self) -> i64 {
+            let n = self.varint();
+            (n >> 1) as i64 ^ -((n & 1) as i64)
+        }
+
+        fn value(&mut self, kind: u8) -> Node {
+            match kind {
+                1 => Node::Int(1),
+                2 => Node::Int(0),
+                3 => Node::Int(self.byte() as i8 as i64),
+                4..=6 => Node::Int(self.zigzag()),
+                8 => {
+                    let len = self.varint() as usize;
+                    self.position += len;
+                    Node::Binary(self.bytes[self.position - len..self.position].to_vec())
+                }
+                9 => {
+                    let header = self.byte();
+                    let len = match header >> 4 {
+                        15 => self.varint() as usize,
+                        len => len as usize,
+                    };
+                    Node::List((0..len).map(|_| self.value(header & 0x0f)).collect())
+                }
+                12 => self.structure(),
+                other => panic!("unexpected thrift type {}", other),
+            }
+        }
+
+        fn structure(&mut self) -> Node {
+            let mut fields = BTreeMap::new();
+            let mut last = 0i16;
+            loop {
+                let header = self.byte();
+                if header == 0 {
+                    return Node::Struct(fields);
+                }
+                let id = match header >> 4 {
+                    0 => self.zigzag() as i16,
+                    delta => last + i16::from(delta),
+                };
+                fields.insert(id, self.value(header & 0x0f));
+                last = id;
+            }
+        }
+    }
+
+    /// Reads every column of a file written by `ParquetTable`, following the
+    /// format specification: footer, schema, column chunk metadata, page
+    /// header, RLE definition levels and PLAIN values.
+    fn read_parquet(bytes: &[u8]) -> (i64, Vec<(String, Vec<Value>)>) {
+        assert_eq!(&bytes[..4], MAGIC);
+        assert_eq!(&bytes[bytes.len() - 4..], MAGIC);
+        let footer_len =
+            u32::from_le_bytes(bytes[bytes.len() - 8..bytes.len() - 4].try_into().unwrap());
+        let footer_start = bytes.len() - 8 - footer_len as usize;
+        let mut reader = Reader {
+            bytes: &bytes[..bytes.len() - 8],
+            position: footer_start,
+        };
+        let metadata = reader.structure();
+        assert_eq!(reader.position, bytes.len() - 8);
+        let rows = metadata.int(3);
+        let schema = metadata.list(2);
+        assert_eq!(schema[0].int(5) as usize, schema.len() - 1);
+        let row_groups = metadata.list(4);
+        assert_eq!(row_groups.len(), 1);
+        assert_eq!(row_groups[0].int(3), rows);
+
+        let chunks = row_groups[0].list(1);
+        let columns = schema[1..]
+            .iter()
+            .zip(chunks)
+            .map(|(element, chunk)| {
+                let name = element.text(4);
+                let meta = chunk.field(3);
+                assert_eq!(meta.int(1), element.int(1));
+                assert_eq!(meta.int(4), 0);
+                assert_eq!(meta.int(5), rows);
+                match meta.list(3) {
+                    [Node::Binary(path)] => assert_eq!(path, name.as_bytes()),
+                    other => panic!("unexpected path {:?}", other),
+                }
+
+                let mut reader = Reader {
+                    bytes,
+                    position: meta.int(9) as usize,
+                };
+                let header = reader.structure();
+                assert_eq!(header.int(1), 0);
+                let page_len = header.int(3) as usize;
+                assert_eq!(
+                    (reader.position + page_len) as i64,
+                    meta.int(9) + meta.int(7)
+                );
+                assert_eq!(header.field(5).int(1), rows);
+                let mut page = Reader {
+                    bytes: &bytes[..reader.position + page_len],
+                    position: reader.position,
+                };
+
+                let present = if element.int(3) == i64::from(OPTIONAL) {
+                    let levels_len = u32::from_le_bytes(
+                        page.bytes[page.position..page.position + 4]
+                            .try_into()
+                            .unwrap(),
+                    ) as usize;
+                    page.position += 4;
+                    let levels_end = page.position + levels_len;
+                    let mut present = Vec::new();
+                    while page.position < levels_end {
+                        let run = page.varint();
+                        assert_eq!(run & 1, 0, "only RLE runs are written");
+                        let level = page.byte();
+                        present.extend(std::iter::repeat_n(level == 1, (run >> 1) as usize));
+                    }
+                    present
+                } else {
+                    vec![true; rows as usize]
+                };
+                assert_eq!(present.len() as i64, rows);
+
+                let count = present.iter().filter(|present| **present).count();
+                let start = page.position;
+                let physical_type = element.int(1) as i32;
+                let mut values: Vec<Value> = match physical_type {
+                    BOOLEAN => (0..count)
+                        .map(|bit| json!(page.bytes[start + bit / 8] >> (bit % 8) & 1 == 1))
+                        .collect(),
+                    INT64 | DOUBLE => (0..count)
+                        .map(|index| {
+                            let raw: [u8; 8] = page.bytes[start + index * 8..start + index * 8 + 8]
+                                .try_into()
+                                .unwrap();
+                            if physical_type == INT64 {
+                                json!(i64::from_le_bytes(raw))
+                            } else {
+                                json!(f64::from_le_bytes(raw))
+                            }
+                        })
+                        .collect(),
+                    BYTE_ARRAY => {
+                        assert!(element.has(6) && element.int(6) == i64::from(UTF8));
+                        (0..count)
+                            .map(|_| {
+                                let raw: [u8; 4] = page.bytes[page.position..page.position + 4]
+                                    .try_into()
+                                    .unwrap();
+                                let len = u32::from_le_bytes(raw) as usize;
+                                page.position += 4 + len;
+                                let text = &page.bytes[page.position - len..page.position];
+                                json!(std::str::from_utf8(text).unwrap())
+                            })
+                            .collect()
+                    }
+                    other => panic!("unexpected physical type {}", other),
+                };
+                values.reverse();
+                let values = present
+                    .iter()
+                    .map(|present| match present {
+                        true => values.pop().unwrap(),
+                        false => Value::Null,
+                    })
+                    .collect();
+                (name, values)
+            })
+            .collect();
+        (rows, columns)
+    }
+
+    #[test]
+    fn test_parquet_round_trip() {
+        let table = ParquetTable::new()
+            .with_int64("id", vec![Some(1), Some(-2), Some(3)])
+            .with_utf8(
+                "tenant",
+                vec![Some("acme".to_string()), None, Some("ünï".to_string())],
+            )
+            .with_boolean("passed", vec![Some(true), Some(false), Some(true)])
+            .with_double("score", vec![None, Some(0.5), None]);
+        let (rows, columns) = read_parquet(&table.to_bytes().unwrap());
+        assert_eq!(rows, 3);
+        assert_eq!(
+            columns,
+            vec![
+                ("id".to_string(), vec![json!(1), json!(-2), json!(3)]),
+                (
+                    "tenant".to_string(),
+                    vec![json!("acme"), Value::Null, json!("ünï")]
+                ),
+                (
+                    "passed".to_string(),
+                    vec![json!(true), json!(false), json!(true)]
+                ),
+                (
+                    "score".to_string(),
+                    vec![Value::Null, json!(0.5), Value::Null]
+                ),
+            ]
+        );
+
+        let wide: Vec<Option<i64>> = (0..20).map(|n| (n % 3 != 0).then_some(n)).collect();
+        let table = (0..16).fold(ParquetTable::new(), |table, index| {
+            table.with_int64(format!("c{}", index), wide.clone())
+        });
+        let (rows, columns) = read_parquet(&table.to_bytes().unwrap());
+        assert_eq!(rows, 20);
+        assert_eq!(columns.len(), 16);
+        assert_eq!(columns[15].1[3], json!(null));
+        assert_eq!(columns[15].1[4], json!(4));
+
+        assert!(ParquetTable::new()
+            .with_int64("a", vec![Some(1)])
+            .with_int64("b", Vec::new())
+            .to_bytes()
+            .is_err());
+    }
+}
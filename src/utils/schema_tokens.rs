@@ -0,0 +1,133 @@
+use serde_json::Value;
+
+/// Deepest `$ref` resolution; recursive schemas are treated as unbounded.
+const MAX_DEPTH: usize = 8;
+
+/// Rough number of tokens a JSON document matching `schema` takes at most,
+/// from its shape: property names and punctuation, `maxLength`, `maxItems`
+/// and `enum` values. `anyOf` and `oneOf` count their largest branch.
+/// `None` when the schema admits documents of any length, e.g. a string
+/// without `maxLength` or an array without `maxItems`.
+pub fn estimate_schema_tokens(schema: &Value) -> Option<u32> {
+    estimate(schema, schema, 0).map(|tokens| tokens.min(u32::MAX as u64) as u32)
+}
+
+fn resolve<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+fn estimate(schema: &Value, root: &Value, depth: usize) -> Option<u64> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return estimate(resolve(root, reference)?, root, depth + 1);
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(branches) = schema.get(key).and_then(Value::as_array) {
+            return branches
+                .iter()
+                .map(|branch| estimate(branch, root, depth + 1))
+                .try_fold(1, |largest, tokens| Some(largest.max(tokens?)));
+        }
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values
+            .iter()
+            .map(|value| (value.to_string().len() as u64).div_ceil(4) + 1)
+            .max()
+            .or(Some(1));
+    }
+
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        // e.g. ["string", "null"]: the first non-null type.
+        Some(Value::Array(kinds)) => kinds
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|kind| *kind != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ if schema.get("items").is_some() => "array",
+        _ => "string",
+    };
+    let bound = |key: &str| schema.get(key).and_then(Value::as_u64);
+    match kind {
+        "object" => schema
+            .get("properties")?
+            .as_object()?
+            .iter()
+            .map(|(name, property)| {
+                let value = estimate(property, root, depth + 1)?;
+                Some((name.len() as u64).div_ceil(4) + 3 + value)
+            })
+            .try_fold(2u64, |total, tokens| Some(total.saturating_add(tokens?))),
+        "array" => {
+            let items = bound("maxItems")?;
+            let item = estimate(schema.get("items")?, root, depth + 1)?;
+            Some(
+                items
+                    .saturating_mul(item.saturating_add(1))
+                    .saturating_add(2),
+            )
+        }
+        "string" => match schema.get("format").and_then(Value::as_str) {
+            Some("date" | "time" | "date-time" | "uuid" | "email") => Some(12),
+            _ => bound("maxLength").map(|length| length.div_ceil(4) + 2),
+        },
+        "number" | "integer" => Some(4),
+        "boolean" => Some(2),
+        _ => Some(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_estimate_schema_tokens() {
+        let flag = json!({"type": "object", "properties": {"ok": {"type": "boolean"}}});
+        assert_eq!(estimate_schema_tokens(&flag), Some(8));
+
+        let invoice = json!({
+            "type": "object",
+            "properties": {
+                "number": {"type": "string", "maxLength": 16},
+                "currency": {"enum": ["EUR", "USD"]},
+                "lines": {"type": "array", "maxItems": 50, "items": {"$ref": "#/$defs/line"}},
+            },
+            "$defs": {
+                "line": {
+                    "type": "object",
+                    "properties": {
+                        "description": {"type": "string", "maxLength": 80},
+                        "amount": {"type": "number"},
+                    }
+                }
+            }
+        });
+        let tokens = estimate_schema_tokens(&invoice).unwrap();
+        assert!(tokens > 50 * 30 && tokens < 50 * 60, "{}", tokens);
+
+        let answer = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+        assert_eq!(estimate_schema_tokens(&answer), None);
+        let list = json!({"type": "array", "items": {"type": "integer"}});
+        assert_eq!(estimate_schema_tokens(&list), None);
+        let huge = json!({
+            "type": "array",
+            "maxItems": u64::MAX,
+            "items": {"type": "array", "maxItems": u64::MAX, "items": {"type": "boolean"}}
+        });
+        assert_eq!(estimate_schema_tokens(&huge), Some(u32::MAX));
+
+        let tree = json!({
+            "$defs": {"node": {"type": "object", "properties": {"children": {
+                "type": "array", "maxItems": 2, "items": {"$ref": "#/$defs/node"}
+            }}}},
+            "$ref": "#/$defs/node"
+        });
+        assert_eq!(estimate_schema_tokens(&tree), None);
+    }
+}
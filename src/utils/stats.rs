@@ -0,0 +1,22 @@
+/// The nearest-rank `quantile` (0.0 to 1.0) of `sorted`, which must be in
+/// ascending order: the smallest value at least that fraction of the values
+/// are less than or equal to. `None` when `sorted` is empty.
+pub fn percentile<T: Copy>(sorted: &[T], quantile: f64) -> Option<T> {
+    let rank = (sorted.len() as f64 * quantile).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len().max(1)) - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let values: Vec<u32> = (1..=10).collect();
+        assert_eq!(percentile(&values, 0.5), Some(5));
+        assert_eq!(percentile(&values, 0.95), Some(10));
+        assert_eq!(percentile(&values, 0.0), Some(1));
+        assert_eq!(percentile(&[7.0], 0.99), Some(7.0));
+        assert_eq!(percentile::<f64>(&[], 0.5), None);
+    }
+}
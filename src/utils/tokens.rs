@@ -0,0 +1,24 @@
+/// Per-message framing overhead used by OpenAI-style chat formats.
+pub const MESSAGE_OVERHEAD_TOKENS: u32 = 4;
+
+/// Flat cost charged for an image, matching OpenAI's low-detail price.
+pub const IMAGE_TOKENS: u32 = 85;
+
+/// Rough token count (about four characters per token for English text).
+/// Good enough for budgeting; use the provider's tokenizer for billing.
+pub fn estimate_tokens(text: &str) -> u32 {
+    let chars = text.chars().count() as u32;
+    chars.div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("hello world!"), 3);
+    }
+}
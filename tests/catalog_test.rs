@@ -0,0 +1,85 @@
+use martian_adapters::{
+    AdapterFactory, CatalogSnapshot, Cost, Model, ModelCapabilities, ModelProperties,
+};
+use tokio::sync::Mutex;
+
+// The catalog is process-global, so tests touching it must not interleave.
+static CATALOG_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn catalog_model(name: &str, capabilities: ModelCapabilities) -> Model {
+    Model {
+        name: name.to_string(),
+        vendor_name: "test".to_string(),
+        provider_name: "test".to_string(),
+        cost: Cost::new(0.000001, 0.000002, 0.0),
+        context_length: 8192,
+        completion_length: Some(1024),
+        capabilities,
+        properties: ModelProperties::default(),
+        knowledge_cutoff: None,
+        release_date: None,
+        last_updated: None,
+    }
+}
+
+#[tokio::test]
+async fn test_catalog_refresh_publishes_diff() {
+    let _guard = CATALOG_LOCK.lock().await;
+    let mut subscription = AdapterFactory::subscribe_catalog_changes();
+
+    AdapterFactory::replace_catalog(vec![
+        catalog_model("catalog-a", ModelCapabilities::default()),
+        catalog_model("catalog-b", ModelCapabilities::default()),
+    ])
+    .await;
+    let initial = subscription.recv().await.unwrap();
+    assert_eq!(
+        initial.added,
+        vec!["test/test/catalog-a", "test/test/catalog-b"]
+    );
+
+    let tools = ModelCapabilities {
+        supports_tools: true,
+        ..ModelCapabilities::default()
+    };
+    let mut repriced = catalog_model("catalog-a", tools);
+    repriced.cost = Cost::new(0.000002, 0.000002, 0.0);
+    let diff = AdapterFactory::replace_catalog(vec![repriced]).await;
+
+    assert!(diff.added.is_empty());
+    assert_eq!(diff.removed, vec!["test/test/catalog-b"]);
+    assert_eq!(diff.price_changes.len(), 1);
+    assert!(diff.price_changes[0].is_increase());
+    assert_eq!(diff.capability_changes[0].enabled, vec!["supports_tools"]);
+    assert_eq!(subscription.recv().await.unwrap().removed, diff.removed);
+}
+
+#[tokio::test]
+async fn test_catalog_export_import_roundtrip() {
+    let _guard = CATALOG_LOCK.lock().await;
+    AdapterFactory::replace_catalog(vec![
+        catalog_model("export-b", ModelCapabilities::default()),
+        catalog_model("export-a", ModelCapabilities::default()),
+    ])
+    .await;
+
+    let json = AdapterFactory::export_catalog().await.to_json().unwrap();
+    AdapterFactory::replace_catalog(Vec::new()).await;
+    assert!(AdapterFactory::get_model("test/test/export-a")
+        .await
+        .is_err());
+
+    let snapshot = CatalogSnapshot::from_json(&json).unwrap();
+    let names: Vec<_> = snapshot.models.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, vec!["export-a", "export-b"]);
+
+    let diff = AdapterFactory::import_catalog(snapshot).await.unwrap();
+    assert_eq!(diff.added.len(), 2);
+    assert!(AdapterFactory::get_model("test/test/export-a")
+        .await
+        .is_ok());
+
+    let mut future = AdapterFactory::export_catalog().await;
+    future.version = 99;
+    assert!(AdapterFactory::import_catalog(future).await.is_err());
+}
@@ -0,0 +1,70 @@
+use martian_adapters::{
+    reload_config, validate, watch_config, AdapterError, AdapterFactory, ProviderQuirks,
+    QuirkAction,
+};
+use std::time::Duration;
+
+// Runs in its own test binary because it points the process-wide config at a
+// temporary directory.
+#[tokio::test]
+async fn test_config_dir_overrides_and_reloads() {
+    assert!(ProviderQuirks::for_provider("openai").len() > 1);
+
+    let dir = std::env::temp_dir().join(format!("adapters-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let quirks = dir.join("provider_quirks.toml");
+    std::fs::write(&quirks, "[[openai]]\naction = \"drop\"\nfield = \"user\"\n").unwrap();
+    std::env::set_var("ADAPTERS_CONFIG_DIR", &dir);
+
+    reload_config().unwrap();
+    let rules = ProviderQuirks::for_provider("openai");
+    assert_eq!(rules.len(), 1);
+    assert_eq!(
+        rules[0].action,
+        QuirkAction::Drop {
+            field: "user".to_string()
+        }
+    );
+
+    std::fs::write(&quirks, "[[openai]\n").unwrap();
+    assert!(reload_config().is_err());
+    assert_eq!(ProviderQuirks::for_provider("openai").len(), 1);
+
+    std::fs::write(
+        &quirks,
+        "[[openai]]\naction = \"drop\"\nfield = \"user\"\nmodels = \"^(o1\"\n\n\
+         [[nonexistent]]\naction = \"drop\"\nfield = \"n\"\n",
+    )
+    .unwrap();
+    let mut keys: Vec<_> = validate().into_iter().filter_map(|d| d.key).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["nonexistent", "openai[0].models"]);
+    match reload_config() {
+        Err(AdapterError::InvalidConfig(diagnostics)) => assert_eq!(diagnostics.len(), 2),
+        other => panic!("expected InvalidConfig, got {:?}", other),
+    }
+    match AdapterFactory::init_config() {
+        Err(AdapterError::InvalidConfig(diagnostics)) => {
+            assert!(diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.key.as_deref() == Some("openai[0].models")))
+        }
+        other => panic!("expected InvalidConfig, got {:?}", other),
+    }
+    assert_eq!(ProviderQuirks::for_provider("openai").len(), 1);
+
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let watcher = watch_config(Duration::from_millis(20), move |result| {
+        let _ = sender.send(result.is_ok());
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    std::fs::remove_file(&quirks).unwrap();
+    let reloaded = tokio::time::timeout(Duration::from_secs(5), receiver.recv())
+        .await
+        .unwrap();
+    watcher.abort();
+
+    assert_eq!(reloaded, Some(true));
+    assert!(ProviderQuirks::for_provider("openai").len() > 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
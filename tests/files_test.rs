@@ -0,0 +1,165 @@
+use martian_adapters::{
+    ContentEntry, ContentEntryData, FileClient, FilePurpose, GeminiFiles, VideoInput,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn test_gemini_resumable_upload() {
+    let mut server = mockito::Server::new_async().await;
+    let start = server
+        .mock("POST", "/upload/v1beta/files")
+        .match_header("x-goog-upload-command", "start")
+        .match_header("x-goog-upload-header-content-type", "video/mp4")
+        .with_header("x-goog-upload-url", &format!("{}/session/1", server.url()))
+        .create_async()
+        .await;
+    let finalize = server
+        .mock("POST", "/session/1")
+        .match_header("x-goog-upload-command", "upload, finalize")
+        .match_body("fake-video")
+        .with_body(
+            json!({"file": {
+                "name": "files/abc",
+                "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc",
+                "mimeType": "video/mp4",
+                "state": "PROCESSING"
+            }})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let files = GeminiFiles::new("test-key")
+        .unwrap()
+        .with_base_url(server.url());
+    let file = files
+        .upload(b"fake-video".to_vec(), "video/mp4", Some("clip"))
+        .await
+        .unwrap();
+
+    start.assert_async().await;
+    finalize.assert_async().await;
+    assert_eq!(file.name, "files/abc");
+    assert!(!file.is_active());
+
+    let entry = ContentEntry::video(file.to_video_input());
+    let ContentEntryData::Video { video } = &entry.data else {
+        panic!("expected a video entry");
+    };
+    assert_eq!(
+        video.to_gemini_part().unwrap(),
+        json!({"fileData": {"mimeType": "video/mp4", "fileUri": file.uri}})
+    );
+}
+
+#[test]
+fn test_inline_video_roundtrip() {
+    let entry = ContentEntry::video(VideoInput::inline(b"hello world", "video/webm"));
+    let json = serde_json::to_value(&entry).unwrap();
+    assert_eq!(
+        json,
+        json!({"type": "video", "video": {"data": "aGVsbG8gd29ybGQ=", "mime_type": "video/webm"}})
+    );
+    let parsed: ContentEntry = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, entry);
+}
+
+#[tokio::test]
+async fn test_openai_file_lifecycle() {
+    let mut server = mockito::Server::new_async().await;
+    let upload = server
+        .mock("POST", "/v1/files")
+        .match_header("authorization", "Bearer sk-test")
+        .match_body(mockito::Matcher::Regex(
+            "name=\"purpose\"\r\n\r\nfine-tune".to_string(),
+        ))
+        .with_body(json!({"id": "file-123", "bytes": 10, "filename": "upload.jsonl"}).to_string())
+        .create_async()
+        .await;
+    let delete = server
+        .mock("DELETE", "/v1/files/file-123")
+        .with_body(json!({"id": "file-123", "deleted": true}).to_string())
+        .create_async()
+        .await;
+
+    let client = FileClient::new("openai", "sk-test")
+        .unwrap()
+        .with_base_url(server.url());
+    let file = client
+        .upload(b"{\"a\": 1}\n".to_vec(), FilePurpose::FineTune)
+        .await
+        .unwrap();
+    assert_eq!(file.id, "file-123");
+    assert_eq!(file.size_bytes, Some(10));
+    assert_eq!(
+        file.to_openai_content(),
+        json!({"type": "file", "file": {"file_id": "file-123"}})
+    );
+
+    client.delete(&file.id).await.unwrap();
+    upload.assert_async().await;
+    delete.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_openai_requests_carry_org_and_project() {
+    let mut server = mockito::Server::new_async().await;
+    let get = server
+        .mock("GET", "/v1/files/file-123")
+        .match_header("openai-organization", "org-acme")
+        .match_header("openai-project", "proj-search")
+        .with_body(json!({"id": "file-123", "bytes": 10}).to_string())
+        .create_async()
+        .await;
+
+    let client = FileClient::new("openai", "sk-test")
+        .unwrap()
+        .with_base_url(server.url())
+        .with_org("org-acme")
+        .with_project("proj-search");
+    client.get("file-123").await.unwrap();
+    get.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_anthropic_upload_uses_files_beta() {
+    let mut server = mockito::Server::new_async().await;
+    let upload = server
+        .mock("POST", "/v1/files")
+        .match_header("x-api-key", "ak-test")
+        .match_header(
+            "anthropic-beta",
+            mockito::Matcher::Regex("files-api".to_string()),
+        )
+        .with_body(
+            json!({"id": "file_01", "mime_type": "application/pdf", "size_bytes": 4}).to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = FileClient::new("anthropic", "ak-test")
+        .unwrap()
+        .with_base_url(server.url());
+    let file = client
+        .upload_as(
+            b"%PDF".to_vec(),
+            "doc.pdf",
+            "application/pdf",
+            FilePurpose::UserData,
+        )
+        .await
+        .unwrap();
+
+    upload.assert_async().await;
+    assert_eq!(
+        ContentEntry::file(file.clone()),
+        ContentEntry {
+            entry_type: "file".to_string(),
+            data: ContentEntryData::File { file: file.clone() },
+        }
+    );
+    assert_eq!(
+        file.to_anthropic_content(),
+        json!({"type": "document", "source": {"type": "file", "file_id": "file_01"}})
+    );
+}
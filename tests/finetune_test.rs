@@ -0,0 +1,105 @@
+use martian_adapters::{
+    training_jsonl, AdapterFactory, Conversation, ConversationRole, FineTuneClient,
+    FineTuneRequest, FineTuneStatus, Turn, TurnType,
+};
+use serde_json::json;
+use std::time::Duration;
+
+fn example(question: &str, answer: &str) -> Conversation {
+    Conversation::with_turns(vec![
+        TurnType::Basic(Turn {
+            role: ConversationRole::User,
+            content: question.to_string(),
+            name: None,
+            metadata: None,
+        }),
+        TurnType::Basic(Turn {
+            role: ConversationRole::Assistant,
+            content: answer.to_string(),
+            name: None,
+            metadata: None,
+        }),
+    ])
+}
+
+#[test]
+fn test_training_jsonl_lines() {
+    let jsonl =
+        training_jsonl(&[example("2+2?", "4"), example("Capital of France?", "Paris")]).unwrap();
+    let lines: Vec<serde_json::Value> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        lines[0],
+        json!({"messages": [
+            {"role": "user", "content": "2+2?"},
+            {"role": "assistant", "content": "4"},
+        ]})
+    );
+}
+
+#[tokio::test]
+async fn test_job_lifecycle_registers_model() {
+    let mut server = mockito::Server::new_async().await;
+    let upload = server
+        .mock("POST", "/v1/files")
+        .with_body(json!({"id": "file-train"}).to_string())
+        .create_async()
+        .await;
+    let create = server
+        .mock("POST", "/v1/fine_tuning/jobs")
+        .match_body(mockito::Matcher::PartialJson(json!({
+            "model": "ft-base-model",
+            "training_file": "file-train",
+            "suffix": "qa",
+        })))
+        .with_body(
+            json!({"id": "ftjob-1", "model": "ft-base-model", "status": "queued"}).to_string(),
+        )
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/v1/fine_tuning/jobs/ftjob-1")
+        .with_body(
+            json!({
+                "id": "ftjob-1",
+                "model": "ft-base-model",
+                "status": "succeeded",
+                "fine_tuned_model": "ft:ft-base-model:org:qa:abc123",
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let client = FineTuneClient::new("openai", "sk-test")
+        .unwrap()
+        .with_base_url(server.url());
+    let job = client
+        .create_job(FineTuneRequest {
+            base_model: "ft-base-model".to_string(),
+            training: vec![example("2+2?", "4")],
+            suffix: Some("qa".to_string()),
+            ..FineTuneRequest::default()
+        })
+        .await
+        .unwrap();
+    upload.assert_async().await;
+    create.assert_async().await;
+    assert_eq!(job.status, FineTuneStatus::Queued);
+
+    let finished = client
+        .wait_for_completion(&job.id, Duration::from_millis(1))
+        .await
+        .unwrap();
+    assert_eq!(finished.status, FineTuneStatus::Succeeded);
+
+    let registered = AdapterFactory::get_supported_models(None)
+        .await
+        .into_iter()
+        .find(|model| model.name == "ft:ft-base-model:org:qa:abc123")
+        .unwrap();
+    assert_eq!(registered.provider_name, "openai");
+}
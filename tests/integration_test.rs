@@ -1,6 +1,8 @@
 use martian_adapters::{
-    delete_none_values, Conversation, ConversationRole, Cost, ModelCapabilities, TokenUsage, Turn,
-    TurnType,
+    delete_none_values, delete_none_values_except, gemini_tool_calls, image_size_limit,
+    without_none_values, AdapterError, ContentEntry, ContentEntryData, ContentTurn, Conversation,
+    ConversationRole, Cost, ImageUrl, ModelCapabilities, TokenUsage, ToolResultContent, Turn,
+    TurnType, MAX_IMAGE_BYTES,
 };
 use serde_json::json;
 
@@ -13,6 +15,8 @@ fn test_conversation_creation() {
     conversation.add_turn(TurnType::Basic(Turn {
         role: ConversationRole::User,
         content: "Hello".to_string(),
+        name: None,
+        metadata: None,
     }));
 
     assert_eq!(conversation.len(), 1);
@@ -63,11 +67,39 @@ fn test_delete_none_values() {
             "c": {
                 "d": 2,
             },
-            "f": [1, null, 3],
+            "f": [1, 3],
         })
     );
 }
 
+#[test]
+fn test_delete_none_values_keeps_listed_pointers() {
+    let value = json!({
+        "logit_bias": null,
+        "stop": null,
+        "a/b": null,
+        "messages": [{"name": null, "content": "hi"}, null],
+    });
+
+    let cleaned = without_none_values(&value, &["/logit_bias", "/a~1b", "/messages/1"]);
+    assert_eq!(
+        cleaned,
+        json!({
+            "logit_bias": null,
+            "a/b": null,
+            "messages": [{"content": "hi"}, null],
+        })
+    );
+    assert!(value["stop"].is_null());
+
+    let mut value = value;
+    delete_none_values_except(&mut value, &["/messages/0/name"]);
+    assert_eq!(
+        value,
+        json!({"messages": [{"name": null, "content": "hi"}]})
+    );
+}
+
 #[test]
 fn test_model_capabilities_default() {
     let capabilities = ModelCapabilities::default();
@@ -84,3 +116,255 @@ fn test_conversation_role_display() {
     assert_eq!(ConversationRole::Assistant.to_string(), "assistant");
     assert_eq!(ConversationRole::System.to_string(), "system");
 }
+
+#[test]
+fn test_tool_output_structured_content_roundtrip() {
+    let turn = TurnType::ToolOutput {
+        role: ConversationRole::Tool,
+        content: Some(ToolResultContent::Json(json!({"temperature": 18}))),
+        tool_call_id: "call_1".to_string(),
+        metadata: None,
+    };
+
+    let serialized = serde_json::to_value(&turn).unwrap();
+    assert_eq!(serialized["content"], json!({"temperature": 18}));
+
+    match serde_json::from_value::<TurnType>(serialized).unwrap() {
+        TurnType::ToolOutput {
+            content: Some(content),
+            tool_call_id,
+            ..
+        } => {
+            assert_eq!(tool_call_id, "call_1");
+            assert_eq!(content.to_openai_content(), json!(r#"{"temperature":18}"#));
+        }
+        other => panic!("expected tool output, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_turn_metadata_survives_serialization_but_not_dispatch() {
+    let turn = TurnType::Basic(Turn {
+        role: ConversationRole::User,
+        content: "Hello".to_string(),
+        name: None,
+        metadata: None,
+    });
+    let tagged = turn
+        .clone()
+        .with_metadata("message_id", "msg_1")
+        .with_metadata("sent_at", 1_700_000_000);
+    assert_eq!(tagged.metadata().unwrap()["message_id"], json!("msg_1"));
+
+    let conversation = Conversation::with_turns(vec![tagged]);
+    let serialized = serde_json::to_string(&conversation).unwrap();
+    let restored: Conversation = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(restored, conversation);
+
+    assert_eq!(
+        conversation.to_openai_messages(),
+        vec![json!({"role": "user", "content": "Hello"})]
+    );
+    assert_eq!(conversation.without_metadata().turns, vec![turn.clone()]);
+    assert_eq!(
+        conversation.content_hash(),
+        Conversation::with_turns(vec![turn]).content_hash()
+    );
+}
+
+#[test]
+fn test_tool_output_blocks_for_anthropic() {
+    let content = ToolResultContent::Blocks(vec![
+        ContentEntry {
+            entry_type: "text".to_string(),
+            data: ContentEntryData::Text {
+                text: "Screenshot taken".to_string(),
+            },
+        },
+        ContentEntry {
+            entry_type: "image_url".to_string(),
+            data: ContentEntryData::Image {
+                image_url: ImageUrl {
+                    url: "data:image/png;base64,aGVsbG8=".to_string(),
+                    detail: None,
+                },
+            },
+        },
+    ]);
+
+    assert_eq!(
+        content.to_anthropic_content().unwrap(),
+        json!([
+            {"type": "text", "text": "Screenshot taken"},
+            {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "aGVsbG8="}},
+        ])
+    );
+    assert_eq!(content.to_openai_content(), json!("Screenshot taken"));
+}
+
+#[test]
+fn test_cost_breakdown_with_cache_and_reasoning_tokens() {
+    let cost = Cost::new(0.000002, 0.000008, 0.0).with_cache_pricing(Some(0.0000005), None);
+    let usage = TokenUsage {
+        cached_prompt_tokens: Some(600),
+        reasoning_tokens: Some(200),
+        ..TokenUsage::new(1000, 500)
+    };
+
+    let breakdown = cost.breakdown(&usage);
+    assert_eq!(breakdown.prompt, 0.000002 * 400.0);
+    assert_eq!(breakdown.cache_read, 0.0000005 * 600.0);
+    assert_eq!(breakdown.cache_write, 0.0);
+    assert_eq!(breakdown.completion, 0.000008 * 300.0);
+    assert_eq!(breakdown.reasoning, 0.000008 * 200.0);
+    assert_eq!(
+        breakdown.total,
+        breakdown.prompt + breakdown.completion + breakdown.cache_read + breakdown.reasoning
+    );
+
+    let plain = TokenUsage::new(1000, 500);
+    assert_eq!(cost.breakdown(&plain).total, cost.calculate(1000, 500));
+}
+
+#[test]
+fn test_token_usage_from_openai_details() {
+    let usage = TokenUsage::from_openai(&json!({
+        "prompt_tokens": 1200,
+        "completion_tokens": 300,
+        "total_tokens": 1500,
+        "prompt_tokens_details": {"cached_tokens": 1024, "audio_tokens": 0},
+        "completion_tokens_details": {"reasoning_tokens": 128, "audio_tokens": 5}
+    }))
+    .unwrap();
+
+    assert_eq!(usage.prompt_tokens, 1200);
+    assert_eq!(usage.cached_prompt_tokens, Some(1024));
+    assert_eq!(usage.reasoning_tokens, Some(128));
+    assert_eq!(usage.audio_tokens, Some(5));
+    assert_eq!(usage.total_tokens, 1500);
+}
+
+#[test]
+fn test_token_usage_from_anthropic_cache_usage() {
+    let usage = TokenUsage::from_anthropic(&json!({
+        "input_tokens": 50,
+        "output_tokens": 20,
+        "cache_creation_input_tokens": 200,
+        "cache_read_input_tokens": 1000
+    }))
+    .unwrap();
+
+    assert_eq!(usage.prompt_tokens, 1250);
+    assert_eq!(usage.completion_tokens, 20);
+    assert_eq!(usage.total_tokens, 1270);
+    assert_eq!(usage.cached_prompt_tokens, Some(1000));
+    assert_eq!(usage.cache_creation_tokens, Some(200));
+    assert_eq!(usage.reasoning_tokens, None);
+}
+
+#[test]
+fn test_gemini_tool_result_roundtrip() {
+    let candidate = json!({
+        "content": {"parts": [{"functionCall": {"name": "lookup", "args": {"q": "rust"}}}]}
+    });
+    let calls = gemini_tool_calls(&candidate);
+    let call_id = calls[0].id.clone();
+
+    let conversation = Conversation::with_turns(vec![
+        TurnType::ToolCalls {
+            role: ConversationRole::Assistant,
+            content: None,
+            tool_calls: calls,
+            metadata: None,
+        },
+        TurnType::ToolOutput {
+            role: ConversationRole::Tool,
+            content: Some(ToolResultContent::Text("found".to_string())),
+            tool_call_id: call_id.clone(),
+            metadata: None,
+        },
+    ]);
+
+    let call = conversation.find_tool_call(&call_id).unwrap();
+    assert_eq!(
+        ToolResultContent::from("found").to_gemini_response(&call.function.name),
+        json!({"functionResponse": {"name": "lookup", "response": {"content": "found"}}})
+    );
+}
+
+#[test]
+fn test_developer_role_downgrades_without_capability() {
+    let turn: Turn =
+        serde_json::from_value(json!({"role": "developer", "content": "Be terse.", "name": "ops"}))
+            .unwrap();
+    assert_eq!(turn.name.as_deref(), Some("ops"));
+    let conversation = Conversation::with_turns(vec![TurnType::Basic(turn)]);
+
+    let legacy = conversation.for_capabilities(&ModelCapabilities::default());
+    let TurnType::Basic(turn) = &legacy.turns[0] else {
+        panic!("expected a basic turn");
+    };
+    assert_eq!(turn.role, ConversationRole::System);
+
+    let capabilities = ModelCapabilities {
+        supports_developer_role: true,
+        ..ModelCapabilities::default()
+    };
+    assert_eq!(conversation.for_capabilities(&capabilities), conversation);
+    assert_eq!(ConversationRole::Developer.to_string(), "developer");
+}
+
+#[test]
+fn test_image_url_from_bytes_and_path() {
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    let image = ImageUrl::from_bytes(png, None).unwrap();
+    assert!(image.url.starts_with("data:image/png;base64,"));
+    assert_eq!(image.inline_size(), Some(png.len()));
+
+    let path = std::env::temp_dir().join(format!("adapters-image-{}.png", std::process::id()));
+    std::fs::write(&path, png).unwrap();
+    assert_eq!(ImageUrl::from_path(&path).unwrap(), image);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(ImageUrl::from_bytes(b"not an image", None).is_err());
+    assert!(ImageUrl::from_bytes(b"not an image", Some("image/png")).is_ok());
+
+    let oversized = vec![0u8; MAX_IMAGE_BYTES + 1];
+    assert!(matches!(
+        ImageUrl::from_bytes(&oversized, Some("image/png")),
+        Err(AdapterError::TooLarge { limit, .. }) if limit == MAX_IMAGE_BYTES
+    ));
+
+    let large = ImageUrl::from_bytes(&vec![0u8; 6 * 1024 * 1024], Some("image/png")).unwrap();
+    assert!(large.ensure_within(image_size_limit("openai")).is_ok());
+    assert!(matches!(
+        large.ensure_within(image_size_limit("anthropic")),
+        Err(AdapterError::TooLarge { size, .. }) if size == 6 * 1024 * 1024
+    ));
+}
+
+#[test]
+fn test_conversation_strip_image_metadata() {
+    let exif = [0xFF, 0xE1, 0x00, 0x06, b'G', b'P', b'S', 0x00];
+    let scan = [0xFF, 0xDA, 0x00, 0x02, 0x12, 0x34, 0xFF, 0xD9];
+    let jpeg = [&[0xFF, 0xD8][..], &exif, &scan].concat();
+    let image = ImageUrl::from_bytes(&jpeg, None).unwrap();
+
+    let conversation = Conversation::with_turns(vec![TurnType::Content(ContentTurn {
+        role: ConversationRole::User,
+        content: vec![
+            ContentEntry::text("Where was this taken?"),
+            ContentEntry::image(image),
+        ],
+        name: None,
+        metadata: None,
+    })]);
+    let stripped = conversation.strip_image_metadata().unwrap();
+
+    let TurnType::Content(turn) = &stripped.turns[0] else {
+        panic!("expected a content turn");
+    };
+    let expected = ImageUrl::from_bytes(&[&[0xFF, 0xD8][..], &scan].concat(), None).unwrap();
+    assert_eq!(turn.content[1], ContentEntry::image(expected));
+    assert_eq!(turn.content[0], ContentEntry::text("Where was this taken?"));
+}
@@ -0,0 +1,100 @@
+#![cfg(feature = "mcp")]
+
+use martian_adapters::{McpClient, ToolCall, ToolResultContent, ToolRunner};
+use mockito::Matcher;
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_mcp_tools_are_routed_through_the_server() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/mcp")
+        .match_body(Matcher::PartialJson(json!({"method": "initialize"})))
+        .with_header("mcp-session-id", "session-1")
+        .with_body(json!({"jsonrpc": "2.0", "id": 1, "result": {"capabilities": {}}}).to_string())
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/mcp")
+        .match_header("mcp-session-id", "session-1")
+        .match_body(Matcher::PartialJson(
+            json!({"method": "notifications/initialized"}),
+        ))
+        .with_status(202)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/mcp")
+        .match_body(Matcher::PartialJson(json!({"method": "tools/list"})))
+        .with_body(
+            json!({"jsonrpc": "2.0", "id": 2, "result": {"tools": [{
+                "name": "add",
+                "description": "Adds two numbers",
+                "inputSchema": {"type": "object", "properties": {"a": {"type": "number"}, "b": {"type": "number"}}}
+            }]}})
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let call = server
+        .mock("POST", "/mcp")
+        .match_body(Matcher::PartialJson(json!({
+            "method": "tools/call",
+            "params": {"name": "add", "arguments": {"a": 2, "b": 3}}
+        })))
+        .with_header("content-type", "text/event-stream")
+        .with_body(format!(
+            "event: message\ndata: {}\n\n",
+            json!({"jsonrpc": "2.0", "id": 3, "result": {"content": [{"type": "text", "text": "5"}]}})
+        ))
+        .create_async()
+        .await;
+
+    let client = Arc::new(
+        McpClient::connect(format!("{}/mcp", server.url()))
+            .await
+            .unwrap(),
+    );
+    let mut runner = ToolRunner::new();
+    let tools = client.register_tools(&mut runner).await.unwrap();
+    assert_eq!(tools[0]["function"]["name"], "add");
+    assert_eq!(tools[0]["function"]["parameters"]["type"], "object");
+    assert!(runner.has_tool("add"));
+
+    let output = runner
+        .call(&ToolCall::function(
+            "call_1",
+            "add",
+            r#"{"a": 2, "b": 3}"#.to_string(),
+        ))
+        .await;
+    assert_eq!(output, ToolResultContent::Text("5".to_string()));
+    call.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mcp_stdio_answers_server_requests_and_times_out() {
+    // Refuses to answer `initialize` until the client has replied to a ping
+    // and rejected an unsupported request, then never answers `tools/list`.
+    let script = r#"
+        read -r init
+        echo '{"jsonrpc":"2.0","id":"s1","method":"ping"}'
+        read -r pong
+        case "$pong" in *'"result"'*) ;; *) exit 1 ;; esac
+        echo '{"jsonrpc":"2.0","id":"s2","method":"sampling/createMessage"}'
+        read -r refusal
+        case "$refusal" in *-32601*) ;; *) exit 1 ;; esac
+        echo '{"jsonrpc":"2.0","id":1,"result":{"capabilities":{}}}'
+        read -r initialized
+        read -r list
+        sleep 5
+    "#;
+    let client = McpClient::spawn("sh", &["-c", script])
+        .await
+        .unwrap()
+        .with_timeout(std::time::Duration::from_millis(200));
+
+    let err = client.list_tools().await.unwrap_err().to_string();
+    assert!(err.contains("did not answer tools/list"), "{}", err);
+}
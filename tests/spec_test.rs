@@ -0,0 +1,1140 @@
+use futures::StreamExt;
+use martian_adapters::{
+    AdapterError, AdapterWarning, BaseAdapter, BuiltinTool, ComputerAction, Conversation,
+    ConversationRole, Cost, ExecuteOptions, Model, ModelCapabilities, ModelProperties, MouseButton,
+    Point, PrefixCache, ProviderSpec, ResponseFormat, ServiceTier, SpecAdapter, Turn, TurnType,
+    WebSearchOptions,
+};
+use mockito::Matcher;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+
+fn spec_model() -> Model {
+    Model {
+        name: "tiny-1".to_string(),
+        vendor_name: "example".to_string(),
+        provider_name: "example".to_string(),
+        cost: Cost::new(0.000001, 0.000002, 0.0),
+        context_length: 8192,
+        completion_length: None,
+        capabilities: ModelCapabilities::default(),
+        properties: ModelProperties::default(),
+        knowledge_cutoff: None,
+        release_date: None,
+        last_updated: None,
+    }
+}
+
+fn hello() -> Conversation {
+    Conversation::with_turns(vec![TurnType::Basic(Turn {
+        role: ConversationRole::User,
+        content: "Hello".to_string(),
+        name: None,
+        metadata: None,
+    })])
+}
+
+#[tokio::test]
+async fn test_spec_adapter_maps_request_and_response() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/v2/generate/tiny-1")
+        .match_header("x-api-key", "test-key")
+        .match_body(Matcher::Json(json!({
+            "model": "tiny-1",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "max_output_tokens": 64,
+            "safe_mode": true,
+        })))
+        .with_body(
+            json!({
+                "request_id": "gen-1",
+                "output": {"text": "Hi there", "stop": "stop"},
+                "meta": {"input": 5, "output": 3},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}/v2"
+        chat_url = "{{base_url}}/generate/{{model}}"
+
+        [auth]
+        scheme = "header"
+        name = "x-api-key"
+
+        [request.fields]
+        max_tokens = "max_output_tokens"
+        temperature = ""
+
+        [request.extra]
+        safe_mode = true
+
+        [response]
+        id = "/request_id"
+        content = "/output/text"
+        finish_reason = "/output/stop"
+        prompt_tokens = "/meta/input"
+        completion_tokens = "/meta/output"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+
+    let options = ExecuteOptions {
+        max_tokens: Some(64),
+        temperature: Some(0.3),
+        ..ExecuteOptions::default()
+    };
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(response.id, "gen-1");
+    assert_eq!(
+        response.choices[0].message.content.as_deref(),
+        Some("Hi there")
+    );
+    assert_eq!(response.choices[0].finish_reason.as_deref(), Some("stop"));
+    assert_eq!(response.usage.as_ref().unwrap().total_tokens, 8);
+    assert!(response.cost > 0.0);
+}
+
+#[tokio::test]
+async fn test_spec_adapter_streams_json_lines() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/stream")
+        .match_body(Matcher::PartialJson(json!({"stream": true})))
+        .with_body(
+            "{\"delta\": \"Hel\"}\n{\"delta\": \"lo\"}\n{\"delta\": \"\", \"done\": \"stop\"}\n",
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_json(
+        &json!({
+            "name": "example",
+            "base_url": server.url(),
+            "auth": {"scheme": "none"},
+            "stream": {
+                "framing": "json_lines",
+                "url": "{base_url}/stream",
+                "content": "/delta",
+                "finish_reason": "/done",
+                "done": null,
+            },
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "").unwrap();
+
+    let chunks: Vec<_> = adapter
+        .execute_stream(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let chunks: Vec<_> = chunks.into_iter().map(Result::unwrap).collect();
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk.choices[0].delta.content.clone())
+        .collect();
+
+    assert_eq!(text, "Hello");
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[2].choices[0].finish_reason.as_deref(), Some("stop"));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_spec_adapter_connects_over_unix_socket() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let socket = std::env::temp_dir().join(format!("adapters-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&socket);
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        while !String::from_utf8_lossy(&request).contains("\"messages\"") {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body = json!({"id": "uds-1", "choices": [{"message": {"content": "local"}}]});
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    });
+
+    let spec = ProviderSpec::from_json(
+        &json!({
+            "name": "local",
+            "base_url": format!("unix://{}:/v1", socket.display()),
+            "auth": {"scheme": "none"},
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "").unwrap();
+    let response = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /v1/chat/completions HTTP/1.1"));
+    assert_eq!(
+        response.choices[0].message.content.as_deref(),
+        Some("local")
+    );
+    std::fs::remove_file(&socket).unwrap();
+}
+
+#[cfg(feature = "custom-transport")]
+#[tokio::test]
+async fn test_spec_adapter_uses_custom_transport() {
+    use martian_adapters::HttpClient;
+
+    let transport = tower::service_fn(|request: http::Request<reqwest::Body>| async move {
+        assert_eq!(request.uri().path(), "/chat/completions");
+        let body = json!({"id": "in-process", "choices": [{"message": {"content": "pong"}}]});
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(http::Response::new(reqwest::Body::from(
+            body.to_string(),
+        )))
+    });
+    let spec = ProviderSpec::from_json(
+        &json!({"name": "local", "base_url": "http://sidecar", "auth": {"scheme": "none"}})
+            .to_string(),
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "")
+        .unwrap()
+        .with_http_client(
+            HttpClient::builder()
+                .with_transport(transport)
+                .build()
+                .unwrap(),
+        );
+
+    let response = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(response.id, "in-process");
+}
+
+#[tokio::test]
+async fn test_spec_adapter_signs_requests() {
+    use martian_adapters::{HmacSigner, HttpClient};
+
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_header(
+            "x-gateway-signature",
+            Matcher::Regex("^[0-9a-f]{64}$".into()),
+        )
+        .match_header("x-timestamp", Matcher::Regex("^[0-9]+$".into()))
+        .with_body(json!({"id": "signed", "choices": [{"message": {"content": "ok"}}]}).to_string())
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_json(
+        &json!({"name": "gateway", "base_url": server.url(), "auth": {"scheme": "none"}})
+            .to_string(),
+    )
+    .unwrap();
+    let http = HttpClient::builder()
+        .with_signer(HmacSigner::new("secret").with_signature_header("x-gateway-signature"))
+        .build()
+        .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "")
+        .unwrap()
+        .with_http_client(http);
+
+    let response = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    mock.assert_async().await;
+    assert_eq!(response.id, "signed");
+}
+
+#[tokio::test]
+async fn test_spec_adapter_uses_per_request_credentials() {
+    use martian_adapters::ProviderCredentials;
+
+    let mut tenant = mockito::Server::new_async().await;
+    let mock = tenant
+        .mock("POST", "/chat/completions")
+        .match_header("authorization", "Bearer tenant-key")
+        .match_header("openai-organization", "org-tenant")
+        .with_body(json!({"id": "tenant", "choices": [{"message": {"content": "ok"}}]}).to_string())
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_json(
+        &json!({
+            "name": "example",
+            "base_url": "http://127.0.0.1:9",
+            "org_header": "OpenAI-Organization",
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "process-key").unwrap();
+    let options = ExecuteOptions::builder()
+        .credentials(
+            ProviderCredentials::default()
+                .with_api_key("tenant-key")
+                .with_base_url(tenant.url())
+                .with_org("org-tenant"),
+        )
+        .build()
+        .unwrap();
+
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    mock.assert_async().await;
+    assert_eq!(response.id, "tenant");
+    assert!(!format!("{:?}", options).contains("tenant-key"));
+    assert_eq!(
+        options.content_hash(&hello()),
+        ExecuteOptions::default().content_hash(&hello())
+    );
+}
+
+#[tokio::test]
+async fn test_key_pool_rotates_past_rate_limited_keys() {
+    use martian_adapters::{KeyPool, KeyPooled};
+    use std::sync::Arc;
+
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("POST", "/chat/completions")
+        .match_header("authorization", "Bearer key-one")
+        .with_status(429)
+        .with_body(
+            json!({"error": {"type": "rate_limit_error", "message": "slow down"}}).to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+    let ok = server
+        .mock("POST", "/chat/completions")
+        .match_header("authorization", "Bearer key-two")
+        .with_body(
+            json!({
+                "id": "pooled",
+                "choices": [{"message": {"content": "ok"}}],
+                "usage": {"prompt_tokens": 4, "completion_tokens": 2},
+            })
+            .to_string(),
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+    let spec =
+        ProviderSpec::from_json(&json!({"name": "example", "base_url": server.url()}).to_string())
+            .unwrap();
+    let pool = Arc::new(
+        KeyPool::new(
+            "example",
+            vec!["key-one".to_string(), "key-two".to_string()],
+        )
+        .unwrap(),
+    );
+    let adapter = KeyPooled::new(
+        SpecAdapter::new(spec, spec_model(), "").unwrap(),
+        pool.clone(),
+    );
+
+    for _ in 0..2 {
+        let response = adapter
+            .execute(&hello(), &ExecuteOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(response.id, "pooled");
+    }
+    limited.assert_async().await;
+    ok.assert_async().await;
+
+    let stats = pool.stats();
+    assert_eq!(stats[0].key_hint, "-one");
+    assert_eq!(stats[0].usage.rate_limited, 1);
+    assert!(stats[0].cooldown_remaining.is_some());
+    assert_eq!(stats[1].usage.requests, 2);
+    assert_eq!(stats[1].usage.prompt_tokens, 8);
+}
+
+#[tokio::test]
+async fn test_key_pool_stops_after_trying_every_key() {
+    use martian_adapters::{KeyPool, KeyPooled};
+    use std::sync::Arc;
+
+    let mut server = mockito::Server::new_async().await;
+    let limited = server
+        .mock("POST", "/chat/completions")
+        .with_status(429)
+        .with_header("retry-after", "0")
+        .with_body(
+            json!({"error": {"type": "rate_limit_error", "message": "slow down"}}).to_string(),
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+    let spec =
+        ProviderSpec::from_json(&json!({"name": "example", "base_url": server.url()}).to_string())
+            .unwrap();
+    let pool = Arc::new(
+        KeyPool::new(
+            "example",
+            vec!["key-one".to_string(), "key-two".to_string()],
+        )
+        .unwrap(),
+    );
+    let adapter = KeyPooled::new(SpecAdapter::new(spec, spec_model(), "").unwrap(), pool);
+    let err = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap_err();
+    assert!(err.is_rate_limited());
+    limited.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_surfaces_gemini_grounding() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/chat/completions")
+        .with_body(
+            json!({
+                "candidates": [{
+                    "content": {"parts": [{"text": "Paris."}]},
+                    "finishReason": "STOP",
+                    "groundingMetadata": {
+                        "groundingChunks": [{"web": {"uri": "https://example.org/paris", "title": "example.org"}}],
+                        "groundingSupports": [{
+                            "segment": {"startIndex": 0, "endIndex": 6},
+                            "groundingChunkIndices": [0]
+                        }]
+                    }
+                }]
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "gemini-like"
+        base_url = "{}"
+
+        [auth]
+        scheme = "none"
+
+        [response]
+        content = "/candidates/0/content/parts/0/text"
+        finish_reason = "/candidates/0/finishReason"
+        citations = "gemini"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "").unwrap();
+    let response = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+
+    let citations = response.choices[0].citations.as_ref().unwrap();
+    assert_eq!(citations.sources[0].uri, "https://example.org/paris");
+    assert_eq!(citations.segments[0].source_indices, vec![0]);
+}
+
+#[tokio::test]
+async fn test_spec_adapter_applies_builtin_web_search() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "tools": [{"type": "web_search_20250305", "name": "web_search", "max_uses": 2}]
+        })))
+        .with_body(
+            json!({
+                "content": [
+                    {"type": "server_tool_use", "name": "web_search", "input": {"query": "capital of france"}},
+                    {"type": "web_search_tool_result", "content": [
+                        {"type": "web_search_result", "url": "https://example.org/paris", "title": "Paris"}
+                    ]},
+                    {"type": "text", "text": "Paris.", "citations": [{
+                        "type": "web_search_result_location",
+                        "url": "https://example.org/paris",
+                        "title": "Paris",
+                        "cited_text": "Paris is the capital of France."
+                    }]}
+                ],
+                "stop_reason": "end_turn"
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "anthropic-like"
+        base_url = "{}"
+
+        [auth]
+        scheme = "none"
+
+        [response]
+        content = "/content/2/text"
+        finish_reason = "/stop_reason"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let mut model = spec_model();
+    model.provider_name = "anthropic".to_string();
+    let adapter = SpecAdapter::new(spec, model, "").unwrap();
+    let options = ExecuteOptions::builder()
+        .builtin_tool(BuiltinTool::web_search_with(WebSearchOptions {
+            max_uses: Some(2),
+            ..Default::default()
+        }))
+        .build()
+        .unwrap();
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    mock.assert_async().await;
+
+    let citations = response.choices[0].citations.as_ref().unwrap();
+    assert_eq!(citations.search_queries, vec!["capital of france"]);
+    assert_eq!(citations.sources.len(), 1);
+    assert_eq!(citations.segments[0].end_index, 6);
+}
+
+#[tokio::test]
+async fn test_spec_adapter_gates_computer_use() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "tools": [{"type": "computer_20250124", "name": "computer"}]
+        })))
+        .with_body(
+            json!({
+                "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "computer",
+                    "input": {"action": "left_click", "coordinate": [10, 20]}
+                }],
+                "stop_reason": "tool_use"
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "anthropic-like"
+        base_url = "{}"
+
+        [auth]
+        scheme = "none"
+
+        [response]
+        finish_reason = "/stop_reason"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let options = ExecuteOptions::builder()
+        .builtin_tool(BuiltinTool::computer_use(1280, 800))
+        .build()
+        .unwrap();
+    let mut model = spec_model();
+    model.provider_name = "anthropic".to_string();
+
+    let adapter = SpecAdapter::new(spec.clone(), model.clone(), "").unwrap();
+    let err = adapter.execute(&hello(), &options).await.unwrap_err();
+    assert!(matches!(err, AdapterError::UnsupportedFeature { .. }));
+
+    model.capabilities.supports_computer_use = true;
+    let adapter = SpecAdapter::new(spec, model, "").unwrap();
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    let calls = response.choices[0].message.computer_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "toolu_1");
+    assert_eq!(
+        calls[0].action,
+        ComputerAction::Click {
+            at: Point { x: 10, y: 20 },
+            button: MouseButton::Left
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_stub_endpoint_answers_through_spec_adapter() {
+    let spec = ProviderSpec::from_toml(
+        r#"
+        name = "stub"
+        base_url = "stub://echo?latency=1-5&seed=7"
+        "#,
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "unused").unwrap();
+
+    let first = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    let second = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(first.choices[0].message.content.as_deref(), Some("Hello"));
+    assert_eq!(first.id, second.id);
+    assert_eq!(first.usage.unwrap().completion_tokens, 1);
+
+    let spec = ProviderSpec::from_toml(
+        r#"
+        name = "stub"
+        base_url = "stub://lorem?tokens=12&token_ms=1"
+        "#,
+    )
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "unused").unwrap();
+    let chunks: Vec<_> = adapter
+        .execute_stream(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap()
+        .collect()
+        .await;
+    let text: String = chunks
+        .iter()
+        .filter_map(|chunk| chunk.as_ref().unwrap().choices[0].delta.content.clone())
+        .collect();
+    assert_eq!(text.split_whitespace().count(), 12);
+}
+
+#[tokio::test]
+async fn test_spec_adapter_reports_dropped_parameters() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::Json(json!({
+            "model": "tiny-1",
+            "messages": [{"role": "user", "content": "Hello"}],
+        })))
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "choices": [{"message": {"content": "Hi"}, "finish_reason": "stop"}],
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+
+        [[quirks]]
+        action = "drop"
+        field = "temperature"
+        models = "^tiny"
+        reason = "tiny models only sample greedily"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key")
+        .unwrap()
+        .on_warning(move |warning| sink.lock().unwrap().push(warning.clone()));
+
+    let options = ExecuteOptions {
+        temperature: Some(0.3),
+        ..ExecuteOptions::default()
+    };
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+
+    mock.assert_async().await;
+    let expected = AdapterWarning::DroppedParameter {
+        parameter: "temperature".to_string(),
+        model: "tiny-1".to_string(),
+        reason: "tiny models only sample greedily".to_string(),
+    };
+    assert_eq!(response.warnings, vec![expected.clone()]);
+    assert_eq!(*seen.lock().unwrap(), vec![expected]);
+}
+
+#[tokio::test]
+async fn test_spec_adapter_emulates_n_and_repairs_json() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({"seed": 7})))
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "choices": [{"message": {"content": "```json\n{\"ok\": true}\n```"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 4},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let second = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({"seed": 8})))
+        .with_body(
+            json!({
+                "id": "chatcmpl-2",
+                "choices": [{"message": {"content": "{\"ok\": false}"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 4},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let mut model = spec_model();
+    model.capabilities.supports_n = false;
+    let adapter = SpecAdapter::new(spec, model, "test-key").unwrap();
+
+    let options = ExecuteOptions {
+        n: Some(2),
+        seed: Some(7),
+        response_format: Some(ResponseFormat::json()),
+        ..ExecuteOptions::default()
+    };
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+
+    mock.assert_async().await;
+    second.assert_async().await;
+    assert_eq!(response.choices.len(), 2);
+    assert_eq!(response.choices[1].index, 1);
+    assert_eq!(
+        response.choices[0].message.content.as_deref(),
+        Some("{\"ok\": true}")
+    );
+    assert_eq!(response.usage.as_ref().unwrap().total_tokens, 18);
+    assert!(matches!(
+        response.warnings[..],
+        [
+            AdapterWarning::RepairedJson {
+                choice_index: 0,
+                ..
+            },
+            AdapterWarning::EmulatedFeature { .. }
+        ]
+    ));
+}
+
+#[tokio::test]
+async fn test_spec_adapter_records_seeded_requests() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "model": "tiny-1-2026-01-01",
+                "system_fingerprint": "fp_42",
+                "choices": [{"message": {"content": "Hi"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 1},
+            })
+            .to_string(),
+        )
+        .expect(2)
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+
+    let unseeded = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    assert!(unseeded.reproducibility.is_none());
+
+    let options = ExecuteOptions {
+        seed: Some(7),
+        temperature: Some(0.5),
+        ..ExecuteOptions::default()
+    };
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    mock.assert_async().await;
+    let record = response.reproducibility.unwrap();
+    assert_eq!(record.provider, "example");
+    assert_eq!(record.seed, 7);
+    assert_eq!(record.model_version.as_deref(), Some("tiny-1-2026-01-01"));
+    assert_eq!(record.system_fingerprint.as_deref(), Some("fp_42"));
+    assert_eq!(record.parameters["temperature"], json!(0.5));
+    assert!(!record.parameters.contains_key("messages"));
+}
+
+#[tokio::test]
+async fn test_spec_adapter_sizes_max_tokens_to_schema() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "max_tokens": 64,
+            "response_format": {"type": "json_schema", "json_schema": {"name": "flag"}},
+        })))
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "choices": [{"message": {"content": "{\"ok\": true}"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 4},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+    let schema = json!({"type": "object", "properties": {"ok": {"type": "boolean"}}});
+    let options = ExecuteOptions {
+        response_format: Some(ResponseFormat::json_schema("flag", schema)),
+        ..ExecuteOptions::default()
+    };
+    adapter.execute(&hello(), &options).await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_maps_predicted_outputs() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({
+            "prediction": {"type": "content", "content": "fn main() {}"},
+        })))
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "choices": [{"message": {"content": "fn main() { run() }"}}],
+                "usage": {
+                    "prompt_tokens": 5,
+                    "completion_tokens": 8,
+                    "completion_tokens_details": {
+                        "accepted_prediction_tokens": 4,
+                        "rejected_prediction_tokens": 2
+                    }
+                },
+            })
+            .to_string(),
+        )
+        .expect(1)
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let mut model = spec_model();
+    model.capabilities.supports_prediction = true;
+    let adapter = SpecAdapter::new(spec.clone(), model, "test-key").unwrap();
+    let options = ExecuteOptions::builder()
+        .prediction("fn main() {}")
+        .build()
+        .unwrap();
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    let usage = response.usage.unwrap();
+    assert_eq!(usage.accepted_prediction_tokens, Some(4));
+    assert_eq!(usage.rejected_prediction_tokens, Some(2));
+    assert!(response.warnings.is_empty());
+    mock.assert_async().await;
+
+    let fallback = server
+        .mock("POST", "/chat/completions")
+        .with_body(
+            json!({
+                "id": "chatcmpl-2",
+                "choices": [{"message": {"content": "fn main() {}"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 4},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    assert!(matches!(
+        &response.warnings[..],
+        [AdapterWarning::DroppedParameter { parameter, .. }] if parameter == "prediction"
+    ));
+    fallback.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_reports_service_tier() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::PartialJson(json!({"service_tier": "flex"})))
+        .with_body(
+            json!({
+                "id": "chatcmpl-1",
+                "service_tier": "flex",
+                "choices": [{"message": {"content": "Hi"}}],
+                "usage": {"prompt_tokens": 5, "completion_tokens": 1},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+    let options = ExecuteOptions {
+        service_tier: Some(ServiceTier::Flex),
+        ..ExecuteOptions::default()
+    };
+    let response = adapter.execute(&hello(), &options).await.unwrap();
+    assert_eq!(response.service_tier.as_deref(), Some("flex"));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_reads_anthropic_service_tier_from_usage() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/chat/completions")
+        .with_body(
+            json!({
+                "id": "msg_1",
+                "content": [{"type": "text", "text": "Hi"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 5, "output_tokens": 1, "service_tier": "priority"},
+            })
+            .to_string(),
+        )
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "anthropic-like"
+        base_url = "{}"
+
+        [auth]
+        scheme = "none"
+
+        [response]
+        content = "/content/0/text"
+        finish_reason = "/stop_reason"
+        prompt_tokens = "/usage/input_tokens"
+        completion_tokens = "/usage/output_tokens"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let mut model = spec_model();
+    model.provider_name = "anthropic".to_string();
+    let adapter = SpecAdapter::new(spec, model, "").unwrap();
+    let response = adapter
+        .execute(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(response.service_tier.as_deref(), Some("priority"));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_caches_prefixes_only_when_sending() {
+    let mut server = mockito::Server::new_async().await;
+    let cached = server
+        .mock("POST", "/chat/completions")
+        .match_body(Matcher::Regex("cache_control".to_string()))
+        .with_body(json!({"choices": [{"message": {"content": "Hi"}}]}).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("POST", "/chat/completions")
+        .with_body(json!({"choices": [{"message": {"content": "Hi"}}]}).to_string())
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let cache = Arc::new(PrefixCache::new().with_min_tokens(1));
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key")
+        .unwrap()
+        .with_prefix_cache(cache);
+    let options = ExecuteOptions::default();
+    for _ in 0..2 {
+        let body = adapter.build_body(&hello(), &options, false).unwrap();
+        assert!(!body.to_string().contains("cache_control"));
+    }
+
+    adapter.execute(&hello(), &options).await.unwrap();
+    adapter.execute(&hello(), &options).await.unwrap();
+    cached.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_forwards_idempotency_key() {
+    let mut server = mockito::Server::new_async().await;
+    let keyed = server
+        .mock("POST", "/chat/completions")
+        .match_header("idempotency-key", "req-7")
+        .with_body(json!({"choices": [{"message": {"content": "Hi"}}]}).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+    let unkeyed = server
+        .mock("POST", "/chat/completions")
+        .match_header("idempotency-key", Matcher::Missing)
+        .with_body(json!({"choices": [{"message": {"content": "Hi"}}]}).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let spec = |idempotency: bool| {
+        ProviderSpec::from_json(
+            &json!({"name": "example", "base_url": server.url(), "idempotency": idempotency})
+                .to_string(),
+        )
+        .unwrap()
+    };
+    let options = ExecuteOptions::builder()
+        .idempotency_key("req-7")
+        .build()
+        .unwrap();
+    let supported = SpecAdapter::new(spec(true), spec_model(), "test-key").unwrap();
+    supported.execute(&hello(), &options).await.unwrap();
+    // Providers that do not deduplicate retries never see the key.
+    let unsupported = SpecAdapter::new(spec(false), spec_model(), "test-key").unwrap();
+    unsupported.execute(&hello(), &options).await.unwrap();
+
+    keyed.assert_async().await;
+    unkeyed.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_spec_adapter_streams_tool_calls() {
+    let mut server = mockito::Server::new_async().await;
+    let frames = [
+        json!({"id": "c1", "choices": [{"delta": {"tool_calls": [{
+            "index": 0, "id": "call_1", "type": "function",
+            "function": {"name": "weather", "arguments": "{\"city\":"}
+        }]}}]}),
+        json!({"id": "c1", "choices": [{"delta": {"tool_calls": [{
+            "index": 0, "function": {"arguments": "\"Oslo\"}"}
+        }]}}]}),
+        json!({"id": "c1", "choices": [{"delta": {}, "finish_reason": "tool_calls"}]}),
+    ];
+    let body: String = frames
+        .iter()
+        .map(|frame| format!("data: {}\n\n", frame))
+        .chain(["data: [DONE]\n\n".to_string()])
+        .collect();
+    server
+        .mock("POST", "/chat/completions")
+        .with_header("content-type", "text/event-stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let spec = ProviderSpec::from_toml(&format!(
+        r#"
+        name = "example"
+        base_url = "{}"
+        "#,
+        server.url()
+    ))
+    .unwrap();
+    let adapter = SpecAdapter::new(spec, spec_model(), "test-key").unwrap();
+    let chunks: Vec<_> = adapter
+        .execute_stream(&hello(), &ExecuteOptions::default())
+        .await
+        .unwrap()
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+
+    let fragments: Vec<_> = chunks
+        .iter()
+        .filter_map(|chunk| chunk.choices[0].delta.tool_calls.clone())
+        .flatten()
+        .collect();
+    assert_eq!(fragments.len(), 2);
+    assert_eq!(fragments[0].id.as_deref(), Some("call_1"));
+    let arguments: String = fragments
+        .iter()
+        .filter_map(|fragment| fragment.function.as_ref()?.arguments.clone())
+        .collect();
+    assert_eq!(arguments, r#"{"city":"Oslo"}"#);
+    assert_eq!(
+        chunks.last().unwrap().choices[0].finish_reason.as_deref(),
+        Some("tool_calls")
+    );
+}